@@ -7,7 +7,9 @@ fn main() {
     let dest = env::var("OUT_DIR").unwrap();
     let mut file = File::create(&Path::new(&dest).join("bindings.rs")).unwrap();
 
-    Registry::new(Api::Gl, (3, 3), Profile::Core, Fallbacks::All, [])
+    let extensions = ["GL_EXT_texture_compression_s3tc", "GL_EXT_texture_sRGB", "GL_ARB_texture_compression_bptc", "GL_EXT_texture_filter_anisotropic"];
+
+    Registry::new(Api::Gl, (3, 3), Profile::Core, Fallbacks::All, extensions)
         .write_bindings(StructGenerator, &mut file)
         .unwrap();
 }
@@ -0,0 +1,172 @@
+pub mod asset {
+    use crate::{gl, Fallbacks, ImageData, Mesh, Program, Rc, Texture, TextureOptions, TextureSource};
+    use std::{collections::HashMap, fmt, fs, path::PathBuf};
+
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct TextureKey {
+        path: PathBuf,
+        options: TextureOptions,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    pub struct MeshKey {
+        path: PathBuf,
+        index: u32,
+    }
+
+    impl MeshKey {
+        pub fn new(model_path: &str, index: u32) -> Self {
+            MeshKey {
+                path: canonical(model_path),
+                index,
+            }
+        }
+    }
+
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct ProgramKey {
+        vertex: PathBuf,
+        fragment: PathBuf,
+    }
+
+    pub struct MemoryUsage {
+        pub textures: usize,
+        pub texture_bytes: usize,
+        pub meshes: usize,
+        pub mesh_bytes: usize,
+        pub programs: usize,
+    }
+
+    impl fmt::Display for MemoryUsage {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "{} textures ({} KiB), {} meshes ({} KiB), {} programs",
+                self.textures,
+                self.texture_bytes / 1024,
+                self.meshes,
+                self.mesh_bytes / 1024,
+                self.programs
+            )
+        }
+    }
+
+    pub struct AssetCache {
+        gl: Rc<gl::Gl>,
+        fallbacks: Rc<Fallbacks>,
+        textures: HashMap<TextureKey, Rc<Texture>>,
+        meshes: HashMap<MeshKey, Rc<Mesh>>,
+        models: HashMap<PathBuf, Vec<MeshKey>>,
+        programs: HashMap<ProgramKey, Rc<Program>>,
+    }
+
+    impl AssetCache {
+        pub fn new(gl: Rc<gl::Gl>) -> Self {
+            AssetCache {
+                fallbacks: Rc::new(Fallbacks::new(Rc::clone(&gl))),
+                gl,
+                textures: HashMap::new(),
+                meshes: HashMap::new(),
+                models: HashMap::new(),
+                programs: HashMap::new(),
+            }
+        }
+
+        pub fn fallbacks(&self) -> Rc<Fallbacks> {
+            Rc::clone(&self.fallbacks)
+        }
+
+        pub fn texture(&mut self, tex_type: &str, source: &TextureSource, options: TextureOptions) -> Rc<Texture> {
+            let path = source.path();
+            let key = TextureKey { path: canonical(&path), options };
+            let gl = &self.gl;
+
+            let texture = self.textures.entry(key).or_insert_with(|| {
+                let texture = Texture::new(Rc::clone(gl), tex_type.to_string(), path.clone());
+                texture.upload(source.decode(options.flip).as_ref(), options);
+                Rc::new(texture)
+            });
+
+            Rc::clone(texture)
+        }
+
+        // a texture holding `placeholder` until the real image is uploaded into it; true if it was just created
+        pub fn texture_placeholder(&mut self, tex_type: &str, path: &str, options: TextureOptions, placeholder: &ImageData) -> (Rc<Texture>, bool) {
+            let key = TextureKey { path: canonical(path), options };
+
+            if let Some(texture) = self.textures.get(&key) {
+                return (Rc::clone(texture), false);
+            }
+
+            let texture = Texture::new(Rc::clone(&self.gl), tex_type.to_string(), path.to_string());
+            texture.upload(Some(placeholder), options);
+
+            let texture = Rc::new(texture);
+            self.textures.insert(key, Rc::clone(&texture));
+
+            (texture, true)
+        }
+
+        pub fn mesh(&self, key: &MeshKey) -> Option<Rc<Mesh>> {
+            self.meshes.get(key).map(Rc::clone)
+        }
+
+        pub fn insert_mesh(&mut self, key: MeshKey, mesh: Mesh) -> Rc<Mesh> {
+            let mesh = Rc::new(mesh);
+            self.meshes.insert(key, Rc::clone(&mesh));
+
+            mesh
+        }
+
+        // the meshes of a previously loaded model, in node order, as long as none were evicted
+        pub fn model(&self, path: &str) -> Option<Vec<Rc<Mesh>>> {
+            self.models.get(&canonical(path))?.iter().map(|key| self.mesh(key)).collect()
+        }
+
+        pub fn insert_model(&mut self, path: &str, keys: Vec<MeshKey>) {
+            self.models.insert(canonical(path), keys);
+        }
+
+        pub fn program(&mut self, vertex_file: &str, fragment_file: &str) -> Rc<Program> {
+            let key = ProgramKey {
+                vertex: canonical(vertex_file),
+                fragment: canonical(fragment_file),
+            };
+            let gl = &self.gl;
+
+            let program = self
+                .programs
+                .entry(key)
+                .or_insert_with(|| Rc::new(Program::new(Rc::clone(gl)).link(vertex_file, fragment_file)));
+
+            Rc::clone(program)
+        }
+
+        pub fn memory_usage(&self) -> MemoryUsage {
+            MemoryUsage {
+                textures: self.textures.len(),
+                texture_bytes: self.textures.values().map(|texture| texture.bytes()).sum(),
+                meshes: self.meshes.len(),
+                mesh_bytes: self.meshes.values().map(|mesh| mesh.bytes()).sum(),
+                programs: self.programs.len(),
+            }
+        }
+
+        // meshes go first so the textures their materials release can be dropped in the same pass
+        pub fn evict_unused(&mut self) -> usize {
+            let before = self.textures.len() + self.meshes.len() + self.programs.len();
+
+            self.meshes.retain(|_, mesh| Rc::strong_count(mesh) > 1);
+            let meshes = &self.meshes;
+            self.models.retain(|_, keys| keys.iter().all(|key| meshes.contains_key(key)));
+            self.textures.retain(|_, texture| Rc::strong_count(texture) > 1);
+            self.programs.retain(|_, program| Rc::strong_count(program) > 1);
+
+            before - (self.textures.len() + self.meshes.len() + self.programs.len())
+        }
+    }
+
+    fn canonical(path: &str) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+    }
+}
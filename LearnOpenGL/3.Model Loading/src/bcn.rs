@@ -0,0 +1,836 @@
+pub mod bcn {
+    use crate::Format;
+    use std::array;
+
+    // expands block compressed images for drivers without the matching compression support,
+    // to RGBA8 for BC1-BC5 and BC7, and to half float RGBA for BC6H; see Format::decompressed
+    pub fn decompress_blocks(format: Format, width: i32, height: i32, blocks: &[u8]) -> Option<Vec<u8>> {
+        let block_bytes = format.block_bytes()?;
+        let pixel_bytes = format.decompressed().pixel_bytes();
+        let (width, height) = (width as usize, height as usize);
+        let blocks_x = width.div_ceil(4);
+        let mut pixels = vec![0; width * height * pixel_bytes];
+
+        for (i, block) in blocks.chunks_exact(block_bytes).enumerate() {
+            let mut texels = [[0; 8]; 16];
+
+            if let Format::Bc6h | Format::Bc6hSigned = format {
+                for (texel, rgb) in texels.iter_mut().zip(bc6h_block(block, format == Format::Bc6hSigned)) {
+                    for (bytes, half) in texel.chunks_exact_mut(2).zip([rgb[0], rgb[1], rgb[2], HALF_ONE]) {
+                        bytes.copy_from_slice(&half.to_le_bytes());
+                    }
+                }
+            } else {
+                let rgba: [[u8; 4]; 16] = match format {
+                    Format::Bc1 => color_block(block, true),
+                    Format::Bc2 => {
+                        let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+                        let mut texels = color_block(&block[8..], false);
+                        for (j, texel) in texels.iter_mut().enumerate() {
+                            texel[3] = ((alpha >> (4 * j)) & 15) as u8 * 17;
+                        }
+                        texels
+                    }
+                    Format::Bc3 => {
+                        let alpha = alpha_block(&block[..8]);
+                        let mut texels = color_block(&block[8..], false);
+                        for (texel, alpha) in texels.iter_mut().zip(alpha) {
+                            texel[3] = alpha;
+                        }
+                        texels
+                    }
+                    Format::Bc4 => alpha_block(block).map(|red| [red, 0, 0, 255]),
+                    Format::Bc5 => {
+                        let (red, green) = (alpha_block(&block[..8]), alpha_block(&block[8..]));
+                        array::from_fn(|j| [red[j], green[j], 0, 255])
+                    }
+                    Format::Bc7 => bc7_block(block),
+                    _ => return None,
+                };
+
+                for (texel, rgba) in texels.iter_mut().zip(rgba) {
+                    texel[..4].copy_from_slice(&rgba);
+                }
+            }
+
+            let (block_x, block_y) = (i % blocks_x * 4, i / blocks_x * 4);
+            for (j, texel) in texels.iter().enumerate() {
+                let (x, y) = (block_x + j % 4, block_y + j / 4);
+
+                if x < width && y < height {
+                    pixels[(y * width + x) * pixel_bytes..][..pixel_bytes].copy_from_slice(&texel[..pixel_bytes]);
+                }
+            }
+        }
+
+        Some(pixels)
+    }
+
+    // BC2 and BC3 always use four colors, only BC1 switches to three plus transparent black when c0 <= c1
+    fn color_block(block: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+        let c0 = u16::from_le_bytes([block[0], block[1]]);
+        let c1 = u16::from_le_bytes([block[2], block[3]]);
+        let (e0, e1) = (rgb565(c0), rgb565(c1));
+
+        let mix = |w0: u32, w1: u32| -> [u8; 4] {
+            let channel = |i: usize| ((w0 * e0[i] as u32 + w1 * e1[i] as u32) / (w0 + w1)) as u8;
+            [channel(0), channel(1), channel(2), 255]
+        };
+
+        let palette = if c0 > c1 || !punch_through {
+            [e0, e1, mix(2, 1), mix(1, 2)]
+        } else {
+            [e0, e1, mix(1, 1), [0, 0, 0, 0]]
+        };
+
+        let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+        array::from_fn(|i| palette[((indices >> (2 * i)) & 3) as usize])
+    }
+
+    fn alpha_block(block: &[u8]) -> [u8; 16] {
+        let (a0, a1) = (block[0] as u32, block[1] as u32);
+
+        let palette: [u32; 8] = if a0 > a1 {
+            array::from_fn(|i| match i {
+                0 => a0,
+                1 => a1,
+                _ => ((8 - i as u32) * a0 + (i as u32 - 1) * a1) / 7,
+            })
+        } else {
+            array::from_fn(|i| match i {
+                0 => a0,
+                1 => a1,
+                6 => 0,
+                7 => 255,
+                _ => ((6 - i as u32) * a0 + (i as u32 - 1) * a1) / 5,
+            })
+        };
+
+        let mut bits = [0; 8];
+        bits[..6].copy_from_slice(&block[2..8]);
+        let indices = u64::from_le_bytes(bits);
+
+        array::from_fn(|i| palette[((indices >> (3 * i)) & 7) as usize] as u8)
+    }
+
+    fn rgb565(color: u16) -> [u8; 4] {
+        let (r, g, b) = ((color >> 11) & 31, (color >> 5) & 63, color & 31);
+
+        [((r << 3) | (r >> 2)) as u8, ((g << 2) | (g >> 4)) as u8, ((b << 3) | (b >> 2)) as u8, 255]
+    }
+
+    const HALF_ONE: u16 = 0x3C00;
+
+    // the BPTC partitions, one bit per texel for two subsets and two bits per texel for three, texel 0 in the lowest bits
+    const PARTITIONS_2: [u16; 64] = [
+        0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00, 0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310,
+        0x3100, 0x8CCE, 0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC,
+        0x6996, 0xC33C, 0x9966, 0x0660, 0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+    ];
+
+    const PARTITIONS_3: [u32; 64] = [
+        0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050, 0xAA550000, 0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4,
+        0xA9A59450, 0x2A0A4250, 0xA5945040, 0x0A425054, 0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500, 0x0050A4A4, 0xAAA59090, 0x14696914, 0x69691400,
+        0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200, 0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424, 0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50, 0x500AA550, 0xAAAA4444,
+        0x66660000, 0xA5A0A5A0, 0x50A050A0, 0x69286928, 0x44AAAA44, 0x66666600, 0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580, 0xAA141414, 0x96960000,
+        0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44, 0x2A4A5254,
+    ];
+
+    // the texel of each subset after the first whose index is stored a bit short, its top bit is always 0
+    const ANCHORS_2: [u8; 64] = [
+        15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+        2, 15, 15, 15, 15, 15, 2, 2, 15,
+    ];
+
+    const ANCHORS_3: [[u8; 2]; 64] = [
+        [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8], [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8], [3, 15], [3, 8], [8, 15], [15, 3],
+        [3, 15], [3, 8], [6, 15], [10, 8], [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8], [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+        [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8], [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15], [5, 15], [10, 15], [8, 15], [13, 15],
+        [15, 3], [12, 15], [3, 15], [3, 8],
+    ];
+
+    const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+    const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+    const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+    fn weights(index_bits: u32) -> &'static [u32] {
+        match index_bits {
+            2 => &WEIGHTS_2,
+            3 => &WEIGHTS_3,
+            _ => &WEIGHTS_4,
+        }
+    }
+
+    fn subset(subsets: usize, partition: usize, texel: usize) -> usize {
+        match subsets {
+            1 => 0,
+            2 => (PARTITIONS_2[partition] >> texel) as usize & 1,
+            _ => (PARTITIONS_3[partition] >> (2 * texel)) as usize & 3,
+        }
+    }
+
+    fn is_anchor(subsets: usize, partition: usize, texel: usize) -> bool {
+        texel == 0
+            || match subsets {
+                2 => texel == ANCHORS_2[partition] as usize,
+                3 => ANCHORS_3[partition].contains(&(texel as u8)),
+                _ => false,
+            }
+    }
+
+    // a block read from its lowest bit up
+    struct Bits {
+        value: u128,
+        position: u32,
+    }
+
+    impl Bits {
+        fn new(block: &[u8]) -> Self {
+            Bits {
+                value: u128::from_le_bytes(block[..16].try_into().unwrap()),
+                position: 0,
+            }
+        }
+
+        fn read(&mut self, count: u32) -> u32 {
+            let bits = (self.value >> self.position) as u32 & ((1u64 << count) - 1) as u32;
+            self.position += count;
+            bits
+        }
+
+        // the texel indices at the end of the block, anchors one bit short
+        fn indices(&mut self, bits: u32, anchor: impl Fn(usize) -> bool) -> [usize; 16] {
+            array::from_fn(|texel| self.read(if anchor(texel) { bits - 1 } else { bits }) as usize)
+        }
+    }
+
+    struct Bc7Mode {
+        subsets: usize,
+        partition_bits: u32,
+        rotation_bits: u32,
+        index_selection_bits: u32,
+        color_bits: u32,
+        alpha_bits: u32,
+        // one p-bit per endpoint, or one shared by both endpoints of a subset
+        endpoint_p_bits: bool,
+        shared_p_bits: bool,
+        index_bits: u32,
+        // the separate alpha indices of modes 4 and 5
+        secondary_index_bits: u32,
+    }
+
+    const BC7_MODES: [Bc7Mode; 8] = [
+        Bc7Mode {
+            subsets: 3,
+            partition_bits: 4,
+            rotation_bits: 0,
+            index_selection_bits: 0,
+            color_bits: 4,
+            alpha_bits: 0,
+            endpoint_p_bits: true,
+            shared_p_bits: false,
+            index_bits: 3,
+            secondary_index_bits: 0,
+        },
+        Bc7Mode {
+            subsets: 2,
+            partition_bits: 6,
+            rotation_bits: 0,
+            index_selection_bits: 0,
+            color_bits: 6,
+            alpha_bits: 0,
+            endpoint_p_bits: false,
+            shared_p_bits: true,
+            index_bits: 3,
+            secondary_index_bits: 0,
+        },
+        Bc7Mode {
+            subsets: 3,
+            partition_bits: 6,
+            rotation_bits: 0,
+            index_selection_bits: 0,
+            color_bits: 5,
+            alpha_bits: 0,
+            endpoint_p_bits: false,
+            shared_p_bits: false,
+            index_bits: 2,
+            secondary_index_bits: 0,
+        },
+        Bc7Mode {
+            subsets: 2,
+            partition_bits: 6,
+            rotation_bits: 0,
+            index_selection_bits: 0,
+            color_bits: 7,
+            alpha_bits: 0,
+            endpoint_p_bits: true,
+            shared_p_bits: false,
+            index_bits: 2,
+            secondary_index_bits: 0,
+        },
+        Bc7Mode {
+            subsets: 1,
+            partition_bits: 0,
+            rotation_bits: 2,
+            index_selection_bits: 1,
+            color_bits: 5,
+            alpha_bits: 6,
+            endpoint_p_bits: false,
+            shared_p_bits: false,
+            index_bits: 2,
+            secondary_index_bits: 3,
+        },
+        Bc7Mode {
+            subsets: 1,
+            partition_bits: 0,
+            rotation_bits: 2,
+            index_selection_bits: 0,
+            color_bits: 7,
+            alpha_bits: 8,
+            endpoint_p_bits: false,
+            shared_p_bits: false,
+            index_bits: 2,
+            secondary_index_bits: 2,
+        },
+        Bc7Mode {
+            subsets: 1,
+            partition_bits: 0,
+            rotation_bits: 0,
+            index_selection_bits: 0,
+            color_bits: 7,
+            alpha_bits: 7,
+            endpoint_p_bits: true,
+            shared_p_bits: false,
+            index_bits: 4,
+            secondary_index_bits: 0,
+        },
+        Bc7Mode {
+            subsets: 2,
+            partition_bits: 6,
+            rotation_bits: 0,
+            index_selection_bits: 0,
+            color_bits: 5,
+            alpha_bits: 5,
+            endpoint_p_bits: true,
+            shared_p_bits: false,
+            index_bits: 2,
+            secondary_index_bits: 0,
+        },
+    ];
+
+    // the mode is the position of the lowest set bit, a block without one is transparent black
+    fn bc7_block(block: &[u8]) -> [[u8; 4]; 16] {
+        let mode_number = block[0].trailing_zeros();
+        let mode = match BC7_MODES.get(mode_number as usize) {
+            Some(mode) => mode,
+            None => return [[0; 4]; 16],
+        };
+
+        let mut bits = Bits::new(block);
+        bits.read(mode_number + 1);
+        let partition = bits.read(mode.partition_bits) as usize;
+        let rotation = bits.read(mode.rotation_bits);
+        let index_selection = bits.read(mode.index_selection_bits);
+
+        // endpoints[subset * 2 + end], channels in rgba order
+        let mut endpoints = [[0u32; 4]; 6];
+        let count = mode.subsets * 2;
+        for channel in 0..3 {
+            for endpoint in &mut endpoints[..count] {
+                endpoint[channel] = bits.read(mode.color_bits);
+            }
+        }
+        for endpoint in &mut endpoints[..count] {
+            endpoint[3] = if mode.alpha_bits > 0 { bits.read(mode.alpha_bits) } else { 255 };
+        }
+
+        let (mut color_bits, mut alpha_bits) = (mode.color_bits, mode.alpha_bits);
+        if mode.endpoint_p_bits || mode.shared_p_bits {
+            let p_bits: Vec<u32> = if mode.endpoint_p_bits {
+                (0..count).map(|_| bits.read(1)).collect()
+            } else {
+                (0..mode.subsets).flat_map(|_| [bits.read(1); 2]).collect()
+            };
+
+            for (endpoint, p_bit) in endpoints.iter_mut().zip(p_bits) {
+                let channels = if mode.alpha_bits > 0 { 4 } else { 3 };
+                for channel in &mut endpoint[..channels] {
+                    *channel = (*channel << 1) | p_bit;
+                }
+            }
+            color_bits += 1;
+            alpha_bits += (alpha_bits > 0) as u32;
+        }
+
+        for endpoint in &mut endpoints[..count] {
+            for channel in &mut endpoint[..3] {
+                *channel = expand(*channel, color_bits);
+            }
+            if alpha_bits > 0 {
+                endpoint[3] = expand(endpoint[3], alpha_bits);
+            }
+        }
+
+        let indices = bits.indices(mode.index_bits, |texel| is_anchor(mode.subsets, partition, texel));
+        let secondary = if mode.secondary_index_bits > 0 { Some(bits.indices(mode.secondary_index_bits, |texel| texel == 0)) } else { None };
+
+        array::from_fn(|texel| {
+            let subset = subset(mode.subsets, partition, texel);
+            let (from, to) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+            // mode 4's selection bit swaps which of its index sets drives the color
+            let (mut color_weight, mut alpha_weight) = (weights(mode.index_bits)[indices[texel]], weights(mode.index_bits)[indices[texel]]);
+            if let Some(secondary) = secondary {
+                alpha_weight = weights(mode.secondary_index_bits)[secondary[texel]];
+                if index_selection == 1 {
+                    (color_weight, alpha_weight) = (alpha_weight, color_weight);
+                }
+            }
+
+            let mix = |channel: usize, weight: u32| ((from[channel] * (64 - weight) + to[channel] * weight + 32) >> 6) as u8;
+            let mut texel = [mix(0, color_weight), mix(1, color_weight), mix(2, color_weight), mix(3, alpha_weight)];
+            if rotation > 0 {
+                texel.swap(3, rotation as usize - 1);
+            }
+            texel
+        })
+    }
+
+    // replicates the top bits into the bottom so the largest value maps to 255
+    fn expand(value: u32, bits: u32) -> u32 {
+        let value = value << (8 - bits);
+        value | (value >> bits)
+    }
+
+    struct Bc6hMode {
+        id: u32,
+        regions: usize,
+        // whether the endpoints after the first are stored as deltas from it
+        transformed: bool,
+        endpoint_bits: u32,
+        delta_bits: [u32; 3],
+        // where each field of the header goes, as (endpoint, channel, lowest bit, bit count) in block order;
+        // endpoints 0 and 1 belong to the first region, 2 and 3 to the second
+        fields: &'static [(u8, u8, u8, u8)],
+    }
+
+    const BC6H_MODES: [Bc6hMode; 14] = [
+        Bc6hMode {
+            id: 0x00,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 10,
+            delta_bits: [5, 5, 5],
+            fields: &[
+                (2, 1, 4, 1), (2, 2, 4, 1), (3, 2, 4, 1), (0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 5), (3, 1, 4, 1), (2, 1, 0, 4), (1, 1, 0, 5), (3, 2, 0, 1), (3, 1, 0, 4),
+                (1, 2, 0, 5), (3, 2, 1, 1), (2, 2, 0, 4), (2, 0, 0, 5), (3, 2, 2, 1), (3, 0, 0, 5), (3, 2, 3, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x01,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 7,
+            delta_bits: [6, 6, 6],
+            fields: &[
+                (2, 1, 5, 1), (3, 1, 4, 1), (3, 1, 5, 1), (0, 0, 0, 7), (3, 2, 0, 1), (3, 2, 1, 1), (2, 2, 4, 1), (0, 1, 0, 7), (2, 2, 5, 1), (3, 2, 2, 1), (2, 1, 4, 1), (0, 2, 0, 7),
+                (3, 2, 3, 1), (3, 2, 5, 1), (3, 2, 4, 1), (1, 0, 0, 6), (2, 1, 0, 4), (1, 1, 0, 6), (3, 1, 0, 4), (1, 2, 0, 6), (2, 2, 0, 4), (2, 0, 0, 6), (3, 0, 0, 6),
+            ],
+        },
+        Bc6hMode {
+            id: 0x02,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 11,
+            delta_bits: [5, 4, 4],
+            fields: &[
+                (0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 5), (0, 0, 10, 1), (2, 1, 0, 4), (1, 1, 0, 4), (0, 1, 10, 1), (3, 2, 0, 1), (3, 1, 0, 4), (1, 2, 0, 4), (0, 2, 10, 1),
+                (3, 2, 1, 1), (2, 2, 0, 4), (2, 0, 0, 5), (3, 2, 2, 1), (3, 0, 0, 5), (3, 2, 3, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x06,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 11,
+            delta_bits: [4, 5, 4],
+            fields: &[
+                (0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 4), (0, 0, 10, 1), (3, 1, 4, 1), (2, 1, 0, 4), (1, 1, 0, 5), (0, 1, 10, 1), (3, 1, 0, 4), (1, 2, 0, 4), (0, 2, 10, 1),
+                (3, 2, 1, 1), (2, 2, 0, 4), (2, 0, 0, 4), (3, 2, 0, 1), (3, 2, 2, 1), (3, 0, 0, 4), (2, 1, 4, 1), (3, 2, 3, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x0A,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 11,
+            delta_bits: [4, 4, 5],
+            fields: &[
+                (0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 4), (0, 0, 10, 1), (2, 2, 4, 1), (2, 1, 0, 4), (1, 1, 0, 4), (0, 1, 10, 1), (3, 2, 0, 1), (3, 1, 0, 4), (1, 2, 0, 5),
+                (0, 2, 10, 1), (2, 2, 0, 4), (2, 0, 0, 4), (3, 2, 1, 1), (3, 2, 2, 1), (3, 0, 0, 4), (3, 2, 4, 1), (3, 2, 3, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x0E,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 9,
+            delta_bits: [5, 5, 5],
+            fields: &[
+                (0, 0, 0, 9), (2, 2, 4, 1), (0, 1, 0, 9), (2, 1, 4, 1), (0, 2, 0, 9), (3, 2, 4, 1), (1, 0, 0, 5), (3, 1, 4, 1), (2, 1, 0, 4), (1, 1, 0, 5), (3, 2, 0, 1), (3, 1, 0, 4),
+                (1, 2, 0, 5), (3, 2, 1, 1), (2, 2, 0, 4), (2, 0, 0, 5), (3, 2, 2, 1), (3, 0, 0, 5), (3, 2, 3, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x12,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 8,
+            delta_bits: [6, 5, 5],
+            fields: &[
+                (0, 0, 0, 8), (3, 1, 4, 1), (2, 2, 4, 1), (0, 1, 0, 8), (3, 2, 2, 1), (2, 1, 4, 1), (0, 2, 0, 8), (3, 2, 3, 1), (3, 2, 4, 1), (1, 0, 0, 6), (2, 1, 0, 4), (1, 1, 0, 5),
+                (3, 2, 0, 1), (3, 1, 0, 4), (1, 2, 0, 5), (3, 2, 1, 1), (2, 2, 0, 4), (2, 0, 0, 6), (3, 0, 0, 6),
+            ],
+        },
+        Bc6hMode {
+            id: 0x16,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 8,
+            delta_bits: [5, 6, 5],
+            fields: &[
+                (0, 0, 0, 8), (3, 2, 0, 1), (2, 2, 4, 1), (0, 1, 0, 8), (2, 1, 5, 1), (2, 1, 4, 1), (0, 2, 0, 8), (3, 1, 5, 1), (3, 2, 4, 1), (1, 0, 0, 5), (3, 1, 4, 1), (2, 1, 0, 4),
+                (1, 1, 0, 6), (3, 1, 0, 4), (1, 2, 0, 5), (3, 2, 1, 1), (2, 2, 0, 4), (2, 0, 0, 5), (3, 2, 2, 1), (3, 0, 0, 5), (3, 2, 3, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x1A,
+            regions: 2,
+            transformed: true,
+            endpoint_bits: 8,
+            delta_bits: [5, 5, 6],
+            fields: &[
+                (0, 0, 0, 8), (3, 2, 1, 1), (2, 2, 4, 1), (0, 1, 0, 8), (2, 2, 5, 1), (2, 1, 4, 1), (0, 2, 0, 8), (3, 2, 5, 1), (3, 2, 4, 1), (1, 0, 0, 5), (3, 1, 4, 1), (2, 1, 0, 4),
+                (1, 1, 0, 5), (3, 2, 0, 1), (3, 1, 0, 4), (1, 2, 0, 6), (2, 2, 0, 4), (2, 0, 0, 5), (3, 2, 2, 1), (3, 0, 0, 5), (3, 2, 3, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x1E,
+            regions: 2,
+            transformed: false,
+            endpoint_bits: 6,
+            delta_bits: [6, 6, 6],
+            fields: &[
+                (0, 0, 0, 6), (3, 1, 4, 1), (3, 2, 0, 1), (3, 2, 1, 1), (2, 2, 4, 1), (0, 1, 0, 6), (2, 1, 5, 1), (2, 2, 5, 1), (3, 2, 2, 1), (2, 1, 4, 1), (0, 2, 0, 6), (3, 1, 5, 1),
+                (3, 2, 3, 1), (3, 2, 5, 1), (3, 2, 4, 1), (1, 0, 0, 6), (2, 1, 0, 4), (1, 1, 0, 6), (3, 1, 0, 4), (1, 2, 0, 6), (2, 2, 0, 4), (2, 0, 0, 6), (3, 0, 0, 6),
+            ],
+        },
+        Bc6hMode {
+            id: 0x03,
+            regions: 1,
+            transformed: false,
+            endpoint_bits: 10,
+            delta_bits: [10, 10, 10],
+            fields: &[(0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 10), (1, 1, 0, 10), (1, 2, 0, 10)],
+        },
+        Bc6hMode {
+            id: 0x07,
+            regions: 1,
+            transformed: true,
+            endpoint_bits: 11,
+            delta_bits: [9, 9, 9],
+            fields: &[(0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 9), (0, 0, 10, 1), (1, 1, 0, 9), (0, 1, 10, 1), (1, 2, 0, 9), (0, 2, 10, 1)],
+        },
+        // the high bits of the first endpoint are stored in reverse in the last two modes
+        Bc6hMode {
+            id: 0x0B,
+            regions: 1,
+            transformed: true,
+            endpoint_bits: 12,
+            delta_bits: [8, 8, 8],
+            fields: &[
+                (0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 8), (0, 0, 11, 1), (0, 0, 10, 1), (1, 1, 0, 8), (0, 1, 11, 1), (0, 1, 10, 1), (1, 2, 0, 8), (0, 2, 11, 1), (0, 2, 10, 1),
+            ],
+        },
+        Bc6hMode {
+            id: 0x0F,
+            regions: 1,
+            transformed: true,
+            endpoint_bits: 16,
+            delta_bits: [4, 4, 4],
+            fields: &[
+                (0, 0, 0, 10), (0, 1, 0, 10), (0, 2, 0, 10), (1, 0, 0, 4), (0, 0, 15, 1), (0, 0, 14, 1), (0, 0, 13, 1), (0, 0, 12, 1), (0, 0, 11, 1), (0, 0, 10, 1), (1, 1, 0, 4),
+                (0, 1, 15, 1), (0, 1, 14, 1), (0, 1, 13, 1), (0, 1, 12, 1), (0, 1, 11, 1), (0, 1, 10, 1), (1, 2, 0, 4), (0, 2, 15, 1), (0, 2, 14, 1), (0, 2, 13, 1), (0, 2, 12, 1),
+                (0, 2, 11, 1), (0, 2, 10, 1),
+            ],
+        },
+    ];
+
+    // rgb half floats; the reserved modes decode to black
+    fn bc6h_block(block: &[u8], signed: bool) -> [[u16; 3]; 16] {
+        let mut bits = Bits::new(block);
+        let mut id = bits.read(2);
+        if id > 1 {
+            id |= bits.read(3) << 2;
+        }
+
+        let mode = match BC6H_MODES.iter().find(|mode| mode.id == id) {
+            Some(mode) => mode,
+            None => return [[0; 3]; 16],
+        };
+
+        let mut endpoints = [[0i32; 3]; 4];
+        for &(endpoint, channel, low, count) in mode.fields {
+            endpoints[endpoint as usize][channel as usize] |= (bits.read(count as u32) << low) as i32;
+        }
+        let partition = if mode.regions == 2 { bits.read(5) as usize } else { 0 };
+
+        let count = mode.regions * 2;
+        let mask = (1 << mode.endpoint_bits) - 1;
+        for channel in 0..3 {
+            if signed {
+                endpoints[0][channel] = sign_extend(endpoints[0][channel], mode.endpoint_bits);
+            }
+
+            let base = endpoints[0][channel];
+            for endpoint in &mut endpoints[1..count] {
+                if mode.transformed {
+                    endpoint[channel] = (base + sign_extend(endpoint[channel], mode.delta_bits[channel])) & mask;
+                }
+                if signed {
+                    endpoint[channel] = sign_extend(endpoint[channel], mode.endpoint_bits);
+                }
+            }
+        }
+
+        let endpoints = endpoints.map(|endpoint| endpoint.map(|value| unquantize(value, mode.endpoint_bits, signed)));
+        let index_bits = if mode.regions == 2 { 3 } else { 4 };
+        let indices = bits.indices(index_bits, |texel| is_anchor(mode.regions, partition, texel));
+
+        array::from_fn(|texel| {
+            let region = subset(mode.regions, partition, texel);
+            let (from, to) = (endpoints[region * 2], endpoints[region * 2 + 1]);
+            let weight = weights(index_bits)[indices[texel]] as i32;
+
+            array::from_fn(|channel| finish_half((from[channel] * (64 - weight) + to[channel] * weight + 32) >> 6, signed))
+        })
+    }
+
+    fn sign_extend(value: i32, bits: u32) -> i32 {
+        let shift = 32 - bits;
+        (value << shift) >> shift
+    }
+
+    // spreads an endpoint over the full 16 bit range before interpolating
+    fn unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+        if !signed {
+            if bits >= 15 || value == 0 {
+                value
+            } else if value == (1 << bits) - 1 {
+                0xFFFF
+            } else {
+                ((value << 16) + 0x8000) >> bits
+            }
+        } else if bits >= 16 || value == 0 {
+            value
+        } else {
+            let magnitude = value.abs();
+            let unquantized = if magnitude >= (1 << (bits - 1)) - 1 { 0x7FFF } else { ((magnitude << 15) + 0x4000) >> (bits - 1) };
+
+            if value < 0 {
+                -unquantized
+            } else {
+                unquantized
+            }
+        }
+    }
+
+    // scales the interpolated value to the half float bit pattern, 31/64 of the range keeps it below infinity
+    fn finish_half(value: i32, signed: bool) -> u16 {
+        if !signed {
+            ((value * 31) >> 6) as u16
+        } else if value < 0 {
+            (((-value * 31) >> 5) | 0x8000) as u16
+        } else {
+            ((value * 31) >> 5) as u16
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // packs fields from the lowest bit up, the way Bits reads them
+        #[derive(Default)]
+        struct Writer {
+            value: u128,
+            position: u32,
+        }
+
+        impl Writer {
+            fn write(&mut self, value: u32, count: u32) -> &mut Self {
+                self.value |= ((value as u128) & ((1u128 << count) - 1)) << self.position;
+                self.position += count;
+                self
+            }
+
+            fn block(&self) -> [u8; 16] {
+                assert_eq!(self.position, 128);
+                self.value.to_le_bytes()
+            }
+        }
+
+        fn halves(block: &[u8], signed: bool) -> Vec<[u16; 4]> {
+            let format = if signed { Format::Bc6hSigned } else { Format::Bc6h };
+            let pixels = decompress_blocks(format, 4, 4, block).unwrap();
+            pixels.chunks_exact(8).map(|pixel| array::from_fn(|i| u16::from_le_bytes([pixel[2 * i], pixel[2 * i + 1]]))).collect()
+        }
+
+        #[test]
+        fn anchors_lie_in_their_subsets() {
+            for partition in 0..64 {
+                assert_eq!(subset(2, partition, 0), 0);
+                assert_eq!(subset(2, partition, ANCHORS_2[partition] as usize), 1);
+
+                assert_eq!(subset(3, partition, 0), 0);
+                assert_eq!(subset(3, partition, ANCHORS_3[partition][0] as usize), 1);
+                assert_eq!(subset(3, partition, ANCHORS_3[partition][1] as usize), 2);
+                assert!((0..16).all(|texel| subset(3, partition, texel) < 3));
+            }
+        }
+
+        #[test]
+        fn bc7_modes_fill_the_block() {
+            for (number, mode) in BC7_MODES.iter().enumerate() {
+                let endpoints = mode.subsets as u32 * 2;
+                let p_bits = if mode.endpoint_p_bits { endpoints } else if mode.shared_p_bits { mode.subsets as u32 } else { 0 };
+                let indices = 16 * mode.index_bits - mode.subsets as u32;
+                let secondary = if mode.secondary_index_bits > 0 { 16 * mode.secondary_index_bits - 1 } else { 0 };
+
+                let total = number as u32 + 1 + mode.partition_bits + mode.rotation_bits + mode.index_selection_bits + endpoints * (3 * mode.color_bits + mode.alpha_bits) + p_bits + indices + secondary;
+                assert_eq!(total, 128, "mode {}", number);
+            }
+        }
+
+        #[test]
+        fn bc6h_layouts_cover_every_endpoint_bit_once() {
+            for mode in &BC6H_MODES {
+                let mut covered = [[0u32; 3]; 4];
+                let mut header = if mode.id > 1 { 5 } else { 2 };
+
+                for &(endpoint, channel, low, count) in mode.fields {
+                    let bits = ((1u32 << count) - 1) << low;
+                    assert_eq!(covered[endpoint as usize][channel as usize] & bits, 0, "mode {:#x}", mode.id);
+                    covered[endpoint as usize][channel as usize] |= bits;
+                    header += count as u32;
+                }
+
+                for (endpoint, channels) in covered.iter().enumerate().take(mode.regions * 2) {
+                    for (channel, &bits) in channels.iter().enumerate() {
+                        let width = if endpoint == 0 || !mode.transformed { mode.endpoint_bits } else { mode.delta_bits[channel] };
+                        assert_eq!(bits, (1 << width) - 1, "mode {:#x} endpoint {} channel {}", mode.id, endpoint, channel);
+                    }
+                }
+
+                let (partition, indices) = if mode.regions == 2 { (5, 46) } else { (0, 63) };
+                assert_eq!(header + partition + indices, 128, "mode {:#x}", mode.id);
+            }
+        }
+
+        #[test]
+        fn bc7_mode_6_interpolates_rgba() {
+            // black to white with p-bits 0 and 1, each texel using its own index
+            let mut writer = Writer::default();
+            writer.write(1 << 6, 7);
+            for _ in 0..4 {
+                writer.write(0, 7).write(127, 7);
+            }
+            writer.write(0, 1).write(1, 1);
+            writer.write(0, 3);
+            for index in 1..16 {
+                writer.write(index, 4);
+            }
+
+            let pixels = decompress_blocks(Format::Bc7, 4, 4, &writer.block()).unwrap();
+            for (texel, pixel) in pixels.chunks_exact(4).enumerate() {
+                let value = ((255 * WEIGHTS_4[texel] + 32) >> 6) as u8;
+                assert_eq!(pixel, [value; 4]);
+            }
+        }
+
+        #[test]
+        fn bc7_mode_4_rotates_and_selects_indices() {
+            // red 31 to 0, alpha 0 to 63, rotation 1 swaps red and alpha, selection 1 drives the color with the three bit indices
+            let mut writer = Writer::default();
+            writer.write(1 << 4, 5).write(1, 2).write(1, 1);
+            writer.write(31, 5).write(0, 5).write(0, 5).write(0, 5).write(0, 5).write(0, 5);
+            writer.write(0, 6).write(63, 6);
+            writer.write(0, 1);
+            for _ in 1..16 {
+                writer.write(3, 2);
+            }
+            writer.write(0, 2);
+            for texel in 1..16 {
+                writer.write(if texel == 2 { 7 } else { 0 }, 3);
+            }
+
+            // texel 0 is red with alpha 0 before the swap, texel 1 opaque red, texel 2 black with alpha 255
+            let pixels = decompress_blocks(Format::Bc7, 4, 4, &writer.block()).unwrap();
+            assert_eq!(&pixels[..4], [0, 0, 0, 255]);
+            assert_eq!(&pixels[4..8], [255, 0, 0, 255]);
+            assert_eq!(&pixels[8..12], [255, 0, 0, 0]);
+
+            // an empty mode byte is transparent black
+            assert_eq!(decompress_blocks(Format::Bc7, 4, 4, &[0; 16]).unwrap(), vec![0; 64]);
+        }
+
+        #[test]
+        fn bc6h_single_region_unquantizes_to_halves() {
+            let mut writer = Writer::default();
+            writer.write(0x03, 5);
+            for value in [0, 0, 0, 1023, 1023, 1023] {
+                writer.write(value, 10);
+            }
+            writer.write(0, 3);
+            for index in 1..16 {
+                writer.write(index, 4);
+            }
+
+            let texels = halves(&writer.block(), false);
+            assert_eq!(texels[0], [0, 0, 0, HALF_ONE]);
+            assert_eq!(texels[15], [0x7BFF, 0x7BFF, 0x7BFF, HALF_ONE]);
+            let middle = ((((0xFFFF * 34 + 32) >> 6) * 31) >> 6) as u16;
+            assert_eq!(texels[8], [middle, middle, middle, HALF_ONE]);
+        }
+
+        #[test]
+        fn bc6h_deltas_are_signed() {
+            // mode 0x00, w = 100 in every channel, every delta -1, partition 0 splits the block in columns
+            let mut writer = Writer::default();
+            writer.write(0, 2);
+            let mut endpoints = [[0u32; 3]; 4];
+            endpoints[0] = [100; 3];
+            for endpoint in &mut endpoints[1..] {
+                *endpoint = [31; 3];
+            }
+            for &(endpoint, channel, low, count) in BC6H_MODES[0].fields {
+                writer.write(endpoints[endpoint as usize][channel as usize] >> low, count as u32);
+            }
+            writer.write(0, 5);
+            for texel in 0..16 {
+                let anchor = texel == 0 || texel == 15;
+                writer.write(if texel % 4 == 1 || texel % 4 == 3 { 7 } else { 0 }, if anchor { 2 } else { 3 });
+            }
+
+            let texels = halves(&writer.block(), false);
+            let half = |value: i32| (((((value << 16) + 0x8000) >> 10) * 31) >> 6) as u16;
+            assert_eq!(texels[0][..3], [half(100); 3]);
+            assert_eq!(texels[1][..3], [half(99); 3]);
+            assert_eq!(texels[2][..3], [half(99); 3]);
+            assert_eq!(texels[3][..3], [half(99); 3]);
+
+            // the same bits read as signed put the first endpoint at 100 and keep the halves positive
+            assert_eq!(halves(&writer.block(), true)[0][..3], [(((((100 << 15) + 0x4000) >> 9) * 31) >> 5) as u16; 3]);
+        }
+    }
+}
@@ -0,0 +1,624 @@
+pub mod builder {
+    use crate::{Vec2, Vec3};
+    use std::{collections::HashMap, f32::consts::PI};
+
+    // squared sine of the smallest corner angle a triangle may have before it counts as collapsed, and the smallest
+    // uv determinant a tangent is solved from
+    const EPSILON: f32 = 1e-12;
+
+    // only uses vector operations glam and gfx-maths share, so the file can be copied into any sample
+    #[derive(Clone, Copy, Debug)]
+    pub struct MeshVertex {
+        pub position: Vec3,
+        pub normal: Vec3,
+        pub tex_coords: Vec2,
+        pub tangent: Vec3,
+        pub bitangent: Vec3,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Attribute {
+        Position,
+        Normal,
+        TexCoords,
+        Tangent,
+        Bitangent,
+    }
+
+    impl Attribute {
+        pub fn components(self) -> usize {
+            match self {
+                Attribute::TexCoords => 2,
+                _ => 3,
+            }
+        }
+    }
+
+    // the attributes back to back per vertex in the given order, for samples that upload plain float arrays
+    pub fn interleave(vertices: &[MeshVertex], attributes: &[Attribute]) -> Vec<f32> {
+        let stride: usize = attributes.iter().map(|attribute| attribute.components()).sum();
+        let mut data = Vec::with_capacity(vertices.len() * stride);
+
+        for vertex in vertices {
+            for attribute in attributes {
+                match attribute {
+                    Attribute::Position => data.extend([vertex.position.x, vertex.position.y, vertex.position.z]),
+                    Attribute::Normal => data.extend([vertex.normal.x, vertex.normal.y, vertex.normal.z]),
+                    Attribute::TexCoords => data.extend([vertex.tex_coords.x, vertex.tex_coords.y]),
+                    Attribute::Tangent => data.extend([vertex.tangent.x, vertex.tangent.y, vertex.tangent.z]),
+                    Attribute::Bitangent => data.extend([vertex.bitangent.x, vertex.bitangent.y, vertex.bitangent.z]),
+                }
+            }
+        }
+
+        data
+    }
+
+    // every triangle with its own three vertices, for samples that draw with DrawArrays
+    pub fn unindex(vertices: &[MeshVertex], indices: &[u32]) -> Vec<MeshVertex> {
+        indices.iter().map(|&index| vertices[index as usize]).collect()
+    }
+
+    // vertices added without a normal or tangent keep them zeroed until build fills them in
+    #[derive(Default)]
+    pub struct MeshBuilder {
+        pub vertices: Vec<MeshVertex>,
+        pub indices: Vec<u32>,
+    }
+
+    impl MeshBuilder {
+        pub fn new() -> Self {
+            MeshBuilder::default()
+        }
+
+        pub fn vertex(&mut self, position: Vec3, tex_coords: Vec2) -> u32 {
+            self.vertex_with_normal(position, zero(), tex_coords)
+        }
+
+        pub fn vertex_with_normal(&mut self, position: Vec3, normal: Vec3, tex_coords: Vec2) -> u32 {
+            self.vertices.push(MeshVertex {
+                position,
+                normal,
+                tex_coords,
+                tangent: zero(),
+                bitangent: zero(),
+            });
+
+            self.vertices.len() as u32 - 1
+        }
+
+        pub fn triangle(&mut self, a: u32, b: u32, c: u32) {
+            self.indices.extend([a, b, c]);
+        }
+
+        pub fn quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+            self.triangle(a, b, c);
+            self.triangle(a, c, d);
+        }
+
+        pub fn append(&mut self, other: MeshBuilder) {
+            let first = self.vertices.len() as u32;
+
+            self.vertices.extend(other.vertices);
+            self.indices.extend(other.indices.iter().map(|index| index + first));
+        }
+
+        // area weighted face normals for every vertex that came without one
+        pub fn compute_normals(&mut self) {
+            let missing: Vec<bool> = self.vertices.iter().map(|vertex| is_zero(vertex.normal)).collect();
+
+            for triangle in self.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize].position);
+                let face = (b - a).cross(c - a);
+
+                for &index in triangle {
+                    if missing[index as usize] {
+                        self.vertices[index as usize].normal += face;
+                    }
+                }
+            }
+
+            for (vertex, _) in self.vertices.iter_mut().zip(&missing).filter(|(_, missing)| **missing) {
+                vertex.normal = normalized_or(vertex.normal, Vec3::new(0.0, 1.0, 0.0));
+            }
+        }
+
+        // tangents follow the texture u direction, bitangents keep the handedness of v
+        pub fn compute_tangents(&mut self) {
+            let missing: Vec<bool> = self.vertices.iter().map(|vertex| is_zero(vertex.tangent)).collect();
+
+            for triangle in self.indices.chunks_exact(3) {
+                let [v0, v1, v2] = [0, 1, 2].map(|i| &self.vertices[triangle[i] as usize]);
+
+                let (edge1, edge2) = (v1.position - v0.position, v2.position - v0.position);
+                let (delta1, delta2) = (v1.tex_coords - v0.tex_coords, v2.tex_coords - v0.tex_coords);
+                let determinant = delta1.x * delta2.y - delta2.x * delta1.y;
+
+                if determinant.abs() < EPSILON {
+                    continue;
+                }
+
+                let tangent = (edge1 * delta2.y - edge2 * delta1.y) / determinant;
+                let bitangent = (edge2 * delta1.x - edge1 * delta2.x) / determinant;
+
+                for &index in triangle {
+                    if missing[index as usize] {
+                        self.vertices[index as usize].tangent += tangent;
+                        self.vertices[index as usize].bitangent += bitangent;
+                    }
+                }
+            }
+
+            for (vertex, _) in self.vertices.iter_mut().zip(&missing).filter(|(_, missing)| **missing) {
+                let normal = vertex.normal;
+                let fallback = if normal.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+
+                let tangent = normalized_or(vertex.tangent - normal * normal.dot(vertex.tangent), normalized_or(fallback.cross(normal), fallback));
+                let bitangent = normal.cross(tangent);
+
+                vertex.tangent = tangent;
+                vertex.bitangent = if bitangent.dot(vertex.bitangent) < 0.0 { -bitangent } else { bitangent };
+            }
+        }
+
+        pub fn build(mut self) -> (Vec<MeshVertex>, Vec<u32>) {
+            self.compute_normals();
+            self.compute_tangents();
+
+            (self.vertices, self.indices)
+        }
+
+        // a flat square on the xz plane facing +y
+        pub fn plane(size: f32, subdivisions: u32) -> Self {
+            let mut builder = MeshBuilder::new();
+            let subdivisions = subdivisions.max(1);
+
+            builder.surface(subdivisions, subdivisions, |u, v| (Vec3::new((u - 0.5) * size, 0.0, (v - 0.5) * size), Vec3::new(0.0, 1.0, 0.0)));
+
+            builder
+        }
+
+        // a single quad on the xy plane facing +z with v pointing up, rectangle(2.0, 2.0) covers the screen in ndc
+        pub fn rectangle(width: f32, height: f32) -> Self {
+            let mut builder = MeshBuilder::new();
+
+            builder.surface(1, 1, |u, v| (Vec3::new((u - 0.5) * width, (v - 0.5) * height, 0.0), Vec3::new(0.0, 0.0, 1.0)));
+
+            builder
+        }
+
+        // every face textured upright as seen from outside, v points to -z on the top face and to +z on the bottom one
+        pub fn cube(size: f32) -> Self {
+            let mut builder = MeshBuilder::new();
+
+            for axis in 0..3 {
+                for sign in [-1.0, 1.0] {
+                    let normal = axis_vector(axis, sign);
+                    let v_axis = if axis == 1 { axis_vector(2, -sign) } else { axis_vector(1, 1.0) };
+                    let u_axis = v_axis.cross(normal);
+
+                    builder.surface(1, 1, |u, v| ((normal * 0.5 + u_axis * (u - 0.5) + v_axis * (v - 0.5)) * size, normal));
+                }
+            }
+
+            builder
+        }
+
+        pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32) -> Self {
+            let mut builder = MeshBuilder::new();
+
+            builder.surface(sectors.max(3), stacks.max(2), |u, v| {
+                let normal = spherical(u * 2.0 * PI, v * PI);
+                (normal * radius, normal)
+            });
+
+            builder
+        }
+
+        // an icosahedron with every face split in four per subdivision, evenly spread unlike the uv sphere
+        pub fn icosphere(radius: f32, subdivisions: u32) -> Self {
+            let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+            let mut points: Vec<Vec3> = [
+                (-1.0, t, 0.0),
+                (1.0, t, 0.0),
+                (-1.0, -t, 0.0),
+                (1.0, -t, 0.0),
+                (0.0, -1.0, t),
+                (0.0, 1.0, t),
+                (0.0, -1.0, -t),
+                (0.0, 1.0, -t),
+                (t, 0.0, -1.0),
+                (t, 0.0, 1.0),
+                (-t, 0.0, -1.0),
+                (-t, 0.0, 1.0),
+            ]
+            .iter()
+            .map(|&(x, y, z)| normalized_or(Vec3::new(x, y, z), zero()))
+            .collect();
+
+            #[rustfmt::skip]
+            let mut faces: Vec<[u32; 3]> = vec![
+                [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+                [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+                [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+                [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+            ];
+
+            for _ in 0..subdivisions {
+                let mut midpoints = HashMap::new();
+                let mut midpoint = |a: u32, b: u32| -> u32 {
+                    *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                        points.push(normalized_or((points[a as usize] + points[b as usize]) * 0.5, zero()));
+                        points.len() as u32 - 1
+                    })
+                };
+
+                faces = faces
+                    .iter()
+                    .flat_map(|&[a, b, c]| {
+                        let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                        [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                    })
+                    .collect();
+            }
+
+            // the u of a point is ambiguous on the seam at -x and at the poles, so faces there get their own copies of those
+            // points with u taken from the rest of the face
+            let mut builder = MeshBuilder::new();
+            let mut copies = HashMap::new();
+
+            for face in faces {
+                let mut tex_coords = face.map(|index| {
+                    let point = points[index as usize];
+                    Vec2::new(point.z.atan2(point.x) / (2.0 * PI) + 0.5, point.y.clamp(-1.0, 1.0).acos() / PI)
+                });
+                let pole = face.map(|index| {
+                    let point = points[index as usize];
+                    point.x * point.x + point.z * point.z < 1e-6
+                });
+
+                let around: Vec<f32> = (0..3).filter(|&i| !pole[i]).map(|i| tex_coords[i].x).collect();
+                let wraps = around.iter().fold(0.0_f32, |max, &u| max.max(u)) - around.iter().fold(1.0_f32, |min, &u| min.min(u)) > 0.5;
+
+                for i in 0..3 {
+                    if wraps && !pole[i] && tex_coords[i].x < 0.5 {
+                        tex_coords[i].x += 1.0;
+                    }
+                }
+                for i in 0..3 {
+                    if pole[i] {
+                        let others: Vec<f32> = (0..3).filter(|&j| !pole[j]).map(|j| tex_coords[j].x).collect();
+                        tex_coords[i].x = others.iter().sum::<f32>() / others.len().max(1) as f32;
+                    }
+                }
+
+                let [a, b, c] = [0, 1, 2].map(|i| {
+                    let point = points[face[i] as usize];
+                    *copies
+                        .entry((face[i], tex_coords[i].x.to_bits()))
+                        .or_insert_with(|| builder.vertex_with_normal(point * radius, point, tex_coords[i]))
+                });
+                builder.outward_triangle(a, b, c);
+            }
+
+            builder
+        }
+
+        // centered on the origin along y, with both ends capped
+        pub fn cylinder(radius: f32, height: f32, segments: u32) -> Self {
+            let mut builder = MeshBuilder::new();
+            let segments = segments.max(3);
+
+            builder.surface(segments, 1, |u, v| {
+                let normal = spherical(u * 2.0 * PI, PI / 2.0);
+                (normal * radius + Vec3::new(0.0, (v - 0.5) * height, 0.0), normal)
+            });
+            builder.disk(Vec3::new(0.0, height / 2.0, 0.0), 1.0, radius, segments);
+            builder.disk(Vec3::new(0.0, -height / 2.0, 0.0), -1.0, radius, segments);
+
+            builder
+        }
+
+        // base centered at -height / 2, apex at +height / 2
+        pub fn cone(radius: f32, height: f32, segments: u32) -> Self {
+            let mut builder = MeshBuilder::new();
+            let segments = segments.max(3);
+
+            builder.surface(segments, 1, |u, v| {
+                let around = spherical(u * 2.0 * PI, PI / 2.0);
+                let normal = normalized_or(around * height + Vec3::new(0.0, radius, 0.0), around);
+                (around * radius * (1.0 - v) + Vec3::new(0.0, (v - 0.5) * height, 0.0), normal)
+            });
+            builder.disk(Vec3::new(0.0, -height / 2.0, 0.0), -1.0, radius, segments);
+
+            builder
+        }
+
+        // lies in the xz plane
+        pub fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Self {
+            let mut builder = MeshBuilder::new();
+
+            builder.surface(major_segments.max(3), minor_segments.max(3), |u, v| {
+                let (theta, phi) = (u * 2.0 * PI, v * 2.0 * PI);
+                let center = Vec3::new(theta.cos(), 0.0, theta.sin()) * major_radius;
+                let normal = Vec3::new(phi.cos() * theta.cos(), phi.sin(), phi.cos() * theta.sin());
+                (center + normal * minor_radius, normal)
+            });
+
+            builder
+        }
+
+        // a cylinder of `height` between two hemispheres, so the total height is height + 2 * radius
+        pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Self {
+            let mut builder = MeshBuilder::new();
+            let rings = rings.max(1);
+            let rows = 2 * rings + 1;
+
+            builder.surface(segments.max(3), rows, |u, v| {
+                let row = v * rows as f32;
+                let (phi, offset) = if row <= rings as f32 {
+                    (row / rings as f32 * PI / 2.0, height / 2.0)
+                } else {
+                    ((row - 1.0) / rings as f32 * PI / 2.0, -height / 2.0)
+                };
+
+                let normal = spherical(u * 2.0 * PI, phi);
+                (normal * radius + Vec3::new(0.0, offset, 0.0), normal)
+            });
+
+            builder
+        }
+
+        // a (columns + 1) x (rows + 1) grid of `point(u, v)` samples, which return a position and its outward normal
+        fn surface(&mut self, columns: u32, rows: u32, point: impl Fn(f32, f32) -> (Vec3, Vec3)) {
+            let first = self.vertices.len() as u32;
+
+            for row in 0..=rows {
+                for column in 0..=columns {
+                    let (u, v) = (column as f32 / columns as f32, row as f32 / rows as f32);
+                    let (position, normal) = point(u, v);
+                    self.vertex_with_normal(position, normal, Vec2::new(u, v));
+                }
+            }
+
+            for row in 0..rows {
+                for column in 0..columns {
+                    let a = first + row * (columns + 1) + column;
+                    let (b, c, d) = (a + 1, a + columns + 2, a + columns + 1);
+
+                    self.outward_triangle(a, b, c);
+                    self.outward_triangle(a, c, d);
+                }
+            }
+        }
+
+        // a fan in the plane y = center.y facing +y or -y
+        fn disk(&mut self, center: Vec3, facing: f32, radius: f32, segments: u32) {
+            let normal = Vec3::new(0.0, facing, 0.0);
+            let middle = self.vertex_with_normal(center, normal, Vec2::new(0.5, 0.5));
+
+            for segment in 0..=segments {
+                let theta = segment as f32 / segments as f32 * 2.0 * PI;
+                let (cos, sin) = (theta.cos(), theta.sin());
+                self.vertex_with_normal(center + Vec3::new(cos, 0.0, sin) * radius, normal, Vec2::new((cos + 1.0) / 2.0, (sin + 1.0) / 2.0));
+            }
+
+            for segment in 0..segments {
+                self.outward_triangle(middle, middle + 1 + segment, middle + 2 + segment);
+            }
+        }
+
+        // winds the triangle counter-clockwise around its vertex normals and drops it if it collapsed, which is judged
+        // against the edge lengths so that small meshes keep their triangles
+        fn outward_triangle(&mut self, a: u32, b: u32, c: u32) {
+            let [va, vb, vc] = [a, b, c].map(|index| &self.vertices[index as usize]);
+            let (edge1, edge2) = (vb.position - va.position, vc.position - va.position);
+            let face = edge1.cross(edge2);
+
+            if face.dot(face) <= EPSILON * edge1.dot(edge1) * edge2.dot(edge2) {
+                return;
+            }
+
+            if face.dot(va.normal + vb.normal + vc.normal) < 0.0 {
+                self.triangle(a, c, b);
+            } else {
+                self.triangle(a, b, c);
+            }
+        }
+    }
+
+    fn axis_vector(axis: usize, sign: f32) -> Vec3 {
+        match axis {
+            0 => Vec3::new(sign, 0.0, 0.0),
+            1 => Vec3::new(0.0, sign, 0.0),
+            _ => Vec3::new(0.0, 0.0, sign),
+        }
+    }
+
+    // phi is measured from +y
+    fn spherical(theta: f32, phi: f32) -> Vec3 {
+        Vec3::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin())
+    }
+
+    fn zero() -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn is_zero(vector: Vec3) -> bool {
+        vector.dot(vector) == 0.0
+    }
+
+    fn normalized_or(vector: Vec3, fallback: Vec3) -> Vec3 {
+        let length = vector.dot(vector).sqrt();
+        if length == 0.0 || !length.is_finite() {
+            return fallback;
+        }
+
+        vector / length
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn primitives() -> Vec<(&'static str, MeshBuilder)> {
+            vec![
+                ("plane", MeshBuilder::plane(2.0, 4)),
+                ("rectangle", MeshBuilder::rectangle(2.0, 1.0)),
+                ("cube", MeshBuilder::cube(2.0)),
+                ("uv sphere", MeshBuilder::uv_sphere(1.0, 16, 8)),
+                ("icosphere", MeshBuilder::icosphere(1.0, 2)),
+                ("cylinder", MeshBuilder::cylinder(0.5, 2.0, 12)),
+                ("cone", MeshBuilder::cone(0.5, 1.0, 12)),
+                ("torus", MeshBuilder::torus(1.0, 0.25, 16, 8)),
+                ("capsule", MeshBuilder::capsule(0.5, 1.0, 12, 4)),
+            ]
+        }
+
+        fn length(vector: Vec3) -> f32 {
+            vector.dot(vector).sqrt()
+        }
+
+        fn face_normal(vertices: &[MeshVertex], triangle: &[u32]) -> Vec3 {
+            let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+            (b - a).cross(c - a)
+        }
+
+        #[test]
+        fn indices_stay_in_range() {
+            for (name, builder) in primitives() {
+                let (vertices, indices) = builder.build();
+
+                assert!(!indices.is_empty() && indices.len() % 3 == 0, "{name}");
+                assert!(indices.iter().all(|&index| (index as usize) < vertices.len()), "{name}");
+            }
+        }
+
+        #[test]
+        fn normals_are_unit_and_face_outward() {
+            for (name, builder) in primitives() {
+                let convex = !matches!(name, "plane" | "rectangle" | "torus");
+                let (vertices, _) = builder.build();
+
+                for vertex in &vertices {
+                    assert!((length(vertex.normal) - 1.0).abs() < 1e-4, "{name}");
+                    if convex {
+                        assert!(vertex.normal.dot(vertex.position) > 0.0, "{name} {:?}", vertex.position);
+                    }
+                }
+            }
+
+            // on the torus outward means away from the ring through the middle of the tube
+            let (vertices, _) = MeshBuilder::torus(1.0, 0.25, 16, 8).build();
+            for vertex in &vertices {
+                let p = vertex.position;
+                let ring = Vec3::new(p.x, 0.0, p.z) / length(Vec3::new(p.x, 0.0, p.z));
+                assert!(vertex.normal.dot(p - ring) > 0.0);
+            }
+        }
+
+        #[test]
+        fn triangles_wind_counter_clockwise_around_their_normals() {
+            for (name, builder) in primitives() {
+                let (vertices, indices) = builder.build();
+
+                for triangle in indices.chunks_exact(3) {
+                    let face = face_normal(&vertices, triangle);
+                    for &index in triangle {
+                        assert!(face.dot(vertices[index as usize].normal) > 0.0, "{name}");
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn tangents_follow_u_and_bitangents_follow_v() {
+            for (name, builder) in primitives() {
+                let (vertices, indices) = builder.build();
+
+                for vertex in &vertices {
+                    assert!((length(vertex.tangent) - 1.0).abs() < 1e-4, "{name}");
+                    assert!(vertex.tangent.dot(vertex.normal).abs() < 1e-4, "{name}");
+                    assert!(vertex.bitangent.dot(vertex.normal).abs() < 1e-4, "{name}");
+                }
+
+                for triangle in indices.chunks_exact(3) {
+                    let [v0, v1, v2] = [0, 1, 2].map(|i| &vertices[triangle[i] as usize]);
+                    let (edge1, edge2) = (v1.position - v0.position, v2.position - v0.position);
+                    let (delta1, delta2) = (v1.tex_coords - v0.tex_coords, v2.tex_coords - v0.tex_coords);
+                    let determinant = delta1.x * delta2.y - delta2.x * delta1.y;
+                    if determinant.abs() < 1e-6 {
+                        continue;
+                    }
+
+                    let tangent = (edge1 * delta2.y - edge2 * delta1.y) / determinant;
+                    let bitangent = (edge2 * delta1.x - edge1 * delta2.x) / determinant;
+                    for vertex in [v0, v1, v2] {
+                        assert!(vertex.tangent.dot(tangent) > 0.0, "{name}");
+                        assert!(vertex.bitangent.dot(bitangent) > 0.0, "{name}");
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn cube_faces_are_upright() {
+            let (vertices, _) = MeshBuilder::cube(2.0).build();
+
+            for vertex in &vertices {
+                let up = if vertex.normal.y > 0.5 {
+                    Vec3::new(0.0, 0.0, -1.0)
+                } else if vertex.normal.y < -0.5 {
+                    Vec3::new(0.0, 0.0, 1.0)
+                } else {
+                    Vec3::new(0.0, 1.0, 0.0)
+                };
+
+                assert!(vertex.bitangent.dot(up) > 0.99, "{:?}", vertex.normal);
+                assert!(vertex.tangent.cross(vertex.bitangent).dot(vertex.normal) > 0.99, "{:?}", vertex.normal);
+            }
+        }
+
+        #[test]
+        fn icosphere_seam_is_split() {
+            let (vertices, indices) = MeshBuilder::icosphere(1.0, 3).build();
+
+            for triangle in indices.chunks_exact(3) {
+                let u = [0, 1, 2].map(|i| vertices[triangle[i] as usize].tex_coords.x);
+                let spread = u.iter().fold(f32::MIN, |max, &u| max.max(u)) - u.iter().fold(f32::MAX, |min, &u| min.min(u));
+                assert!(spread < 0.2, "{u:?}");
+            }
+
+            // the seam and poles cost extra vertices but the surface stays closed
+            assert_eq!(indices.len() / 3, 20 * 4_usize.pow(3));
+            assert!(vertices.len() > 10 * 4_usize.pow(3) + 2);
+        }
+
+        #[test]
+        fn collapsed_triangles_are_dropped_but_small_ones_kept() {
+            let (sectors, stacks) = (16, 8);
+            let (_, indices) = MeshBuilder::uv_sphere(1.0, sectors, stacks).build();
+            assert_eq!(indices.len() / 3, (2 * sectors * stacks - 2 * sectors) as usize);
+
+            let (_, indices) = MeshBuilder::cube(1e-4).build();
+            assert_eq!(indices.len() / 3, 12);
+
+            let (_, indices) = MeshBuilder::icosphere(1e-3, 2).build();
+            assert_eq!(indices.len() / 3, 20 * 16);
+        }
+
+        #[test]
+        fn interleave_follows_the_attribute_order() {
+            let (vertices, indices) = MeshBuilder::rectangle(2.0, 2.0).build();
+            let data = interleave(&vertices, &[Attribute::Position, Attribute::TexCoords]);
+
+            assert_eq!(data.len(), vertices.len() * 5);
+            for (vertex, chunk) in vertices.iter().zip(data.chunks_exact(5)) {
+                assert_eq!(chunk, [vertex.position.x, vertex.position.y, vertex.position.z, vertex.tex_coords.x, vertex.tex_coords.y]);
+                assert_eq!([vertex.position.x, vertex.position.y], [vertex.tex_coords.x * 2.0 - 1.0, vertex.tex_coords.y * 2.0 - 1.0]);
+            }
+
+            assert_eq!(unindex(&vertices, &indices).len(), 6);
+        }
+    }
+}
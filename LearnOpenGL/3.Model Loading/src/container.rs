@@ -0,0 +1,581 @@
+pub mod container {
+    use crate::{gl, ImageData};
+    use std::{fs, path::Path};
+
+    const DDS_MAGIC: &[u8] = b"DDS ";
+    const KTX_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'1', b'1', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+    const KTX2_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+
+    const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+    const DDPF_FOURCC: u32 = 0x4;
+    const DDPF_RGB: u32 = 0x40;
+    const DDPF_LUMINANCE: u32 = 0x20000;
+    const DDSCAPS2_CUBEMAP: u32 = 0x200;
+    const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+    // the largest textures accepted, which keeps every size and offset below in range
+    const MAX_SIZE: u32 = 16384;
+    const MAX_LAYERS: u32 = 2048;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Format {
+        R8,
+        Rg8,
+        Rgb8,
+        Rgba8,
+        Bgra8,
+        R16,
+        Rg16,
+        Rgb16,
+        Rgba16,
+        Rgba16f,
+        R32f,
+        Rg32f,
+        Rgb32f,
+        Rgba32f,
+        Bc1,
+        Bc2,
+        Bc3,
+        Bc4,
+        Bc5,
+        Bc6h,
+        Bc6hSigned,
+        Bc7,
+    }
+
+    impl Format {
+        pub fn block_bytes(self) -> Option<usize> {
+            match self {
+                Format::Bc1 | Format::Bc4 => Some(8),
+                Format::Bc2 | Format::Bc3 | Format::Bc5 | Format::Bc6h | Format::Bc6hSigned | Format::Bc7 => Some(16),
+                _ => None,
+            }
+        }
+
+        // what decompress_blocks expands a block compressed format to, other formats stay as they are
+        pub fn decompressed(self) -> Format {
+            match self {
+                Format::Bc6h | Format::Bc6hSigned => Format::Rgba16f,
+                _ if self.block_bytes().is_some() => Format::Rgba8,
+                _ => self,
+            }
+        }
+
+        pub fn pixel_bytes(self) -> usize {
+            match self {
+                Format::R8 => 1,
+                Format::Rg8 | Format::R16 => 2,
+                Format::Rgb8 => 3,
+                Format::Rgba8 | Format::Bgra8 | Format::Rg16 | Format::R32f => 4,
+                Format::Rgb16 => 6,
+                Format::Rgba16 | Format::Rgba16f | Format::Rg32f => 8,
+                Format::Rgb32f => 12,
+                Format::Rgba32f => 16,
+                _ => 0,
+            }
+        }
+
+        // bytes of one face or layer at the given size, compressed formats round up to whole 4x4 blocks
+        pub fn image_size(self, width: i32, height: i32) -> usize {
+            let (width, height) = (width as usize, height as usize);
+
+            match self.block_bytes() {
+                Some(block_bytes) => width.div_ceil(4) * height.div_ceil(4) * block_bytes,
+                None => width * height * self.pixel_bytes(),
+            }
+        }
+    }
+
+    pub fn is_container(path: &str) -> bool {
+        let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or("");
+
+        ["dds", "ktx", "ktx2"].iter().any(|container| extension.eq_ignore_ascii_case(container))
+    }
+
+    pub fn read_container(path: &str) -> Result<ImageData, String> {
+        let bytes = fs::read(path).map_err(|error| error.to_string())?;
+
+        if bytes.starts_with(DDS_MAGIC) {
+            read_dds(&bytes)
+        } else if bytes.starts_with(&KTX_IDENTIFIER) {
+            read_ktx(&bytes)
+        } else if bytes.starts_with(&KTX2_IDENTIFIER) {
+            read_ktx2(&bytes)
+        } else {
+            Err("unknown container".to_string())
+        }
+    }
+
+    fn read_dds(bytes: &[u8]) -> Result<ImageData, String> {
+        slice(bytes, 0, 128)?;
+
+        let flags = u32_at(bytes, 8)?;
+        let height = u32_at(bytes, 12)?;
+        let width = u32_at(bytes, 16)?;
+        let level_count = if flags & DDSD_MIPMAPCOUNT != 0 { u32_at(bytes, 28)?.max(1) } else { 1 };
+        let pixel_flags = u32_at(bytes, 80)?;
+        let four_cc = slice(bytes, 84, 4)?;
+        let bit_count = u32_at(bytes, 88)?;
+        let red_mask = u32_at(bytes, 92)?;
+        let caps2 = u32_at(bytes, 112)?;
+
+        let mut faces = if caps2 & DDSCAPS2_CUBEMAP != 0 { 6 } else { 1 };
+        let mut layers = 1;
+        let mut offset = 128;
+
+        let (format, srgb) = if pixel_flags & DDPF_FOURCC != 0 {
+            match four_cc {
+                b"DXT1" => (Format::Bc1, false),
+                b"DXT2" | b"DXT3" => (Format::Bc2, false),
+                b"DXT4" | b"DXT5" => (Format::Bc3, false),
+                b"ATI1" | b"BC4U" => (Format::Bc4, false),
+                b"ATI2" | b"BC5U" => (Format::Bc5, false),
+                [113, 0, 0, 0] => (Format::Rgba16f, false),
+                [116, 0, 0, 0] => (Format::Rgba32f, false),
+                b"DX10" => {
+                    let format = dxgi_format(u32_at(bytes, 128)?)?;
+                    if u32_at(bytes, 136)? & DDS_RESOURCE_MISC_TEXTURECUBE != 0 {
+                        faces = 6;
+                    }
+                    layers = u32_at(bytes, 140)?.max(1);
+                    offset = 148;
+
+                    format
+                }
+                _ => return Err(format!("unsupported DDS format {}", String::from_utf8_lossy(four_cc))),
+            }
+        } else if pixel_flags & DDPF_RGB != 0 && bit_count == 32 {
+            match red_mask {
+                0x000000FF => (Format::Rgba8, false),
+                0x00FF0000 => (Format::Bgra8, false),
+                _ => return Err(format!("unsupported DDS channel masks {red_mask:#x}")),
+            }
+        } else if pixel_flags & DDPF_LUMINANCE != 0 && bit_count == 8 {
+            (Format::R8, false)
+        } else {
+            return Err(format!("unsupported DDS pixel format {pixel_flags:#x}"));
+        };
+
+        let extent = Extent::new(width, height, layers, faces, level_count)?;
+
+        // DDS stores the whole mip chain of a layer or face before the next one
+        let mut levels = vec![vec![]; extent.levels];
+        for _ in 0..extent.images() {
+            for (level, pixels) in levels.iter_mut().enumerate() {
+                let size = image_bytes(format, extent.level_width(level), extent.level_height(level), 1)?;
+                pixels.extend_from_slice(slice(bytes, offset, size)?);
+                offset += size;
+            }
+        }
+
+        Ok(extent.image(format, srgb, levels))
+    }
+
+    fn read_ktx(bytes: &[u8]) -> Result<ImageData, String> {
+        if u32_at(bytes, 12)? != 0x04030201 {
+            return Err("big endian KTX files are not supported".to_string());
+        }
+
+        let pixel_format = u32_at(bytes, 24)?;
+        let internal_format = u32_at(bytes, 28)?;
+        let width = u32_at(bytes, 36)?;
+        let height = u32_at(bytes, 40)?.max(1);
+        let depth = u32_at(bytes, 44)?;
+        let layers = u32_at(bytes, 48)?.max(1);
+        let faces = u32_at(bytes, 52)?.max(1);
+        let level_count = u32_at(bytes, 56)?.max(1);
+        let key_value_bytes = u32_at(bytes, 60)? as usize;
+
+        if depth > 1 {
+            return Err("3D textures are not supported".to_string());
+        }
+        let extent = Extent::new(width, height, layers, faces, level_count)?;
+
+        let (format, srgb) = match (internal_format, pixel_format) {
+            (gl::RGBA8 | gl::RGBA, gl::BGRA) => (Format::Bgra8, false),
+            (gl::SRGB8_ALPHA8, gl::BGRA) => (Format::Bgra8, true),
+            _ => gl_format(internal_format)?,
+        };
+
+        let mut offset = 64usize.checked_add(key_value_bytes).filter(|&offset| offset <= bytes.len()).ok_or_else(|| "file is truncated".to_string())?;
+        let mut levels = vec![];
+
+        for level in 0..extent.levels {
+            let level_width = extent.level_width(level);
+            let size = padded_size(format, level_width, extent.level_height(level))?;
+
+            // skip imageSize, every image size follows from the format
+            offset += 4;
+
+            let mut pixels = vec![];
+            for _ in 0..extent.images() {
+                pixels.extend(unpad_rows(slice(bytes, offset, size)?, format, level_width));
+                offset = align4(offset + size);
+            }
+
+            levels.push(pixels);
+        }
+
+        Ok(extent.image(format, srgb, levels))
+    }
+
+    fn read_ktx2(bytes: &[u8]) -> Result<ImageData, String> {
+        let vk_format = u32_at(bytes, 12)?;
+        let width = u32_at(bytes, 20)?;
+        let height = u32_at(bytes, 24)?.max(1);
+        let depth = u32_at(bytes, 28)?;
+        let layers = u32_at(bytes, 32)?.max(1);
+        let faces = u32_at(bytes, 36)?.max(1);
+        let level_count = u32_at(bytes, 40)?.max(1);
+        let supercompression = u32_at(bytes, 44)?;
+
+        if depth > 1 {
+            return Err("3D textures are not supported".to_string());
+        }
+        if supercompression != 0 {
+            return Err(format!("KTX2 supercompression scheme {supercompression} is not supported"));
+        }
+
+        let extent = Extent::new(width, height, layers, faces, level_count)?;
+        let (format, srgb) = vk_format_of(vk_format)?;
+
+        // the level index holds an offset and a length per level, each level stores its layers and faces back to back
+        let mut levels = vec![];
+        for level in 0..extent.levels {
+            let entry = 80 + level * 24;
+            let offset = usize::try_from(u64_at(bytes, entry)?).map_err(|_| "file is truncated".to_string())?;
+            let size = image_bytes(format, extent.level_width(level), extent.level_height(level), extent.images())?;
+
+            if u64_at(bytes, entry + 8)? < size as u64 {
+                return Err(format!("KTX2 level {level} is shorter than its images"));
+            }
+            levels.push(slice(bytes, offset, size)?.to_vec());
+        }
+
+        Ok(extent.image(format, srgb, levels))
+    }
+
+    // the validated size of a container image
+    struct Extent {
+        width: i32,
+        height: i32,
+        layers: i32,
+        faces: i32,
+        levels: usize,
+    }
+
+    impl Extent {
+        fn new(width: u32, height: u32, layers: u32, faces: u32, levels: u32) -> Result<Self, String> {
+            if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+                return Err(format!("image size {width}x{height} is out of range"));
+            }
+            if layers > MAX_LAYERS {
+                return Err(format!("{layers} layers is out of range"));
+            }
+            if faces != 1 && faces != 6 {
+                return Err(format!("{faces} faces is neither a 2D texture nor a cube map"));
+            }
+
+            // a chain ends at 1x1
+            let full_chain = 32 - width.max(height).leading_zeros();
+            if levels > full_chain {
+                return Err(format!("{levels} mip levels is more than a {width}x{height} image has"));
+            }
+
+            Ok(Extent {
+                width: width as i32,
+                height: height as i32,
+                layers: layers as i32,
+                faces: faces as i32,
+                levels: levels as usize,
+            })
+        }
+
+        fn images(&self) -> i32 {
+            self.layers * self.faces
+        }
+
+        fn level_width(&self, level: usize) -> i32 {
+            (self.width >> level).max(1)
+        }
+
+        fn level_height(&self, level: usize) -> i32 {
+            (self.height >> level).max(1)
+        }
+
+        fn image(self, format: Format, srgb: bool, levels: Vec<Vec<u8>>) -> ImageData {
+            ImageData {
+                width: self.width,
+                height: self.height,
+                layers: self.layers,
+                faces: self.faces,
+                format,
+                srgb,
+                levels,
+            }
+        }
+    }
+
+    fn dxgi_format(format: u32) -> Result<(Format, bool), String> {
+        match format {
+            2 => Ok((Format::Rgba32f, false)),
+            6 => Ok((Format::Rgb32f, false)),
+            10 => Ok((Format::Rgba16f, false)),
+            11 => Ok((Format::Rgba16, false)),
+            16 => Ok((Format::Rg32f, false)),
+            28 => Ok((Format::Rgba8, false)),
+            29 => Ok((Format::Rgba8, true)),
+            35 => Ok((Format::Rg16, false)),
+            41 => Ok((Format::R32f, false)),
+            49 => Ok((Format::Rg8, false)),
+            56 => Ok((Format::R16, false)),
+            61 => Ok((Format::R8, false)),
+            71 => Ok((Format::Bc1, false)),
+            72 => Ok((Format::Bc1, true)),
+            74 => Ok((Format::Bc2, false)),
+            75 => Ok((Format::Bc2, true)),
+            77 => Ok((Format::Bc3, false)),
+            78 => Ok((Format::Bc3, true)),
+            80 => Ok((Format::Bc4, false)),
+            83 => Ok((Format::Bc5, false)),
+            87 => Ok((Format::Bgra8, false)),
+            91 => Ok((Format::Bgra8, true)),
+            95 => Ok((Format::Bc6h, false)),
+            96 => Ok((Format::Bc6hSigned, false)),
+            98 => Ok((Format::Bc7, false)),
+            99 => Ok((Format::Bc7, true)),
+            _ => Err(format!("unsupported DXGI format {format}")),
+        }
+    }
+
+    fn gl_format(format: u32) -> Result<(Format, bool), String> {
+        match format {
+            gl::R8 | gl::RED => Ok((Format::R8, false)),
+            gl::RG8 | gl::RG => Ok((Format::Rg8, false)),
+            gl::RGB8 | gl::RGB => Ok((Format::Rgb8, false)),
+            gl::SRGB8 => Ok((Format::Rgb8, true)),
+            gl::RGBA8 | gl::RGBA => Ok((Format::Rgba8, false)),
+            gl::SRGB8_ALPHA8 => Ok((Format::Rgba8, true)),
+            gl::R16 => Ok((Format::R16, false)),
+            gl::RG16 => Ok((Format::Rg16, false)),
+            gl::RGB16 => Ok((Format::Rgb16, false)),
+            gl::RGBA16 => Ok((Format::Rgba16, false)),
+            gl::RGBA16F => Ok((Format::Rgba16f, false)),
+            gl::R32F => Ok((Format::R32f, false)),
+            gl::RG32F => Ok((Format::Rg32f, false)),
+            gl::RGB32F => Ok((Format::Rgb32f, false)),
+            gl::RGBA32F => Ok((Format::Rgba32f, false)),
+            gl::COMPRESSED_RGB_S3TC_DXT1_EXT | gl::COMPRESSED_RGBA_S3TC_DXT1_EXT => Ok((Format::Bc1, false)),
+            gl::COMPRESSED_SRGB_S3TC_DXT1_EXT | gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT => Ok((Format::Bc1, true)),
+            gl::COMPRESSED_RGBA_S3TC_DXT3_EXT => Ok((Format::Bc2, false)),
+            gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT => Ok((Format::Bc2, true)),
+            gl::COMPRESSED_RGBA_S3TC_DXT5_EXT => Ok((Format::Bc3, false)),
+            gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT => Ok((Format::Bc3, true)),
+            gl::COMPRESSED_RED_RGTC1 => Ok((Format::Bc4, false)),
+            gl::COMPRESSED_RG_RGTC2 => Ok((Format::Bc5, false)),
+            gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT_ARB => Ok((Format::Bc6h, false)),
+            gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT_ARB => Ok((Format::Bc6hSigned, false)),
+            gl::COMPRESSED_RGBA_BPTC_UNORM_ARB => Ok((Format::Bc7, false)),
+            gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM_ARB => Ok((Format::Bc7, true)),
+            _ => Err(format!("unsupported GL format {format:#x}")),
+        }
+    }
+
+    fn vk_format_of(format: u32) -> Result<(Format, bool), String> {
+        match format {
+            9 => Ok((Format::R8, false)),
+            16 => Ok((Format::Rg8, false)),
+            23 => Ok((Format::Rgb8, false)),
+            29 => Ok((Format::Rgb8, true)),
+            37 => Ok((Format::Rgba8, false)),
+            43 => Ok((Format::Rgba8, true)),
+            44 => Ok((Format::Bgra8, false)),
+            50 => Ok((Format::Bgra8, true)),
+            70 => Ok((Format::R16, false)),
+            77 => Ok((Format::Rg16, false)),
+            91 => Ok((Format::Rgba16, false)),
+            97 => Ok((Format::Rgba16f, false)),
+            100 => Ok((Format::R32f, false)),
+            103 => Ok((Format::Rg32f, false)),
+            106 => Ok((Format::Rgb32f, false)),
+            109 => Ok((Format::Rgba32f, false)),
+            131 | 133 => Ok((Format::Bc1, false)),
+            132 | 134 => Ok((Format::Bc1, true)),
+            135 => Ok((Format::Bc2, false)),
+            136 => Ok((Format::Bc2, true)),
+            137 => Ok((Format::Bc3, false)),
+            138 => Ok((Format::Bc3, true)),
+            139 => Ok((Format::Bc4, false)),
+            141 => Ok((Format::Bc5, false)),
+            143 => Ok((Format::Bc6h, false)),
+            144 => Ok((Format::Bc6hSigned, false)),
+            145 => Ok((Format::Bc7, false)),
+            146 => Ok((Format::Bc7, true)),
+            _ => Err(format!("unsupported Vulkan format {format}")),
+        }
+    }
+
+    fn align4(offset: usize) -> usize {
+        (offset + 3) & !3
+    }
+
+    // Format::image_size of that many images, failing instead of overflowing
+    fn image_bytes(format: Format, width: i32, height: i32, images: i32) -> Result<usize, String> {
+        let (columns, rows, unit) = match format.block_bytes() {
+            Some(block_bytes) => ((width as usize).div_ceil(4), (height as usize).div_ceil(4), block_bytes),
+            None => (width as usize, height as usize, format.pixel_bytes()),
+        };
+
+        columns
+            .checked_mul(rows)
+            .and_then(|size| size.checked_mul(unit))
+            .and_then(|size| size.checked_mul(images as usize))
+            .ok_or_else(|| "image size overflows".to_string())
+    }
+
+    // KTX 1 pads uncompressed rows to four bytes, like GL_UNPACK_ALIGNMENT's default
+    fn padded_size(format: Format, width: i32, height: i32) -> Result<usize, String> {
+        match format.block_bytes() {
+            Some(_) => image_bytes(format, width, height, 1),
+            None => align4(image_bytes(format, width, 1, 1)?).checked_mul(height as usize).ok_or_else(|| "image size overflows".to_string()),
+        }
+    }
+
+    fn unpad_rows(pixels: &[u8], format: Format, width: i32) -> Vec<u8> {
+        let row = width as usize * format.pixel_bytes();
+
+        if format.block_bytes().is_some() || row == align4(row) {
+            return pixels.to_vec();
+        }
+
+        pixels.chunks(align4(row)).flat_map(|padded| &padded[..row]).copied().collect()
+    }
+
+    fn slice(bytes: &[u8], offset: usize, size: usize) -> Result<&[u8], String> {
+        offset.checked_add(size).and_then(|end| bytes.get(offset..end)).ok_or_else(|| "file is truncated".to_string())
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(slice(bytes, offset, 4)?.try_into().unwrap()))
+    }
+
+    fn u64_at(bytes: &[u8], offset: usize) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(slice(bytes, offset, 8)?.try_into().unwrap()))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn put(bytes: &mut Vec<u8>, offset: usize, value: u32) {
+            if bytes.len() < offset + 4 {
+                bytes.resize(offset + 4, 0);
+            }
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        // a BC1 DDS with the given size and mip count, followed by data bytes
+        fn dds(width: u32, height: u32, levels: u32, data: usize) -> Vec<u8> {
+            let mut bytes = DDS_MAGIC.to_vec();
+            bytes.resize(128, 0);
+            put(&mut bytes, 8, DDSD_MIPMAPCOUNT);
+            put(&mut bytes, 12, height);
+            put(&mut bytes, 16, width);
+            put(&mut bytes, 28, levels);
+            put(&mut bytes, 80, DDPF_FOURCC);
+            bytes[84..88].copy_from_slice(b"DXT1");
+            bytes.resize(128 + data, 0x55);
+            bytes
+        }
+
+        // an uncompressed RGB8 KTX with the given size, faces and key value bytes, followed by data bytes
+        fn ktx(width: u32, height: u32, faces: u32, key_value_bytes: u32, data: usize) -> Vec<u8> {
+            let mut bytes = KTX_IDENTIFIER.to_vec();
+            put(&mut bytes, 12, 0x04030201);
+            put(&mut bytes, 24, gl::RGB);
+            put(&mut bytes, 28, gl::RGB8);
+            put(&mut bytes, 36, width);
+            put(&mut bytes, 40, height);
+            put(&mut bytes, 52, faces);
+            put(&mut bytes, 56, 1);
+            put(&mut bytes, 60, key_value_bytes);
+            bytes.resize(64 + data, 0);
+            bytes
+        }
+
+        fn ktx2(width: u32, level_offset: u64, level_length: u64, data: usize) -> Vec<u8> {
+            let mut bytes = KTX2_IDENTIFIER.to_vec();
+            put(&mut bytes, 12, 37);
+            put(&mut bytes, 20, width);
+            put(&mut bytes, 24, width);
+            put(&mut bytes, 40, 1);
+            put(&mut bytes, 80, level_offset as u32);
+            put(&mut bytes, 84, (level_offset >> 32) as u32);
+            put(&mut bytes, 88, level_length as u32);
+            put(&mut bytes, 92, (level_length >> 32) as u32);
+            bytes.resize(104 + data, 7);
+            bytes
+        }
+
+        #[test]
+        fn reads_well_formed_containers() {
+            let image = read_dds(&dds(8, 8, 4, 32 + 8 + 8 + 8)).unwrap();
+            assert_eq!((image.width, image.height, image.format), (8, 8, Format::Bc1));
+            assert_eq!(image.levels.iter().map(Vec::len).collect::<Vec<_>>(), [32, 8, 8, 8]);
+
+            // 3 RGB8 texels make a 9 byte row padded to 12
+            let mut bytes = ktx(3, 2, 1, 0, 4 + 24);
+            for (i, byte) in bytes[68..].iter_mut().enumerate() {
+                *byte = if i % 12 < 9 { 1 } else { 9 };
+            }
+            let image = read_ktx(&bytes).unwrap();
+            assert_eq!(image.levels, [vec![1; 18]]);
+
+            let image = read_ktx2(&ktx2(2, 104, 16, 16)).unwrap();
+            assert_eq!((image.format, image.levels.len(), image.levels[0].len()), (Format::Rgba8, 1, 16));
+        }
+
+        #[test]
+        fn rejects_sizes_out_of_range() {
+            // used to wrap to negative i32 sizes
+            assert!(read_dds(&dds(0x8000_0000, 4, 1, 64)).is_err());
+            assert!(read_dds(&dds(4, u32::MAX, 1, 64)).is_err());
+            assert!(read_dds(&dds(0, 4, 1, 64)).is_err());
+            assert!(read_dds(&dds(MAX_SIZE + 1, 4, 1, 64)).is_err());
+
+            // more levels than the chain has, and more than the file holds
+            assert!(read_dds(&dds(8, 8, 5, 64)).is_err());
+            assert!(read_dds(&dds(8, 8, u32::MAX, 64)).is_err());
+
+            assert!(read_ktx(&ktx(4, 4, 5, 0, 1024)).is_err());
+
+            let mut layered = dds(4, 4, 1, 0);
+            layered[84..88].copy_from_slice(b"DX10");
+            put(&mut layered, 128, 71);
+            put(&mut layered, 140, u32::MAX);
+            assert!(read_dds(&layered).is_err());
+        }
+
+        #[test]
+        fn rejects_truncated_containers() {
+            assert!(read_dds(&dds(8, 8, 4, 32 + 8 + 8)).is_err());
+            assert!(read_dds(DDS_MAGIC).is_err());
+            assert!(read_ktx(&ktx(3, 2, 1, 0, 4 + 23)).is_err());
+            assert!(read_ktx(&ktx(3, 2, 1, u32::MAX, 64)).is_err());
+            assert!(read_ktx(&KTX_IDENTIFIER).is_err());
+
+            assert!(read_ktx2(&ktx2(2, u64::MAX, 16, 16)).is_err());
+            assert!(read_ktx2(&ktx2(2, u64::MAX - 8, 16, 16)).is_err());
+            assert!(read_ktx2(&ktx2(2, 110, 16, 16)).is_err());
+            assert!(read_ktx2(&ktx2(2, 104, 15, 16)).is_err());
+            assert!(read_ktx2(&ktx2(16384, 104, u64::MAX, 16)).is_err());
+        }
+
+        #[test]
+        fn image_bytes_fail_instead_of_overflowing() {
+            assert_eq!(image_bytes(Format::Bc7, 5, 5, 2), Ok(2 * 4 * 16));
+            assert_eq!(padded_size(Format::Rgb8, 3, 2), Ok(24));
+            assert!(image_bytes(Format::Rgba32f, i32::MAX, i32::MAX, i32::MAX).is_err());
+        }
+    }
+}
@@ -0,0 +1,298 @@
+pub mod loader {
+    use crate::{build_material, texture_options, gl, AssetCache, ImageData, Material, Mesh, MeshBuilder, MeshData, MeshKey, Rc, RefCell, Report, SceneData, Slot, StaticVertex, Texture, TextureOptions, TextureSource, Vertex};
+    use std::{
+        collections::{HashMap, VecDeque},
+        mem,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    const MAX_WORKERS: usize = 4;
+
+    enum Request {
+        Scene { id: usize, path: String },
+        Image { id: usize, source: TextureSource, options: TextureOptions },
+    }
+
+    enum Decoded {
+        Scene { id: usize, scene: Result<SceneData, String> },
+        Image { id: usize, image: Option<ImageData> },
+    }
+
+    // work that has to happen on the thread owning the GL context
+    enum Upload {
+        Mesh { model: usize, key: MeshKey, data: Option<MeshData> },
+        Image { texture: Rc<Texture>, image: Option<ImageData>, options: TextureOptions },
+    }
+
+    struct PendingModel {
+        path: String,
+        meshes: Rc<RefCell<Vec<Rc<Mesh>>>>,
+        keys: Vec<MeshKey>,
+        remaining: usize,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Progress {
+        pub done: usize,
+        pub total: usize,
+    }
+
+    impl Progress {
+        pub fn fraction(&self) -> f32 {
+            if self.total == 0 {
+                1.0
+            } else {
+                self.done as f32 / self.total as f32
+            }
+        }
+    }
+
+    pub struct Loader {
+        gl: Rc<gl::Gl>,
+        cache: Rc<RefCell<AssetCache>>,
+        requests: Option<mpsc::Sender<Request>>,
+        decoded: mpsc::Receiver<Decoded>,
+        workers: Vec<thread::JoinHandle<()>>,
+        next_id: usize,
+        models: HashMap<usize, PendingModel>,
+        textures: HashMap<usize, (Rc<Texture>, TextureOptions)>,
+        uploads: VecDeque<Upload>,
+        placeholder: Option<Rc<Mesh>>,
+        progress: Progress,
+        // the optimisation report of every scene read, by path
+        reports: Vec<(String, Report)>,
+    }
+
+    impl Loader {
+        pub fn new(gl: Rc<gl::Gl>, cache: Rc<RefCell<AssetCache>>) -> Self {
+            let (request_sender, request_receiver) = mpsc::channel();
+            let (decoded_sender, decoded_receiver) = mpsc::channel();
+            let request_receiver = Arc::new(Mutex::new(request_receiver));
+
+            let count = thread::available_parallelism().map_or(1, |count| count.get()).min(MAX_WORKERS);
+            let workers = (0..count)
+                .map(|_| {
+                    let requests = Arc::clone(&request_receiver);
+                    let decoded = decoded_sender.clone();
+                    thread::spawn(move || work(requests, decoded))
+                })
+                .collect();
+
+            Loader {
+                gl,
+                cache,
+                requests: Some(request_sender),
+                decoded: decoded_receiver,
+                workers,
+                next_id: 0,
+                models: HashMap::new(),
+                textures: HashMap::new(),
+                uploads: VecDeque::new(),
+                placeholder: None,
+                progress: Progress { done: 0, total: 0 },
+                reports: vec![],
+            }
+        }
+
+        // the meshes are pushed into `meshes` as they get uploaded
+        pub fn load_model(&mut self, path: &str, meshes: Rc<RefCell<Vec<Rc<Mesh>>>>) {
+            let id = self.next_id();
+
+            self.models.insert(
+                id,
+                PendingModel {
+                    path: path.to_string(),
+                    meshes,
+                    keys: vec![],
+                    remaining: 0,
+                },
+            );
+            self.request(Request::Scene { id, path: path.to_string() });
+        }
+
+        // a unit cube to draw in place of models that are still loading
+        pub fn placeholder(&mut self) -> Rc<Mesh> {
+            if let Some(placeholder) = &self.placeholder {
+                return Rc::clone(placeholder);
+            }
+
+            let material = Material::new(self.cache.borrow().fallbacks());
+            let (vertices, indices) = MeshBuilder::cube(1.0).build();
+            let vertices: Vec<Vertex> = vertices.iter().map(Vertex::from).collect();
+            let placeholder = Rc::new(Mesh::new(Rc::clone(&self.gl), StaticVertex::pack(&vertices), indices, material));
+            self.placeholder = Some(Rc::clone(&placeholder));
+
+            placeholder
+        }
+
+        pub fn progress(&self) -> Progress {
+            self.progress
+        }
+
+        pub fn reports(&self) -> &[(String, Report)] {
+            &self.reports
+        }
+
+        pub fn is_idle(&self) -> bool {
+            self.progress.done == self.progress.total
+        }
+
+        // collects finished work from the workers and uploads it until `budget` is spent
+        pub fn poll(&mut self, budget: Duration) {
+            let start = Instant::now();
+
+            while let Ok(decoded) = self.decoded.try_recv() {
+                match decoded {
+                    Decoded::Scene { id, scene } => self.queue_scene(id, scene),
+                    Decoded::Image { id, image } => {
+                        let (texture, options) = self.textures.remove(&id).unwrap();
+                        self.uploads.push_back(Upload::Image { texture, image, options });
+                    }
+                }
+            }
+
+            while start.elapsed() < budget {
+                match self.uploads.pop_front() {
+                    Some(upload) => self.upload(upload),
+                    None => break,
+                }
+                self.progress.done += 1;
+            }
+        }
+
+        fn next_id(&mut self) -> usize {
+            self.next_id += 1;
+            self.next_id
+        }
+
+        fn request(&mut self, request: Request) {
+            self.progress.total += 1;
+            self.requests.as_ref().unwrap().send(request).unwrap();
+        }
+
+        fn queue_scene(&mut self, id: usize, scene: Result<SceneData, String>) {
+            self.progress.done += 1;
+
+            let mut scene = match scene {
+                Ok(scene) => scene,
+                Err(error) => {
+                    println!("{error}");
+                    self.models.remove(&id);
+                    return;
+                }
+            };
+
+            let model = self.models.get_mut(&id).unwrap();
+            model.remaining = scene.order.len();
+            self.reports.push((model.path.clone(), scene.report));
+
+            for index in scene.order {
+                let key = MeshKey::new(&model.path, index);
+                let data = scene.meshes.get_mut(index as usize).map(mem::take);
+
+                self.uploads.push_back(Upload::Mesh { model: id, key, data });
+                self.progress.total += 1;
+            }
+
+            if model.remaining == 0 {
+                self.finish_model(id);
+            }
+        }
+
+        fn upload(&mut self, upload: Upload) {
+            match upload {
+                Upload::Mesh { model, key, data } => {
+                    let cached = self.cache.borrow().mesh(&key);
+                    let mesh = match (cached, data) {
+                        (Some(mesh), _) => mesh,
+                        (None, data) => {
+                            let data = data.unwrap_or_default();
+                            let textures = data.textures.iter().map(|(slot, source)| (*slot, self.texture(*slot, source))).collect();
+
+                            let material = build_material(&self.cache.borrow(), data.factors, textures);
+                            let mesh = Mesh::new(Rc::clone(&self.gl), StaticVertex::pack(&data.vertices), data.indices, material);
+
+                            self.cache.borrow_mut().insert_mesh(key.clone(), mesh)
+                        }
+                    };
+
+                    let pending = self.models.get_mut(&model).unwrap();
+                    pending.meshes.borrow_mut().push(mesh);
+                    pending.keys.push(key);
+                    pending.remaining -= 1;
+
+                    if pending.remaining == 0 {
+                        self.finish_model(model);
+                    }
+                }
+                Upload::Image { texture, image, options } => texture.upload(image.as_ref(), options),
+            }
+        }
+
+        // hands out the cached texture, or a placeholder that the decoded image is later uploaded into
+        fn texture(&mut self, slot: Slot, source: &TextureSource) -> Rc<Texture> {
+            let color = match slot {
+                Slot::Normal => [128, 128, 255, 255],
+                Slot::Height => [0, 0, 0, 255],
+                _ => [128, 128, 128, 255],
+            };
+
+            let options = texture_options(slot);
+            let (texture, fresh) = self.cache.borrow_mut().texture_placeholder(&format!("{slot:?}"), &source.path(), options, &ImageData::solid(color));
+
+            if fresh {
+                let id = self.next_id();
+                self.textures.insert(id, (Rc::clone(&texture), options));
+                self.request(Request::Image {
+                    id,
+                    source: source.clone(),
+                    options,
+                });
+            }
+
+            texture
+        }
+
+        fn finish_model(&mut self, id: usize) {
+            let model = self.models.remove(&id).unwrap();
+            self.cache.borrow_mut().insert_model(&model.path, model.keys);
+        }
+    }
+
+    impl Drop for Loader {
+        fn drop(&mut self) {
+            // closing the channel lets the workers run out of requests and return
+            self.requests = None;
+
+            // a worker that panicked has already printed why, exiting shouldn't panic again
+            for worker in self.workers.drain(..) {
+                if worker.join().is_err() {
+                    println!("A loader worker panicked.");
+                }
+            }
+        }
+    }
+
+    fn work(requests: Arc<Mutex<mpsc::Receiver<Request>>>, decoded: mpsc::Sender<Decoded>) {
+        loop {
+            let request = match requests.lock().unwrap().recv() {
+                Ok(request) => request,
+                Err(_) => return,
+            };
+
+            let result = match request {
+                Request::Scene { id, path } => Decoded::Scene { id, scene: SceneData::read(&path) },
+                Request::Image { id, source, options } => Decoded::Image {
+                    id,
+                    image: source.decode(options.flip),
+                },
+            };
+
+            if decoded.send(result).is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -1,21 +1,47 @@
 use gfx_maths::{mat4::Mat4, vec2::Vec2, vec3::Vec3};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
-use std::{f32::consts::PI, fs::File, io::Read, mem::size_of, ptr, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    f32::consts::PI,
+    fs::File,
+    io::Read,
+    mem::size_of,
+    ptr,
+    rc::Rc,
+    time::Duration,
+};
 
 const SCR_WIDTH: u32 = 800;
 const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
-
+const UPLOAD_BUDGET: Duration = Duration::from_millis(4);
+
+pub mod asset;
+use asset::asset::{AssetCache, MeshKey};
+pub mod bcn;
+use bcn::bcn::decompress_blocks;
+pub mod builder;
+use builder::builder::{MeshBuilder, MeshVertex};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
+pub mod container;
+use container::container::{is_container, read_container, Format};
+pub mod loader;
+use loader::loader::Loader;
+pub mod material;
+use material::material::{Factors, Fallbacks, Material, MaterialLayout, Slot};
 pub mod mesh;
-use mesh::mesh::{stbi_flip_vertical, Mesh, Texture, Vertex};
+use mesh::mesh::{ColorSpace, Filter, ImageData, Mesh, Sampler, SamplerOptions, Texture, TextureOptions};
 pub mod model;
-use model::model::Model;
+use model::model::{build_material, texture_options, MeshData, Model, SceneData, TextureSource};
+pub mod optimize;
+use optimize::optimize::{optimize, Report};
 pub mod shader;
-use shader::shader::Program;
+use shader::shader::{Program, Uniform};
+pub mod vertex;
+use vertex::vertex::{Attribute, Indices, StaticVertex, Stream, Vertex, VertexLayout};
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
@@ -37,11 +63,25 @@ fn main() {
 
     let gl = Rc::new(gl::Gl::load_with(|s| window.get_proc_address(s).cast()));
 
-    stbi_flip_vertical(true);
-
     gl.depth_enable();
-    let shader = Program::new(Rc::clone(&gl)).link("src/1.model_loading.vs", "src/1.model_loading.fs");
-    let model = Model::new(Rc::clone(&gl)).load_model("resources/objects/backpack/backpack.obj");
+    // base color maps are decoded to linear on sampling, so the framebuffer has to encode again
+    gl.framebuffer_srgb_enable();
+    let cache = Rc::new(RefCell::new(AssetCache::new(Rc::clone(&gl))));
+    let shader = cache.borrow_mut().program("src/1.model_loading.vs", "src/1.model_loading.fs");
+    let mut loader = Loader::new(Rc::clone(&gl), Rc::clone(&cache));
+    let model = Model::new(Rc::clone(&gl), Rc::clone(&cache)).load_model_async("resources/objects/backpack/backpack.obj", &mut loader);
+    let mut loading = true;
+
+    let point_sampler = Sampler::new(
+        Rc::clone(&gl),
+        SamplerOptions {
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Nearest,
+            mip_filter: Some(Filter::Nearest),
+            ..Default::default()
+        },
+    );
+    let mut point_sampling = Toggle { is_on: false, pressed: false };
 
     glfw.poll_events();
 
@@ -55,7 +95,21 @@ fn main() {
         let delta_time = current_frame - last_frame;
         last_frame = current_frame;
 
-        process_input(&mut camera, &mut window, delta_time);
+        process_input(&mut camera, &mut point_sampling, &mut window, delta_time);
+
+        loader.poll(UPLOAD_BUDGET);
+        if loading {
+            if loader.is_idle() {
+                loading = false;
+                window.set_title("LearnOpenGL");
+                println!("Loaded {}", cache.borrow().memory_usage());
+                for (path, report) in loader.reports() {
+                    println!("{path}: {report}");
+                }
+            } else {
+                window.set_title(&format!("LearnOpenGL - loading {:.0}%", loader.progress().fraction() * 100.0));
+            }
+        }
 
         gl.clear(0.05, 0.05, 0.05, 1.0);
 
@@ -67,6 +121,14 @@ fn main() {
         shader.set_mat4("view", view);
         shader.set_mat4("model", Mat4::translate(Vec3::zero()) * Mat4::scale(Vec3::one()));
 
+        for unit in 0..Slot::ALL.len() as u32 {
+            if point_sampling.is_on {
+                point_sampler.bind(unit);
+            } else {
+                Sampler::unbind(&gl, unit);
+            }
+        }
+
         model.draw(&shader);
 
         window.swap_buffers();
@@ -78,7 +140,7 @@ fn main() {
     }
 }
 
-fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
+fn process_input(camera: &mut Camera, point_sampling: &mut Toggle, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
     }
@@ -95,6 +157,15 @@ fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::D) == Action::Press {
         camera.process_keyboard(Movement::Right, delta_time);
     }
+
+    if window.get_key(Key::N) == Action::Press && !point_sampling.pressed {
+        point_sampling.is_on = !point_sampling.is_on;
+        point_sampling.pressed = true;
+    }
+
+    if window.get_key(Key::N) == Action::Release {
+        point_sampling.pressed = false;
+    }
 }
 
 fn handle_window_event(gl: &gl::Gl, camera: &mut Camera, window: &mut glfw::Window, event: glfw::WindowEvent) {
@@ -128,17 +199,24 @@ mod gl {
                 self.Enable(self::DEPTH_TEST);
             }
         }
+
+        pub fn framebuffer_srgb_enable(&self) {
+            unsafe {
+                self.Enable(self::FRAMEBUFFER_SRGB);
+            }
+        }
     }
 
     pub fn c_name(name: &str) -> Vec<i8> {
         c_finish(&mut name.as_bytes().iter())
     }
 
-    pub fn c_named(name: &str, number: &str) -> Vec<i8> {
-        c_finish(&mut name.as_bytes().iter().chain(number.as_bytes().iter()))
-    }
-
     fn c_finish(iter: &mut dyn Iterator<Item = &u8>) -> Vec<i8> {
         iter.filter(|&u| *u < 128u8).map(|u| *u as i8).chain(std::iter::once(0)).collect::<Vec<_>>()
     }
 }
+
+pub struct Toggle {
+    pub is_on: bool,
+    pub pressed: bool,
+}
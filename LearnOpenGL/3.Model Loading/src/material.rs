@@ -0,0 +1,242 @@
+pub mod material {
+    use crate::{gl, ColorSpace, Program, Rc, Texture, Uniform, Vec3};
+
+    const SLOT_COUNT: usize = 6;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Slot {
+        BaseColor,
+        MetallicRoughness,
+        Normal,
+        Occlusion,
+        Emissive,
+        Height,
+    }
+
+    impl Slot {
+        pub const ALL: [Slot; SLOT_COUNT] = [Slot::BaseColor, Slot::MetallicRoughness, Slot::Normal, Slot::Occlusion, Slot::Emissive, Slot::Height];
+
+        // only colors are authored in sRGB, data maps have to be sampled as stored
+        pub fn color_space(self) -> ColorSpace {
+            match self {
+                Slot::BaseColor | Slot::Emissive => ColorSpace::Srgb,
+                _ => ColorSpace::Linear,
+            }
+        }
+
+        // the texture_*1 names keep the tutorial shaders working
+        fn sampler_names(self) -> &'static [&'static str] {
+            match self {
+                Slot::BaseColor => &["baseColorMap", "albedoMap", "texture_diffuse1"],
+                Slot::MetallicRoughness => &["metallicRoughnessMap"],
+                Slot::Normal => &["normalMap", "texture_normal1"],
+                Slot::Occlusion => &["occlusionMap", "aoMap"],
+                Slot::Emissive => &["emissiveMap"],
+                Slot::Height => &["heightMap", "texture_height1"],
+            }
+        }
+    }
+
+    pub struct Factors {
+        pub base_color: [f32; 4],
+        pub metallic: f32,
+        pub roughness: f32,
+        pub normal_scale: f32,
+        pub occlusion_strength: f32,
+        pub emissive: Vec3,
+        pub height_scale: f32,
+    }
+
+    impl Default for Factors {
+        fn default() -> Self {
+            Factors {
+                base_color: [1.0; 4],
+                metallic: 1.0,
+                roughness: 1.0,
+                normal_scale: 1.0,
+                occlusion_strength: 1.0,
+                emissive: Vec3::zero(),
+                height_scale: 0.1,
+            }
+        }
+    }
+
+    // where a program takes each slot's sampler and each factor, reflected once when it is linked
+    #[derive(Default)]
+    pub struct MaterialLayout {
+        // location and unit, counted from the first unit a material binds at
+        samplers: [Option<(gl::types::GLint, u32)>; SLOT_COUNT],
+        base_color: Option<gl::types::GLint>,
+        metallic: Option<gl::types::GLint>,
+        roughness: Option<gl::types::GLint>,
+        normal_scale: Option<gl::types::GLint>,
+        occlusion_strength: Option<gl::types::GLint>,
+        emissive: Option<gl::types::GLint>,
+        height_scale: Option<gl::types::GLint>,
+    }
+
+    impl MaterialLayout {
+        pub fn reflect(uniforms: &[Uniform]) -> Self {
+            let find = |name: &str, kind: Option<gl::types::GLenum>| {
+                uniforms.iter().find(|uniform| uniform.name == name && kind.is_none_or(|kind| uniform.kind == kind)).map(|uniform| uniform.location)
+            };
+
+            let mut units = 0;
+            let samplers = Slot::ALL.map(|slot| {
+                let location = slot.sampler_names().iter().find_map(|name| find(name, Some(gl::SAMPLER_2D)))?;
+                units += 1;
+                Some((location, units - 1))
+            });
+
+            MaterialLayout {
+                samplers,
+                base_color: find("baseColorFactor", None),
+                metallic: find("metallicFactor", None),
+                roughness: find("roughnessFactor", None),
+                normal_scale: find("normalScale", None),
+                occlusion_strength: find("occlusionStrength", None),
+                emissive: find("emissiveFactor", None),
+                height_scale: find("heightScale", None),
+            }
+        }
+
+        pub fn units(&self) -> u32 {
+            self.samplers.iter().flatten().count() as u32
+        }
+    }
+
+    pub struct Fallbacks {
+        gl: Rc<gl::Gl>,
+        white: Texture,
+        black: Texture,
+        flat_normal: Texture,
+    }
+
+    impl Fallbacks {
+        pub fn new(gl: Rc<gl::Gl>) -> Self {
+            let white = Texture::new_color(Rc::clone(&gl), [255, 255, 255, 255]);
+            let black = Texture::new_color(Rc::clone(&gl), [0, 0, 0, 255]);
+            let flat_normal = Texture::new_color(Rc::clone(&gl), [128, 128, 255, 255]);
+
+            Fallbacks { gl, white, black, flat_normal }
+        }
+
+        fn texture(&self, slot: Slot) -> &Texture {
+            match slot {
+                Slot::BaseColor | Slot::MetallicRoughness | Slot::Occlusion | Slot::Emissive => &self.white,
+                Slot::Height => &self.black,
+                Slot::Normal => &self.flat_normal,
+            }
+        }
+    }
+
+    pub struct Material {
+        textures: [Option<Rc<Texture>>; SLOT_COUNT],
+        pub factors: Factors,
+        fallbacks: Rc<Fallbacks>,
+    }
+
+    impl Material {
+        pub fn new(fallbacks: Rc<Fallbacks>) -> Self {
+            Material {
+                textures: Default::default(),
+                factors: Factors::default(),
+                fallbacks,
+            }
+        }
+
+        pub fn with_texture(mut self, slot: Slot, texture: Rc<Texture>) -> Self {
+            self.set_texture(slot, texture);
+            self
+        }
+
+        pub fn set_texture(&mut self, slot: Slot, texture: Rc<Texture>) {
+            self.textures[slot as usize] = Some(texture);
+        }
+
+        pub fn texture(&self, slot: Slot) -> Option<&Rc<Texture>> {
+            self.textures[slot as usize].as_ref()
+        }
+
+        // returns the next free texture unit
+        pub fn bind(&self, shader: &Program, first_unit: u32) -> u32 {
+            let gl = &self.fallbacks.gl;
+            let layout = shader.material_layout();
+
+            for (slot, sampler) in Slot::ALL.into_iter().zip(layout.samplers) {
+                if let Some((location, offset)) = sampler {
+                    let texture = match self.texture(slot) {
+                        Some(texture) => texture,
+                        None => self.fallbacks.texture(slot),
+                    };
+                    let unit = first_unit + offset;
+
+                    unsafe {
+                        gl.ActiveTexture(gl::TEXTURE0 + unit);
+                        texture.bind();
+                        gl.Uniform1i(location, unit as i32);
+                    }
+                }
+            }
+
+            let factors = &self.factors;
+            unsafe {
+                if let Some(location) = layout.base_color {
+                    gl.Uniform4fv(location, 1, factors.base_color.as_ptr());
+                }
+                if let Some(location) = layout.metallic {
+                    gl.Uniform1f(location, factors.metallic);
+                }
+                if let Some(location) = layout.roughness {
+                    gl.Uniform1f(location, factors.roughness);
+                }
+                if let Some(location) = layout.normal_scale {
+                    gl.Uniform1f(location, factors.normal_scale);
+                }
+                if let Some(location) = layout.occlusion_strength {
+                    gl.Uniform1f(location, factors.occlusion_strength);
+                }
+                if let Some(location) = layout.emissive {
+                    gl.Uniform3f(location, factors.emissive.x, factors.emissive.y, factors.emissive.z);
+                }
+                if let Some(location) = layout.height_scale {
+                    gl.Uniform1f(location, factors.height_scale);
+                }
+
+                gl.ActiveTexture(gl::TEXTURE0);
+            }
+
+            first_unit + layout.units()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn uniform(name: &str, kind: gl::types::GLenum, location: gl::types::GLint) -> Uniform {
+            Uniform { name: name.to_string(), kind, location }
+        }
+
+        #[test]
+        fn reflection_packs_the_units_of_the_samplers_a_program_has() {
+            let uniforms = [
+                uniform("heightMap", gl::SAMPLER_2D, 4),
+                uniform("texture_diffuse1", gl::SAMPLER_2D, 2),
+                // not a 2D sampler, so the slot falls through to the next name
+                uniform("normalMap", gl::SAMPLER_CUBE, 7),
+                uniform("texture_normal1", gl::SAMPLER_2D, 3),
+                uniform("roughnessFactor", gl::FLOAT, 9),
+            ];
+            let layout = MaterialLayout::reflect(&uniforms);
+
+            assert_eq!(layout.samplers[Slot::BaseColor as usize], Some((2, 0)));
+            assert_eq!(layout.samplers[Slot::MetallicRoughness as usize], None);
+            assert_eq!(layout.samplers[Slot::Normal as usize], Some((3, 1)));
+            assert_eq!(layout.samplers[Slot::Height as usize], Some((4, 2)));
+            assert_eq!(layout.units(), 3);
+            assert_eq!(layout.roughness, Some(9));
+            assert_eq!(layout.metallic, None);
+        }
+    }
+}
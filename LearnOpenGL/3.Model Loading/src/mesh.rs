@@ -1,119 +1,65 @@
 pub mod mesh {
-    use crate::{gl, ptr, size_of, File, Program, Rc, Read, Vec2, Vec3};
-
-    const MAX_BONE_INFLUENCE: usize = 4;
-    const FLOAT_SIZE: usize = size_of::<f32>();
-
-    #[repr(C)]
-    pub struct Vertex {
-        pub position: Vec3,
-        pub normal: Vec3,
-        pub tex_coords: Vec2,
-        pub tangent: Vec3,
-        pub bitangent: Vec3,
-        pub bone_ids: [i32; MAX_BONE_INFLUENCE],
-        pub weights: [f32; MAX_BONE_INFLUENCE],
-    }
+    use crate::{decompress_blocks, gl, is_container, ptr, read_container, Cell, File, Format, Indices, Material, Program, Rc, Read, Stream, VertexLayout};
+    use std::ffi::CStr;
 
     pub struct Mesh {
         gl: Rc<gl::Gl>,
-        indices: Vec<u32>,
-        textures: Vec<Rc<Texture>>,
+        index_count: usize,
+        index_type: gl::types::GLenum,
+        bytes: usize,
+        pub material: Material,
         vao: VertexArray,
     }
 
     impl Mesh {
-        pub fn new(gl: Rc<gl::Gl>, vertices: Vec<Vertex>, indices: Vec<u32>, textures: Vec<Rc<Texture>>) -> Self {
-            let vao = VertexArray::new(Rc::clone(&gl));
+        pub fn new<V: VertexLayout>(gl: Rc<gl::Gl>, vertices: Vec<V>, indices: Vec<u32>, material: Material) -> Self {
+            Mesh::from_streams(gl, &[Stream::interleaved(&vertices)], Indices::compact(indices), material)
+        }
+
+        pub fn from_streams(gl: Rc<gl::Gl>, streams: &[Stream], indices: Indices, material: Material) -> Self {
+            let vao = VertexArray::new(Rc::clone(&gl), streams.len());
+            let index_bytes = indices.bytes();
 
             unsafe {
                 gl.BindVertexArray(vao.vertex_array);
 
-                gl.BindBuffer(gl::ARRAY_BUFFER, vao.vertex_buffer);
-                gl.BufferData(gl::ARRAY_BUFFER, (size_of::<Vertex>() * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vao.index_buffer);
-                gl.BufferData(
-                    gl::ELEMENT_ARRAY_BUFFER,
-                    (size_of::<u32>() * indices.len()) as isize,
-                    indices.as_ptr().cast(),
-                    gl::STATIC_DRAW,
-                );
+                for (stream, buffer) in streams.iter().zip(&vao.vertex_buffers) {
+                    gl.BindBuffer(gl::ARRAY_BUFFER, *buffer);
+                    gl.BufferData(gl::ARRAY_BUFFER, stream.data.len() as isize, stream.data.as_ptr().cast(), gl::STATIC_DRAW);
 
-                gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, size_of::<Vertex>() as i32, ptr::null());
-                gl.EnableVertexAttribArray(0);
-
-                gl.VertexAttribPointer(1, 3, gl::FLOAT, gl::FALSE, size_of::<Vertex>() as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(1);
-
-                gl.VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, size_of::<Vertex>() as i32, ((6 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(2);
-
-                gl.VertexAttribPointer(3, 3, gl::FLOAT, gl::FALSE, size_of::<Vertex>() as i32, ((8 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(3);
-
-                gl.VertexAttribPointer(4, 3, gl::FLOAT, gl::FALSE, size_of::<Vertex>() as i32, ((11 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(4);
-
-                gl.VertexAttribIPointer(5, 4, gl::INT, size_of::<Vertex>() as i32, ((14 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(5);
+                    for attribute in &stream.attributes {
+                        attribute.enable(&gl, stream.stride);
+                    }
+                }
 
-                gl.VertexAttribPointer(6, 4, gl::FLOAT, gl::FALSE, size_of::<Vertex>() as i32, ((18 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(6);
+                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vao.index_buffer);
+                gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, index_bytes.len() as isize, index_bytes.as_ptr().cast(), gl::STATIC_DRAW);
 
                 gl.BindVertexArray(0);
             }
 
-            Mesh { gl, indices, textures, vao }
+            Mesh {
+                gl,
+                index_count: indices.len(),
+                index_type: indices.data_type(),
+                bytes: streams.iter().map(|stream| stream.data.len()).sum::<usize>() + index_bytes.len(),
+                material,
+                vao,
+            }
+        }
+
+        pub fn bytes(&self) -> usize {
+            self.bytes
         }
 
         pub fn draw(&self, shader: &Program) {
             let gl = &self.gl;
 
-            let mut diffuse = 1u32;
-            let mut specular = 1u32;
-            let mut normal = 1u32;
-            let mut height = 1u32;
+            self.material.bind(shader, 0);
 
             unsafe {
-                for i in 0..self.textures.len() {
-                    gl.ActiveTexture(gl::TEXTURE0 + i as u32);
-
-                    let name = self.textures[i].tex_type.as_ref();
-                    let number = match name {
-                        "texture_diffuse" => ({
-                            diffuse += 1;
-                            diffuse - 1
-                        })
-                        .to_string(),
-                        "texture_specular" => ({
-                            specular += 1;
-                            specular - 1
-                        })
-                        .to_string(),
-                        "texture_normal" => ({
-                            normal += 1;
-                            normal - 1
-                        })
-                        .to_string(),
-                        "texture_height" => ({
-                            height += 1;
-                            height - 1
-                        })
-                        .to_string(),
-                        _ => {
-                            panic!("Unexpected texture type: {name}");
-                        }
-                    };
-
-                    gl.Uniform1i(gl.GetUniformLocation(shader.program(), gl::c_named(name, &number).as_ptr()), i as i32);
-                    gl.BindTexture(gl::TEXTURE_2D, self.textures[i].texture);
-                }
-
                 gl.BindVertexArray(self.vao.vertex_array);
-                gl.DrawElements(gl::TRIANGLES, self.indices.len() as i32, gl::UNSIGNED_INT, ptr::null());
-
-                gl.ActiveTexture(gl::TEXTURE0);
+                gl.DrawElements(gl::TRIANGLES, self.index_count as i32, self.index_type, ptr::null());
             }
         }
     }
@@ -121,26 +67,26 @@ pub mod mesh {
     struct VertexArray {
         gl: Rc<gl::Gl>,
         vertex_array: gl::types::GLuint,
-        vertex_buffer: gl::types::GLuint,
+        vertex_buffers: Vec<gl::types::GLuint>,
         index_buffer: gl::types::GLuint,
     }
 
     impl VertexArray {
-        pub fn new(gl: Rc<gl::Gl>) -> Self {
+        pub fn new(gl: Rc<gl::Gl>, streams: usize) -> Self {
             let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
+            let mut vertex_buffers = vec![0; streams];
             let mut index_buffer = 0;
 
             unsafe {
                 gl.GenVertexArrays(1, &mut vertex_array);
-                gl.GenBuffers(1, &mut vertex_buffer);
+                gl.GenBuffers(streams as i32, vertex_buffers.as_mut_ptr());
                 gl.GenBuffers(1, &mut index_buffer);
             }
 
             VertexArray {
                 gl,
                 vertex_array,
-                vertex_buffer,
+                vertex_buffers,
                 index_buffer,
             }
         }
@@ -150,7 +96,7 @@ pub mod mesh {
         fn drop(&mut self) {
             unsafe {
                 self.gl.DeleteVertexArrays(1, &self.vertex_array);
-                self.gl.DeleteBuffers(1, &self.vertex_buffer);
+                self.gl.DeleteBuffers(self.vertex_buffers.len() as i32, self.vertex_buffers.as_ptr());
                 self.gl.DeleteBuffers(1, &self.index_buffer);
             }
         }
@@ -158,33 +104,42 @@ pub mod mesh {
 
     use stb_image::stb_image::bindgen;
 
-    pub fn stbi_flip_vertical(flip: bool) {
-        unsafe {
-            bindgen::stbi_set_flip_vertically_on_load(flip as i32);
-        }
-    }
-
     struct Image {
         data: *mut u8,
         width: i32,
         height: i32,
         components: i32,
+        channel_bytes: i32,
     }
 
     impl Image {
-        fn new(path: &str) -> Self {
-            let mut file = File::open(path).unwrap();
+        // None when the file can't be read, a null data pointer when stb_image can't decode it;
+        // the bundled stb_image has no 16-bit loader, so 16-bit sources come in through containers
+        fn new(path: &str) -> Option<Self> {
             let mut contents = vec![];
-
-            file.read_to_end(&mut contents).unwrap();
+            File::open(path).and_then(|mut file| file.read_to_end(&mut contents)).ok()?;
 
             let mut width = 0;
             let mut height = 0;
             let mut components = 0;
 
-            let data = unsafe { bindgen::stbi_load_from_memory(contents.as_mut_ptr(), contents.len() as i32, &mut width, &mut height, &mut components, 0) };
+            let (data, channel_bytes) = unsafe {
+                if bindgen::stbi_is_hdr_from_memory(contents.as_ptr(), contents.len() as i32) != 0 {
+                    let data = bindgen::stbi_loadf_from_memory(contents.as_mut_ptr(), contents.len() as i32, &mut width, &mut height, &mut components, 0);
+                    (data.cast(), 4)
+                } else {
+                    let data = bindgen::stbi_load_from_memory(contents.as_mut_ptr(), contents.len() as i32, &mut width, &mut height, &mut components, 0);
+                    (data, 1)
+                }
+            };
 
-            Image { data, width, height, components }
+            Some(Image {
+                data,
+                width,
+                height,
+                components,
+                channel_bytes,
+            })
         }
     }
 
@@ -196,11 +151,300 @@ pub mod mesh {
         }
     }
 
+    // every level holds all of its layers and faces back to back
+    pub struct ImageData {
+        pub width: i32,
+        pub height: i32,
+        pub layers: i32,
+        pub faces: i32,
+        pub format: Format,
+        pub srgb: bool,
+        pub levels: Vec<Vec<u8>>,
+    }
+
+    impl ImageData {
+        // stb_image's own flip flag is global and would race between loader threads, so rows are flipped here;
+        // containers are used as authored since block compressed data can't be flipped row by row
+        pub fn decode(path: &str, flip: bool) -> Option<Self> {
+            if is_container(path) {
+                return read_container(path).map_err(|error| println!("Failed to load texture {path}: {error}")).ok();
+            }
+
+            let image = match Image::new(path) {
+                Some(image) if !image.data.is_null() => image,
+                _ => {
+                    println!("Failed to load texture {path}");
+                    return None;
+                }
+            };
+
+            let format = match (image.components, image.channel_bytes) {
+                (1, 1) => Format::R8,
+                (2, 1) => Format::Rg8,
+                (3, 1) => Format::Rgb8,
+                (_, 1) => Format::Rgba8,
+                (1, _) => Format::R32f,
+                (2, _) => Format::Rg32f,
+                (3, _) => Format::Rgb32f,
+                (_, _) => Format::Rgba32f,
+            };
+
+            let len = format.image_size(image.width, image.height);
+            let mut pixels = unsafe { std::slice::from_raw_parts(image.data, len) }.to_vec();
+
+            if flip {
+                let row = format.image_size(image.width, 1);
+                let rows = image.height as usize;
+
+                for y in 0..rows / 2 {
+                    let (top, bottom) = pixels.split_at_mut((rows - 1 - y) * row);
+                    top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+                }
+            }
+
+            Some(ImageData {
+                width: image.width,
+                height: image.height,
+                layers: 1,
+                faces: 1,
+                format,
+                srgb: false,
+                levels: vec![pixels],
+            })
+        }
+
+        pub fn solid(color: [u8; 4]) -> Self {
+            ImageData {
+                width: 1,
+                height: 1,
+                layers: 1,
+                faces: 1,
+                format: Format::Rgba8,
+                srgb: false,
+                levels: vec![color.to_vec()],
+            }
+        }
+
+        // packs separate maps the way glTF stores them: roughness in green, metallic in blue. a missing map
+        // reads as white so only its factor applies, and the smaller map is resampled to the larger one's size
+        pub fn pack_metallic_roughness(metallic: Option<&ImageData>, roughness: Option<&ImageData>) -> Option<Self> {
+            let metallic = metallic.and_then(ImageData::first_channel);
+            let roughness = roughness.and_then(ImageData::first_channel);
+
+            let sizes = [&metallic, &roughness].into_iter().flatten().map(|(width, height, _)| (*width, *height));
+            let width = sizes.clone().map(|(width, _)| width).max()?;
+            let height = sizes.map(|(_, height)| height).max()?;
+
+            let sample = |map: &Option<(i32, i32, Vec<u8>)>, x: i32, y: i32| match map {
+                Some((map_width, map_height, values)) => values[((y * map_height / height) * map_width + x * map_width / width) as usize],
+                None => 255,
+            };
+
+            let mut pixels = Vec::with_capacity(Format::Rgb8.image_size(width, height));
+            for y in 0..height {
+                for x in 0..width {
+                    pixels.extend([0, sample(&roughness, x, y), sample(&metallic, x, y)]);
+                }
+            }
+
+            Some(ImageData {
+                width,
+                height,
+                layers: 1,
+                faces: 1,
+                format: Format::Rgb8,
+                srgb: false,
+                levels: vec![pixels],
+            })
+        }
+
+        // the red channel of the first image as a byte a pixel, None for float formats
+        fn first_channel(&self) -> Option<(i32, i32, Vec<u8>)> {
+            let decompressed;
+            let image = if self.format.block_bytes().is_some() {
+                decompressed = self.decompress()?;
+                &decompressed
+            } else {
+                self
+            };
+
+            // 16 bit channels are little endian, so their high byte comes second
+            let offset = match image.format {
+                Format::R8 | Format::Rg8 | Format::Rgb8 | Format::Rgba8 => 0,
+                Format::Bgra8 => 2,
+                Format::R16 | Format::Rg16 | Format::Rgb16 | Format::Rgba16 => 1,
+                _ => {
+                    println!("Failed to pack {:?} into a metallic roughness map", image.format);
+                    return None;
+                }
+            };
+
+            let stride = image.format.pixel_bytes();
+            let pixels = (image.width * image.height) as usize;
+            let values = image.levels[0].iter().skip(offset).step_by(stride).take(pixels).copied().collect();
+
+            Some((image.width, image.height, values))
+        }
+
+        fn decompress(&self) -> Option<Self> {
+            let mut levels = vec![];
+
+            for (level, blocks) in self.levels.iter().enumerate() {
+                let (width, height) = ((self.width >> level).max(1), (self.height >> level).max(1));
+                let size = self.format.image_size(width, height);
+
+                let mut pixels = vec![];
+                for image in blocks.chunks(size) {
+                    pixels.extend(decompress_blocks(self.format, width, height, image)?);
+                }
+                levels.push(pixels);
+            }
+
+            Some(ImageData {
+                format: self.format.decompressed(),
+                levels,
+                ..*self
+            })
+        }
+
+        fn target(&self) -> gl::types::GLenum {
+            if self.faces == 6 {
+                gl::TEXTURE_CUBE_MAP
+            } else if self.layers > 1 {
+                gl::TEXTURE_2D_ARRAY
+            } else {
+                gl::TEXTURE_2D
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum Wrap {
+        Repeat,
+        MirroredRepeat,
+        ClampToEdge,
+        ClampToBorder,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum Filter {
+        Nearest,
+        Linear,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum ColorSpace {
+        Linear,
+        Srgb,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum Channel {
+        Red,
+        Green,
+        Blue,
+        Alpha,
+        Zero,
+        One,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct SamplerOptions {
+        // s, t and r
+        pub wrap: [Wrap; 3],
+        pub min_filter: Filter,
+        pub mag_filter: Filter,
+        // None samples the base level only
+        pub mip_filter: Option<Filter>,
+        // 1 turns anisotropic filtering off
+        pub max_anisotropy: u32,
+        pub border_color: [u8; 4],
+    }
+
+    impl Default for SamplerOptions {
+        fn default() -> Self {
+            SamplerOptions {
+                wrap: [Wrap::Repeat; 3],
+                min_filter: Filter::Linear,
+                mag_filter: Filter::Linear,
+                mip_filter: Some(Filter::Linear),
+                max_anisotropy: 1,
+                border_color: [0; 4],
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct TextureOptions {
+        // only 8 bit formats have an sRGB variant, wider sources are always linear
+        pub color_space: ColorSpace,
+        pub flip: bool,
+        pub swizzle: [Channel; 4],
+        pub sampler: SamplerOptions,
+    }
+
+    impl Default for TextureOptions {
+        fn default() -> Self {
+            TextureOptions {
+                color_space: ColorSpace::Linear,
+                flip: false,
+                swizzle: [Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha],
+                sampler: SamplerOptions::default(),
+            }
+        }
+    }
+
+    // overrides the sampling state of whatever texture is bound to the same unit
+    pub struct Sampler {
+        gl: Rc<gl::Gl>,
+        sampler: gl::types::GLuint,
+        options: SamplerOptions,
+    }
+
+    impl Sampler {
+        pub fn new(gl: Rc<gl::Gl>, options: SamplerOptions) -> Self {
+            let mut sampler = 0;
+
+            unsafe {
+                gl.GenSamplers(1, &mut sampler);
+            }
+            set_sampling(&gl, Parameters::Sampler(sampler), &options, true);
+
+            Sampler { gl, sampler, options }
+        }
+
+        pub fn options(&self) -> SamplerOptions {
+            self.options
+        }
+
+        pub fn bind(&self, unit: u32) {
+            unsafe {
+                self.gl.BindSampler(unit, self.sampler);
+            }
+        }
+
+        pub fn unbind(gl: &gl::Gl, unit: u32) {
+            unsafe {
+                gl.BindSampler(unit, 0);
+            }
+        }
+    }
+
+    impl Drop for Sampler {
+        fn drop(&mut self) {
+            unsafe {
+                self.gl.DeleteSamplers(1, &self.sampler);
+            }
+        }
+    }
+
     pub struct Texture {
         gl: Rc<gl::Gl>,
         pub tex_type: String,
         pub tex_path: String,
-        texture: gl::types::GLuint,
+        texture: Cell<gl::types::GLuint>,
+        target: Cell<gl::types::GLenum>,
+        bytes: Cell<usize>,
     }
 
     impl Texture {
@@ -211,49 +455,283 @@ pub mod mesh {
                 gl.GenTextures(1, &mut texture);
             }
 
-            Texture { gl, tex_type, tex_path, texture }
+            Texture {
+                gl,
+                tex_type,
+                tex_path,
+                texture: Cell::new(texture),
+                target: Cell::new(gl::TEXTURE_2D),
+                bytes: Cell::new(0),
+            }
         }
 
-        pub fn load(self, path: &str) -> Self {
-            let gl = &self.gl;
-            let texture = self.texture;
+        pub fn new_color(gl: Rc<gl::Gl>, color: [u8; 4]) -> Self {
+            let texture = Texture::new(gl, "".to_string(), "".to_string());
+            texture.bytes.set(color.len());
 
             unsafe {
-                gl.BindTexture(gl::TEXTURE_2D, texture);
+                texture.gl.BindTexture(gl::TEXTURE_2D, texture.texture.get());
+                texture.gl.TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA as i32, 1, 1, 0, gl::RGBA, gl::UNSIGNED_BYTE, color.as_ptr().cast());
 
-                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-
-                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
             }
 
-            let image = Image::new(path);
+            texture
+        }
 
-            let format = match image.components {
-                1 => gl::RED,
-                3 => gl::RGB,
-                4 => gl::RGBA,
-                _ => panic!("Unexpected image format"),
+        // replaces the contents in place, so handles given out for a placeholder see the real image
+        pub fn upload(&self, image: Option<&ImageData>, options: TextureOptions) {
+            let gl = &self.gl;
+
+            let image = match image {
+                Some(image) => image,
+                None => return,
             };
 
-            if image.data == ptr::null_mut() {
-                println!("Failed to load texture");
+            let decompressed;
+            let image = if image.format.block_bytes().is_some() && !compression_supported(gl, image.format) {
+                decompressed = match image.decompress() {
+                    Some(decompressed) => decompressed,
+                    None => {
+                        println!("Failed to load texture {}: {:?} is not supported by the driver", self.tex_path, image.format);
+                        return;
+                    }
+                };
+                &decompressed
             } else {
+                image
+            };
+
+            let target = image.target();
+            let srgb = options.color_space == ColorSpace::Srgb || image.srgb;
+            // block compressed formats can't be relied on to support glGenerateMipmap
+            let generate_mipmaps = image.levels.len() == 1 && image.format.block_bytes().is_none();
+            let mipmapped = generate_mipmaps || image.levels.len() > 1;
+            let swizzle = options.swizzle.map(|channel| channel.gl() as i32);
+
+            // a name keeps the target it was first bound to, so a cube map or array replacing a 2D placeholder
+            // gets a fresh one
+            if target != self.target.get() {
                 unsafe {
-                    gl.TexImage2D(gl::TEXTURE_2D, 0, format as i32, image.width, image.height, 0, format, gl::UNSIGNED_BYTE, image.data.cast());
-                    gl.GenerateMipmap(gl::TEXTURE_2D);
+                    gl.DeleteTextures(1, &self.texture.get());
+                    let mut texture = 0;
+                    gl.GenTextures(1, &mut texture);
+                    self.texture.set(texture);
                 }
             }
+            self.target.set(target);
 
-            self
+            unsafe {
+                gl.BindTexture(target, self.texture.get());
+                gl.TexParameteriv(target, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+                gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            }
+            set_sampling(gl, Parameters::Texture(target), &options.sampler, mipmapped);
+
+            for (level, pixels) in image.levels.iter().enumerate() {
+                let (width, height) = ((image.width >> level).max(1), (image.height >> level).max(1));
+                let size = image.format.image_size(width, height);
+
+                match target {
+                    gl::TEXTURE_CUBE_MAP => {
+                        for (face, pixels) in pixels.chunks(size).take(6).enumerate() {
+                            self.specify(gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32, level, image.format, srgb, (width, height, 1), pixels);
+                        }
+                    }
+                    _ => self.specify(target, level, image.format, srgb, (width, height, image.layers), pixels),
+                }
+            }
+
+            unsafe {
+                // set either way, an earlier upload into the same name may have capped it at a stored chain
+                if generate_mipmaps {
+                    gl.TexParameteri(target, gl::TEXTURE_MAX_LEVEL, 1000);
+                    gl.GenerateMipmap(target);
+                } else {
+                    gl.TexParameteri(target, gl::TEXTURE_MAX_LEVEL, image.levels.len() as i32 - 1);
+                }
+                gl.PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            }
+
+            let bytes = image.levels.iter().map(|level| level.len()).sum::<usize>();
+            // a full mip chain adds a third on top of the base level
+            self.bytes.set(if generate_mipmaps { bytes * 4 / 3 } else { bytes });
+        }
+
+        fn specify(&self, target: gl::types::GLenum, level: usize, format: Format, srgb: bool, (width, height, layers): (i32, i32, i32), pixels: &[u8]) {
+            let gl = &self.gl;
+            let level = level as i32;
+
+            unsafe {
+                match gl_formats(format, srgb) {
+                    (internal_format, 0, _) if target == gl::TEXTURE_2D_ARRAY => {
+                        gl.CompressedTexImage3D(target, level, internal_format, width, height, layers, 0, pixels.len() as i32, pixels.as_ptr().cast())
+                    }
+                    (internal_format, 0, _) => gl.CompressedTexImage2D(target, level, internal_format, width, height, 0, pixels.len() as i32, pixels.as_ptr().cast()),
+                    (internal_format, format, data_type) if target == gl::TEXTURE_2D_ARRAY => {
+                        gl.TexImage3D(target, level, internal_format as i32, width, height, layers, 0, format, data_type, pixels.as_ptr().cast())
+                    }
+                    (internal_format, format, data_type) => gl.TexImage2D(target, level, internal_format as i32, width, height, 0, format, data_type, pixels.as_ptr().cast()),
+                }
+            }
+        }
+
+        pub fn bind(&self) {
+            unsafe {
+                self.gl.BindTexture(self.target.get(), self.texture.get());
+            }
+        }
+
+        pub fn bytes(&self) -> usize {
+            self.bytes.get()
         }
     }
 
     impl Drop for Texture {
         fn drop(&mut self) {
             unsafe {
-                self.gl.DeleteTextures(1, &self.texture);
+                self.gl.DeleteTextures(1, &self.texture.get());
+            }
+        }
+    }
+
+    // (internal format, pixel format, data type), compressed formats have no pixel format
+    fn gl_formats(format: Format, srgb: bool) -> (gl::types::GLenum, gl::types::GLenum, gl::types::GLenum) {
+        match (format, srgb) {
+            (Format::R8, _) => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+            (Format::Rg8, _) => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+            (Format::Rgb8, false) => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            (Format::Rgb8, true) => (gl::SRGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            (Format::Rgba8, false) => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            (Format::Rgba8, true) => (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            (Format::Bgra8, false) => (gl::RGBA8, gl::BGRA, gl::UNSIGNED_BYTE),
+            (Format::Bgra8, true) => (gl::SRGB8_ALPHA8, gl::BGRA, gl::UNSIGNED_BYTE),
+            (Format::R16, _) => (gl::R16, gl::RED, gl::UNSIGNED_SHORT),
+            (Format::Rg16, _) => (gl::RG16, gl::RG, gl::UNSIGNED_SHORT),
+            (Format::Rgb16, _) => (gl::RGB16, gl::RGB, gl::UNSIGNED_SHORT),
+            (Format::Rgba16, _) => (gl::RGBA16, gl::RGBA, gl::UNSIGNED_SHORT),
+            (Format::Rgba16f, _) => (gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT),
+            (Format::R32f, _) => (gl::R32F, gl::RED, gl::FLOAT),
+            (Format::Rg32f, _) => (gl::RG32F, gl::RG, gl::FLOAT),
+            (Format::Rgb32f, _) => (gl::RGB32F, gl::RGB, gl::FLOAT),
+            (Format::Rgba32f, _) => (gl::RGBA32F, gl::RGBA, gl::FLOAT),
+            (Format::Bc1, false) => (gl::COMPRESSED_RGBA_S3TC_DXT1_EXT, 0, 0),
+            (Format::Bc1, true) => (gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT, 0, 0),
+            (Format::Bc2, false) => (gl::COMPRESSED_RGBA_S3TC_DXT3_EXT, 0, 0),
+            (Format::Bc2, true) => (gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT, 0, 0),
+            (Format::Bc3, false) => (gl::COMPRESSED_RGBA_S3TC_DXT5_EXT, 0, 0),
+            (Format::Bc3, true) => (gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT, 0, 0),
+            (Format::Bc4, _) => (gl::COMPRESSED_RED_RGTC1, 0, 0),
+            (Format::Bc5, _) => (gl::COMPRESSED_RG_RGTC2, 0, 0),
+            (Format::Bc6h, _) => (gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT_ARB, 0, 0),
+            (Format::Bc6hSigned, _) => (gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT_ARB, 0, 0),
+            (Format::Bc7, false) => (gl::COMPRESSED_RGBA_BPTC_UNORM_ARB, 0, 0),
+            (Format::Bc7, true) => (gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM_ARB, 0, 0),
+        }
+    }
+
+    // RGTC (BC4 and BC5) is core since 3.0, S3TC and BPTC come from extensions on a 3.3 context
+    fn compression_supported(gl: &gl::Gl, format: Format) -> bool {
+        match format {
+            Format::Bc1 | Format::Bc2 | Format::Bc3 => has_extension(gl, "GL_EXT_texture_compression_s3tc"),
+            Format::Bc6h | Format::Bc6hSigned | Format::Bc7 => has_extension(gl, "GL_ARB_texture_compression_bptc"),
+            _ => true,
+        }
+    }
+
+    fn has_extension(gl: &gl::Gl, name: &str) -> bool {
+        let mut count = 0;
+
+        unsafe {
+            gl.GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+
+            (0..count as u32).any(|i| CStr::from_ptr(gl.GetStringi(gl::EXTENSIONS, i).cast()).to_bytes() == name.as_bytes())
+        }
+    }
+
+    impl Wrap {
+        fn gl(self) -> gl::types::GLenum {
+            match self {
+                Wrap::Repeat => gl::REPEAT,
+                Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+                Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+                Wrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+            }
+        }
+    }
+
+    impl Channel {
+        fn gl(self) -> gl::types::GLenum {
+            match self {
+                Channel::Red => gl::RED,
+                Channel::Green => gl::GREEN,
+                Channel::Blue => gl::BLUE,
+                Channel::Alpha => gl::ALPHA,
+                Channel::Zero => gl::ZERO,
+                Channel::One => gl::ONE,
+            }
+        }
+    }
+
+    // textures and sampler objects take the same parameter names through different entry points
+    enum Parameters {
+        Texture(gl::types::GLenum),
+        Sampler(gl::types::GLuint),
+    }
+
+    impl Parameters {
+        unsafe fn int(&self, gl: &gl::Gl, name: gl::types::GLenum, value: gl::types::GLenum) {
+            match *self {
+                Parameters::Texture(target) => gl.TexParameteri(target, name, value as i32),
+                Parameters::Sampler(sampler) => gl.SamplerParameteri(sampler, name, value as i32),
+            }
+        }
+
+        unsafe fn float(&self, gl: &gl::Gl, name: gl::types::GLenum, value: f32) {
+            match *self {
+                Parameters::Texture(target) => gl.TexParameterf(target, name, value),
+                Parameters::Sampler(sampler) => gl.SamplerParameterf(sampler, name, value),
+            }
+        }
+
+        unsafe fn floats(&self, gl: &gl::Gl, name: gl::types::GLenum, values: &[f32; 4]) {
+            match *self {
+                Parameters::Texture(target) => gl.TexParameterfv(target, name, values.as_ptr()),
+                Parameters::Sampler(sampler) => gl.SamplerParameterfv(sampler, name, values.as_ptr()),
+            }
+        }
+    }
+
+    fn set_sampling(gl: &gl::Gl, parameters: Parameters, options: &SamplerOptions, mipmapped: bool) {
+        let mip_filter = if mipmapped { options.mip_filter } else { None };
+        let min_filter = match (options.min_filter, mip_filter) {
+            (Filter::Nearest, None) => gl::NEAREST,
+            (Filter::Linear, None) => gl::LINEAR,
+            (Filter::Nearest, Some(Filter::Nearest)) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear, Some(Filter::Nearest)) => gl::LINEAR_MIPMAP_NEAREST,
+            (Filter::Nearest, Some(Filter::Linear)) => gl::NEAREST_MIPMAP_LINEAR,
+            (Filter::Linear, Some(Filter::Linear)) => gl::LINEAR_MIPMAP_LINEAR,
+        };
+        let mag_filter = match options.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        };
+        let border_color = options.border_color.map(|channel| channel as f32 / 255.0);
+
+        unsafe {
+            parameters.int(gl, gl::TEXTURE_WRAP_S, options.wrap[0].gl());
+            parameters.int(gl, gl::TEXTURE_WRAP_T, options.wrap[1].gl());
+            parameters.int(gl, gl::TEXTURE_WRAP_R, options.wrap[2].gl());
+            parameters.int(gl, gl::TEXTURE_MIN_FILTER, min_filter);
+            parameters.int(gl, gl::TEXTURE_MAG_FILTER, mag_filter);
+            parameters.floats(gl, gl::TEXTURE_BORDER_COLOR, &border_color);
+
+            if options.max_anisotropy > 1 && has_extension(gl, "GL_EXT_texture_filter_anisotropic") {
+                let mut max_anisotropy = 1.0;
+                gl.GetFloatv(gl::MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max_anisotropy);
+
+                parameters.float(gl, gl::TEXTURE_MAX_ANISOTROPY_EXT, (options.max_anisotropy as f32).min(max_anisotropy));
             }
         }
     }
@@ -1,31 +1,74 @@
 pub mod model {
-    use crate::{gl, Mesh, Program, Rc, Texture, Vec2, Vec3, Vertex};
+    use crate::{gl, optimize, AssetCache, Factors, ImageData, Loader, Material, Mesh, MeshKey, Program, Report, Rc, RefCell, SamplerOptions, Slot, StaticVertex, Texture, TextureOptions, Vec2, Vec3, Vertex};
+    use std::mem;
     use russimp::{
-        material::Material,
+        material::{self, PropertyTypeInfo},
         mesh,
         node::Node,
         scene::{PostProcess, Scene},
         texture::TextureType,
     };
 
-    pub struct Model {
-        gl: Rc<gl::Gl>,
-        textures_loaded: Vec<Rc<Texture>>,
-        meshes: Vec<Mesh>,
-        directory: String,
+    pub fn texture_options(slot: Slot) -> TextureOptions {
+        TextureOptions {
+            color_space: slot.color_space(),
+            flip: true,
+            sampler: SamplerOptions {
+                max_anisotropy: 8,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
     }
 
-    impl Model {
-        pub fn new(gl: Rc<gl::Gl>) -> Self {
-            Model {
-                gl,
-                textures_loaded: vec![],
-                meshes: vec![],
-                directory: "".to_string(),
+    // where a slot's image comes from
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum TextureSource {
+        File(String),
+        // separate single channel maps, packed into one texture when decoded
+        MetallicRoughness { metallic: Option<String>, roughness: Option<String> },
+    }
+
+    impl TextureSource {
+        // what the asset cache knows the texture by
+        pub fn path(&self) -> String {
+            match self {
+                TextureSource::File(path) => path.clone(),
+                TextureSource::MetallicRoughness { metallic, roughness } => {
+                    format!("{}+{}", metallic.as_deref().unwrap_or("-"), roughness.as_deref().unwrap_or("-"))
+                }
+            }
+        }
+
+        pub fn decode(&self, flip: bool) -> Option<ImageData> {
+            match self {
+                TextureSource::File(path) => ImageData::decode(path, flip),
+                TextureSource::MetallicRoughness { metallic, roughness } => {
+                    let decode = |path: &Option<String>| path.as_ref().and_then(|path| ImageData::decode(path, flip));
+                    ImageData::pack_metallic_roughness(decode(metallic).as_ref(), decode(roughness).as_ref())
+                }
             }
         }
+    }
+
+    // plain data, so a scene can be imported on a worker thread and uploaded later
+    #[derive(Default)]
+    pub struct MeshData {
+        pub vertices: Vec<Vertex>,
+        pub indices: Vec<u32>,
+        pub textures: Vec<(Slot, TextureSource)>,
+        pub factors: Factors,
+    }
+
+    pub struct SceneData {
+        pub meshes: Vec<MeshData>,
+        pub order: Vec<u32>,
+        // what welding and reordering did to all the meshes together
+        pub report: Report,
+    }
 
-        pub fn load_model(mut self, path: &str) -> Self {
+    impl SceneData {
+        pub fn read(path: &str) -> Result<Self, String> {
             let scene = Scene::from_file(
                 path,
                 vec![
@@ -35,116 +78,249 @@ pub mod model {
                     PostProcess::CalculateTangentSpace,
                 ],
             )
-            .unwrap();
+            .map_err(|error| format!("Failed to load scene {path}: {error}"))?;
 
             if scene.flags > 0 || scene.root.is_none() {
-                panic!("Failed to load scene.");
+                return Err(format!("Failed to load scene {path}."));
             }
 
-            self.directory = path.rsplit_once("/").unwrap().0.to_string();
+            // a bare file name has its textures next to it
+            let directory = path.rsplit_once('/').map_or(".", |(directory, _)| directory);
 
-            self.process_node(&*scene.root.as_ref().unwrap().borrow(), &scene);
+            let mut report = Report::default();
 
-            self
+            // welded and reordered here so the work stays on the loader's worker threads
+            let meshes = scene
+                .meshes
+                .iter()
+                .map(|mesh| {
+                    let mut data = process_mesh(mesh, scene.materials.get(mesh.material_index as usize).unwrap(), directory);
+                    let (vertices, indices, mesh_report) = optimize(mem::take(&mut data.vertices), mem::take(&mut data.indices));
+
+                    report = report.merge(mesh_report);
+                    MeshData { vertices, indices, ..data }
+                })
+                .collect();
+
+            let mut order = vec![];
+            process_node(&*scene.root.as_ref().unwrap().borrow(), &mut order);
+
+            Ok(SceneData { meshes, order, report })
         }
+    }
 
-        fn process_node(&mut self, node: &Node, scene: &Scene) {
-            for mesh in &node.meshes {
-                let mesh = self.process_mesh(scene.meshes.get(*mesh as usize).unwrap(), scene);
-                self.meshes.push(mesh);
-            }
+    pub struct Model {
+        gl: Rc<gl::Gl>,
+        cache: Rc<RefCell<AssetCache>>,
+        meshes: Rc<RefCell<Vec<Rc<Mesh>>>>,
+        placeholder: Option<Rc<Mesh>>,
+    }
 
-            for node in &node.children {
-                self.process_node(&*node.borrow(), scene);
+    impl Model {
+        pub fn new(gl: Rc<gl::Gl>, cache: Rc<RefCell<AssetCache>>) -> Self {
+            Model {
+                gl,
+                cache,
+                meshes: Rc::new(RefCell::new(vec![])),
+                placeholder: None,
             }
         }
 
-        fn process_mesh(&mut self, mesh: &mesh::Mesh, scene: &Scene) -> Mesh {
-            let mut vertices: Vec<Vertex> = vec![];
-            let mut indices: Vec<u32> = vec![];
-            let mut textures: Vec<Rc<Texture>> = vec![];
+        pub fn load_model(self, path: &str) -> Self {
+            let cached = self.cache.borrow().model(path);
+            if let Some(meshes) = cached {
+                *self.meshes.borrow_mut() = meshes;
+                return self;
+            }
 
-            for i in 0..mesh.vertices.len() {
-                let vector = mesh.vertices.get(i).unwrap();
-                let position = Vec3::new(vector.x, vector.y, vector.z);
+            let mut scene = SceneData::read(path).unwrap_or_else(|error| panic!("{error}"));
 
-                let normal = if i < mesh.normals.len() {
-                    let vector = mesh.normals.get(i).unwrap();
-                    Vec3::new(vector.x, vector.y, vector.z)
-                } else {
-                    Vec3::zero()
-                };
+            let mut keys = vec![];
+            for index in scene.order {
+                let key = MeshKey::new(path, index);
+
+                let cached = self.cache.borrow().mesh(&key);
+                let mesh = match cached {
+                    Some(mesh) => mesh,
+                    None => {
+                        let data = mem::take(&mut scene.meshes[index as usize]);
+                        let mut cache = self.cache.borrow_mut();
 
-                let (tex_coords, tangent, bitangent) = if mesh.texture_coords.len() > 0 {
-                    let vector = mesh.texture_coords.get(0).unwrap().as_ref().unwrap().get(i).unwrap();
-                    let tex_coords = Vec2::new(vector.x, vector.y);
-                    let vector = mesh.tangents.get(i).unwrap();
-                    let tangent = Vec3::new(vector.x, vector.y, vector.z);
-                    let vector = mesh.bitangents.get(i).unwrap();
-                    let bitangent = Vec3::new(vector.x, vector.y, vector.z);
-
-                    (tex_coords, tangent, bitangent)
-                } else {
-                    (Vec2::zero(), Vec3::zero(), Vec3::zero())
+                        let textures = data
+                            .textures
+                            .iter()
+                            .map(|(slot, source)| (*slot, cache.texture(&format!("{slot:?}"), source, texture_options(*slot))))
+                            .collect();
+                        let material = build_material(&cache, data.factors, textures);
+
+                        cache.insert_mesh(key.clone(), Mesh::new(Rc::clone(&self.gl), StaticVertex::pack(&data.vertices), data.indices, material))
+                    }
                 };
 
-                vertices.push(Vertex {
-                    position,
-                    normal,
-                    tex_coords,
-                    tangent,
-                    bitangent,
-                    bone_ids: [0; 4],
-                    weights: [0.0; 4],
-                });
+                self.meshes.borrow_mut().push(mesh);
+                keys.push(key);
+            }
+            self.cache.borrow_mut().insert_model(path, keys);
+
+            self
+        }
+
+        pub fn load_model_async(mut self, path: &str, loader: &mut Loader) -> Self {
+            let cached = self.cache.borrow().model(path);
+            if let Some(meshes) = cached {
+                *self.meshes.borrow_mut() = meshes;
+                return self;
             }
 
-            for face in &mesh.faces {
-                for index in &face.0 {
-                    indices.push(*index);
+            self.placeholder = Some(loader.placeholder());
+            loader.load_model(path, Rc::clone(&self.meshes));
+
+            self
+        }
+
+        pub fn draw(&self, shader: &Program) {
+            let meshes = self.meshes.borrow();
+
+            if meshes.is_empty() {
+                if let Some(placeholder) = &self.placeholder {
+                    placeholder.draw(shader);
                 }
             }
 
-            let material = scene.materials.get(mesh.material_index as usize).unwrap();
+            for mesh in meshes.iter() {
+                mesh.draw(shader)
+            }
+        }
+    }
+
+    pub fn build_material(cache: &AssetCache, factors: Factors, textures: Vec<(Slot, Rc<Texture>)>) -> Material {
+        let mut material = Material::new(cache.fallbacks());
+        material.factors = factors;
 
-            let mut diffuse_maps = self.load_material_textures(material, TextureType::Diffuse, "texture_diffuse");
-            textures.append(&mut diffuse_maps);
-            let mut specular_maps = self.load_material_textures(material, TextureType::Specular, "texture_specular");
-            textures.append(&mut specular_maps);
-            let mut normal_maps = self.load_material_textures(material, TextureType::Height, "texture_normal");
-            textures.append(&mut normal_maps);
-            let mut height_maps = self.load_material_textures(material, TextureType::Ambient, "texture_height");
-            textures.append(&mut height_maps);
+        for (slot, texture) in textures {
+            material.set_texture(slot, texture);
+        }
+
+        material
+    }
 
-            Mesh::new(Rc::clone(&self.gl), vertices, indices, textures)
+    fn process_node(node: &Node, order: &mut Vec<u32>) {
+        order.extend(&node.meshes);
+
+        for node in &node.children {
+            process_node(&*node.borrow(), order);
         }
+    }
 
-        fn load_material_textures(&mut self, material: &Material, tex_type: TextureType, type_name: &str) -> Vec<Rc<Texture>> {
-            let mut textures = vec![];
+    fn process_mesh(mesh: &mesh::Mesh, material: &material::Material, directory: &str) -> MeshData {
+        let mut vertices: Vec<Vertex> = vec![];
+        let mut indices: Vec<u32> = vec![];
 
-            for vector in material.textures.get(&tex_type) {
-                for texture in vector {
-                    let matches = self.textures_loaded.iter().filter(|t| texture.path == t.tex_path).collect::<Vec<_>>();
+        for i in 0..mesh.vertices.len() {
+            let vector = mesh.vertices.get(i).unwrap();
+            let position = Vec3::new(vector.x, vector.y, vector.z);
 
-                    if matches.len() > 0 {
-                        textures.push(Rc::clone(*matches.get(0).unwrap()));
-                    } else {
-                        let tex_path = self.directory.clone() + &("/".to_string() + &texture.path);
-                        let mesh_tex = Rc::new(Texture::new(Rc::clone(&self.gl), type_name.to_string(), texture.path.clone()).load(&tex_path));
+            let normal = if i < mesh.normals.len() {
+                let vector = mesh.normals.get(i).unwrap();
+                Vec3::new(vector.x, vector.y, vector.z)
+            } else {
+                Vec3::zero()
+            };
 
-                        textures.push(Rc::clone(&mesh_tex));
-                        self.textures_loaded.push(mesh_tex);
-                    }
-                }
+            let (tex_coords, tangent, bitangent) = if mesh.texture_coords.len() > 0 {
+                let vector = mesh.texture_coords.get(0).unwrap().as_ref().unwrap().get(i).unwrap();
+                let tex_coords = Vec2::new(vector.x, vector.y);
+                let vector = mesh.tangents.get(i).unwrap();
+                let tangent = Vec3::new(vector.x, vector.y, vector.z);
+                let vector = mesh.bitangents.get(i).unwrap();
+                let bitangent = Vec3::new(vector.x, vector.y, vector.z);
+
+                (tex_coords, tangent, bitangent)
+            } else {
+                (Vec2::zero(), Vec3::zero(), Vec3::zero())
+            };
+
+            vertices.push(Vertex {
+                position,
+                normal,
+                tex_coords,
+                tangent,
+                bitangent,
+                bone_ids: [0; 4],
+                weights: [0.0; 4],
+            });
+        }
+
+        for face in &mesh.faces {
+            for index in &face.0 {
+                indices.push(*index);
             }
+        }
+
+        let (textures, factors) = process_material(material, directory);
 
-            textures
+        MeshData {
+            vertices,
+            indices,
+            textures,
+            factors,
         }
+    }
 
-        pub fn draw(&self, shader: &Program) {
-            for i in 0..self.meshes.len() {
-                self.meshes[i].draw(shader)
+    fn process_material(source: &material::Material, directory: &str) -> (Vec<(Slot, TextureSource)>, Factors) {
+        let mut textures = vec![];
+        let mut factors = Factors::default();
+
+        // the OBJ importer reports bump maps as height, so Height feeds the normal slot as in the tutorial.
+        // specular maps are left out: a metallic-roughness material has no specular color to scale, and
+        // the tutorial's specular maps are greyscale masks that would misread as either metallic or roughness
+        let slots = [
+            (Slot::BaseColor, [TextureType::BaseColor, TextureType::Diffuse]),
+            (Slot::Normal, [TextureType::Normals, TextureType::Height]),
+            (Slot::Occlusion, [TextureType::AmbientOcclusion, TextureType::LightMap]),
+            (Slot::Emissive, [TextureType::EmissionColor, TextureType::Emissive]),
+            (Slot::Height, [TextureType::Displacement, TextureType::Ambient]),
+        ];
+
+        for (slot, tex_types) in slots {
+            if let Some(tex_path) = tex_types.iter().find_map(|tex_type| material_texture_path(source, *tex_type, directory)) {
+                textures.push((slot, TextureSource::File(tex_path)));
+            }
+        }
+
+        // glTF reports its packed map as both, anything else has one channel per file
+        let metallic = material_texture_path(source, TextureType::Metalness, directory);
+        let roughness = material_texture_path(source, TextureType::Roughness, directory);
+        match (metallic, roughness) {
+            (None, None) => {}
+            (Some(metallic), Some(roughness)) if metallic == roughness => textures.push((Slot::MetallicRoughness, TextureSource::File(metallic))),
+            (metallic, roughness) => textures.push((Slot::MetallicRoughness, TextureSource::MetallicRoughness { metallic, roughness })),
+        }
+
+        for property in source.properties.iter().filter(|property| property.semantic == TextureType::None) {
+            match (property.key.as_ref(), &property.data) {
+                ("$clr.base", PropertyTypeInfo::FloatArray(color)) | ("$clr.diffuse", PropertyTypeInfo::FloatArray(color)) if color.len() >= 3 => {
+                    factors.base_color = [color[0], color[1], color[2], *color.get(3).unwrap_or(&1.0)];
+                }
+                ("$mat.metallicFactor", PropertyTypeInfo::FloatArray(value)) if !value.is_empty() => factors.metallic = value[0],
+                ("$mat.roughnessFactor", PropertyTypeInfo::FloatArray(value)) if !value.is_empty() => factors.roughness = value[0],
+                ("$clr.emissive", PropertyTypeInfo::FloatArray(color)) if color.len() >= 3 => {
+                    factors.emissive = Vec3::new(color[0], color[1], color[2]);
+                }
+                _ => {}
             }
         }
+
+        if textures.iter().any(|(slot, _)| *slot == Slot::Emissive) && factors.emissive.dot(factors.emissive) == 0.0 {
+            factors.emissive = Vec3::one();
+        }
+
+        (textures, factors)
+    }
+
+    fn material_texture_path(material: &material::Material, tex_type: TextureType, directory: &str) -> Option<String> {
+        let texture = material.textures.get(&tex_type)?.first()?;
+
+        Some(directory.to_string() + &("/".to_string() + &texture.path))
     }
 }
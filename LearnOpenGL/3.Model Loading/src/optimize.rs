@@ -0,0 +1,456 @@
+pub mod optimize {
+    use crate::{Vec3, Vertex};
+    use std::{collections::HashMap, fmt};
+
+    // the cache Forsyth's scores are tuned for, and the FIFO the statistics simulate as a typical GPU
+    const CACHE_SIZE: usize = 32;
+    const SIMULATED_CACHE_SIZE: usize = 16;
+    const OVERDRAW_THRESHOLD: f32 = 1.05;
+
+    const LAST_TRIANGLE_SCORE: f32 = 0.75;
+    const CACHE_DECAY_POWER: f32 = 1.5;
+    const VALENCE_BOOST_SCALE: f32 = 2.0;
+    const VALENCE_BOOST_POWER: f32 = 0.5;
+
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Statistics {
+        pub vertices: usize,
+        pub triangles: usize,
+        // vertex shader invocations per triangle, 0.5 is the best possible for a regular grid and 3 the worst
+        pub acmr: f32,
+        // vertex shader invocations per vertex, 1 is ideal
+        pub atvr: f32,
+    }
+
+    impl Statistics {
+        pub fn measure(vertex_count: usize, indices: &[u32]) -> Self {
+            let misses = cache_misses(indices, SIMULATED_CACHE_SIZE) as f32;
+            let triangles = indices.len() / 3;
+
+            Statistics {
+                vertices: vertex_count,
+                triangles,
+                acmr: if triangles == 0 { 0.0 } else { misses / triangles as f32 },
+                atvr: if vertex_count == 0 { 0.0 } else { misses / vertex_count as f32 },
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Report {
+        pub before: Statistics,
+        pub after: Statistics,
+    }
+
+    impl Report {
+        pub fn merge(self, other: Report) -> Report {
+            Report {
+                before: merge(self.before, other.before),
+                after: merge(self.after, other.after),
+            }
+        }
+    }
+
+    impl fmt::Display for Report {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "{} -> {} vertices, ACMR {:.3} -> {:.3}, ATVR {:.3} -> {:.3}",
+                self.before.vertices, self.after.vertices, self.before.acmr, self.after.acmr, self.before.atvr, self.after.atvr
+            )
+        }
+    }
+
+    // the whole pipeline, in the order each step expects its input
+    pub fn optimize(vertices: Vec<Vertex>, indices: Vec<u32>) -> (Vec<Vertex>, Vec<u32>, Report) {
+        let before = Statistics::measure(vertices.len(), &indices);
+
+        let (vertices, indices) = weld(&vertices, &indices);
+        let indices = optimize_vertex_cache(&indices, vertices.len());
+        let indices = optimize_overdraw(&vertices, &indices, OVERDRAW_THRESHOLD);
+        let (vertices, indices) = optimize_vertex_fetch(&vertices, &indices);
+
+        let after = Statistics::measure(vertices.len(), &indices);
+
+        (vertices, indices, Report { before, after })
+    }
+
+    // merges vertices whose attributes are bitwise identical
+    pub fn weld(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+        let mut unique = HashMap::new();
+        let mut welded = vec![];
+
+        let remap: Vec<u32> = vertices
+            .iter()
+            .map(|vertex| {
+                *unique.entry(vertex_key(vertex)).or_insert_with(|| {
+                    welded.push(*vertex);
+                    welded.len() as u32 - 1
+                })
+            })
+            .collect();
+
+        (welded, indices.iter().map(|&index| remap[index as usize]).collect())
+    }
+
+    // Tom Forsyth's linear-speed vertex cache optimisation: greedily emits the triangle whose vertices
+    // score best, favouring ones already in the cache and ones with few triangles left to draw
+    pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+        let triangle_count = indices.len() / 3;
+
+        let mut adjacency: Vec<Vec<usize>> = vec![vec![]; vertex_count];
+        for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+            for &vertex in corners {
+                adjacency[vertex as usize].push(triangle);
+            }
+        }
+
+        let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+        let mut vertex_scores: Vec<f32> = adjacency.iter().map(|triangles| vertex_score(None, triangles.len())).collect();
+        let mut emitted = vec![false; triangle_count];
+
+        let mut cache: Vec<u32> = vec![];
+        let mut output = Vec::with_capacity(indices.len());
+        let mut cursor = 0;
+        let mut best = None;
+
+        for _ in 0..triangle_count {
+            // nothing in the cache touches an open triangle, so restart from the first one left
+            let triangle = match best {
+                Some(triangle) => triangle,
+                None => {
+                    while emitted[cursor] {
+                        cursor += 1;
+                    }
+                    cursor
+                }
+            };
+
+            let corners = &indices[triangle * 3..triangle * 3 + 3];
+            output.extend_from_slice(corners);
+            emitted[triangle] = true;
+
+            for &vertex in corners {
+                adjacency[vertex as usize].retain(|&other| other != triangle);
+            }
+
+            let mut touched: Vec<u32> = cache.iter().copied().filter(|vertex| !corners.contains(vertex)).collect();
+            cache = corners.iter().copied().chain(touched.iter().copied()).collect();
+            touched.extend_from_slice(corners);
+
+            for vertex in cache.drain(CACHE_SIZE.min(cache.len())..) {
+                cache_position[vertex as usize] = None;
+            }
+            for (position, &vertex) in cache.iter().enumerate() {
+                cache_position[vertex as usize] = Some(position);
+            }
+
+            for &vertex in &touched {
+                let vertex = vertex as usize;
+                vertex_scores[vertex] = vertex_score(cache_position[vertex], adjacency[vertex].len());
+            }
+
+            best = None;
+            let mut best_score = f32::MIN;
+
+            for &vertex in &touched {
+                for &other in &adjacency[vertex as usize] {
+                    let score: f32 = indices[other * 3..other * 3 + 3].iter().map(|&corner| vertex_scores[corner as usize]).sum();
+
+                    if score > best_score {
+                        best = Some(other);
+                        best_score = score;
+                    }
+                }
+            }
+        }
+
+        output
+    }
+
+    // splits the cache optimised order into clusters where the cache restarts, then draws the clusters facing
+    // outwards first so they occlude the rest; keeps the input if that costs more than `threshold` in ACMR
+    pub fn optimize_overdraw(vertices: &[Vertex], indices: &[u32], threshold: f32) -> Vec<u32> {
+        let mut clusters: Vec<(usize, usize)> = vec![];
+        let mut cache: Vec<u32> = vec![];
+        let mut start = 0;
+
+        for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+            let misses = corners.iter().filter(|vertex| !cache.contains(vertex)).count();
+
+            if misses == 3 && triangle > start {
+                clusters.push((start, triangle));
+                start = triangle;
+            }
+            simulate_fifo(&mut cache, corners, SIMULATED_CACHE_SIZE);
+        }
+        clusters.push((start, indices.len() / 3));
+
+        if clusters.len() < 2 {
+            return indices.to_vec();
+        }
+
+        let position = |index: u32| vertices[index as usize].position;
+        let mesh_center = vertices.iter().fold(Vec3::zero(), |sum, vertex| sum + vertex.position) * (1.0 / vertices.len() as f32);
+
+        let mut keyed: Vec<(f32, (usize, usize))> = clusters
+            .into_iter()
+            .map(|(first, last)| {
+                let mut sum = Vec3::zero();
+                let mut normal = Vec3::zero();
+
+                for corners in indices[first * 3..last * 3].chunks_exact(3) {
+                    let [a, b, c] = [0, 1, 2].map(|i| position(corners[i]));
+                    sum += (a + b + c) * (1.0 / 3.0);
+                    normal += (b - a).cross(c - a);
+                }
+                let center = sum * (1.0 / (last - first) as f32);
+
+                let length = normal.dot(normal).sqrt();
+                let key = if length > 0.0 { (center - mesh_center).dot(normal) / length } else { 0.0 };
+
+                (key, (first, last))
+            })
+            .collect();
+
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let reordered: Vec<u32> = keyed.iter().flat_map(|&(_, (first, last))| indices[first * 3..last * 3].iter().copied()).collect();
+
+        if cache_misses(&reordered, SIMULATED_CACHE_SIZE) as f32 > cache_misses(indices, SIMULATED_CACHE_SIZE) as f32 * threshold {
+            indices.to_vec()
+        } else {
+            reordered
+        }
+    }
+
+    // renumbers vertices in the order the indices first use them, so fetches walk memory forwards; unused ones are dropped
+    pub fn optimize_vertex_fetch(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+        let mut remap = vec![None; vertices.len()];
+        let mut reordered = vec![];
+
+        let indices = indices
+            .iter()
+            .map(|&index| {
+                *remap[index as usize].get_or_insert_with(|| {
+                    reordered.push(vertices[index as usize]);
+                    reordered.len() as u32 - 1
+                })
+            })
+            .collect();
+
+        (reordered, indices)
+    }
+
+    pub fn cache_misses(indices: &[u32], cache_size: usize) -> usize {
+        let mut cache = vec![];
+
+        indices.chunks_exact(3).map(|corners| simulate_fifo(&mut cache, corners, cache_size)).sum()
+    }
+
+    fn simulate_fifo(cache: &mut Vec<u32>, corners: &[u32], cache_size: usize) -> usize {
+        let mut misses = 0;
+
+        for &vertex in corners {
+            if !cache.contains(&vertex) {
+                cache.push(vertex);
+                if cache.len() > cache_size {
+                    cache.remove(0);
+                }
+                misses += 1;
+            }
+        }
+
+        misses
+    }
+
+    fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+        if remaining_triangles == 0 {
+            return -1.0;
+        }
+
+        let cache_score = match cache_position {
+            // the last triangle's vertices score lower so it isn't simply extended into a strip
+            Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+            Some(position) => (1.0 - (position - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(CACHE_DECAY_POWER),
+            None => 0.0,
+        };
+
+        cache_score + VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER)
+    }
+
+    fn vertex_key(vertex: &Vertex) -> Vec<u32> {
+        let vectors = [vertex.position, vertex.normal, vertex.tangent, vertex.bitangent];
+
+        vectors
+            .iter()
+            .flat_map(|vector| [vector.x, vector.y, vector.z])
+            .chain([vertex.tex_coords.x, vertex.tex_coords.y])
+            .chain(vertex.weights)
+            .map(f32::to_bits)
+            .chain(vertex.bone_ids.map(|id| id as u32))
+            .collect()
+    }
+
+    fn merge(a: Statistics, b: Statistics) -> Statistics {
+        let triangles = a.triangles + b.triangles;
+        let vertices = a.vertices + b.vertices;
+        let misses = a.acmr * a.triangles as f32 + b.acmr * b.triangles as f32;
+
+        Statistics {
+            vertices,
+            triangles,
+            acmr: if triangles == 0 { 0.0 } else { misses / triangles as f32 },
+            atvr: if vertices == 0 { 0.0 } else { misses / vertices as f32 },
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::Vec2;
+
+        // xorshift, so the shuffles are the same every run
+        struct Random(u32);
+
+        impl Random {
+            fn next(&mut self) -> u32 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 17;
+                self.0 ^= self.0 << 5;
+                self.0
+            }
+
+            fn shuffle<T>(&mut self, values: &mut [T]) {
+                for i in (1..values.len()).rev() {
+                    values.swap(i, self.next() as usize % (i + 1));
+                }
+            }
+        }
+
+        fn vertex(x: f32, y: f32, u: f32) -> Vertex {
+            Vertex {
+                position: Vec3::new(x, y, 0.0),
+                normal: Vec3::new(0.0, 0.0, 1.0),
+                tex_coords: Vec2::new(u, y),
+                tangent: Vec3::new(1.0, 0.0, 0.0),
+                bitangent: Vec3::new(0.0, 1.0, 0.0),
+                bone_ids: [0; 4],
+                weights: [0.0; 4],
+            }
+        }
+
+        // a size by size grid as a triangle soup, in shuffled order, with a uv seam down the middle
+        // so the seam's positions appear twice with different texture coordinates
+        fn soup(size: usize, random: &mut Random) -> (Vec<Vertex>, Vec<u32>, usize) {
+            let corner = |x: usize, y: usize, right_of_seam: bool| {
+                let u = if right_of_seam { x as f32 + 1.0 } else { x as f32 };
+                vertex(x as f32, y as f32, u)
+            };
+
+            let mut triangles = vec![];
+            for y in 0..size {
+                for x in 0..size {
+                    let right = x >= size / 2;
+                    let [a, b, c, d] = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)].map(|(x, y)| corner(x, y, right));
+                    triangles.push([a, b, c]);
+                    triangles.push([a, c, d]);
+                }
+            }
+            random.shuffle(&mut triangles);
+
+            let vertices: Vec<Vertex> = triangles.into_iter().flatten().collect();
+            let indices = (0..vertices.len() as u32).collect();
+            let distinct = (size + 1) * (size + 1) + (size + 1);
+            (vertices, indices, distinct)
+        }
+
+        // each triangle as its corners' attributes, rotated to start at the smallest so winding is kept
+        fn triangles(vertices: &[Vertex], indices: &[u32]) -> Vec<Vec<Vec<u32>>> {
+            let mut triangles: Vec<_> = indices
+                .chunks_exact(3)
+                .map(|corners| {
+                    let mut triangle: Vec<_> = corners.iter().map(|&index| vertex_key(&vertices[index as usize])).collect();
+                    let first = (0..3).min_by_key(|&i| triangle[i].clone()).unwrap();
+                    triangle.rotate_left(first);
+                    triangle
+                })
+                .collect();
+            triangles.sort();
+            triangles
+        }
+
+        fn in_range(indices: &[u32], vertex_count: usize) -> bool {
+            indices.iter().all(|&index| (index as usize) < vertex_count)
+        }
+
+        #[test]
+        fn weld_keeps_every_distinct_vertex() {
+            let (vertices, indices, distinct) = soup(8, &mut Random(0x9e3779b9));
+            let (welded, remapped) = weld(&vertices, &indices);
+
+            assert_eq!(welded.len(), distinct);
+            assert_eq!(remapped.len(), indices.len());
+            assert!(in_range(&remapped, welded.len()));
+
+            let mut keys: Vec<_> = welded.iter().map(vertex_key).collect();
+            keys.sort();
+            keys.dedup();
+            assert_eq!(keys.len(), welded.len(), "two welded vertices are identical");
+
+            for (&before, &after) in indices.iter().zip(&remapped) {
+                assert_eq!(vertex_key(&vertices[before as usize]), vertex_key(&welded[after as usize]));
+            }
+        }
+
+        #[test]
+        fn reordering_keeps_every_triangle() {
+            let (vertices, indices, _) = soup(8, &mut Random(0x2545f491));
+            let expected = triangles(&vertices, &indices);
+            let (vertices, indices) = weld(&vertices, &indices);
+
+            let cache = optimize_vertex_cache(&indices, vertices.len());
+            assert!(in_range(&cache, vertices.len()));
+            assert_eq!(triangles(&vertices, &cache), expected);
+
+            let overdraw = optimize_overdraw(&vertices, &cache, OVERDRAW_THRESHOLD);
+            assert!(in_range(&overdraw, vertices.len()));
+            assert_eq!(triangles(&vertices, &overdraw), expected);
+
+            let (fetched, fetch) = optimize_vertex_fetch(&vertices, &overdraw);
+            assert_eq!(fetched.len(), vertices.len());
+            assert!(in_range(&fetch, fetched.len()));
+            assert_eq!(triangles(&fetched, &fetch), expected);
+            // fetch order means the first use of each vertex comes in order
+            let mut next = 0;
+            for &index in &fetch {
+                assert!(index <= next);
+                next = next.max(index + 1);
+            }
+        }
+
+        #[test]
+        fn acmr_does_not_increase() {
+            let mut random = Random(0x1b873593);
+
+            for size in [2, 4, 16, 32] {
+                let (vertices, indices, distinct) = soup(size, &mut random);
+                let expected = triangles(&vertices, &indices);
+
+                // measured on the welded mesh so the before and after share a vertex count
+                let (welded, welded_indices) = weld(&vertices, &indices);
+                let before = Statistics::measure(welded.len(), &welded_indices);
+                let cache = optimize_vertex_cache(&welded_indices, welded.len());
+                assert!(Statistics::measure(welded.len(), &cache).acmr <= before.acmr);
+
+                let (vertices, indices, report) = optimize(vertices, indices);
+                assert_eq!(vertices.len(), distinct);
+                assert_eq!(report.after.vertices, distinct);
+                assert_eq!(report.after.triangles, report.before.triangles);
+                assert_eq!(triangles(&vertices, &indices), expected);
+                assert!(report.after.acmr <= before.acmr, "{size}: {report}");
+                assert!(report.after.acmr <= report.before.acmr, "{size}: {report}");
+            }
+        }
+    }
+}
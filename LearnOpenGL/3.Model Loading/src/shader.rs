@@ -1,8 +1,9 @@
 pub mod shader {
-    use crate::{gl, Mat4, Vec3};
+    use crate::{gl, Mat4, MaterialLayout, Vec3};
     use std::{fs::File, io::prelude::*, ptr, rc::Rc, str};
 
     const LOG_SIZE: usize = 1024;
+    const NAME_SIZE: usize = 256;
 
     struct Shader {
         gl: Rc<gl::Gl>,
@@ -53,18 +54,31 @@ pub mod shader {
         }
     }
 
+    pub struct Uniform {
+        pub name: String,
+        pub kind: gl::types::GLenum,
+        pub location: gl::types::GLint,
+    }
+
     pub struct Program {
         gl: Rc<gl::Gl>,
         program: gl::types::GLuint,
+        uniforms: Vec<Uniform>,
+        material_layout: MaterialLayout,
     }
 
     impl Program {
         pub fn new(gl: Rc<gl::Gl>) -> Self {
             let program = unsafe { gl.CreateProgram() };
-            Program { gl, program }
+            Program {
+                gl,
+                program,
+                uniforms: vec![],
+                material_layout: MaterialLayout::default(),
+            }
         }
 
-        pub fn link(self, vertex_file: &str, fragment_file: &str) -> Self {
+        pub fn link(mut self, vertex_file: &str, fragment_file: &str) -> Self {
             let gl = &self.gl;
             let program = self.program;
 
@@ -102,9 +116,48 @@ pub mod shader {
                 );
             }
 
+            self.uniforms = self.active_uniforms();
+            self.material_layout = MaterialLayout::reflect(&self.uniforms);
+
             self
         }
 
+        fn active_uniforms(&self) -> Vec<Uniform> {
+            let gl = &self.gl;
+            let program = self.program;
+
+            let mut count = 0;
+            unsafe {
+                gl.GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+            }
+
+            let mut uniforms = vec![];
+            for index in 0..count as u32 {
+                let mut name = [0; NAME_SIZE];
+                let mut length = 0;
+                let mut size = 0;
+                let mut kind = 0;
+
+                let location = unsafe {
+                    gl.GetActiveUniform(program, index, NAME_SIZE as i32, &mut length, &mut size, &mut kind, name.as_mut_ptr());
+                    gl.GetUniformLocation(program, name.as_ptr())
+                };
+
+                let name = str::from_utf8(&name[..length as usize].iter().map(|i| *i as u8).collect::<Vec<_>>()).unwrap().to_string();
+                uniforms.push(Uniform { name, kind, location });
+            }
+
+            uniforms
+        }
+
+        pub fn uniform(&self, name: &str) -> Option<&Uniform> {
+            self.uniforms.iter().find(|uniform| uniform.name == name)
+        }
+
+        pub fn material_layout(&self) -> &MaterialLayout {
+            &self.material_layout
+        }
+
         pub fn apply(&self) {
             unsafe {
                 self.gl.UseProgram(self.program);
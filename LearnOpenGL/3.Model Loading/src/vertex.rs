@@ -0,0 +1,275 @@
+pub mod vertex {
+    use crate::{gl, size_of, MeshVertex, Vec2, Vec3};
+    use std::{mem, slice};
+
+    pub const MAX_BONE_INFLUENCE: usize = 4;
+
+    // component count per attribute, the normalized formats read as floats in [0, 1] or [-1, 1]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum AttributeFormat {
+        Float(i32),
+        Half(i32),
+        Unorm8(i32),
+        Snorm8(i32),
+        Unorm16(i32),
+        Snorm16(i32),
+        Int(i32),
+        UnsignedByte(i32),
+    }
+
+    impl AttributeFormat {
+        // (components, data type, normalized, read as integer)
+        fn gl(self) -> (i32, gl::types::GLenum, gl::types::GLboolean, bool) {
+            match self {
+                AttributeFormat::Float(components) => (components, gl::FLOAT, gl::FALSE, false),
+                AttributeFormat::Half(components) => (components, gl::HALF_FLOAT, gl::FALSE, false),
+                AttributeFormat::Unorm8(components) => (components, gl::UNSIGNED_BYTE, gl::TRUE, false),
+                AttributeFormat::Snorm8(components) => (components, gl::BYTE, gl::TRUE, false),
+                AttributeFormat::Unorm16(components) => (components, gl::UNSIGNED_SHORT, gl::TRUE, false),
+                AttributeFormat::Snorm16(components) => (components, gl::SHORT, gl::TRUE, false),
+                AttributeFormat::Int(components) => (components, gl::INT, gl::FALSE, true),
+                AttributeFormat::UnsignedByte(components) => (components, gl::UNSIGNED_BYTE, gl::FALSE, true),
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Attribute {
+        pub location: u32,
+        pub format: AttributeFormat,
+        pub offset: usize,
+    }
+
+    impl Attribute {
+        pub fn enable(&self, gl: &gl::Gl, stride: usize) {
+            let (components, data_type, normalized, integer) = self.format.gl();
+            let offset = (self.offset as *const usize).cast();
+
+            unsafe {
+                if integer {
+                    gl.VertexAttribIPointer(self.location, components, data_type, stride as i32, offset);
+                } else {
+                    gl.VertexAttribPointer(self.location, components, data_type, normalized, stride as i32, offset);
+                }
+                gl.EnableVertexAttribArray(self.location);
+            }
+        }
+    }
+
+    // implemented with vertex_layout!, which takes the offsets from the struct itself
+    pub trait VertexLayout: Copy {
+        fn attributes() -> Vec<Attribute>;
+    }
+
+    #[macro_export]
+    macro_rules! vertex_layout {
+        ($vertex:ty { $($field:ident: $location:literal => $format:expr),* $(,)? }) => {
+            impl $crate::VertexLayout for $vertex {
+                fn attributes() -> Vec<$crate::Attribute> {
+                    vec![$($crate::Attribute {
+                        location: $location,
+                        format: $format,
+                        offset: std::mem::offset_of!($vertex, $field),
+                    }),*]
+                }
+            }
+        };
+    }
+
+    // full precision, as imported and processed on the CPU and as the skinning shaders expect it
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Vertex {
+        pub position: Vec3,
+        pub normal: Vec3,
+        pub tex_coords: Vec2,
+        pub tangent: Vec3,
+        pub bitangent: Vec3,
+        pub bone_ids: [i32; MAX_BONE_INFLUENCE],
+        pub weights: [f32; MAX_BONE_INFLUENCE],
+    }
+
+    impl From<&MeshVertex> for Vertex {
+        fn from(vertex: &MeshVertex) -> Self {
+            Vertex {
+                position: vertex.position,
+                normal: vertex.normal,
+                tex_coords: vertex.tex_coords,
+                tangent: vertex.tangent,
+                bitangent: vertex.bitangent,
+                bone_ids: [0; MAX_BONE_INFLUENCE],
+                weights: [0.0; MAX_BONE_INFLUENCE],
+            }
+        }
+    }
+
+    crate::vertex_layout!(Vertex {
+        position: 0 => AttributeFormat::Float(3),
+        normal: 1 => AttributeFormat::Float(3),
+        tex_coords: 2 => AttributeFormat::Float(2),
+        tangent: 3 => AttributeFormat::Float(3),
+        bitangent: 4 => AttributeFormat::Float(3),
+        bone_ids: 5 => AttributeFormat::Int(4),
+        weights: 6 => AttributeFormat::Float(4),
+    });
+
+    // 28 bytes instead of 76 for meshes that never animate, the bitangent is cross(normal, tangent.xyz) * tangent.w
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct StaticVertex {
+        pub position: Vec3,
+        pub normal: [i16; 4],
+        pub tex_coords: [Half; 2],
+        pub tangent: [i8; 4],
+    }
+
+    crate::vertex_layout!(StaticVertex {
+        position: 0 => AttributeFormat::Float(3),
+        normal: 1 => AttributeFormat::Snorm16(3),
+        tex_coords: 2 => AttributeFormat::Half(2),
+        tangent: 3 => AttributeFormat::Snorm8(4),
+    });
+
+    impl StaticVertex {
+        pub fn pack(vertices: &[Vertex]) -> Vec<StaticVertex> {
+            vertices.iter().map(StaticVertex::from).collect()
+        }
+    }
+
+    impl From<&Vertex> for StaticVertex {
+        fn from(vertex: &Vertex) -> Self {
+            let (normal, tangent) = (vertex.normal, vertex.tangent);
+            let handedness = if normal.cross(tangent).dot(vertex.bitangent) < 0.0 { -1.0 } else { 1.0 };
+
+            StaticVertex {
+                position: vertex.position,
+                normal: [snorm16(normal.x), snorm16(normal.y), snorm16(normal.z), 0],
+                tex_coords: [Half::from_f32(vertex.tex_coords.x), Half::from_f32(vertex.tex_coords.y)],
+                tangent: [snorm8(tangent.x), snorm8(tangent.y), snorm8(tangent.z), snorm8(handedness)],
+            }
+        }
+    }
+
+    // IEEE 754 binary16, rounded to nearest even
+    #[repr(transparent)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Half(pub u16);
+
+    impl Half {
+        pub fn from_f32(value: f32) -> Self {
+            let bits = value.to_bits();
+            let sign = ((bits >> 16) & 0x8000) as u16;
+            let exponent = ((bits >> 23) & 0xFF) as i32;
+            let mantissa = bits & 0x7FFFFF;
+
+            if exponent == 0xFF {
+                return Half(sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 });
+            }
+
+            let exponent = exponent - 127 + 15;
+            if exponent >= 0x1F {
+                return Half(sign | 0x7C00);
+            }
+
+            // too small for a normal half, shift the implicit one into a subnormal
+            let (half, mantissa, shift) = if exponent <= 0 {
+                if exponent < -10 {
+                    return Half(sign);
+                }
+                let mantissa = mantissa | 0x800000;
+                let shift = (14 - exponent) as u32;
+                (mantissa >> shift, mantissa, shift)
+            } else {
+                (((exponent as u32) << 10) | (mantissa >> 13), mantissa, 13)
+            };
+
+            // a carry out of the mantissa rolls into the exponent, up to infinity
+            let (remainder, halfway) = (mantissa & ((1 << shift) - 1), 1 << (shift - 1));
+            let half = if remainder > halfway || (remainder == halfway && half & 1 == 1) { half + 1 } else { half };
+
+            Half(sign | half as u16)
+        }
+    }
+
+    // one vertex buffer, either interleaved from a layout or holding a single attribute
+    pub struct Stream {
+        pub data: Vec<u8>,
+        pub stride: usize,
+        pub attributes: Vec<Attribute>,
+    }
+
+    impl Stream {
+        pub fn interleaved<V: VertexLayout>(vertices: &[V]) -> Self {
+            Stream {
+                data: bytes_of(vertices),
+                stride: size_of::<V>(),
+                attributes: V::attributes(),
+            }
+        }
+
+        pub fn separate<T: Copy>(location: u32, format: AttributeFormat, values: &[T]) -> Self {
+            Stream {
+                data: bytes_of(values),
+                stride: size_of::<T>(),
+                attributes: vec![Attribute { location, format, offset: 0 }],
+            }
+        }
+
+        pub fn vertex_count(&self) -> usize {
+            self.data.len() / self.stride
+        }
+    }
+
+    pub enum Indices {
+        U16(Vec<u16>),
+        U32(Vec<u32>),
+    }
+
+    impl Indices {
+        // 16 bit whenever every index fits
+        pub fn compact(indices: Vec<u32>) -> Self {
+            if indices.iter().all(|&index| index <= u16::MAX as u32) {
+                Indices::U16(indices.iter().map(|&index| index as u16).collect())
+            } else {
+                Indices::U32(indices)
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            match self {
+                Indices::U16(indices) => indices.len(),
+                Indices::U32(indices) => indices.len(),
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn data_type(&self) -> gl::types::GLenum {
+            match self {
+                Indices::U16(_) => gl::UNSIGNED_SHORT,
+                Indices::U32(_) => gl::UNSIGNED_INT,
+            }
+        }
+
+        pub fn bytes(&self) -> Vec<u8> {
+            match self {
+                Indices::U16(indices) => bytes_of(indices),
+                Indices::U32(indices) => bytes_of(indices),
+            }
+        }
+    }
+
+    fn bytes_of<T: Copy>(values: &[T]) -> Vec<u8> {
+        unsafe { slice::from_raw_parts(values.as_ptr().cast::<u8>(), mem::size_of_val(values)) }.to_vec()
+    }
+
+    fn snorm16(value: f32) -> i16 {
+        (value.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+    }
+
+    fn snorm8(value: f32) -> i8 {
+        (value.clamp(-1.0, 1.0) * i8::MAX as f32).round() as i8
+    }
+}
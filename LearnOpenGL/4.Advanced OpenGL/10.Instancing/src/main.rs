@@ -13,9 +13,11 @@ use camera::camera::Movement;
 pub mod mesh;
 use mesh::mesh::{Mesh, Texture, Vertex};
 pub mod model;
-use model::model::Model;
+use model::model::{LodSettings, Model};
 pub mod shader;
 use shader::shader::Program;
+pub mod simplify;
+use simplify::simplify::simplify;
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
@@ -42,11 +44,12 @@ fn main() {
     let asteroid_shader = Program::new(Rc::clone(&gl)).link("src/10.3.asteroids.vs", "src/10.3.asteroids.fs", None);
     let planet_shader = Program::new(Rc::clone(&gl)).link("src/10.3.planet.vs", "src/10.3.planet.fs", None);
 
-    let rock = Model::new(Rc::clone(&gl)).load_model("resources/objects/rock/rock.obj");
+    let rock = Model::new(Rc::clone(&gl)).lods(LodSettings::default()).load_model("resources/objects/rock/rock.obj");
     let planet = Model::new(Rc::clone(&gl)).load_model("resources/objects/planet/planet.obj");
 
     let amount = 100_000;
     let mut model_matrices = Vec::new();
+    let mut positions = Vec::new();
     let radius = 150.0;
     let offset = 25.0;
     for i in 0..amount {
@@ -62,12 +65,14 @@ fn main() {
 
         let rot_angle = (fastrand::i32(..) % 360) as f32;
 
+        positions.push(Vec3::new(x, y, z));
         model_matrices.push(
             Mat4::translate(Vec3::new(x, y, z)) * Mat4::scale(Vec3::new(scale, scale, scale)) * Mat4::rotate(Quaternion::axis_angle(Vec3::new(0.4, 0.6, 0.8), rot_angle)),
         );
     }
 
-    let _buffer = InstanceBuffer::new(Rc::clone(&gl), amount, model_matrices);
+    let instances = InstanceBuffer::new(Rc::clone(&gl), amount, model_matrices.clone());
+    let mut batched = Vec::with_capacity(amount);
 
     rock.instance_meshes();
 
@@ -101,8 +106,21 @@ fn main() {
         asteroid_shader.apply();
         asteroid_shader.set_int("texture_diffuse1", 0);
         rock.bind_texture();
-        for mesh in &rock.meshes {
-            mesh.draw_instanced(amount as i32);
+
+        // rocks are regrouped by level every frame so each level is one contiguous range of the buffer
+        let settings = rock.lod_settings().unwrap();
+        let counts = batch_by_lod(settings, camera.position(), &positions, &model_matrices, &mut batched);
+        instances.update(&batched);
+
+        let mut first = 0;
+        for (level, &count) in counts.iter().enumerate() {
+            if count > 0 {
+                for mesh in &rock.meshes {
+                    mesh.instance_from(first);
+                    mesh.draw_instanced(level, count as i32);
+                }
+            }
+            first += count;
         }
 
         window.swap_buffers();
@@ -114,6 +132,20 @@ fn main() {
     }
 }
 
+// fills `batched` with the matrices ordered by level and returns how many there are of each
+fn batch_by_lod(settings: &LodSettings, camera_position: Vec3, positions: &[Vec3], matrices: &[Mat4], batched: &mut Vec<Mat4>) -> Vec<usize> {
+    let levels: Vec<usize> = positions.iter().map(|&position| settings.level((position - camera_position).magnitude())).collect();
+
+    batched.clear();
+    (0..=settings.distances.len())
+        .map(|level| {
+            let start = batched.len();
+            batched.extend(matrices.iter().zip(&levels).filter(|(_, other)| **other == level).map(|(matrix, _)| *matrix));
+            batched.len() - start
+        })
+        .collect()
+}
+
 fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
@@ -203,11 +235,19 @@ impl InstanceBuffer {
         unsafe {
             gl.GenBuffers(1, &mut buffer);
             gl.BindBuffer(gl::ARRAY_BUFFER, buffer);
-            gl.BufferData(gl::ARRAY_BUFFER, (amount * size_of::<Mat4>()) as isize, matrices.as_ptr().cast(), gl::STATIC_DRAW);
+            gl.BufferData(gl::ARRAY_BUFFER, (amount * size_of::<Mat4>()) as isize, matrices.as_ptr().cast(), gl::DYNAMIC_DRAW);
         }
 
         InstanceBuffer { gl, buffer }
     }
+
+    // leaves the buffer bound so instance attributes can be pointed into it
+    pub fn update(&self, matrices: &[Mat4]) {
+        unsafe {
+            self.gl.BindBuffer(gl::ARRAY_BUFFER, self.buffer);
+            self.gl.BufferSubData(gl::ARRAY_BUFFER, 0, (matrices.len() * size_of::<Mat4>()) as isize, matrices.as_ptr().cast());
+        }
+    }
 }
 
 impl Drop for InstanceBuffer {
@@ -1,8 +1,9 @@
 pub mod mesh {
-    use crate::{gl, ptr, size_of, File, Program, Rc, Read, Vec2, Vec3};
+    use crate::{gl, ptr, size_of, File, Mat4, Program, Rc, Read, Vec2, Vec3};
 
     const MAX_BONE_INFLUENCE: usize = 4;
     const FLOAT_SIZE: usize = size_of::<f32>();
+    const U32_SIZE: usize = size_of::<u32>();
 
     #[repr(C)]
     pub struct Vertex {
@@ -15,17 +16,41 @@ pub mod mesh {
         pub weights: [f32; MAX_BONE_INFLUENCE],
     }
 
+    // a range of the element buffer, every level indexes the same vertices
+    #[derive(Clone, Copy)]
+    pub struct Lod {
+        pub offset: usize,
+        pub count: usize,
+        pub error: f32,
+    }
+
     pub struct Mesh {
         gl: Rc<gl::Gl>,
-        indices: Vec<u32>,
+        lods: Vec<Lod>,
         textures: Vec<Rc<Texture>>,
         pub vao: VertexArray,
     }
 
     impl Mesh {
         pub fn new(gl: Rc<gl::Gl>, vertices: Vec<Vertex>, indices: Vec<u32>, textures: Vec<Rc<Texture>>) -> Self {
+            Mesh::with_lods(gl, vertices, vec![(indices, 0.0)], textures)
+        }
+
+        // levels from the most detailed down, with the error each one was simplified to
+        pub fn with_lods(gl: Rc<gl::Gl>, vertices: Vec<Vertex>, levels: Vec<(Vec<u32>, f32)>, textures: Vec<Rc<Texture>>) -> Self {
             let vao = VertexArray::new(Rc::clone(&gl));
 
+            let mut lods = vec![];
+            let mut indices = vec![];
+            for (level, error) in levels {
+                lods.push(Lod {
+                    offset: indices.len(),
+                    count: level.len(),
+                    error,
+                });
+                indices.extend(level);
+            }
+
             unsafe {
                 gl.BindVertexArray(vao.vertex_array);
 
@@ -64,7 +89,11 @@ pub mod mesh {
                 gl.BindVertexArray(0);
             }
 
-            Mesh { gl, indices, textures, vao }
+            Mesh { gl, lods, textures, vao }
+        }
+
+        pub fn lods(&self) -> &[Lod] {
+            &self.lods
         }
 
         pub fn draw(&self, shader: &Program) {
@@ -108,17 +137,46 @@ pub mod mesh {
                 }
 
                 gl.BindVertexArray(self.vao.vertex_array);
-                gl.DrawElements(gl::TRIANGLES, self.indices.len() as i32, gl::UNSIGNED_INT, ptr::null());
+                gl.DrawElements(gl::TRIANGLES, self.lods[0].count as i32, gl::UNSIGNED_INT, ptr::null());
 
                 gl.ActiveTexture(gl::TEXTURE0);
             }
         }
 
-        pub fn draw_instanced(&self, amount: i32) {
+        // points the instance matrix at `first` in the buffer bound to GL_ARRAY_BUFFER, there's no base instance in 3.3
+        pub fn instance_from(&self, first: usize) {
+            let gl = &self.gl;
+            let matrix_size = size_of::<Mat4>();
+
+            unsafe {
+                gl.BindVertexArray(self.vao.vertex_array);
+
+                for column in 0..4 {
+                    let location = 3 + column as u32;
+                    let offset = first * matrix_size + column * 4 * FLOAT_SIZE;
+
+                    gl.EnableVertexAttribArray(location);
+                    gl.VertexAttribPointer(location, 4, gl::FLOAT, gl::FALSE, matrix_size as i32, (offset as *const usize).cast());
+                    gl.VertexAttribDivisor(location, 1);
+                }
+
+                gl.BindVertexArray(0);
+            }
+        }
+
+        // levels past the last one this mesh has draw its coarsest
+        pub fn draw_instanced(&self, level: usize, amount: i32) {
+            let lod = self.lods[level.min(self.lods.len() - 1)];
+
             unsafe {
                 self.gl.BindVertexArray(self.vao.vertex_array);
-                self.gl
-                    .DrawElementsInstanced(gl::TRIANGLES, self.indices.len() as i32, gl::UNSIGNED_INT, ptr::null(), amount);
+                self.gl.DrawElementsInstanced(
+                    gl::TRIANGLES,
+                    lod.count as i32,
+                    gl::UNSIGNED_INT,
+                    ((lod.offset * U32_SIZE) as *const usize).cast(),
+                    amount,
+                );
                 self.gl.BindVertexArray(0);
             }
         }
@@ -1,5 +1,5 @@
 pub mod model {
-    use crate::{gl, ptr, size_of, Mat4, Mesh, Program, Rc, Texture, Vec2, Vec3, Vertex};
+    use crate::{gl, simplify, Mesh, Program, Rc, Texture, Vec2, Vec3, Vertex};
     use russimp::{
         material::Material,
         mesh,
@@ -8,11 +8,39 @@ pub mod model {
         texture::TextureType,
     };
 
+    pub struct LodSettings {
+        // levels after the first, each keeping this fraction of the previous one's triangles
+        pub levels: usize,
+        pub reduction: f32,
+        // relative to the mesh's extent, the chain ends early at the first level that can't get smaller within it
+        pub max_error: f32,
+        // camera distance past which each level after the first is drawn
+        pub distances: Vec<f32>,
+    }
+
+    impl LodSettings {
+        pub fn level(&self, distance: f32) -> usize {
+            self.distances.iter().take_while(|&&start| distance >= start).count()
+        }
+    }
+
+    impl Default for LodSettings {
+        fn default() -> Self {
+            LodSettings {
+                levels: 3,
+                reduction: 0.5,
+                max_error: 0.05,
+                distances: vec![20.0, 60.0, 150.0],
+            }
+        }
+    }
+
     pub struct Model {
         gl: Rc<gl::Gl>,
         textures_loaded: Vec<Rc<Texture>>,
         pub meshes: Vec<Mesh>,
         directory: String,
+        lod_settings: Option<LodSettings>,
     }
 
     impl Model {
@@ -22,9 +50,20 @@ pub mod model {
                 textures_loaded: vec![],
                 meshes: vec![],
                 directory: "".to_string(),
+                lod_settings: None,
             }
         }
 
+        // set before load_model, which then simplifies every mesh into a chain of levels
+        pub fn lods(mut self, settings: LodSettings) -> Self {
+            self.lod_settings = Some(settings);
+            self
+        }
+
+        pub fn lod_settings(&self) -> Option<&LodSettings> {
+            self.lod_settings.as_ref()
+        }
+
         pub fn load_model(mut self, path: &str) -> Self {
             let scene = Scene::from_file(
                 path,
@@ -116,7 +155,16 @@ pub mod model {
             let mut height_maps = self.load_material_textures(material, TextureType::Ambient, "texture_height");
             textures.append(&mut height_maps);
 
-            Mesh::new(Rc::clone(&self.gl), vertices, indices, textures)
+            match &self.lod_settings {
+                Some(settings) => {
+                    let levels = generate_lods(&vertices, indices, settings);
+                    let triangles: Vec<String> = levels.iter().map(|(level, _)| (level.len() / 3).to_string()).collect();
+                    println!("{}: LOD triangles {}", mesh.name, triangles.join(" / "));
+
+                    Mesh::with_lods(Rc::clone(&self.gl), vertices, levels, textures)
+                }
+                None => Mesh::new(Rc::clone(&self.gl), vertices, indices, textures),
+            }
         }
 
         fn load_material_textures(&mut self, material: &Material, tex_type: TextureType, type_name: &str) -> Vec<Rc<Texture>> {
@@ -154,31 +202,29 @@ pub mod model {
             }
         }
 
+        // the instance buffer has to be bound to GL_ARRAY_BUFFER
         pub fn instance_meshes(&self) {
             for mesh in &self.meshes {
-                let gl = &self.gl;
-                let float_size = size_of::<f32>();
-                let matrix_size = size_of::<Mat4>() as i32;
-
-                unsafe {
-                    gl.BindVertexArray(mesh.vao.vertex_array);
-                    gl.EnableVertexAttribArray(3);
-                    gl.VertexAttribPointer(3, 4, gl::FLOAT, gl::FALSE, matrix_size, ptr::null());
-                    gl.EnableVertexAttribArray(4);
-                    gl.VertexAttribPointer(4, 4, gl::FLOAT, gl::FALSE, matrix_size, ((4 * float_size) as *const usize).cast());
-                    gl.EnableVertexAttribArray(5);
-                    gl.VertexAttribPointer(5, 4, gl::FLOAT, gl::FALSE, matrix_size, ((8 * float_size) as *const usize).cast());
-                    gl.EnableVertexAttribArray(6);
-                    gl.VertexAttribPointer(6, 4, gl::FLOAT, gl::FALSE, matrix_size, ((12 * float_size) as *const usize).cast());
-
-                    gl.VertexAttribDivisor(3, 1);
-                    gl.VertexAttribDivisor(4, 1);
-                    gl.VertexAttribDivisor(5, 1);
-                    gl.VertexAttribDivisor(6, 1);
-
-                    gl.BindVertexArray(0);
-                }
+                mesh.instance_from(0);
+            }
+        }
+    }
+
+    // every level is simplified from the full mesh, so errors don't pile up down the chain
+    fn generate_lods(vertices: &[Vertex], indices: Vec<u32>, settings: &LodSettings) -> Vec<(Vec<u32>, f32)> {
+        let mut target = indices.len() as f32;
+        let mut levels = vec![(indices, 0.0)];
+
+        for _ in 0..settings.levels {
+            target *= settings.reduction;
+
+            let (level, error) = simplify(vertices, &levels[0].0, (target as usize) / 3 * 3, settings.max_error);
+            if level.len() >= levels.last().unwrap().0.len() {
+                break;
             }
+            levels.push((level, error));
         }
+
+        levels
     }
 }
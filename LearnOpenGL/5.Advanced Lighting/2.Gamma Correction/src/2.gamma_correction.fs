@@ -45,7 +45,6 @@ void main()
     for(int i = 0; i < 4; ++i)
         lighting += BlinnPhong(normalize(fs_in.Normal), fs_in.FragPos, lightPositions[i], lightColors[i]);
     color *= lighting;
-    if(gamma)
-        color = pow(color, vec3(1.0/2.2));
+    // with gamma on, GL_FRAMEBUFFER_SRGB encodes the linear result on write
     FragColor = vec4(color, 1.0);
 }
\ No newline at end of file
@@ -11,7 +11,7 @@ pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
 pub mod mesh;
-use mesh::mesh::{Texture, VertexArray};
+use mesh::mesh::{ColorSpace, Texture, VertexArray};
 pub mod shader;
 use shader::shader::Program;
 
@@ -42,8 +42,8 @@ fn main() {
 
     let plane = VertexArray::new_shape(Rc::clone(&gl), Vec::from(&PLANE_VERTICES[..]), Vec::from(&INDICES[..6]));
 
-    let floor = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/wood.png", false);
-    let floor_gamma = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/wood.png", true);
+    let floor = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/wood.png", ColorSpace::Linear);
+    let floor_gamma = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/wood.png", ColorSpace::Srgb);
 
     shader.apply();
     shader.set_int("floorTexture", 0);
@@ -71,6 +71,8 @@ fn main() {
 
         process_input(&mut camera, &mut gamma, &mut window, delta_time);
 
+        // the sRGB texture is decoded to linear on sampling, so the framebuffer encodes the lit result again
+        gl.framebuffer_srgb_enable(gamma.is_on);
         gl.clear(0.1, 0.1, 0.1, 1.0);
 
         shader.apply();
@@ -174,6 +176,18 @@ mod gl {
             }
         }
 
+        pub fn framebuffer_srgb_enable(&self, on: bool) {
+            if on {
+                unsafe {
+                    self.Enable(self::FRAMEBUFFER_SRGB);
+                }
+            } else {
+                unsafe {
+                    self.Disable(self::FRAMEBUFFER_SRGB);
+                }
+            }
+        }
+
         pub fn blend_enable(&self) {
             unsafe {
                 self.Enable(self::BLEND);
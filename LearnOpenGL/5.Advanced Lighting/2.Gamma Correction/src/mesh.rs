@@ -255,6 +255,13 @@ pub mod mesh {
         }
     }
 
+    // how the texel values are read, sRGB ones are decoded to linear on sampling
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum ColorSpace {
+        Linear,
+        Srgb,
+    }
+
     pub struct Texture {
         gl: Rc<gl::Gl>,
         pub tex_type: String,
@@ -278,7 +285,7 @@ pub mod mesh {
             }
         }
 
-        pub fn load(self, path: &str, gamma_correction: bool) -> Self {
+        pub fn load(self, path: &str, color_space: ColorSpace) -> Self {
             let gl = &self.gl;
             let texture = self.texture;
 
@@ -294,10 +301,13 @@ pub mod mesh {
 
             let image = Image::new(path);
 
-            let (internal_format, data_format) = match image.components {
-                1 => (gl::RED, gl::RED),
-                3 => (if gamma_correction { gl::SRGB } else { gl::RGB }, gl::RGB),
-                4 => (if gamma_correction { gl::SRGB_ALPHA } else { gl::RGBA }, gl::RGBA),
+            // a single channel has no sRGB variant
+            let (internal_format, data_format) = match (image.components, color_space) {
+                (1, _) => (gl::R8, gl::RED),
+                (3, ColorSpace::Linear) => (gl::RGB8, gl::RGB),
+                (3, ColorSpace::Srgb) => (gl::SRGB8, gl::RGB),
+                (4, ColorSpace::Linear) => (gl::RGBA8, gl::RGBA),
+                (4, ColorSpace::Srgb) => (gl::SRGB8_ALPHA8, gl::RGBA),
                 _ => panic!("Unexpected image format"),
             };
 
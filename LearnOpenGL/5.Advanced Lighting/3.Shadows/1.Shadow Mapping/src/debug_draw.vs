@@ -1,10 +1,14 @@
-#version 460 core
+#version 330 core
 layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec4 aColor;
+
+out vec4 Color;
 
 uniform mat4 view;
 uniform mat4 projection;
 
 void main()
 {
+    Color = aColor;
     gl_Position = projection * view * vec4(aPos, 1.0);
-}
\ No newline at end of file
+}
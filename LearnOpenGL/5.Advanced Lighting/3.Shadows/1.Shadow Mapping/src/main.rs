@@ -1,4 +1,4 @@
-use gfx_maths::{mat4::Mat4, quaternion::Quaternion, vec2::Vec2, vec3::Vec3};
+use gfx_maths::{mat4::Mat4, vec2::Vec2, vec3::Vec3, vec4::Vec4};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
 use std::{f32::consts::PI, fs::File, io::Read, mem::size_of, ptr, rc::Rc};
 
@@ -8,12 +8,25 @@ const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
 const SHADOW_WIDTH: u32 = 1024;
 const SHADOW_HEIGHT: u32 = 1024;
+const SCENE_PATH: &str = "resources/scenes/cubes.json";
+const LIGHT_COLOR: Vec4 = Vec4::new(1.0, 0.8, 0.1, 1.0);
+const BOUNDS_COLOR: Vec4 = Vec4::new(0.2, 0.9, 0.3, 1.0);
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
+pub mod debug_draw;
+use debug_draw::debug_draw::DebugDraw;
+pub mod font;
+use font::font::{strokes, GLYPH_ADVANCE};
+pub mod json;
+use json::json::{Json, ParseError};
 pub mod mesh;
 use mesh::mesh::{Texture, VertexArray};
+pub mod scene_file;
+use scene_file::scene_file::{NodeDescription, Primitive, Rotation, SceneDescription, SceneError};
 pub mod shader;
 use shader::shader::Program;
 
@@ -42,10 +55,12 @@ fn main() {
     let shader = Program::new(Rc::clone(&gl)).link("src/3.1.3.shadow_mapping.vs", "src/3.1.3.shadow_mapping.fs");
     let depth_shader = Program::new(Rc::clone(&gl)).link("src/3.1.3.shadow_mapping_depth.vs", "src/3.1.3.shadow_mapping_depth.fs");
     let debug_shader = Program::new(Rc::clone(&gl)).link("src/3.1.3.debug_quad.vs", "src/3.1.3.debug_quad_depth.fs");
+    let debug_draw_shader = Program::new(Rc::clone(&gl)).link("src/debug_draw.vs", "src/debug_draw.fs");
+    let mut debug = DebugDraw::new(Rc::clone(&gl));
 
-    let cube = VertexArray::new_shape(Rc::clone(&gl), Vec::from(&CUBE_VERTICES[..]), Vec::from(&INDICES[..]));
-    let plane = VertexArray::new_shape(Rc::clone(&gl), Vec::from(&PLANE_VERTICES[..]), Vec::from(&INDICES[..6]));
-    let quad = VertexArray::new_3d_tex(Rc::clone(&gl), Vec::from(&QUAD_VERTICES[..]), Vec::from(&INDICES[..6]));
+    let cube = VertexArray::new_cube(Rc::clone(&gl));
+    let plane = VertexArray::new_plane(Rc::clone(&gl));
+    let quad = VertexArray::new_quad(Rc::clone(&gl));
 
     let wood_tex = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/wood.png", false);
 
@@ -59,12 +74,31 @@ fn main() {
 
     let light_pos = Vec3::new(-2.0, 4.0, -1.0);
 
+    let description = match SceneDescription::load(SCENE_PATH) {
+        Ok(description) => description,
+        Err(SceneError::Io { error, .. }) if error.kind() == std::io::ErrorKind::NotFound => {
+            let description = three_cubes();
+            if let Err(error) = std::fs::create_dir_all("resources/scenes").map_err(|error| error.to_string()).and_then(|_| description.save(SCENE_PATH).map_err(|error| error.to_string())) {
+                println!("Failed to write the default scene: {error}");
+            }
+            description
+        }
+        Err(error) => {
+            println!("Failed to load the scene, using the default one: {error}");
+            three_cubes()
+        }
+    };
+    let nodes: Vec<(Primitive, Mat4)> = description.nodes.iter().map(|node| (node.model, node.model_matrix())).collect();
+
     glfw.poll_events();
 
     let (x_pos, y_pos) = window.get_cursor_pos();
     let mut camera = Camera::new(Vec3::new(0.0, 0.0, 3.0), x_pos as f32, y_pos as f32);
 
     let mut last_frame = 0.0;
+    // b shows what the depth map is rendered from, the light's box and the cubes' bounds
+    let mut show_debug = false;
+    let mut debug_held = false;
 
     while !window.should_close() {
         let current_frame = glfw.get_time() as f32;
@@ -73,6 +107,12 @@ fn main() {
 
         process_input(&mut camera, &mut window, delta_time);
 
+        let debug_pressed = window.get_key(Key::B) == Action::Press;
+        if debug_pressed && !debug_held {
+            show_debug = !show_debug;
+        }
+        debug_held = debug_pressed;
+
         gl.color_clear(0.1, 0.1, 0.1, 1.0);
 
         let near_plane = 1.0;
@@ -87,7 +127,7 @@ fn main() {
         gl.viewport(SHADOW_WIDTH, SHADOW_HEIGHT);
         depth_map.bind();
         gl.clear();
-        render_scene(&depth_shader, &plane, &cube);
+        render_scene(&depth_shader, &nodes, &plane, &cube);
         gl.unbind_framebuffer();
 
         gl.viewport(SCR_WIDTH, SCR_HEIGHT);
@@ -102,7 +142,12 @@ fn main() {
         shader.set_mat4("lightSpaceMatrix", light_space);
         wood_tex.bind_active(gl::TEXTURE0);
         depth_map.bind_texture(1);
-        render_scene(&shader, &plane, &cube);
+        render_scene(&shader, &nodes, &plane, &cube);
+
+        if show_debug {
+            draw_light(&mut debug, light_pos, light_space, &nodes);
+        }
+        debug.flush(&debug_draw_shader, projection, view);
 
         debug_shader.apply();
         debug_shader.set_float("near_plane", near_plane);
@@ -120,21 +165,54 @@ fn main() {
     }
 }
 
-fn render_scene(shader: &Program, plane: &VertexArray, cube: &VertexArray) {
-    shader.set_mat4("model", Mat4::identity());
-    plane.bind();
-    plane.draw();
-    let model = Mat4::translate(Vec3::new(0.0, 1.5, 0.0)) * Mat4::scale(Vec3::new(0.5, 0.5, 0.5));
-    shader.set_mat4("model", model);
-    cube.bind();
-    cube.draw();
-    let model = Mat4::translate(Vec3::new(2.0, 0.0, 1.0)) * Mat4::scale(Vec3::new(0.5, 0.5, 0.5));
-    shader.set_mat4("model", model);
-    cube.draw();
-    let model =
-        Mat4::translate(Vec3::new(-1.0, 0.0, 2.0)) * Mat4::rotate(Quaternion::axis_angle(Vec3::new(1.0, 0.0, 1.0), PI / 3.0)) * Mat4::scale(Vec3::new(0.25, 0.25, 0.25));
-    shader.set_mat4("model", model);
-    cube.draw();
+fn render_scene(shader: &Program, nodes: &[(Primitive, Mat4)], plane: &VertexArray, cube: &VertexArray) {
+    for (primitive, model) in nodes {
+        let vertex_array = match primitive {
+            Primitive::Plane => plane,
+            Primitive::Cube => cube,
+        };
+        shader.set_mat4("model", *model);
+        vertex_array.bind();
+        vertex_array.draw();
+    }
+}
+
+// the box the orthographic light projection renders depth from, which the cubes have to stay inside to cast shadows
+fn draw_light(debug: &mut DebugDraw, light_pos: Vec3, light_space: Mat4, nodes: &[(Primitive, Mat4)]) {
+    debug.frustum(light_space, LIGHT_COLOR);
+    debug.arrow(light_pos, Vec3::zero(), LIGHT_COLOR);
+    debug.text(light_pos + Vec3::new(0.1, 0.1, 0.0), "light", 0.2, LIGHT_COLOR);
+
+    for (primitive, model) in nodes {
+        if let Primitive::Cube = primitive {
+            debug.cube(*model, BOUNDS_COLOR);
+        }
+    }
+}
+
+// the floor and the tutorial's three cubes, what the sample shows when there is no scene file
+fn three_cubes() -> SceneDescription {
+    let floor = NodeDescription::new("floor", Primitive::Plane);
+
+    let mut raised = NodeDescription::new("raised cube", Primitive::Cube);
+    raised.position = [0.0, 1.5, 0.0];
+    raised.scale = [0.5; 3];
+
+    let mut right = NodeDescription::new("right cube", Primitive::Cube);
+    right.position = [2.0, 0.0, 1.0];
+    right.scale = [0.5; 3];
+
+    // a third of a turn about (1, 0, 1)
+    let (sin, cos) = (PI / 6.0).sin_cos();
+    let axis = 1.0 / 2f32.sqrt();
+    let mut tilted = NodeDescription::new("tilted cube", Primitive::Cube);
+    tilted.position = [-1.0, 0.0, 2.0];
+    tilted.rotation = Rotation::Quaternion([axis * sin, 0.0, axis * sin, cos]);
+    tilted.scale = [0.25; 3];
+
+    SceneDescription {
+        nodes: vec![floor, raised, right, tilted],
+    }
 }
 
 fn look_at(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
@@ -244,63 +322,6 @@ mod gl {
     }
 }
 
-const CUBE_COUNT: usize = 24;
-const CUBE_COMPONENTS: usize = 8;
-const CUBE_VERTICES: [f32; CUBE_COMPONENTS * CUBE_COUNT] = [
-    // back face
-    1.0, -1.0, -1.0, 0.0, 0.0, -1.0, 1.0, 0.0, // bottom-right
-    -1.0, -1.0, -1.0, 0.0, 0.0, -1.0, 0.0, 0.0, // bottom-left
-    1.0, 1.0, -1.0, 0.0, 0.0, -1.0, 1.0, 1.0, // top-right
-    -1.0, 1.0, -1.0, 0.0, 0.0, -1.0, 0.0, 1.0, // top-left
-    // front face
-    -1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, // bottom-left
-    1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, // bottom-right
-    -1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, // top-left
-    1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, // top-right
-    // left face
-    -1.0, -1.0, -1.0, -1.0, 0.0, 0.0, 0.0, 1.0, // bottom-left
-    -1.0, -1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, // bottom-right
-    -1.0, 1.0, -1.0, -1.0, 0.0, 0.0, 1.0, 1.0, // top-left
-    -1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 1.0, 0.0, // top-right
-    // right face
-    1.0, -1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, // bottom-left
-    1.0, -1.0, -1.0, 1.0, 0.0, 0.0, 0.0, 1.0, // bottom-right
-    1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, // top-left
-    1.0, 1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, // top-right
-    // bottom face
-    -1.0, -1.0, -1.0, 0.0, -1.0, 0.0, 0.0, 1.0, // top-right
-    1.0, -1.0, -1.0, 0.0, -1.0, 0.0, 1.0, 1.0, // top-left
-    -1.0, -1.0, 1.0, 0.0, -1.0, 0.0, 0.0, 0.0, // bottom-right
-    1.0, -1.0, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, // bottom-left
-    // top face
-    -1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, // bottom-left
-    1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, // bottom-right
-    -1.0, 1.0, -1.0, 0.0, 1.0, 0.0, 0.0, 1.0, // top-left
-    1.0, 1.0, -1.0, 0.0, 1.0, 0.0, 1.0, 1.0, // top-right
-];
-const QUAD_COUNT: usize = 4;
-const QUAD_COMPONENTS: usize = 5;
-const QUAD_VERTICES: [f32; QUAD_COMPONENTS * QUAD_COUNT] = [
-    // positions	texture Coords
-    -1.0, -1.0, 0.0, 0.0, 0.0, 1.0, -1.0, 0.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0,
-];
-const PLANE_COUNT: usize = 4;
-const PLANE_COMPONENTS: usize = 8;
-const PLANE_VERTICES: [f32; PLANE_COMPONENTS * PLANE_COUNT] = [
-    // positions	normals		texcoords
-    -25.0, -0.5, 25.0, 0.0, 1.0, 0.0, 0.0, 0.0, 25.0, -0.5, 25.0, 0.0, 1.0, 0.0, 25.0, 0.0, -25.0, -0.5, -25.0, 0.0, 1.0, 0.0, 0.0, 25.0, 25.0, -0.5, -25.0, 0.0, 1.0, 0.0,
-    25.0, 25.0,
-];
-const INDEX_COUNT: usize = 36;
-const INDICES: [u8; INDEX_COUNT] = [
-    0, 1, 2, 2, 1, 3, // first quad
-    4, 5, 6, 6, 5, 7, // second quad
-    8, 9, 10, 10, 9, 11, // third quad
-    12, 13, 14, 14, 13, 15, // fourth quad
-    16, 17, 18, 18, 17, 19, // fifth quad
-    20, 21, 22, 22, 21, 23, // sixth quad
-];
-
 pub struct Framebuffer {
     gl: Rc<gl::Gl>,
     framebuffer: gl::types::GLuint,
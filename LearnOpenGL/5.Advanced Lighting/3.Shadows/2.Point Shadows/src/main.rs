@@ -9,6 +9,8 @@ const SCR_FAR: f32 = 100.0;
 const SHADOW_WIDTH: u32 = 1024;
 const SHADOW_HEIGHT: u32 = 1024;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
@@ -47,7 +49,7 @@ fn main() {
         Some("src/3.2.2.point_shadows_depth.gs"),
     );
 
-    let cube = VertexArray::new_shape(Rc::clone(&gl), Vec::from(&CUBE_VERTICES[..]), Vec::from(&INDICES[..]));
+    let cube = VertexArray::new_cube(Rc::clone(&gl));
 
     let wood_tex = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/wood.png", false);
 
@@ -280,50 +282,6 @@ mod gl {
     }
 }
 
-const CUBE_COUNT: usize = 24;
-const CUBE_COMPONENTS: usize = 8;
-const CUBE_VERTICES: [f32; CUBE_COMPONENTS * CUBE_COUNT] = [
-    // back face
-    1.0, -1.0, -1.0, 0.0, 0.0, -1.0, 1.0, 0.0, // bottom-right
-    -1.0, -1.0, -1.0, 0.0, 0.0, -1.0, 0.0, 0.0, // bottom-left
-    1.0, 1.0, -1.0, 0.0, 0.0, -1.0, 1.0, 1.0, // top-right
-    -1.0, 1.0, -1.0, 0.0, 0.0, -1.0, 0.0, 1.0, // top-left
-    // front face
-    -1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, // bottom-left
-    1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, // bottom-right
-    -1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, // top-left
-    1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, // top-right
-    // left face
-    -1.0, -1.0, -1.0, -1.0, 0.0, 0.0, 0.0, 1.0, // bottom-left
-    -1.0, -1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, // bottom-right
-    -1.0, 1.0, -1.0, -1.0, 0.0, 0.0, 1.0, 1.0, // top-left
-    -1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 1.0, 0.0, // top-right
-    // right face
-    1.0, -1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, // bottom-left
-    1.0, -1.0, -1.0, 1.0, 0.0, 0.0, 0.0, 1.0, // bottom-right
-    1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, // top-left
-    1.0, 1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, // top-right
-    // bottom face
-    -1.0, -1.0, -1.0, 0.0, -1.0, 0.0, 0.0, 1.0, // top-right
-    1.0, -1.0, -1.0, 0.0, -1.0, 0.0, 1.0, 1.0, // top-left
-    -1.0, -1.0, 1.0, 0.0, -1.0, 0.0, 0.0, 0.0, // bottom-right
-    1.0, -1.0, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, // bottom-left
-    // top face
-    -1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, // bottom-left
-    1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, // bottom-right
-    -1.0, 1.0, -1.0, 0.0, 1.0, 0.0, 0.0, 1.0, // top-left
-    1.0, 1.0, -1.0, 0.0, 1.0, 0.0, 1.0, 1.0, // top-right
-];
-const INDEX_COUNT: usize = 36;
-const INDICES: [u8; INDEX_COUNT] = [
-    0, 1, 2, 2, 1, 3, // first quad
-    4, 5, 6, 6, 5, 7, // second quad
-    8, 9, 10, 10, 9, 11, // third quad
-    12, 13, 14, 14, 13, 15, // fourth quad
-    16, 17, 18, 18, 17, 19, // fifth quad
-    20, 21, 22, 22, 21, 23, // sixth quad
-];
-
 pub struct Framebuffer {
     gl: Rc<gl::Gl>,
     framebuffer: gl::types::GLuint,
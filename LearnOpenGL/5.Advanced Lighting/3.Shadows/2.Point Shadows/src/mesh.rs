@@ -1,5 +1,5 @@
 pub mod mesh {
-    use crate::{gl, ptr, size_of, File, Program, Rc, Read, Vec2, Vec3};
+    use crate::{gl, interleave, ptr, size_of, Attribute, File, MeshBuilder, Program, Rc, Read, Vec2, Vec3};
 
     const MAX_BONE_INFLUENCE: usize = 4;
     const FLOAT_SIZE: usize = size_of::<f32>();
@@ -153,79 +153,41 @@ pub mod mesh {
             }
         }
 
-        pub fn new_shape(gl: Rc<gl::Gl>, vertices: Vec<f32>, indices: Vec<u8>) -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
+        // the attributes go to locations 0, 1, 2... in the order given
+        pub fn from_builder(gl: Rc<gl::Gl>, builder: MeshBuilder, attributes: &[Attribute]) -> Self {
+            let mut vao = VertexArray::new(gl);
+            let gl = &vao.gl;
 
-            unsafe {
-                gl.GenVertexArrays(1, &mut vertex_array);
-                gl.GenBuffers(1, &mut vertex_buffer);
-                gl.GenBuffers(1, &mut index_buffer);
+            let (vertices, indices) = builder.build();
+            let vertices = interleave(&vertices, attributes);
+            let stride: usize = attributes.iter().map(|attribute| attribute.components()).sum();
 
-                gl.BindVertexArray(vertex_array);
+            unsafe {
+                gl.BindVertexArray(vao.vertex_array);
 
-                gl.BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+                gl.BindBuffer(gl::ARRAY_BUFFER, vao.vertex_buffer);
                 gl.BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
 
-                gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 8) as i32, ptr::null());
-                gl.EnableVertexAttribArray(0);
-
-                gl.VertexAttribPointer(1, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 8) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(1);
-
-                gl.VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 8) as i32, ((6 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(2);
+                let mut offset = 0;
+                for (location, attribute) in attributes.iter().enumerate() {
+                    let components = attribute.components();
+                    gl.VertexAttribPointer(location as u32, components as i32, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * stride) as i32, ((offset * FLOAT_SIZE) as *const usize).cast());
+                    gl.EnableVertexAttribArray(location as u32);
+                    offset += components;
+                }
 
-                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
-                gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, indices.len() as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
+                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vao.index_buffer);
+                gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, (size_of::<u32>() * indices.len()) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
 
                 gl.BindVertexArray(0);
             }
 
-            VertexArray {
-                gl,
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-            }
+            vao.index_count = indices.len();
+            vao
         }
 
-        pub fn new_3d_tex(gl: Rc<gl::Gl>, vertices: Vec<f32>, indices: Vec<u8>) -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
-
-            unsafe {
-                gl.GenVertexArrays(1, &mut vertex_array);
-                gl.GenBuffers(1, &mut vertex_buffer);
-                gl.GenBuffers(1, &mut index_buffer);
-
-                gl.BindVertexArray(vertex_array);
-
-                gl.BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
-                gl.BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ptr::null());
-                gl.EnableVertexAttribArray(0);
-
-                gl.VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(1);
-
-                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
-                gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, indices.len() as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl.BindVertexArray(0);
-            }
-
-            VertexArray {
-                gl,
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-            }
+        pub fn new_cube(gl: Rc<gl::Gl>) -> Self {
+            VertexArray::from_builder(gl, MeshBuilder::cube(2.0), &[Attribute::Position, Attribute::Normal, Attribute::TexCoords])
         }
 
         pub fn bind(&self) {
@@ -236,7 +198,7 @@ pub mod mesh {
 
         pub fn draw(&self) {
             unsafe {
-                self.gl.DrawElements(gl::TRIANGLES, self.index_count as i32, gl::UNSIGNED_BYTE, ptr::null());
+                self.gl.DrawElements(gl::TRIANGLES, self.index_count as i32, gl::UNSIGNED_INT, ptr::null());
             }
         }
     }
@@ -7,6 +7,8 @@ const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
@@ -8,6 +8,8 @@ const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 50.0;
 const SSAO_KERNEL: usize = 64usize;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
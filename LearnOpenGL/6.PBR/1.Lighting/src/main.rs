@@ -7,9 +7,13 @@ const SCR_HEIGHT: u32 = 720;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
+pub mod material;
+use material::material::{Fallbacks, Material, Slot};
 pub mod mesh;
 use mesh::mesh::{Texture, VertexArray};
 pub mod shader;
@@ -41,17 +45,16 @@ fn main() {
     let shader = Program::new(Rc::clone(&gl)).link("src/1.2.pbr.vs", "src/1.2.pbr.fs");
 
     shader.apply();
-    shader.set_int("albedoMap", 0);
-    shader.set_int("normalMap", 1);
-    shader.set_int("metallicMap", 2);
-    shader.set_int("roughnessMap", 3);
-    shader.set_int("aoMap", 4);
-
-    let albedo = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/albedo.png", false);
-    let normal = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/normal.png", false);
-    let metallic = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/metallic.png", false);
-    let roughness = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/roughness.png", false);
-    let ao = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/ao.png", false);
+
+    let fallbacks = Rc::new(Fallbacks::new(Rc::clone(&gl)));
+    let rusted_iron = Material::new(Rc::clone(&fallbacks))
+        .with_texture(Slot::BaseColor, Rc::new(Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/albedo.png", false)))
+        .with_texture(Slot::Normal, Rc::new(Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/normal.png", false)))
+        .with_texture(
+            Slot::MetallicRoughness,
+            Rc::new(Texture::new(Rc::clone(&gl), "", "").load_metallic_roughness("resources/textures/pbr/rusted_iron/metallic.png", "resources/textures/pbr/rusted_iron/roughness.png")),
+        )
+        .with_texture(Slot::Occlusion, Rc::new(Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/ao.png", false)));
 
     let sphere = VertexArray::new_sphere(Rc::clone(&gl));
 
@@ -85,16 +88,7 @@ fn main() {
         shader.set_mat4("view", view);
         shader.set_vec3("camPos", camera.position());
 
-        gl.active_texture(0);
-        albedo.bind();
-        gl.active_texture(1);
-        normal.bind();
-        gl.active_texture(2);
-        metallic.bind();
-        gl.active_texture(3);
-        roughness.bind();
-        gl.active_texture(4);
-        ao.bind();
+        rusted_iron.bind(&shader, 0);
 
         sphere.bind();
         for row in 0..nr_rows {
@@ -0,0 +1,134 @@
+pub mod loader {
+    use crate::{gl, HdrImage, Rc, Texture};
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    struct Request {
+        id: usize,
+        path: String,
+    }
+
+    struct Decoded {
+        id: usize,
+        image: Option<HdrImage>,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Progress {
+        pub done: usize,
+        pub total: usize,
+    }
+
+    impl Progress {
+        pub fn fraction(&self) -> f32 {
+            if self.total == 0 {
+                1.0
+            } else {
+                self.done as f32 / self.total as f32
+            }
+        }
+    }
+
+    // decodes on one worker thread, the sample only has a single environment map to wait for
+    pub struct Loader {
+        requests: Option<mpsc::Sender<Request>>,
+        decoded: mpsc::Receiver<Decoded>,
+        worker: Option<thread::JoinHandle<()>>,
+        next_id: usize,
+        textures: HashMap<usize, Rc<Texture>>,
+        uploads: VecDeque<(Rc<Texture>, Option<HdrImage>)>,
+        progress: Progress,
+    }
+
+    impl Loader {
+        pub fn new() -> Self {
+            let (request_sender, request_receiver) = mpsc::channel();
+            let (decoded_sender, decoded_receiver) = mpsc::channel();
+
+            Loader {
+                requests: Some(request_sender),
+                decoded: decoded_receiver,
+                worker: Some(thread::spawn(move || work(request_receiver, decoded_sender))),
+                next_id: 0,
+                textures: HashMap::new(),
+                uploads: VecDeque::new(),
+                progress: Progress { done: 0, total: 0 },
+            }
+        }
+
+        // hands out a placeholder that the decoded image is later uploaded into
+        pub fn load_hdr(&mut self, gl: Rc<gl::Gl>, path: &str) -> Rc<Texture> {
+            self.next_id += 1;
+            let id = self.next_id;
+
+            let texture = Rc::new(Texture::new_hdr(gl));
+            self.textures.insert(id, Rc::clone(&texture));
+
+            // one step for the decode and one for the upload
+            self.progress.total += 2;
+            self.requests.as_ref().unwrap().send(Request { id, path: path.to_string() }).unwrap();
+
+            texture
+        }
+
+        pub fn progress(&self) -> Progress {
+            self.progress
+        }
+
+        pub fn is_idle(&self) -> bool {
+            self.progress.done == self.progress.total
+        }
+
+        // collects finished work from the worker and uploads it until `budget` is spent
+        pub fn poll(&mut self, budget: Duration) {
+            let start = Instant::now();
+
+            while let Ok(Decoded { id, image }) = self.decoded.try_recv() {
+                let texture = self.textures.remove(&id).unwrap();
+                self.uploads.push_back((texture, image));
+                self.progress.done += 1;
+            }
+
+            while start.elapsed() < budget {
+                match self.uploads.pop_front() {
+                    Some((texture, Some(image))) => texture.upload_hdr(&image),
+                    // the placeholder stays when the file couldn't be decoded
+                    Some((_, None)) => {}
+                    None => break,
+                }
+                self.progress.done += 1;
+            }
+        }
+    }
+
+    impl Default for Loader {
+        fn default() -> Self {
+            Loader::new()
+        }
+    }
+
+    impl Drop for Loader {
+        fn drop(&mut self) {
+            // closing the channel lets the worker run out of requests and return
+            self.requests = None;
+
+            if let Some(worker) = self.worker.take() {
+                worker.join().unwrap();
+            }
+        }
+    }
+
+    fn work(requests: mpsc::Receiver<Request>, decoded: mpsc::Sender<Decoded>) {
+        for Request { id, path } in requests {
+            let image = HdrImage::decode(&path);
+
+            if decoded.send(Decoded { id, image }).is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -1,17 +1,22 @@
 use gfx_maths::{mat4::Mat4, vec2::Vec2, vec3::Vec3};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
-use std::{f32::consts::PI, fs::File, io::Read, mem::size_of, ptr, rc::Rc};
+use std::{f32::consts::PI, fs::File, io::Read, mem::size_of, ptr, rc::Rc, time::Duration};
 
 const SCR_WIDTH: u32 = 1280;
 const SCR_HEIGHT: u32 = 720;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
+const UPLOAD_BUDGET: Duration = Duration::from_millis(4);
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
+pub mod loader;
+use loader::loader::Loader;
 pub mod mesh;
-use mesh::mesh::{stbi_flip_vertical, Texture, VertexArray};
+use mesh::mesh::{stbi_flip_vertical, HdrImage, Texture, VertexArray};
 pub mod shader;
 use shader::shader::Program;
 
@@ -73,8 +78,11 @@ fn main() {
 
     let capture_fbo = Framebuffer::new(Rc::clone(&gl));
 
+    // stb_image's flip flag is global, so it's set once before the loader's worker starts decoding
     stbi_flip_vertical(true);
-    let hdr_tex = Texture::new_hdr(Rc::clone(&gl), "resources/textures/hdr/newport_loft.hdr");
+    let mut loader = Loader::new();
+    let hdr_tex = loader.load_hdr(Rc::clone(&gl), "resources/textures/hdr/newport_loft.hdr");
+    let mut loading = true;
 
     let cube_map = Texture::new_cube(Rc::clone(&gl), 512);
     let irradiance_map = Texture::new_cube(Rc::clone(&gl), 32);
@@ -89,41 +97,47 @@ fn main() {
         look_at(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, -1.0, 0.0)),
     ];
 
-    equirectangular_cubemap.apply();
-    equirectangular_cubemap.set_int("equirectangularMap", 0);
-    equirectangular_cubemap.set_mat4("projection", capture_projection);
-    gl.active_texture(0);
-    hdr_tex.bind();
-
-    gl.viewport(512, 512);
-    capture_fbo.bind();
-    cube.bind();
-    for i in 0..6 {
-        equirectangular_cubemap.set_mat4("view", *capture_views.get(i).unwrap());
-        cube_map.attach(i as u32);
-        gl.clear();
+    // run once with the placeholder so the maps aren't garbage, and again when the HDR has been uploaded
+    let capture_environment = || {
+        capture_fbo.render_dimension(512);
 
-        cube.draw();
-    }
-    gl.unbind_framebuffer();
+        equirectangular_cubemap.apply();
+        equirectangular_cubemap.set_int("equirectangularMap", 0);
+        equirectangular_cubemap.set_mat4("projection", capture_projection);
+        gl.active_texture(0);
+        hdr_tex.bind();
 
-    capture_fbo.render_dimension(32);
+        gl.viewport(512, 512);
+        capture_fbo.bind();
+        cube.bind();
+        for i in 0..6 {
+            equirectangular_cubemap.set_mat4("view", *capture_views.get(i).unwrap());
+            cube_map.attach(i as u32);
+            gl.clear();
 
-    irradiance_shader.apply();
-    irradiance_shader.set_int("environmentMap", 0);
-    irradiance_shader.set_mat4("projection", capture_projection);
-    cube_map.bind();
+            cube.draw();
+        }
+        gl.unbind_framebuffer();
 
-    gl.viewport(32, 32);
-    capture_fbo.bind();
-    for i in 0..6 {
-        irradiance_shader.set_mat4("view", *capture_views.get(i).unwrap());
-        irradiance_map.attach(i as u32);
-        gl.clear();
+        capture_fbo.render_dimension(32);
 
-        cube.draw();
-    }
-    gl.unbind_framebuffer();
+        irradiance_shader.apply();
+        irradiance_shader.set_int("environmentMap", 0);
+        irradiance_shader.set_mat4("projection", capture_projection);
+        cube_map.bind();
+
+        gl.viewport(32, 32);
+        capture_fbo.bind();
+        for i in 0..6 {
+            irradiance_shader.set_mat4("view", *capture_views.get(i).unwrap());
+            irradiance_map.attach(i as u32);
+            gl.clear();
+
+            cube.draw();
+        }
+        gl.unbind_framebuffer();
+    };
+    capture_environment();
 
     glfw.poll_events();
 
@@ -147,6 +161,20 @@ fn main() {
 
         process_input(&mut camera, &mut window, delta_time);
 
+        loader.poll(UPLOAD_BUDGET);
+        if loading {
+            if loader.is_idle() {
+                loading = false;
+                window.set_title("LearnOpenGL");
+
+                capture_environment();
+                let (width, height) = window.get_framebuffer_size();
+                gl.viewport(width, height);
+            } else {
+                window.set_title(&format!("LearnOpenGL - loading {:.0}%", loader.progress().fraction() * 100.0));
+            }
+        }
+
         gl.clear_color(0.2, 0.3, 0.3, 1.0);
         gl.clear();
 
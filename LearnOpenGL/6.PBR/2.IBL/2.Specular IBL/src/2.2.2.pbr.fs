@@ -7,10 +7,13 @@ in vec3 Normal;
 // material parameters
 uniform sampler2D albedoMap;
 uniform sampler2D normalMap;
-uniform sampler2D metallicMap;
-uniform sampler2D roughnessMap;
+uniform sampler2D metallicRoughnessMap;
 uniform sampler2D aoMap;
 
+uniform vec4 baseColorFactor;
+uniform float metallicFactor;
+uniform float roughnessFactor;
+
 // IBL
 uniform samplerCube irradianceMap;
 uniform samplerCube prefilterMap;
@@ -93,9 +96,10 @@ vec3 fresnelSchlickRoughness(float cosTheta, vec3 F0, float roughness)
 void main()
 {		
     // material properties
-    vec3 albedo = pow(texture(albedoMap, TexCoords).rgb, vec3(2.2));
-    float metallic = texture(metallicMap, TexCoords).r;
-    float roughness = texture(roughnessMap, TexCoords).r;
+    vec3 albedo = pow(texture(albedoMap, TexCoords).rgb, vec3(2.2)) * baseColorFactor.rgb;
+    vec2 metallicRoughness = texture(metallicRoughnessMap, TexCoords).bg;
+    float metallic = metallicRoughness.x * metallicFactor;
+    float roughness = metallicRoughness.y * roughnessFactor;
     float ao = texture(aoMap, TexCoords).r;
        
     // input lighting data
@@ -1,17 +1,24 @@
 use gfx_maths::{mat4::Mat4, vec2::Vec2, vec3::Vec3};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
-use std::{f32::consts::PI, fs::File, io::Read, mem::size_of, ptr, rc::Rc};
+use std::{f32::consts::PI, fs::File, io::Read, mem::size_of, ptr, rc::Rc, time::Duration};
 
 const SCR_WIDTH: u32 = 1280;
 const SCR_HEIGHT: u32 = 720;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
+const UPLOAD_BUDGET: Duration = Duration::from_millis(4);
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
+pub mod material;
+use material::material::{Fallbacks, Material, Slot};
+pub mod loader;
+use loader::loader::Loader;
 pub mod mesh;
-use mesh::mesh::{stbi_flip_vertical, Texture, VertexArray};
+use mesh::mesh::{stbi_flip_vertical, HdrImage, Texture, VertexArray};
 pub mod shader;
 use shader::shader::Program;
 
@@ -55,44 +62,16 @@ fn main() {
     pbr_shader.set_int("irradianceMap", 0);
     pbr_shader.set_int("prefilterMap", 1);
     pbr_shader.set_int("brdfLUT", 2);
-    pbr_shader.set_int("albedoMap", 3);
-    pbr_shader.set_int("normalMap", 4);
-    pbr_shader.set_int("metallicMap", 5);
-    pbr_shader.set_int("roughnessMap", 6);
-    pbr_shader.set_int("aoMap", 7);
 
     background_shader.apply();
     background_shader.set_int("environmentMap", 0);
 
-    let iron_albedo = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/albedo.png");
-    let iron_normal = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/normal.png");
-    let iron_metallic = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/metallic.png");
-    let iron_roughness = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/roughness.png");
-    let iron_ao = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/rusted_iron/ao.png");
-
-    let gold_albedo = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/gold/albedo.png");
-    let gold_normal = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/gold/normal.png");
-    let gold_metallic = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/gold/metallic.png");
-    let gold_roughness = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/gold/roughness.png");
-    let gold_ao = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/gold/ao.png");
-
-    let grass_albedo = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/grass/albedo.png");
-    let grass_normal = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/grass/normal.png");
-    let grass_metallic = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/grass/metallic.png");
-    let grass_roughness = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/grass/roughness.png");
-    let grass_ao = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/grass/ao.png");
-
-    let plastic_albedo = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/plastic/albedo.png");
-    let plastic_normal = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/plastic/normal.png");
-    let plastic_metallic = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/plastic/metallic.png");
-    let plastic_roughness = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/plastic/roughness.png");
-    let plastic_ao = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/plastic/ao.png");
-
-    let wall_albedo = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/wall/albedo.png");
-    let wall_normal = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/wall/normal.png");
-    let wall_metallic = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/wall/metallic.png");
-    let wall_roughness = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/wall/roughness.png");
-    let wall_ao = Texture::new(Rc::clone(&gl), "", "").load("resources/textures/pbr/wall/ao.png");
+    let fallbacks = Rc::new(Fallbacks::new(Rc::clone(&gl)));
+    let iron = load_material(&gl, &fallbacks, "resources/textures/pbr/rusted_iron");
+    let gold = load_material(&gl, &fallbacks, "resources/textures/pbr/gold");
+    let grass = load_material(&gl, &fallbacks, "resources/textures/pbr/grass");
+    let plastic = load_material(&gl, &fallbacks, "resources/textures/pbr/plastic");
+    let wall = load_material(&gl, &fallbacks, "resources/textures/pbr/wall");
 
     let light_positions = vec![
         Vec3::new(-10.0, 10.0, 10.0),
@@ -109,8 +88,11 @@ fn main() {
 
     let capture_fbo = Framebuffer::new(Rc::clone(&gl));
 
+    // stb_image's flip flag is global, so it's set once before the loader's worker starts decoding
     stbi_flip_vertical(true);
-    let hdr_tex = Texture::new_hdr(Rc::clone(&gl), "resources/textures/hdr/newport_loft.hdr");
+    let mut loader = Loader::new();
+    let hdr_tex = loader.load_hdr(Rc::clone(&gl), "resources/textures/hdr/newport_loft.hdr");
+    let mut loading = true;
     let brdf_lut = Texture::new_lut(Rc::clone(&gl));
 
     let cube_map = Texture::new_cube(Rc::clone(&gl), 512, true);
@@ -127,71 +109,79 @@ fn main() {
         look_at(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, -1.0, 0.0)),
     ];
 
-    equirectangular_cubemap.apply();
-    equirectangular_cubemap.set_int("equirectangularMap", 0);
-    equirectangular_cubemap.set_mat4("projection", capture_projection);
-    gl.active_texture(0);
-    hdr_tex.bind();
+    // run once with the placeholder so the maps aren't garbage, and again when the HDR has been uploaded
+    let capture_environment = || {
+        capture_fbo.render_dimension(512, 512);
 
-    gl.viewport(512, 512);
-    capture_fbo.bind();
-    cube.bind();
-    for i in 0..6 {
-        equirectangular_cubemap.set_mat4("view", *capture_views.get(i).unwrap());
-        cube_map.attach(i as u32);
-
-        gl.clear();
-        cube.draw();
-    }
-    gl.unbind_framebuffer();
-
-    cube_map.bind();
-    cube_map.generate_mipmap();
-
-    capture_fbo.render_dimension(32, 32);
+        equirectangular_cubemap.apply();
+        equirectangular_cubemap.set_int("equirectangularMap", 0);
+        equirectangular_cubemap.set_mat4("projection", capture_projection);
+        gl.active_texture(0);
+        hdr_tex.bind();
 
-    irradiance_shader.apply();
-    irradiance_shader.set_int("environmentMap", 0);
-    irradiance_shader.set_mat4("projection", capture_projection);
+        gl.viewport(512, 512);
+        capture_fbo.bind();
+        cube.bind();
+        for i in 0..6 {
+            equirectangular_cubemap.set_mat4("view", *capture_views.get(i).unwrap());
+            cube_map.attach(i as u32);
 
-    gl.viewport(32, 32);
-    capture_fbo.bind();
-    for i in 0..6 {
-        irradiance_shader.set_mat4("view", *capture_views.get(i).unwrap());
-        irradiance_map.attach(i as u32);
+            gl.clear();
+            cube.draw();
+        }
+        gl.unbind_framebuffer();
 
-        gl.clear();
-        cube.draw();
-    }
-    gl.unbind_framebuffer();
+        cube_map.bind();
+        cube_map.generate_mipmap();
 
-    prefilter_map.bind();
-    prefilter_map.generate_mipmap();
+        capture_fbo.render_dimension(32, 32);
 
-    prefilter_shader.apply();
-    prefilter_shader.set_int("environmentMap", 0);
-    prefilter_shader.set_mat4("projection", capture_projection);
-    cube_map.bind();
+        irradiance_shader.apply();
+        irradiance_shader.set_int("environmentMap", 0);
+        irradiance_shader.set_mat4("projection", capture_projection);
 
-    capture_fbo.bind();
-    let max_mip_levels = 5;
-    for mip in 0..max_mip_levels {
-        let mip_width = (128.0 * f32::powf(0.5, mip as f32)) as i32;
-        let mip_height = (128.0 * f32::powf(0.5, mip as f32)) as i32;
-        capture_fbo.render_dimension(mip_width, mip_height);
-        gl.viewport(mip_width, mip_height);
-
-        let roughness = mip as f32 / max_mip_levels as f32;
-        prefilter_shader.set_float("roughness", roughness);
+        gl.viewport(32, 32);
+        capture_fbo.bind();
         for i in 0..6 {
-            prefilter_shader.set_mat4("view", *capture_views.get(i).unwrap());
-            prefilter_map.attach(i as u32);
+            irradiance_shader.set_mat4("view", *capture_views.get(i).unwrap());
+            irradiance_map.attach(i as u32);
 
             gl.clear();
             cube.draw();
         }
-    }
+        gl.unbind_framebuffer();
+
+        prefilter_map.bind();
+        prefilter_map.generate_mipmap();
+
+        prefilter_shader.apply();
+        prefilter_shader.set_int("environmentMap", 0);
+        prefilter_shader.set_mat4("projection", capture_projection);
+        cube_map.bind();
+
+        capture_fbo.bind();
+        let max_mip_levels = 5;
+        for mip in 0..max_mip_levels {
+            let mip_width = (128.0 * f32::powf(0.5, mip as f32)) as i32;
+            let mip_height = (128.0 * f32::powf(0.5, mip as f32)) as i32;
+            capture_fbo.render_dimension(mip_width, mip_height);
+            gl.viewport(mip_width, mip_height);
+
+            let roughness = mip as f32 / max_mip_levels as f32;
+            prefilter_shader.set_float("roughness", roughness);
+            for i in 0..6 {
+                prefilter_shader.set_mat4("view", *capture_views.get(i).unwrap());
+                prefilter_map.attach(i as u32);
+
+                gl.clear();
+                cube.draw();
+            }
+        }
+        gl.unbind_framebuffer();
+    };
+    capture_environment();
 
+    capture_fbo.bind();
     capture_fbo.render_dimension(512, 512);
     brdf_lut.attach(0);
 
@@ -225,6 +215,20 @@ fn main() {
 
         process_input(&mut camera, &mut window, delta_time);
 
+        loader.poll(UPLOAD_BUDGET);
+        if loading {
+            if loader.is_idle() {
+                loading = false;
+                window.set_title("LearnOpenGL");
+
+                capture_environment();
+                let (width, height) = window.get_framebuffer_size();
+                gl.viewport(width, height);
+            } else {
+                window.set_title(&format!("LearnOpenGL - loading {:.0}%", loader.progress().fraction() * 100.0));
+            }
+        }
+
         gl.clear_color(0.2, 0.3, 0.3, 1.0);
         gl.clear();
 
@@ -239,77 +243,32 @@ fn main() {
         gl.active_texture(2);
         brdf_lut.bind();
 
-        gl.active_texture(3);
-        iron_albedo.bind();
-        gl.active_texture(4);
-        iron_normal.bind();
-        gl.active_texture(5);
-        iron_metallic.bind();
-        gl.active_texture(6);
-        iron_roughness.bind();
-        gl.active_texture(7);
-        iron_ao.bind();
+        iron.bind(&pbr_shader, 3);
 
         let model = Mat4::translate(Vec3::new(-5.0, 0.0, 2.0));
         pbr_shader.set_mat4("model", model);
         sphere.bind();
         sphere.draw();
 
-        gl.active_texture(3);
-        gold_albedo.bind();
-        gl.active_texture(4);
-        gold_normal.bind();
-        gl.active_texture(5);
-        gold_metallic.bind();
-        gl.active_texture(6);
-        gold_roughness.bind();
-        gl.active_texture(7);
-        gold_ao.bind();
+        gold.bind(&pbr_shader, 3);
 
         let model = Mat4::translate(Vec3::new(-3.0, 0.0, 2.0));
         pbr_shader.set_mat4("model", model);
         sphere.draw();
 
-        gl.active_texture(3);
-        grass_albedo.bind();
-        gl.active_texture(4);
-        grass_normal.bind();
-        gl.active_texture(5);
-        grass_metallic.bind();
-        gl.active_texture(6);
-        grass_roughness.bind();
-        gl.active_texture(7);
-        grass_ao.bind();
+        grass.bind(&pbr_shader, 3);
 
         let model = Mat4::translate(Vec3::new(-1.0, 0.0, 2.0));
         pbr_shader.set_mat4("model", model);
         sphere.draw();
 
-        gl.active_texture(3);
-        plastic_albedo.bind();
-        gl.active_texture(4);
-        plastic_normal.bind();
-        gl.active_texture(5);
-        plastic_metallic.bind();
-        gl.active_texture(6);
-        plastic_roughness.bind();
-        gl.active_texture(7);
-        plastic_ao.bind();
+        plastic.bind(&pbr_shader, 3);
 
         let model = Mat4::translate(Vec3::new(1.0, 0.0, 2.0));
         pbr_shader.set_mat4("model", model);
         sphere.draw();
 
-        gl.active_texture(3);
-        wall_albedo.bind();
-        gl.active_texture(4);
-        wall_normal.bind();
-        gl.active_texture(5);
-        wall_metallic.bind();
-        gl.active_texture(6);
-        wall_roughness.bind();
-        gl.active_texture(7);
-        wall_ao.bind();
+        wall.bind(&pbr_shader, 3);
 
         let model = Mat4::translate(Vec3::new(3.0, 0.0, 2.0));
         pbr_shader.set_mat4("model", model);
@@ -357,6 +316,17 @@ fn look_at(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
     Mat4::from([s.x, u.x, f.x, 0.0, s.y, u.y, f.y, 0.0, s.z, u.z, f.z, 0.0, -s.dot(eye), -u.dot(eye), -f.dot(eye), 1.0])
 }
 
+fn load_material(gl: &Rc<gl::Gl>, fallbacks: &Rc<Fallbacks>, directory: &str) -> Material {
+    let texture = |name: &str| Rc::new(Texture::new(Rc::clone(gl), "", "").load(&format!("{directory}/{name}.png")));
+    let metallic_roughness = Texture::new(Rc::clone(gl), "", "").load_metallic_roughness(&format!("{directory}/metallic.png"), &format!("{directory}/roughness.png"));
+
+    Material::new(Rc::clone(fallbacks))
+        .with_texture(Slot::BaseColor, texture("albedo"))
+        .with_texture(Slot::Normal, texture("normal"))
+        .with_texture(Slot::MetallicRoughness, Rc::new(metallic_roughness))
+        .with_texture(Slot::Occlusion, texture("ao"))
+}
+
 fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
@@ -0,0 +1,162 @@
+pub mod material {
+    use crate::{gl, Program, Rc, Texture, Vec3};
+
+    const SLOT_COUNT: usize = 6;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Slot {
+        BaseColor,
+        MetallicRoughness,
+        Normal,
+        Occlusion,
+        Emissive,
+        Height,
+    }
+
+    impl Slot {
+        pub const ALL: [Slot; SLOT_COUNT] = [Slot::BaseColor, Slot::MetallicRoughness, Slot::Normal, Slot::Occlusion, Slot::Emissive, Slot::Height];
+
+        // the texture_*1 names keep the tutorial shaders working
+        fn sampler_names(self) -> &'static [&'static str] {
+            match self {
+                Slot::BaseColor => &["baseColorMap", "albedoMap", "texture_diffuse1"],
+                Slot::MetallicRoughness => &["metallicRoughnessMap"],
+                Slot::Normal => &["normalMap", "texture_normal1"],
+                Slot::Occlusion => &["occlusionMap", "aoMap"],
+                Slot::Emissive => &["emissiveMap"],
+                Slot::Height => &["heightMap", "texture_height1"],
+            }
+        }
+    }
+
+    pub struct Factors {
+        pub base_color: [f32; 4],
+        pub metallic: f32,
+        pub roughness: f32,
+        pub normal_scale: f32,
+        pub occlusion_strength: f32,
+        pub emissive: Vec3,
+        pub height_scale: f32,
+    }
+
+    impl Default for Factors {
+        fn default() -> Self {
+            Factors {
+                base_color: [1.0; 4],
+                metallic: 1.0,
+                roughness: 1.0,
+                normal_scale: 1.0,
+                occlusion_strength: 1.0,
+                emissive: Vec3::zero(),
+                height_scale: 0.1,
+            }
+        }
+    }
+
+    pub struct Fallbacks {
+        gl: Rc<gl::Gl>,
+        white: Texture,
+        black: Texture,
+        flat_normal: Texture,
+    }
+
+    impl Fallbacks {
+        pub fn new(gl: Rc<gl::Gl>) -> Self {
+            let white = Texture::new_color(Rc::clone(&gl), [255, 255, 255, 255]);
+            let black = Texture::new_color(Rc::clone(&gl), [0, 0, 0, 255]);
+            let flat_normal = Texture::new_color(Rc::clone(&gl), [128, 128, 255, 255]);
+
+            Fallbacks { gl, white, black, flat_normal }
+        }
+
+        fn texture(&self, slot: Slot) -> &Texture {
+            match slot {
+                Slot::BaseColor | Slot::MetallicRoughness | Slot::Occlusion | Slot::Emissive => &self.white,
+                Slot::Height => &self.black,
+                Slot::Normal => &self.flat_normal,
+            }
+        }
+    }
+
+    pub struct Material {
+        textures: [Option<Rc<Texture>>; SLOT_COUNT],
+        pub factors: Factors,
+        fallbacks: Rc<Fallbacks>,
+    }
+
+    impl Material {
+        pub fn new(fallbacks: Rc<Fallbacks>) -> Self {
+            Material {
+                textures: Default::default(),
+                factors: Factors::default(),
+                fallbacks,
+            }
+        }
+
+        pub fn with_texture(mut self, slot: Slot, texture: Rc<Texture>) -> Self {
+            self.set_texture(slot, texture);
+            self
+        }
+
+        pub fn set_texture(&mut self, slot: Slot, texture: Rc<Texture>) {
+            self.textures[slot as usize] = Some(texture);
+        }
+
+        pub fn texture(&self, slot: Slot) -> Option<&Rc<Texture>> {
+            self.textures[slot as usize].as_ref()
+        }
+
+        // returns the next free texture unit
+        pub fn bind(&self, shader: &Program, first_unit: u32) -> u32 {
+            let gl = &self.fallbacks.gl;
+            let mut unit = first_unit;
+
+            for slot in Slot::ALL {
+                let sampler = slot.sampler_names().iter().filter_map(|name| shader.uniform(name)).find(|uniform| uniform.kind == gl::SAMPLER_2D);
+
+                if let Some(sampler) = sampler {
+                    let texture = match self.texture(slot) {
+                        Some(texture) => texture,
+                        None => self.fallbacks.texture(slot),
+                    };
+
+                    unsafe {
+                        gl.ActiveTexture(gl::TEXTURE0 + unit);
+                        texture.bind();
+                        gl.Uniform1i(sampler.location, unit as i32);
+                    }
+                    unit += 1;
+                }
+            }
+
+            let factors = &self.factors;
+            unsafe {
+                if let Some(uniform) = shader.uniform("baseColorFactor") {
+                    gl.Uniform4fv(uniform.location, 1, factors.base_color.as_ptr());
+                }
+                if let Some(uniform) = shader.uniform("metallicFactor") {
+                    gl.Uniform1f(uniform.location, factors.metallic);
+                }
+                if let Some(uniform) = shader.uniform("roughnessFactor") {
+                    gl.Uniform1f(uniform.location, factors.roughness);
+                }
+                if let Some(uniform) = shader.uniform("normalScale") {
+                    gl.Uniform1f(uniform.location, factors.normal_scale);
+                }
+                if let Some(uniform) = shader.uniform("occlusionStrength") {
+                    gl.Uniform1f(uniform.location, factors.occlusion_strength);
+                }
+                if let Some(uniform) = shader.uniform("emissiveFactor") {
+                    gl.Uniform3f(uniform.location, factors.emissive.x, factors.emissive.y, factors.emissive.z);
+                }
+                if let Some(uniform) = shader.uniform("heightScale") {
+                    gl.Uniform1f(uniform.location, factors.height_scale);
+                }
+
+                gl.ActiveTexture(gl::TEXTURE0);
+            }
+
+            unit
+        }
+    }
+}
@@ -1,5 +1,5 @@
 pub mod mesh {
-    use crate::{gl, ptr, size_of, File, Program, Rc, Read, Vec2, Vec3, PI};
+    use crate::{gl, interleave, ptr, size_of, Attribute, File, MeshBuilder, Program, Rc, Read, Vec2, Vec3};
 
     const MAX_BONE_INFLUENCE: usize = 4;
     const FLOAT_SIZE: usize = size_of::<f32>();
@@ -125,23 +125,12 @@ pub mod mesh {
         }
     }
 
-    const INDEX_COUNT: usize = 36;
-    const INDICES: [u32; INDEX_COUNT] = [
-        0, 1, 2, 2, 1, 3, // first quad
-        4, 5, 6, 6, 5, 7, // second quad
-        8, 9, 10, 10, 9, 11, // third quad
-        12, 13, 14, 14, 13, 15, // fourth quad
-        16, 17, 18, 18, 17, 19, // fifth quad
-        20, 21, 22, 22, 21, 23, // sixth quad
-    ];
-
     pub struct VertexArray {
         gl: Rc<gl::Gl>,
         vertex_array: gl::types::GLuint,
         vertex_buffer: gl::types::GLuint,
         index_buffer: gl::types::GLuint,
         index_count: usize,
-        draw_type: gl::types::GLenum,
     }
 
     impl VertexArray {
@@ -162,216 +151,52 @@ pub mod mesh {
                 vertex_buffer,
                 index_buffer,
                 index_count: 0,
-                draw_type: gl::TRIANGLES,
             }
         }
 
-        pub fn new_quad(gl: Rc<gl::Gl>) -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
+        // the attributes go to locations 0, 1, 2... in the order given
+        pub fn from_builder(gl: Rc<gl::Gl>, builder: MeshBuilder, attributes: &[Attribute]) -> Self {
+            let mut vao = VertexArray::new(gl);
+            let gl = &vao.gl;
 
-            let vertices: Vec<f32> = vec![-1.0, -1.0, 0.0, 0.0, 0.0, 1.0, -1.0, 0.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0];
-            let indices = Vec::from(&INDICES[..6]);
+            let (vertices, indices) = builder.build();
+            let vertices = interleave(&vertices, attributes);
+            let stride: usize = attributes.iter().map(|attribute| attribute.components()).sum();
 
             unsafe {
-                gl.GenVertexArrays(1, &mut vertex_array);
-                gl.GenBuffers(1, &mut vertex_buffer);
-                gl.GenBuffers(1, &mut index_buffer);
-
-                gl.BindVertexArray(vertex_array);
+                gl.BindVertexArray(vao.vertex_array);
 
-                gl.BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+                gl.BindBuffer(gl::ARRAY_BUFFER, vao.vertex_buffer);
                 gl.BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
 
-                gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ptr::null());
-                gl.EnableVertexAttribArray(0);
-
-                gl.VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(1);
+                let mut offset = 0;
+                for (location, attribute) in attributes.iter().enumerate() {
+                    let components = attribute.components();
+                    gl.VertexAttribPointer(location as u32, components as i32, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * stride) as i32, ((offset * FLOAT_SIZE) as *const usize).cast());
+                    gl.EnableVertexAttribArray(location as u32);
+                    offset += components;
+                }
 
-                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
+                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vao.index_buffer);
                 gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
 
                 gl.BindVertexArray(0);
             }
 
-            VertexArray {
-                gl,
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-                draw_type: gl::TRIANGLES,
-            }
+            vao.index_count = indices.len();
+            vao
         }
 
-        pub fn new_cube(gl: Rc<gl::Gl>) -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
-
-            let vertices: Vec<f32> = vec![
-                // back face
-                1.0, -1.0, -1.0, 0.0, 0.0, -1.0, 1.0, 0.0, // bottom-right
-                -1.0, -1.0, -1.0, 0.0, 0.0, -1.0, 0.0, 0.0, // bottom-left
-                1.0, 1.0, -1.0, 0.0, 0.0, -1.0, 1.0, 1.0, // top-right
-                -1.0, 1.0, -1.0, 0.0, 0.0, -1.0, 0.0, 1.0, // top-left
-                // front face
-                -1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, // bottom-left
-                1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, // bottom-right
-                -1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, // top-left
-                1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, // top-right
-                // left face
-                -1.0, -1.0, -1.0, -1.0, 0.0, 0.0, 0.0, 1.0, // bottom-left
-                -1.0, -1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, // bottom-right
-                -1.0, 1.0, -1.0, -1.0, 0.0, 0.0, 1.0, 1.0, // top-left
-                -1.0, 1.0, 1.0, -1.0, 0.0, 0.0, 1.0, 0.0, // top-right
-                // right face
-                1.0, -1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, // bottom-left
-                1.0, -1.0, -1.0, 1.0, 0.0, 0.0, 0.0, 1.0, // bottom-right
-                1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, // top-left
-                1.0, 1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, // top-right
-                // bottom face
-                1.0, -1.0, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, // bottom-left
-                -1.0, -1.0, 1.0, 0.0, -1.0, 0.0, 0.0, 0.0, // bottom-right
-                1.0, -1.0, -1.0, 0.0, -1.0, 0.0, 1.0, 1.0, // top-left
-                -1.0, -1.0, -1.0, 0.0, -1.0, 0.0, 0.0, 1.0, // top-right
-                // top face
-                -1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, // bottom-left
-                1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, // bottom-right
-                -1.0, 1.0, -1.0, 0.0, 1.0, 0.0, 0.0, 1.0, // top-left
-                1.0, 1.0, -1.0, 0.0, 1.0, 0.0, 1.0, 1.0, // top-right
-            ];
-            let indices = Vec::from(&INDICES[..]);
-
-            unsafe {
-                gl.GenVertexArrays(1, &mut vertex_array);
-                gl.GenBuffers(1, &mut vertex_buffer);
-                gl.GenBuffers(1, &mut index_buffer);
-
-                gl.BindVertexArray(vertex_array);
-
-                gl.BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
-                gl.BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 8) as i32, ptr::null());
-                gl.EnableVertexAttribArray(0);
-
-                gl.VertexAttribPointer(1, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 8) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(1);
-
-                gl.VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 8) as i32, ((6 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(2);
-
-                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
-                gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl.BindVertexArray(0);
-            }
+        pub fn new_quad(gl: Rc<gl::Gl>) -> Self {
+            VertexArray::from_builder(gl, MeshBuilder::rectangle(2.0, 2.0), &[Attribute::Position, Attribute::TexCoords])
+        }
 
-            VertexArray {
-                gl,
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-                draw_type: gl::TRIANGLES,
-            }
+        pub fn new_cube(gl: Rc<gl::Gl>) -> Self {
+            VertexArray::from_builder(gl, MeshBuilder::cube(2.0), &[Attribute::Position, Attribute::Normal, Attribute::TexCoords])
         }
 
         pub fn new_sphere(gl: Rc<gl::Gl>) -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
-
-            let mut positions: Vec<Vec3> = Vec::new();
-            let mut uvs: Vec<Vec2> = Vec::new();
-            let mut normals: Vec<Vec3> = Vec::new();
-            let mut indices: Vec<u32> = Vec::new();
-
-            const X_SEGMENTS: usize = 64usize;
-            const Y_SEGMENTS: usize = 64usize;
-
-            for x in 0..=X_SEGMENTS {
-                for y in 0..=Y_SEGMENTS {
-                    let x_segment = x as f32 / X_SEGMENTS as f32;
-                    let y_segment = y as f32 / Y_SEGMENTS as f32;
-                    let x_pos = f32::cos(x_segment * 2.0 * PI) * f32::sin(y_segment * PI);
-                    let y_pos = f32::cos(y_segment * PI);
-                    let z_pos = f32::sin(x_segment * 2.0 * PI) * f32::sin(y_segment * PI);
-
-                    positions.push(Vec3::new(x_pos, y_pos, z_pos));
-                    uvs.push(Vec2::new(x_segment, y_segment));
-                    normals.push(Vec3::new(x_pos, y_pos, z_pos));
-                }
-            }
-            let mut odd_row = false;
-            for y in 0..Y_SEGMENTS {
-                if !odd_row {
-                    for x in 0..=X_SEGMENTS {
-                        indices.push((y * (X_SEGMENTS + 1) + x) as u32);
-                        indices.push(((y + 1) * (X_SEGMENTS + 1) + x) as u32);
-                    }
-                } else {
-                    for x in (0..=X_SEGMENTS).rev() {
-                        indices.push(((y + 1) * (X_SEGMENTS + 1) + x) as u32);
-                        indices.push((y * (X_SEGMENTS + 1) + x) as u32);
-                    }
-                }
-                odd_row = !odd_row;
-            }
-
-            let mut vertices = Vec::new();
-            for i in 0..positions.len() {
-                let position = positions.get(i).unwrap();
-                let normal = normals.get(i).unwrap();
-                let uv = uvs.get(i).unwrap();
-
-                vertices.push(position.x);
-                vertices.push(position.y);
-                vertices.push(position.z);
-                vertices.push(normal.x);
-                vertices.push(normal.y);
-                vertices.push(normal.z);
-                vertices.push(uv.x);
-                vertices.push(uv.y);
-            }
-
-            unsafe {
-                gl.GenVertexArrays(1, &mut vertex_array);
-                gl.GenBuffers(1, &mut vertex_buffer);
-                gl.GenBuffers(1, &mut index_buffer);
-
-                gl.BindVertexArray(vertex_array);
-
-                gl.BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
-                gl.BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                let stride = (FLOAT_SIZE * 8) as i32;
-                gl.VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
-                gl.EnableVertexAttribArray(0);
-
-                gl.VertexAttribPointer(1, 3, gl::FLOAT, gl::FALSE, stride, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(1);
-
-                gl.VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, stride, ((6 * FLOAT_SIZE) as *const usize).cast());
-                gl.EnableVertexAttribArray(2);
-
-                gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
-                gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl.BindVertexArray(0);
-            }
-
-            VertexArray {
-                gl,
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-                draw_type: gl::TRIANGLE_STRIP,
-            }
+            VertexArray::from_builder(gl, MeshBuilder::uv_sphere(1.0, 64, 64), &[Attribute::Position, Attribute::Normal, Attribute::TexCoords])
         }
 
         pub fn bind(&self) {
@@ -382,7 +207,7 @@ pub mod mesh {
 
         pub fn draw(&self) {
             unsafe {
-                self.gl.DrawElements(self.draw_type, self.index_count as i32, gl::UNSIGNED_INT, ptr::null());
+                self.gl.DrawElements(gl::TRIANGLES, self.index_count as i32, gl::UNSIGNED_INT, ptr::null());
             }
         }
     }
@@ -425,7 +250,7 @@ pub mod mesh {
             let mut components = 0;
 
             if hdr {
-                let dataf32 = unsafe { bindgen::stbi_loadf_from_memory(contents.as_mut_ptr(), contents.len() as i32, &mut width, &mut height, &mut components, 0) };
+                let dataf32 = unsafe { bindgen::stbi_loadf_from_memory(contents.as_mut_ptr(), contents.len() as i32, &mut width, &mut height, &mut components, 3) };
 
                 Image {
                     dataf32,
@@ -461,6 +286,33 @@ pub mod mesh {
         }
     }
 
+    // decoded on the loader's worker thread and uploaded on the GL thread
+    pub struct HdrImage {
+        pub width: i32,
+        pub height: i32,
+        pub pixels: Vec<f32>,
+    }
+
+    impl HdrImage {
+        pub fn decode(path: &str) -> Option<Self> {
+            let image = Image::new(path, true);
+
+            if image.dataf32 == ptr::null_mut() {
+                println!("Failed to load HDR image {path}");
+                return None;
+            }
+
+            let len = (3 * image.width * image.height) as usize;
+            let pixels = unsafe { std::slice::from_raw_parts(image.dataf32, len) }.to_vec();
+
+            Some(HdrImage {
+                width: image.width,
+                height: image.height,
+                pixels,
+            })
+        }
+    }
+
     pub struct Texture {
         gl: Rc<gl::Gl>,
         pub tex_type: String,
@@ -486,6 +338,62 @@ pub mod mesh {
             }
         }
 
+        pub fn new_color(gl: Rc<gl::Gl>, color: [u8; 4]) -> Self {
+            let texture = Texture::new(gl, "", "");
+
+            unsafe {
+                texture.gl.BindTexture(gl::TEXTURE_2D, texture.texture);
+                texture.gl.TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA as i32, 1, 1, 0, gl::RGBA, gl::UNSIGNED_BYTE, color.as_ptr().cast());
+
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            }
+
+            texture
+        }
+
+        // packs separate maps the way glTF does: roughness in green, metallic in blue
+        pub fn load_metallic_roughness(self, metallic_path: &str, roughness_path: &str) -> Self {
+            let gl = &self.gl;
+
+            let metallic = Image::new(metallic_path, false);
+            let roughness = Image::new(roughness_path, false);
+
+            if metallic.datau8 == ptr::null_mut() || roughness.datau8 == ptr::null_mut() {
+                println!("Failed to load texture");
+                return self;
+            }
+
+            // a smaller map is resampled to the larger one's size
+            let width = metallic.width.max(roughness.width);
+            let height = metallic.height.max(roughness.height);
+            let sample = |image: &Image, x: i32, y: i32| {
+                let pixel = (y * image.height / height) * image.width + x * image.width / width;
+                unsafe { *image.datau8.add(pixel as usize * 3) }
+            };
+
+            let mut packed = Vec::with_capacity((3 * width * height) as usize);
+            for y in 0..height {
+                for x in 0..width {
+                    packed.extend([0, sample(&roughness, x, y), sample(&metallic, x, y)]);
+                }
+            }
+
+            unsafe {
+                gl.BindTexture(gl::TEXTURE_2D, self.texture);
+                gl.TexImage2D(gl::TEXTURE_2D, 0, gl::RGB as i32, width, height, 0, gl::RGB, gl::UNSIGNED_BYTE, packed.as_ptr().cast());
+                gl.GenerateMipmap(gl::TEXTURE_2D);
+
+                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+
+                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+                gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            }
+
+            self
+        }
+
         pub fn load(self, path: &str) -> Self {
             let gl = &self.gl;
             let texture = self.texture;
@@ -528,35 +436,36 @@ pub mod mesh {
             self
         }
 
-        pub fn new_hdr(gl: Rc<gl::Gl>, path: &str) -> Self {
-            let mut texture = 0;
+        // a grey stand-in until upload_hdr replaces it with the decoded image
+        pub fn new_hdr(gl: Rc<gl::Gl>) -> Self {
+            let texture = Texture::new(gl, "", "");
 
-            let image = Image::new(path, true);
+            unsafe {
+                texture.gl.BindTexture(gl::TEXTURE_2D, texture.texture);
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
 
-            if image.dataf32 == ptr::null_mut() {
-                panic!("Failed to load HDR image");
-            } else {
-                unsafe {
-                    gl.GenTextures(1, &mut texture);
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                texture.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            }
 
-                    gl.BindTexture(gl::TEXTURE_2D, texture);
-                    gl.TexImage2D(gl::TEXTURE_2D, 0, gl::RGB16F as i32, image.width, image.height, 0, gl::RGB, gl::FLOAT, image.dataf32.cast());
-                    gl.GenerateMipmap(gl::TEXTURE_2D);
+            texture.upload_hdr(&HdrImage {
+                width: 1,
+                height: 1,
+                pixels: vec![0.5; 3],
+            });
 
-                    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-                    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            texture
+        }
 
-                    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-                    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-                }
-            }
+        // replaces the contents in place, so the handle given out for the placeholder sees the real image
+        pub fn upload_hdr(&self, image: &HdrImage) {
+            let gl = &self.gl;
 
-            Texture {
-                gl,
-                tex_type: "".to_string(),
-                tex_path: "".to_string(),
-                texture,
-                texture_type: gl::TEXTURE_2D,
+            unsafe {
+                gl.BindTexture(gl::TEXTURE_2D, self.texture);
+                gl.TexImage2D(gl::TEXTURE_2D, 0, gl::RGB16F as i32, image.width, image.height, 0, gl::RGB, gl::FLOAT, image.pixels.as_ptr().cast());
+                gl.GenerateMipmap(gl::TEXTURE_2D);
             }
         }
 
@@ -1,4 +1,4 @@
-use gfx_maths::{mat4::Mat4, quaternion::Quaternion, vec3::Vec3};
+use gfx_maths::{mat4::Mat4, quaternion::Quaternion, vec2::Vec2, vec3::Vec3};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
 use std::{f32::consts::PI, ffi::c_void, fs::File, io::Read, mem::size_of, ptr, slice, str};
 
@@ -7,6 +7,8 @@ const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 10.0;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::Camera;
 use camera::camera::Movement;
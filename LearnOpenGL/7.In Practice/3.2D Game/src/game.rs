@@ -84,15 +84,15 @@ pub mod game {
             let (width, height) = self.screen_size;
 
             let projection = Mat4::orthographic_lh(0.0, width as f32, height as f32, 0.0, -1.0, 1.0);
-            let sprite_shader = ResourceManager::load_shader("src/sprite.vs", "src/sprite.fs");
+            let sprite_shader = self.resources.load_shader("src/sprite.vs", "src/sprite.fs");
             sprite_shader.r#use();
             sprite_shader.set_int("sprite", 0);
             sprite_shader.set_mat4("projection", projection);
-            let particle_shader = ResourceManager::load_shader("src/particle.vs", "src/particle.fs");
+            let particle_shader = self.resources.load_shader("src/particle.vs", "src/particle.fs");
             particle_shader.r#use();
             particle_shader.set_int("sprite", 0);
             particle_shader.set_mat4("projection", projection);
-            let post_processing_shader = ResourceManager::load_shader("src/post_processing.vs", "src/post_processing.fs");
+            let post_processing_shader = self.resources.load_shader("src/post_processing.vs", "src/post_processing.fs");
 
             self.resources.load_texture("resources/textures/background.jpg", false, "background");
             self.resources.load_texture("resources/textures/awesomeface.png", true, "face");
@@ -110,7 +110,7 @@ pub mod game {
             let renderer = SpriteRenderer::new(sprite_shader);
             let particles = ParticleGenerator::new(particle_shader, self.resources.get_texture("particle"), 500);
             let effects = PostProcessor::new(post_processing_shader, width, height);
-            let mut text = TextRenderer::new(width as i32, height as i32);
+            let mut text = TextRenderer::new(&mut self.resources, width as i32, height as i32);
             text.load("resources/fonts/OCRAEXT.TTF", 24);
 
             let mut one = GameLevel::new();
@@ -125,6 +125,7 @@ pub mod game {
             self.levels.push(two);
             self.levels.push(three);
             self.levels.push(four);
+            println!("Loaded {}", self.resources.memory_usage());
 
             let player_pos = Vec2::new(width as f32 / 2.0 - PLAYER_SIZE.x / 2.0, height as f32 - PLAYER_SIZE.y);
             let player = GameObject::new_with(player_pos, PLAYER_SIZE, self.resources.get_texture("paddle"), Vec3::ONE, Vec2::ZERO);
@@ -24,7 +24,7 @@ pub mod particle_generator {
     pub struct ParticleGenerator {
         particles: Vec<Particle>,
         amount: u32,
-        shader: Shader,
+        shader: Rc<Shader>,
         texture: Rc<Texture>,
         last_used: u32,
         vao: u32,
@@ -41,7 +41,7 @@ pub mod particle_generator {
     }
 
     impl ParticleGenerator {
-        pub fn new(shader: Shader, texture: Rc<Texture>, amount: u32) -> Self {
+        pub fn new(shader: Rc<Shader>, texture: Rc<Texture>, amount: u32) -> Self {
             ParticleGenerator {
                 particles: Vec::new(),
                 amount,
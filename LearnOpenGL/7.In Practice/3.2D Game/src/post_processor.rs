@@ -1,9 +1,9 @@
 pub mod post_processor {
     use crate::{Shader, Texture};
-    use std::{mem::size_of, ptr};
+    use std::{mem::size_of, ptr, rc::Rc};
 
     pub struct PostProcessor {
-        shader: Shader,
+        shader: Rc<Shader>,
         texture: Texture,
         screen_size: (u32, u32),
         pub confuse: bool,
@@ -29,7 +29,7 @@ pub mod post_processor {
     }
 
     impl PostProcessor {
-        pub fn new(shader: Shader, width: u32, height: u32) -> Self {
+        pub fn new(shader: Rc<Shader>, width: u32, height: u32) -> Self {
             let screen_size = (width, height);
             let confuse = false;
             let chaos = false;
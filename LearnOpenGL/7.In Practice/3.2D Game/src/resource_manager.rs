@@ -1,18 +1,92 @@
 pub mod resource_manager {
     use crate::{Shader, Texture};
     use stb_image::stb_image::bindgen;
-    use std::{collections::HashMap, fs::File, io::Read, rc::Rc};
+    use std::{collections::HashMap, fmt, fs, fs::File, io::Read, path::PathBuf, rc::Rc};
 
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct TextureKey {
+        path: PathBuf,
+        alpha: bool,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct ShaderKey {
+        vertex: PathBuf,
+        fragment: PathBuf,
+    }
+
+    pub struct MemoryUsage {
+        pub textures: usize,
+        pub texture_bytes: usize,
+        pub shaders: usize,
+    }
+
+    impl fmt::Display for MemoryUsage {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} textures ({} KiB), {} shaders", self.textures, self.texture_bytes / 1024, self.shaders)
+        }
+    }
+
+    // keyed by canonical path and load options like the model loading sample's asset cache. the game looks its
+    // textures up by name, so names point into the cache and two names for the same file share one texture
     pub struct ResourceManager {
-        textures: HashMap<Box<str>, Rc<Texture>>,
+        textures: HashMap<TextureKey, Rc<Texture>>,
+        names: HashMap<Box<str>, TextureKey>,
+        shaders: HashMap<ShaderKey, Rc<Shader>>,
     }
 
     impl ResourceManager {
         pub fn new() -> Self {
-            ResourceManager { textures: HashMap::new() }
+            ResourceManager {
+                textures: HashMap::new(),
+                names: HashMap::new(),
+                shaders: HashMap::new(),
+            }
         }
 
-        pub fn load_shader(vs_file: &str, fs_file: &str) -> Shader {
+        pub fn load_shader(&mut self, vs_file: &str, fs_file: &str) -> Rc<Shader> {
+            let key = ShaderKey {
+                vertex: canonical(vs_file),
+                fragment: canonical(fs_file),
+            };
+
+            let shader = self.shaders.entry(key).or_insert_with(|| Rc::new(ResourceManager::load_shader_from_file(vs_file, fs_file)));
+
+            Rc::clone(shader)
+        }
+
+        pub fn load_texture(&mut self, path: &str, alpha: bool, name: &str) -> &Rc<Texture> {
+            let key = TextureKey { path: canonical(path), alpha };
+
+            self.names.insert(Box::from(name), key.clone());
+            self.textures.entry(key).or_insert_with(|| Rc::new(ResourceManager::load_texture_from_file(path, alpha)))
+        }
+
+        pub fn get_texture(&self, name: &str) -> Rc<Texture> {
+            Rc::clone(&self.textures[&self.names[name]])
+        }
+
+        pub fn memory_usage(&self) -> MemoryUsage {
+            MemoryUsage {
+                textures: self.textures.len(),
+                texture_bytes: self.textures.values().map(|texture| texture.bytes()).sum(),
+                shaders: self.shaders.len(),
+            }
+        }
+
+        // drops what only the cache still holds, along with the names of evicted textures
+        pub fn evict_unused(&mut self) -> usize {
+            let before = self.textures.len() + self.shaders.len();
+
+            self.textures.retain(|_, texture| Rc::strong_count(texture) > 1);
+            let textures = &self.textures;
+            self.names.retain(|_, key| textures.contains_key(key));
+            self.shaders.retain(|_, shader| Rc::strong_count(shader) > 1);
+
+            before - (self.textures.len() + self.shaders.len())
+        }
+
+        fn load_shader_from_file(vs_file: &str, fs_file: &str) -> Shader {
             let mut vertex_source = Vec::new();
             File::open(vs_file).unwrap().read_to_end(&mut vertex_source).unwrap();
             vertex_source.push(0);
@@ -24,15 +98,6 @@ pub mod resource_manager {
             Shader::compile(vertex_source, fragment_source)
         }
 
-        pub fn load_texture(&mut self, path: &str, alpha: bool, name: &str) -> &Rc<Texture> {
-            self.textures.insert(Box::from(name), Rc::new(ResourceManager::load_texture_from_file(path, alpha)));
-            self.textures.get(name).unwrap()
-        }
-
-        pub fn get_texture(&self, name: &str) -> Rc<Texture> {
-            Rc::clone(&self.textures[name])
-        }
-
         fn load_texture_from_file(path: &str, alpha: bool) -> Texture {
             let mut texture = Texture::new();
             if alpha {
@@ -55,4 +120,14 @@ pub mod resource_manager {
             texture
         }
     }
+
+    impl Default for ResourceManager {
+        fn default() -> Self {
+            ResourceManager::new()
+        }
+    }
+
+    fn canonical(path: &str) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+    }
 }
@@ -4,7 +4,7 @@ pub mod sprite_renderer {
     use std::{mem::size_of, ptr, rc::Rc};
 
     pub struct SpriteRenderer {
-        shader: Shader,
+        shader: Rc<Shader>,
         quad_vao: u32,
         vbo: u32,
     }
@@ -19,7 +19,7 @@ pub mod sprite_renderer {
     }
 
     impl SpriteRenderer {
-        pub fn new(shader: Shader) -> Self {
+        pub fn new(shader: Rc<Shader>) -> Self {
             let vertices: [f32; 24] = [
                 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0,
             ];
@@ -2,7 +2,7 @@ pub mod text_renderer {
     use crate::{ResourceManager, Shader};
     use freetype::freetype::{FT_Done_Face, FT_Done_FreeType, FT_Face, FT_Init_FreeType, FT_Library, FT_Load_Char, FT_New_Face, FT_Set_Pixel_Sizes, FT_LOAD_RENDER};
     use glam::{Mat4, Vec2, Vec3};
-    use std::{collections::HashMap, mem::size_of, ptr};
+    use std::{collections::HashMap, mem::size_of, ptr, rc::Rc};
 
     struct Character {
         texture_id: u32,
@@ -21,7 +21,7 @@ pub mod text_renderer {
 
     pub struct TextRenderer {
         characters: HashMap<char, Character>,
-        text_shader: Shader,
+        text_shader: Rc<Shader>,
         vao: u32,
         vbo: u32,
     }
@@ -36,8 +36,8 @@ pub mod text_renderer {
     }
 
     impl TextRenderer {
-        pub fn new(width: i32, height: i32) -> Self {
-            let text_shader = ResourceManager::load_shader("src/text_2d.vs", "src/text_2d.fs");
+        pub fn new(resources: &mut ResourceManager, width: i32, height: i32) -> Self {
+            let text_shader = resources.load_shader("src/text_2d.vs", "src/text_2d.fs");
             text_shader.r#use();
             text_shader.set_mat4("projection", Mat4::orthographic_lh(0.0, width as f32, 0.0, height as f32, 0.0, 1.0));
             text_shader.set_int("text", 0);
@@ -50,6 +50,12 @@ pub mod texture {
             }
         }
 
+        // the base level as uploaded
+        pub fn bytes(&self) -> usize {
+            let components = if self.image_format == gl::RGBA { 4 } else { 3 };
+            (self.screen_size.0 * self.screen_size.1) as usize * components
+        }
+
         pub fn bind(&self) {
             unsafe {
                 gl::BindTexture(gl::TEXTURE_2D, self.id);
@@ -1,4 +1,4 @@
-use glam::{Mat4, Vec3, Vec4};
+use glam::{Mat4, Vec2, Vec3, Vec4};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
 use std::ptr;
 
@@ -7,6 +7,8 @@ const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::{Camera, Movement};
 pub mod mesh;
@@ -1,19 +1,10 @@
 pub mod mesh {
+    use crate::{interleave, Attribute, MeshBuilder};
     use std::{mem::size_of, ptr};
 
     const FLOAT_SIZE: usize = size_of::<f32>();
     const U32_SIZE: usize = size_of::<u32>();
 
-    const INDEX_COUNT: usize = 36;
-    const INDICES: [u32; INDEX_COUNT] = [
-        0, 1, 2, 2, 1, 3, // first quad
-        4, 5, 6, 6, 5, 7, // second quad
-        8, 9, 10, 10, 9, 11, // third quad
-        12, 13, 14, 14, 13, 15, // fourth quad
-        16, 17, 18, 18, 17, 19, // fifth quad
-        20, 21, 22, 22, 21, 23, // sixth quad
-    ];
-
     pub struct VertexArray {
         vertex_array: gl::types::GLuint,
         vertex_buffer: gl::types::GLuint,
@@ -41,42 +32,40 @@ pub mod mesh {
             }
         }
 
-        pub fn new_quad() -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
+        // the attributes go to locations 0, 1, 2... in the order given
+        pub fn from_builder(builder: MeshBuilder, attributes: &[Attribute]) -> Self {
+            let mut vao = VertexArray::new();
 
-            let vertices: Vec<f32> = vec![-1.0, -1.0, 0.0, 0.0, 0.0, 1.0, -1.0, 0.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0];
-            let indices = Vec::from(&INDICES[..6]);
+            let (vertices, indices) = builder.build();
+            let vertices = interleave(&vertices, attributes);
+            let stride: usize = attributes.iter().map(|attribute| attribute.components()).sum();
 
             unsafe {
-                gl::GenVertexArrays(1, &mut vertex_array);
-                gl::GenBuffers(1, &mut vertex_buffer);
-                gl::GenBuffers(1, &mut index_buffer);
-
-                gl::BindVertexArray(vertex_array);
+                gl::BindVertexArray(vao.vertex_array);
 
-                gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vao.vertex_buffer);
                 gl::BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
 
-                gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ptr::null());
-                gl::EnableVertexAttribArray(0);
+                let mut offset = 0;
+                for (location, attribute) in attributes.iter().enumerate() {
+                    let components = attribute.components();
+                    gl::VertexAttribPointer(location as u32, components as i32, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * stride) as i32, ((offset * FLOAT_SIZE) as *const usize).cast());
+                    gl::EnableVertexAttribArray(location as u32);
+                    offset += components;
+                }
 
-                gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl::EnableVertexAttribArray(1);
-
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vao.index_buffer);
                 gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
 
                 gl::BindVertexArray(0);
             }
 
-            VertexArray {
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-            }
+            vao.index_count = indices.len();
+            vao
+        }
+
+        pub fn new_quad() -> Self {
+            VertexArray::from_builder(MeshBuilder::rectangle(2.0, 2.0), &[Attribute::Position, Attribute::TexCoords])
         }
 
         pub fn bind(&self) {
@@ -2,10 +2,14 @@
 out vec4 FragColor;
 
 in vec2 TexCoords;
+in vec3 Normal;
 
 uniform sampler2D texture_diffuse1;
 
 void main()
 {    
-    FragColor = texture(texture_diffuse1, TexCoords);
+    vec4 color = texture(texture_diffuse1, TexCoords);
+    // a sky above and darker ground below, so the morphed and skinned normals show
+    float sky = 0.5 + 0.5 * normalize(Normal).y;
+    FragColor = vec4(color.rgb * mix(0.6, 1.0, sky), color.a);
 }
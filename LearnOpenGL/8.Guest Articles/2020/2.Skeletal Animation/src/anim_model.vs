@@ -5,37 +5,152 @@ layout(location = 1) in vec3 norm;
 layout(location = 2) in vec2 tex;
 layout(location = 3) in vec3 tangent;
 layout(location = 4) in vec3 bitangent;
-layout(location = 5) in ivec4 boneIds; 
+layout(location = 5) in ivec4 boneIds;
 layout(location = 6) in vec4 weights;
 
 uniform mat4 projection;
 uniform mat4 view;
 uniform mat4 model;
 
-const int MAX_BONES = 100;
+// MAX_BONES is defined from the model's bone count when the program is linked
 const int MAX_BONE_INFLUENCE = 4;
-uniform mat4 finalBonesMatrices[MAX_BONES];
+
+#if defined(BONE_TEXTURE)
+// palettes too big for a uniform block, every bone as whole texels starting at the instance's offset
+uniform samplerBuffer bonePalette;
+uniform int bonePaletteOffset;
+
+mat2x4 boneQuaternion(int bone)
+{
+    int texel = bonePaletteOffset + bone * 2;
+    return mat2x4(texelFetch(bonePalette, texel), texelFetch(bonePalette, texel + 1));
+}
+
+mat4 boneMatrix(int bone)
+{
+    int texel = bonePaletteOffset + bone * 4;
+    return mat4(texelFetch(bonePalette, texel), texelFetch(bonePalette, texel + 1), texelFetch(bonePalette, texel + 2), texelFetch(bonePalette, texel + 3));
+}
+#elif defined(DUAL_QUATERNION)
+layout(std140) uniform BonePalette
+{
+    // rotation then translation, x y z w
+    mat2x4 finalBonesQuaternions[MAX_BONES];
+};
+
+mat2x4 boneQuaternion(int bone)
+{
+    return finalBonesQuaternions[bone];
+}
+#else
+layout(std140) uniform BonePalette
+{
+    mat4 finalBonesMatrices[MAX_BONES];
+};
+
+mat4 boneMatrix(int bone)
+{
+    return finalBonesMatrices[bone];
+}
+#endif
+
+#ifdef DUAL_QUATERNION
+vec3 rotate(vec4 q, vec3 v)
+{
+    return v + 2.0 * cross(q.xyz, cross(q.xyz, v) + q.w * v);
+}
+#endif
+
+// the size of MAX_MORPH_TARGETS in morph.rs
+const int MAX_MORPH_TARGETS = 32;
+
+// position, normal and tangent deltas of every vertex, target after target
+uniform samplerBuffer morphDeltas;
+uniform int morphCount;
+uniform int morphVertexCount;
+uniform float morphWeights[MAX_MORPH_TARGETS];
 
 out vec2 TexCoords;
+// world space, with the morph targets and bones applied
+out vec3 Normal;
+out vec3 Tangent;
 
 void main()
 {
+    vec3 position = pos;
+    vec3 normal = norm;
+    vec3 morphedTangent = tangent;
+    for(int i = 0 ; i < morphCount ; i++)
+    {
+        int texel = (i * morphVertexCount + gl_VertexID) * 3;
+        position += morphWeights[i] * texelFetch(morphDeltas, texel).xyz;
+        normal += morphWeights[i] * texelFetch(morphDeltas, texel + 1).xyz;
+        morphedTangent += morphWeights[i] * texelFetch(morphDeltas, texel + 2).xyz;
+    }
+
+#ifdef DUAL_QUATERNION
+    vec4 real = vec4(0.0f);
+    vec4 dual = vec4(0.0f);
+    vec4 first = vec4(0.0f);
+    for(int i = 0 ; i < MAX_BONE_INFLUENCE ; i++)
+    {
+        if(boneIds[i] == -1 || boneIds[i] >= MAX_BONES)
+            continue;
+        mat2x4 bone = boneQuaternion(boneIds[i]);
+        if(first == vec4(0.0f))
+            first = bone[0];
+        // q and -q are the same rotation, keep them all on the first one's side
+        float weight = dot(first, bone[0]) < 0.0f ? -weights[i] : weights[i];
+        real += bone[0] * weight;
+        dual += bone[1] * weight;
+    }
+
+    vec4 totalPosition = vec4(0.0f);
+    vec3 totalNormal = normal;
+    vec3 totalTangent = morphedTangent;
+    float len = length(real);
+    if(len > 0.0f)
+    {
+        real /= len;
+        dual /= len;
+        vec3 translation = 2.0f * (real.w * dual.xyz - dual.w * real.xyz + cross(real.xyz, dual.xyz));
+        totalPosition = vec4(rotate(real, position) + translation, 1.0f);
+        totalNormal = rotate(real, normal);
+        totalTangent = rotate(real, morphedTangent);
+    }
+#else
     vec4 totalPosition = vec4(0.0f);
+    vec3 totalNormal = vec3(0.0f);
+    vec3 totalTangent = vec3(0.0f);
     for(int i = 0 ; i < MAX_BONE_INFLUENCE ; i++)
     {
-        if(boneIds[i] == -1) 
+        if(boneIds[i] == -1)
             continue;
-        if(boneIds[i] >=MAX_BONES) 
+        if(boneIds[i] >=MAX_BONES)
         {
-            totalPosition = vec4(pos,1.0f);
+            totalPosition = vec4(position,1.0f);
+            totalNormal = normal;
+            totalTangent = morphedTangent;
             break;
         }
-        vec4 localPosition = finalBonesMatrices[boneIds[i]] * vec4(pos,1.0f);
-        totalPosition += localPosition * weights[i];
-        vec3 localNormal = mat3(finalBonesMatrices[boneIds[i]]) * norm;
+        mat4 bone = boneMatrix(boneIds[i]);
+        totalPosition += bone * vec4(position,1.0f) * weights[i];
+        totalNormal += mat3(bone) * normal * weights[i];
+        totalTangent += mat3(bone) * morphedTangent * weights[i];
    }
-	
+#endif
+
+    // props on sockets have no bone weights
+    if(totalPosition.w == 0.0f)
+    {
+        totalPosition = vec4(position, 1.0f);
+        totalNormal = normal;
+        totalTangent = morphedTangent;
+    }
+
     mat4 viewModel = view * model;
     gl_Position =  projection * viewModel * totalPosition;
 	TexCoords = tex;
+    Normal = mat3(transpose(inverse(model))) * totalNormal;
+    Tangent = mat3(model) * totalTangent;
 }
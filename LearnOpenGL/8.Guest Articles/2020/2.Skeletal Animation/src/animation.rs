@@ -1,6 +1,6 @@
 pub mod animation {
-    use crate::{convert_matrix, Bone, BoneInfo, Model};
-    use glam::Mat4;
+    use crate::{convert_matrix, Bone, BoneInfo, BoneMask, IkGoal, Model, MorphWeights, Pose, Skeleton, Socket, WrapMode};
+    use glam::{Mat4, Vec3};
     use russimp::{
         animation,
         node::Node,
@@ -8,66 +8,444 @@ pub mod animation {
     };
     use std::{collections::HashMap, mem};
 
+    // assimp leaves it at 0 when the file doesn't say
+    const DEFAULT_TICKS_PER_SECOND: f64 = 25.0;
+
+    // one clip playing at its own time
+    #[derive(Clone)]
+    pub struct Player {
+        pub clip: usize,
+        pub time: f32,
+        pub speed: f32,
+        pub weight: f32,
+        pub wrap: WrapMode,
+    }
+
+    impl Player {
+        pub fn new(clip: usize) -> Self {
+            Player {
+                clip,
+                time: 0.0,
+                speed: 1.0,
+                weight: 1.0,
+                wrap: WrapMode::Loop,
+            }
+        }
+
+        // returns how far the root bone moved, root being its bone in the clip
+        fn advance(&mut self, clip: &Animation, root: Option<usize>, dt: f32) -> Vec3 {
+            let previous = self.time;
+            let time = self.time + clip.ticks_per_second * self.speed * dt;
+            self.time = self.wrap.advance(time, clip.duration);
+
+            root.map_or(Vec3::ZERO, |bone| clip.root_displacement(bone, self.wrap, previous, time))
+        }
+
+        fn clip_time(&self, clip: &Animation) -> f32 {
+            self.wrap.sample_time(self.time, clip.duration)
+        }
+
+        fn is_finished(&self, clip: &Animation) -> bool {
+            self.wrap == WrapMode::Clamp && self.time >= clip.duration
+        }
+    }
+
+    // clips mixed by their players' weights
+    #[derive(Clone, Default)]
+    pub struct Blend {
+        pub players: Vec<Player>,
+    }
+
+    impl Blend {
+        pub fn single(player: Player) -> Self {
+            Blend { players: vec![player] }
+        }
+
+        fn advance(&mut self, clips: &[Animation], roots: &[Option<usize>], dt: f32) -> Vec3 {
+            let mut displacement = Vec3::ZERO;
+            let mut total_weight = 0.0;
+
+            for player in &mut self.players {
+                let moved = player.advance(&clips[player.clip], roots[player.clip], dt);
+
+                if player.weight > 0.0 {
+                    total_weight += player.weight;
+                    displacement = displacement.lerp(moved, player.weight / total_weight);
+                }
+            }
+
+            displacement
+        }
+
+        fn sample(&self, clips: &[Animation], bindings: &[Vec<Option<usize>>], skeleton: &Skeleton, from_start: bool) -> Pose {
+            let mut pose = skeleton.bind_pose();
+            let mut total_weight = 0.0;
+
+            for player in self.players.iter().filter(|player| player.weight > 0.0) {
+                let clip = &clips[player.clip];
+                let time = if from_start { 0.0 } else { player.clip_time(clip) };
+                let sampled = clip.sample(time, skeleton, &bindings[player.clip]);
+
+                // running average, so the first clip replaces the bind pose outright
+                total_weight += player.weight;
+                pose.blend(&sampled, player.weight / total_weight, None);
+            }
+
+            pose
+        }
+
+        // starts from weights, so targets no clip animates keep theirs
+        fn sample_morphs(&self, clips: &[Animation], model: &Model, weights: &MorphWeights) -> MorphWeights {
+            let mut result = weights.clone();
+            let mut total_weight = 0.0;
+
+            for player in self.players.iter().filter(|player| player.weight > 0.0) {
+                let clip = &clips[player.clip];
+                let mut sampled = weights.clone();
+                clip.sample_morphs(player.clip_time(clip), model, &mut sampled);
+
+                total_weight += player.weight;
+                result.lerp(&sampled, player.weight / total_weight);
+            }
+
+            result
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    pub enum LayerMode {
+        // replaces the layers below, as far as weight and mask let it
+        Override,
+        // adds how far its clips have moved from their first frame
+        Additive,
+    }
+
+    pub struct Layer {
+        pub mode: LayerMode,
+        pub mask: Option<BoneMask>,
+        pub weight: f32,
+        current: Blend,
+        previous: Option<Blend>,
+        fade_time: f32,
+        fade_duration: f32,
+    }
+
+    impl Layer {
+        pub fn new(mode: LayerMode, mask: Option<BoneMask>) -> Self {
+            Layer {
+                mode,
+                mask,
+                weight: 1.0,
+                current: Blend::default(),
+                previous: None,
+                fade_time: 0.0,
+                fade_duration: 0.0,
+            }
+        }
+
+        // cross-fades from whatever was playing over fade_duration seconds
+        pub fn play(&mut self, blend: Blend, fade_duration: f32) {
+            let previous = mem::replace(&mut self.current, blend);
+
+            self.previous = if fade_duration > 0.0 && !previous.players.is_empty() { Some(previous) } else { None };
+            self.fade_time = 0.0;
+            self.fade_duration = fade_duration;
+        }
+
+        pub fn blend(&self) -> &Blend {
+            &self.current
+        }
+
+        pub fn blend_mut(&mut self) -> &mut Blend {
+            &mut self.current
+        }
+
+        pub fn is_fading(&self) -> bool {
+            self.previous.is_some()
+        }
+
+        // the root displacement, faded like the pose
+        fn advance(&mut self, clips: &[Animation], roots: &[Option<usize>], dt: f32) -> Vec3 {
+            let mut displacement = self.current.advance(clips, roots, dt);
+
+            self.fade_time += dt;
+            if let Some(previous) = &mut self.previous {
+                let faded = previous.advance(clips, roots, dt);
+                displacement = faded.lerp(displacement, f32::min(self.fade_time / self.fade_duration, 1.0));
+            }
+
+            if self.fade_time >= self.fade_duration {
+                self.previous = None;
+            }
+
+            displacement
+        }
+
+        fn sample(&self, clips: &[Animation], bindings: &[Vec<Option<usize>>], skeleton: &Skeleton, from_start: bool) -> Pose {
+            let mut pose = self.current.sample(clips, bindings, skeleton, from_start);
+
+            if let Some(previous) = &self.previous {
+                let mut faded = previous.sample(clips, bindings, skeleton, from_start);
+                faded.blend(&pose, self.fade_time / self.fade_duration, None);
+                pose = faded;
+            }
+
+            pose
+        }
+
+        fn sample_morphs(&self, clips: &[Animation], model: &Model, weights: &MorphWeights) -> MorphWeights {
+            let mut sampled = self.current.sample_morphs(clips, model, weights);
+
+            if let Some(previous) = &self.previous {
+                let mut faded = previous.sample_morphs(clips, model, weights);
+                faded.lerp(&sampled, self.fade_time / self.fade_duration);
+                sampled = faded;
+            }
+
+            sampled
+        }
+    }
+
+    // the root bone's motion, on the chosen axes of its parent's space, moves the character instead of the bone
+    pub struct RootMotion {
+        pub node: usize,
+        pub axes: Vec3,
+    }
+
     pub struct Animator {
         final_bone_matrices: Vec<Mat4>,
-        current_animation: Animation,
-        pub current_time: f32,
+        // model space, per skeleton node, as of the last update
+        global_transforms: Vec<Mat4>,
+        sockets: Vec<Socket>,
+        root_motion: Option<RootMotion>,
+        // for each clip, the bone of the root motion node
+        root_bones: Vec<Option<usize>>,
+        root_displacement: Vec3,
+        // solved in order on top of the sampled pose
+        ik_goals: Vec<IkGoal>,
+        clips: Vec<Animation>,
+        // for each clip, the bone animating each skeleton node
+        bindings: Vec<Vec<Option<usize>>>,
+        skeleton: Skeleton,
+        bone_info_map: HashMap<String, BoneInfo>,
+        layers: Vec<Layer>,
         delta_time: f32,
     }
 
-    macro_rules! fmod {
-        ($x:expr, $y:expr) => {
-            f32::max(0.0, $x - f32::round($x / $y) * $y)
-        };
-    }
-
     impl Animator {
-        pub fn new(current_animation: Animation) -> Self {
-            let mut final_bone_matrices = Vec::new();
-            for _ in 0..100 {
-                final_bone_matrices.push(Mat4::IDENTITY);
+        // starts with the first clip looping on an override layer
+        pub fn new(clips: Vec<Animation>) -> Self {
+            assert!(!clips.is_empty(), "No animation to play.");
+            let skeleton = clips[0].skeleton();
+            let bindings = clips.iter().map(|clip| clip.bind(&skeleton)).collect();
+
+            // bones only some clips animate get ids after the others
+            let mut bone_info_map = clips[0].bone_id_map().clone();
+            for clip in &clips[1..] {
+                for (name, info) in clip.bone_id_map() {
+                    if !bone_info_map.contains_key(name) {
+                        let id = bone_info_map.len() as i32;
+                        bone_info_map.insert(name.clone(), BoneInfo { id, offset: info.offset });
+                    }
+                }
             }
 
+            // ids run from 0 without gaps, the model's own bones first
+            let final_bone_matrices = vec![Mat4::IDENTITY; bone_info_map.len()];
+
+            let mut base = Layer::new(LayerMode::Override, None);
+            base.play(Blend::single(Player::new(0)), 0.0);
+
             Animator {
                 final_bone_matrices,
-                current_animation,
-                current_time: 0.0,
+                global_transforms: vec![Mat4::IDENTITY; skeleton.nodes.len()],
+                sockets: vec![],
+                root_motion: None,
+                root_bones: vec![None; clips.len()],
+                root_displacement: Vec3::ZERO,
+                ik_goals: vec![],
+                clips,
+                bindings,
+                skeleton,
+                bone_info_map,
+                layers: vec![base],
                 delta_time: 0.0,
             }
         }
 
+        pub fn clip_index(&self, name: &str) -> Option<usize> {
+            self.clips.iter().position(|clip| clip.name() == name)
+        }
+
+        pub fn clips(&self) -> &[Animation] {
+            &self.clips
+        }
+
+        pub fn skeleton(&self) -> &Skeleton {
+            &self.skeleton
+        }
+
+        // layers apply in the order they were added, on top of the base layer 0
+        pub fn add_layer(&mut self, layer: Layer) -> usize {
+            self.layers.push(layer);
+            self.layers.len() - 1
+        }
+
+        pub fn layer(&self, index: usize) -> &Layer {
+            &self.layers[index]
+        }
+
+        pub fn layer_mut(&mut self, index: usize) -> &mut Layer {
+            &mut self.layers[index]
+        }
+
+        // plays a clip by name, returns false if there is no such clip
+        pub fn play(&mut self, layer: usize, name: &str, fade_duration: f32) -> bool {
+            match self.clip_index(name) {
+                Some(clip) => {
+                    self.layers[layer].play(Blend::single(Player::new(clip)), fade_duration);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        pub fn is_finished(&self, layer: usize) -> bool {
+            let blend = &self.layers[layer].current;
+            !blend.players.is_empty() && blend.players.iter().all(|player| player.is_finished(&self.clips[player.clip]))
+        }
+
+        // axes picks what the character takes over, e.g. Vec3::new(1.0, 0.0, 1.0) to keep the bounce in the bone
+        pub fn enable_root_motion(&mut self, bone: &str, axes: Vec3) -> bool {
+            match self.skeleton.find(bone) {
+                Some(node) => {
+                    self.root_bones = self.bindings.iter().map(|binding| binding[node]).collect();
+                    self.root_motion = Some(RootMotion { node, axes });
+                    true
+                }
+                None => false,
+            }
+        }
+
+        pub fn disable_root_motion(&mut self) {
+            self.root_bones = vec![None; self.clips.len()];
+            self.root_motion = None;
+            self.root_displacement = Vec3::ZERO;
+        }
+
+        // model space distance the root moved over the last update, for the caller to move the model matrix by
+        pub fn root_motion(&self) -> Vec3 {
+            self.root_displacement
+        }
+
+        pub fn node_transform(&self, node: usize) -> Mat4 {
+            self.global_transforms[node]
+        }
+
+        // model space, multiply by the model matrix for world space
+        pub fn bone_transform(&self, name: &str) -> Option<Mat4> {
+            self.skeleton.find(name).map(|node| self.global_transforms[node])
+        }
+
+        // offset is in the bone's space, returns None if there is no such bone
+        pub fn add_socket(&mut self, name: &str, bone: &str, offset: Mat4) -> Option<usize> {
+            let node = self.skeleton.find(bone)?;
+
+            self.sockets.push(Socket {
+                name: name.to_string(),
+                node,
+                offset,
+            });
+            Some(self.sockets.len() - 1)
+        }
+
+        pub fn socket(&self, name: &str) -> Option<usize> {
+            self.sockets.iter().position(|socket| socket.name == name)
+        }
+
+        pub fn socket_transform(&self, socket: usize) -> Mat4 {
+            let socket = &self.sockets[socket];
+            self.global_transforms[socket.node] * socket.offset
+        }
+
+        // build the goal against skeleton(), its target is set each frame in model space
+        pub fn add_ik(&mut self, goal: IkGoal) -> usize {
+            self.ik_goals.push(goal);
+            self.ik_goals.len() - 1
+        }
+
+        pub fn ik(&self, index: usize) -> &IkGoal {
+            &self.ik_goals[index]
+        }
+
+        pub fn ik_mut(&mut self, index: usize) -> &mut IkGoal {
+            &mut self.ik_goals[index]
+        }
+
         pub fn update_animation(&mut self, dt: f32) {
             self.delta_time = dt;
 
-            self.current_time += self.current_animation.ticks_per_second as f32 * dt;
-            self.current_time = fmod!(self.current_time, self.current_animation.duration as f32);
-            let root_node = mem::take(&mut self.current_animation.root_node).unwrap();
-            self.calculate_bone_transform(&root_node, Mat4::IDENTITY);
-            self.current_animation.root_node = Some(root_node);
-        }
+            let mut displacement = Vec3::ZERO;
+            for layer in &mut self.layers {
+                let moved = layer.advance(&self.clips, &self.root_bones, dt);
+
+                // additive layers don't move the character
+                if let (Some(root), LayerMode::Override) = (&self.root_motion, layer.mode) {
+                    let weight = layer.weight * layer.mask.as_ref().map_or(1.0, |mask| mask.weights[root.node]);
+                    displacement = displacement.lerp(moved, weight);
+                }
+            }
 
-        pub fn calculate_bone_transform(&mut self, node: &RussimpNodeData, parent_transform: Mat4) {
-            let node_name = &node.name;
-            let mut node_transform = node.transformation;
+            let mut pose = self.skeleton.bind_pose();
 
-            let mut bone = self.current_animation.find_bone(node_name);
+            for layer in self.layers.iter().filter(|layer| layer.weight > 0.0) {
+                let sampled = layer.sample(&self.clips, &self.bindings, &self.skeleton, false);
 
-            if let Some(bone) = &mut bone {
-                bone.update(self.current_time);
-                node_transform = bone.local_transform();
+                match layer.mode {
+                    LayerMode::Override => pose.blend(&sampled, layer.weight, layer.mask.as_ref()),
+                    LayerMode::Additive => {
+                        let reference = layer.sample(&self.clips, &self.bindings, &self.skeleton, true);
+                        pose.add(&sampled, &reference, layer.weight, layer.mask.as_ref());
+                    }
+                }
             }
 
-            let global_transformation = parent_transform * node_transform;
+            // the bone stays where the bind pose has it on the axes the character takes over
+            if let Some(root) = &self.root_motion {
+                let local = &mut pose.locals[root.node];
+                let bind = self.skeleton.nodes[root.node].bind.translation;
+                local.translation = local.translation * (Vec3::ONE - root.axes) + bind * root.axes;
+            }
 
-            let bone_info = self.current_animation.bone_id_map();
-            if bone_info.contains_key(node_name) {
-                let index = bone_info[node_name].id;
-                let offset = bone_info[node_name].offset;
-                self.final_bone_matrices[index as usize] = global_transformation * offset;
+            for goal in &mut self.ik_goals {
+                goal.solve(&self.skeleton, &mut pose);
             }
 
-            for node in &node.children {
-                self.calculate_bone_transform(node, global_transformation);
+            self.calculate_bone_transforms(&pose);
+
+            if let Some(root) = &self.root_motion {
+                let parent = self.skeleton.nodes[root.node].parent.map_or(Mat4::IDENTITY, |parent| self.global_transforms[parent]);
+                self.root_displacement = parent.transform_vector3(displacement * root.axes);
+            }
+        }
+
+        // after update_animation, targets the clips animate are overwritten, the rest keep what was set from code
+        pub fn update_morphs(&self, model: &Model, weights: &mut MorphWeights) {
+            for layer in self.layers.iter().filter(|layer| layer.mode == LayerMode::Override && layer.weight > 0.0) {
+                let sampled = layer.sample_morphs(&self.clips, model, weights);
+                weights.lerp(&sampled, layer.weight);
+            }
+        }
+
+        fn calculate_bone_transforms(&mut self, pose: &Pose) {
+            self.global_transforms = pose.globals(&self.skeleton);
+
+            for (node, global_transformation) in self.skeleton.nodes.iter().zip(&self.global_transforms) {
+                if let Some(bone_info) = self.bone_info_map.get(&node.name) {
+                    if let Some(matrix) = self.final_bone_matrices.get_mut(bone_info.id as usize) {
+                        *matrix = *global_transformation * bone_info.offset;
+                    }
+                }
             }
         }
 
@@ -76,7 +454,7 @@ pub mod animation {
         }
     }
 
-    #[derive(Debug)]
+    #[derive(Clone, Debug)]
     pub struct RussimpNodeData {
         transformation: Mat4,
         name: String,
@@ -84,30 +462,102 @@ pub mod animation {
         children: Vec<RussimpNodeData>,
     }
 
+    // the weights a mesh's morph targets have at one time, by target index
+    #[derive(Clone, Debug)]
+    pub struct MorphKey {
+        pub time: f32,
+        pub weights: Vec<(usize, f32)>,
+    }
+
+    impl MorphKey {
+        fn weight(&self, target: usize) -> f32 {
+            self.weights.iter().find(|(index, _)| *index == target).map_or(0.0, |(_, weight)| *weight)
+        }
+    }
+
+    // morph weights over time for the meshes of one node
+    #[derive(Clone, Debug)]
+    pub struct MorphChannel {
+        pub node: String,
+        pub keys: Vec<MorphKey>,
+    }
+
+    impl MorphChannel {
+        fn end_time(&self) -> f32 {
+            self.keys.last().map_or(0.0, |key| key.time)
+        }
+
+        fn sample(&self, time: f32, weights: &mut [f32]) {
+            if self.keys.is_empty() {
+                return;
+            }
+
+            let index = self.keys.partition_point(|key| key.time <= time).clamp(1, self.keys.len()) - 1;
+            let (from, to) = (&self.keys[index], &self.keys[(index + 1).min(self.keys.len() - 1)]);
+            let span = to.time - from.time;
+            let t = if span > 0.0 { ((time - from.time) / span).clamp(0.0, 1.0) } else { 0.0 };
+
+            for (target, _) in from.weights.iter().chain(&to.weights) {
+                if let Some(weight) = weights.get_mut(*target) {
+                    *weight = from.weight(*target) + (to.weight(*target) - from.weight(*target)) * t;
+                }
+            }
+        }
+    }
+
+    #[derive(Clone)]
     pub struct Animation {
+        name: String,
         duration: f32,
-        ticks_per_second: i32,
+        ticks_per_second: f32,
         bones: Vec<Bone>,
+        bone_indices: HashMap<String, usize>,
+        morph_channels: Vec<MorphChannel>,
         root_node: Option<RussimpNodeData>,
         bone_info_map: HashMap<String, BoneInfo>,
     }
 
     impl Animation {
         pub fn new(path: &str, model: &Model) -> Self {
+            Animation::load_all(path, model).swap_remove(0)
+        }
+
+        // every clip in the file, by the name it was exported with
+        pub fn load_all(path: &str, model: &Model) -> Vec<Self> {
             let scene = Scene::from_file(path, vec![PostProcess::Triangulate, PostProcess::FixOrRemoveInvalidData]).unwrap();
 
             assert!(scene.root.is_some());
-            let animation = &scene.animations[0];
-            // issue with Blender timeline to AssImp duration
-            let duration = 2.0 * animation.duration as f32;
-            let ticks_per_second = animation.ticks_per_second as i32;
-            let _global_transformation = convert_matrix(scene.root.as_ref().unwrap().borrow().transformation).inverse();
+            assert!(!scene.animations.is_empty(), "No animation in {path}.");
+
+            scene.animations.iter().map(|animation| Animation::from_scene(&scene, animation, model)).collect()
+        }
+
+        fn from_scene(scene: &Scene, animation: &animation::Animation, model: &Model) -> Self {
+            let duration = animation.duration as f32;
+            let ticks_per_second = if animation.ticks_per_second > 0.0 { animation.ticks_per_second } else { DEFAULT_TICKS_PER_SECOND } as f32;
 
             let root_node = None;
             let mut anim = Animation {
+                name: animation.name.clone(),
                 duration,
                 ticks_per_second,
                 bones: Vec::new(),
+                bone_indices: HashMap::new(),
+                morph_channels: animation
+                    .morph_mesh_channels
+                    .iter()
+                    .map(|channel| MorphChannel {
+                        node: channel.name.clone(),
+                        keys: channel
+                            .keys
+                            .iter()
+                            .map(|key| MorphKey {
+                                time: key.time as f32,
+                                weights: key.values.iter().zip(&key.weights).map(|(target, weight)| (*target as usize, *weight as f32)).collect(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
                 root_node,
                 bone_info_map: HashMap::new(),
             };
@@ -122,19 +572,90 @@ pub mod animation {
             anim.root_node = Some(root_node);
             anim.read_missing_bones(&animation, model);
 
+            // exporters don't always agree with their keys on the length
+            anim.duration = anim.bones.iter().map(Bone::end_time).fold(anim.duration, f32::max);
+            anim.duration = anim.morph_channels.iter().map(MorphChannel::end_time).fold(anim.duration, f32::max);
+
             anim
         }
 
         pub fn find_bone(&mut self, name: &str) -> Option<&mut Bone> {
-            for bone in self.bones.iter_mut() {
-                if bone.bone_name() == name {
-                    return Some(bone);
+            self.bone_indices.get(name).map(|&index| &mut self.bones[index])
+        }
+
+        pub fn bones_mut(&mut self) -> &mut [Bone] {
+            &mut self.bones
+        }
+
+        // looked up once, so sampling never compares names
+        pub fn bind(&self, skeleton: &Skeleton) -> Vec<Option<usize>> {
+            skeleton.nodes.iter().map(|node| self.bone_indices.get(&node.name).copied()).collect()
+        }
+
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        // nodes without a channel keep their bind transform, binding comes from bind
+        pub fn sample(&self, time: f32, skeleton: &Skeleton, binding: &[Option<usize>]) -> Pose {
+            Pose {
+                locals: skeleton
+                    .nodes
+                    .iter()
+                    .zip(binding)
+                    .map(|(node, bone)| match bone {
+                        Some(bone) => self.bones[*bone].sample(time),
+                        None => node.bind,
+                    })
+                    .collect(),
+            }
+        }
+
+        // how far a bone moves from one time to another, to is not wrapped yet so whole loops add the clip's travel
+        fn root_displacement(&self, bone: usize, wrap: WrapMode, from: f32, to: f32) -> Vec3 {
+            let bone = &self.bones[bone];
+            let at = |time: f32| bone.sample(wrap.sample_time(wrap.advance(time, self.duration), self.duration)).translation;
+            let moved = at(to) - at(from);
+
+            match wrap {
+                WrapMode::Loop if self.duration > 0.0 => {
+                    let loops = (to / self.duration).floor() - (from / self.duration).floor();
+                    moved + loops * (bone.sample(self.duration).translation - bone.sample(0.0).translation)
                 }
+                _ => moved,
+            }
+        }
+
+        pub fn morph_channels(&self) -> &[MorphChannel] {
+            &self.morph_channels
+        }
+
+        // weights of the model's meshes hanging from each channel's node
+        pub fn sample_morphs(&self, time: f32, model: &Model, weights: &mut MorphWeights) {
+            for channel in &self.morph_channels {
+                for (mesh, weights) in model.meshes.iter().zip(&mut weights.weights) {
+                    if mesh.node == channel.node {
+                        channel.sample(time, weights);
+                    }
+                }
+            }
+        }
+
+        pub fn skeleton(&self) -> Skeleton {
+            let mut skeleton = Skeleton::default();
+            Animation::flatten(self.root_node(), None, &mut skeleton);
+            skeleton
+        }
+
+        fn flatten(node: &RussimpNodeData, parent: Option<usize>, skeleton: &mut Skeleton) {
+            let index = skeleton.push(node.name.clone(), parent, node.transformation);
+
+            for child in &node.children {
+                Animation::flatten(child, Some(index), skeleton);
             }
-            None
         }
 
-        pub fn ticks_per_second(&self) -> i32 {
+        pub fn ticks_per_second(&self) -> f32 {
             self.ticks_per_second
         }
 
@@ -167,6 +688,7 @@ pub mod animation {
                     );
                     bone_count += 1;
                 }
+                self.bone_indices.insert(bone_name.to_string(), self.bones.len());
                 self.bones.push(Bone::new(bone_name.to_string(), bone_info_map[bone_name].id, &channel));
             }
             self.bone_info_map = bone_info_map;
@@ -1,26 +1,160 @@
 pub mod bone {
+    use crate::Transform;
     use glam::{Mat4, Quat, Vec3};
     use russimp::animation::NodeAnim;
+    use std::cell::Cell;
 
-    struct KeyPosition {
-        position: Vec3,
-        time_stamp: f32,
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Interpolation {
+        // holds each key until the next one
+        Step,
+        Linear,
+        // Catmull-Rom through the neighbouring keys
+        Cubic,
     }
 
-    struct KeyRotation {
-        orientation: Quat,
-        time_stamp: f32,
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum WrapMode {
+        Loop,
+        Clamp,
+        PingPong,
     }
 
-    struct KeyScale {
-        scale: Vec3,
-        time_stamp: f32,
+    impl WrapMode {
+        // keeps a running time bounded, ping-pong runs over twice the duration
+        pub fn advance(self, time: f32, duration: f32) -> f32 {
+            if duration <= 0.0 {
+                return 0.0;
+            }
+
+            match self {
+                WrapMode::Loop => time.rem_euclid(duration),
+                WrapMode::Clamp => time.clamp(0.0, duration),
+                WrapMode::PingPong => time.rem_euclid(2.0 * duration),
+            }
+        }
+
+        // where in the clip a time kept by advance falls
+        pub fn sample_time(self, time: f32, duration: f32) -> f32 {
+            match self {
+                WrapMode::PingPong if time > duration => 2.0 * duration - time,
+                _ => time,
+            }
+        }
+    }
+
+    pub trait Keyframe: Copy {
+        fn lerp(self, other: Self, t: f32) -> Self;
+        fn catmull_rom(before: Self, from: Self, to: Self, after: Self, t: f32) -> Self;
     }
 
+    impl Keyframe for Vec3 {
+        fn lerp(self, other: Self, t: f32) -> Self {
+            Vec3::lerp(self, other, t)
+        }
+
+        fn catmull_rom(before: Self, from: Self, to: Self, after: Self, t: f32) -> Self {
+            let (t2, t3) = (t * t, t * t * t);
+
+            0.5 * (2.0 * from + (to - before) * t + (2.0 * before - 5.0 * from + 4.0 * to - after) * t2 + (3.0 * from - before - 3.0 * to + after) * t3)
+        }
+    }
+
+    impl Keyframe for Quat {
+        fn lerp(self, other: Self, t: f32) -> Self {
+            self.slerp(other, t).normalize()
+        }
+
+        // componentwise on the hemisphere of `from`, close enough to squad between dense keys
+        fn catmull_rom(before: Self, from: Self, to: Self, after: Self, t: f32) -> Self {
+            let align = |q: Quat| if q.dot(from) < 0.0 { -q } else { q };
+            let [before, to] = [align(before), align(to)];
+            let after = if after.dot(to) < 0.0 { -after } else { after };
+
+            let spline = Vec3::catmull_rom;
+            let xyz = spline(before.xyz(), from.xyz(), to.xyz(), after.xyz(), t);
+            let w = spline(Vec3::splat(before.w), Vec3::splat(from.w), Vec3::splat(to.w), Vec3::splat(after.w), t).x;
+
+            Quat::from_xyzw(xyz.x, xyz.y, xyz.z, w).normalize()
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Key<T> {
+        pub time: f32,
+        pub value: T,
+    }
+
+    // keys sorted by time, with the segment of the last lookup remembered since time mostly moves forward a little
+    #[derive(Clone, Debug)]
+    pub struct Track<T: Keyframe> {
+        pub keys: Vec<Key<T>>,
+        cursor: Cell<usize>,
+    }
+
+    impl<T: Keyframe> Track<T> {
+        pub fn new(keys: Vec<Key<T>>) -> Self {
+            Track { keys, cursor: Cell::new(0) }
+        }
+
+        pub fn end_time(&self) -> f32 {
+            self.keys.last().map_or(0.0, |key| key.time)
+        }
+
+        // the key at or before time, so keys[index + 1] is after it; times outside the keys hold the end keys
+        pub fn segment(&self, time: f32) -> usize {
+            let keys = &self.keys;
+            let contains = |index: usize| keys[index].time <= time && time < keys[index + 1].time;
+
+            let cursor = self.cursor.get().min(keys.len() - 2);
+            let index = if contains(cursor) {
+                cursor
+            } else if cursor + 2 < keys.len() && contains(cursor + 1) {
+                cursor + 1
+            } else {
+                keys.partition_point(|key| key.time <= time).clamp(1, keys.len() - 1) - 1
+            };
+
+            self.cursor.set(index);
+            index
+        }
+
+        pub fn sample(&self, time: f32, interpolation: Interpolation) -> Option<T> {
+            match self.keys.len() {
+                0 => return None,
+                1 => return Some(self.keys[0].value),
+                _ => {}
+            }
+
+            let index = self.segment(time);
+            let (from, to) = (&self.keys[index], &self.keys[index + 1]);
+            let span = to.time - from.time;
+            let t = if span > 0.0 { ((time - from.time) / span).clamp(0.0, 1.0) } else { 0.0 };
+
+            Some(match interpolation {
+                Interpolation::Step => {
+                    if t >= 1.0 {
+                        to.value
+                    } else {
+                        from.value
+                    }
+                }
+                Interpolation::Linear => from.value.lerp(to.value, t),
+                Interpolation::Cubic => {
+                    let before = self.keys[index.saturating_sub(1)].value;
+                    let after = self.keys[(index + 2).min(self.keys.len() - 1)].value;
+                    T::catmull_rom(before, from.value, to.value, after, t)
+                }
+            })
+        }
+    }
+
+    #[derive(Clone)]
     pub struct Bone {
-        positions: Vec<KeyPosition>,
-        rotations: Vec<KeyRotation>,
-        scales: Vec<KeyScale>,
+        positions: Track<Vec3>,
+        rotations: Track<Quat>,
+        scales: Track<Vec3>,
+        pub interpolation: Interpolation,
         local_transform: Mat4,
         name: String,
         id: i32,
@@ -28,51 +162,59 @@ pub mod bone {
 
     impl Bone {
         pub fn new(name: String, id: i32, channel: &NodeAnim) -> Self {
-            let local_transform = Mat4::IDENTITY;
-
-            let mut positions = Vec::new();
-            for position in &channel.position_keys {
-                let pos = position.value;
-                positions.push(KeyPosition {
-                    position: Vec3::new(pos.x, pos.y, pos.z),
-                    time_stamp: position.time as f32,
-                });
-            }
+            let positions = channel
+                .position_keys
+                .iter()
+                .map(|key| Key {
+                    time: key.time as f32,
+                    value: Vec3::new(key.value.x, key.value.y, key.value.z),
+                })
+                .collect();
 
-            let mut rotations = Vec::new();
-            for rotation in &channel.rotation_keys {
-                let quat = Quat::from_xyzw(rotation.value.x, rotation.value.y, rotation.value.z, rotation.value.w);
-                let (axis, angle) = quat.to_axis_angle();
-                rotations.push(KeyRotation {
-                    orientation: Quat::from_axis_angle(Vec3::new(axis.x, axis.y, axis.z), angle),
-                    time_stamp: rotation.time as f32,
-                });
-            }
+            let rotations = channel
+                .rotation_keys
+                .iter()
+                .map(|key| Key {
+                    time: key.time as f32,
+                    value: Quat::from_xyzw(key.value.x, key.value.y, key.value.z, key.value.w).normalize(),
+                })
+                .collect();
 
-            let mut scales = Vec::new();
-            for scaling in &channel.scaling_keys {
-                let scl = scaling.value;
-                scales.push(KeyScale {
-                    scale: Vec3::new(scl.x, scl.y, scl.z),
-                    time_stamp: scaling.time as f32,
-                });
-            }
+            let scales = channel
+                .scaling_keys
+                .iter()
+                .map(|key| Key {
+                    time: key.time as f32,
+                    value: Vec3::new(key.value.x, key.value.y, key.value.z),
+                })
+                .collect();
+
+            Bone::from_tracks(name, id, Track::new(positions), Track::new(rotations), Track::new(scales))
+        }
 
+        pub fn from_tracks(name: String, id: i32, positions: Track<Vec3>, rotations: Track<Quat>, scales: Track<Vec3>) -> Self {
             Bone {
                 positions,
                 rotations,
                 scales,
-                local_transform,
+                interpolation: Interpolation::Linear,
+                local_transform: Mat4::IDENTITY,
                 name,
                 id,
             }
         }
 
         pub fn update(&mut self, animation_time: f32) {
-            let translation = self.interpolate_position(animation_time);
-            let rotation = self.interpolate_rotation(animation_time);
-            let scale = self.interpolate_scaling(animation_time);
-            self.local_transform = translation * rotation * scale;
+            self.local_transform = self.sample(animation_time).to_matrix();
+        }
+
+        // tracks without keys keep the identity
+        pub fn sample(&self, animation_time: f32) -> Transform {
+            Transform {
+                translation: self.positions.sample(animation_time, self.interpolation).unwrap_or(Vec3::ZERO),
+                rotation: self.rotations.sample(animation_time, self.interpolation).unwrap_or(Quat::IDENTITY),
+                scale: self.scales.sample(animation_time, self.interpolation).unwrap_or(Vec3::ONE),
+            }
         }
 
         pub fn local_transform(&self) -> Mat4 {
@@ -87,77 +229,112 @@ pub mod bone {
             self.id
         }
 
-        pub fn position_index(&self, animation_time: f32) -> i32 {
-            for index in 0..self.positions.len() - 1 {
-                if animation_time < self.positions[index + 1].time_stamp {
-                    return index as i32;
-                }
-            }
-            panic!("Incomplete animation.");
+        pub fn end_time(&self) -> f32 {
+            f32::max(self.positions.end_time(), f32::max(self.rotations.end_time(), self.scales.end_time()))
         }
+    }
 
-        pub fn rotation_index(&self, animation_time: f32) -> i32 {
-            for index in 0..self.rotations.len() - 1 {
-                if animation_time < self.rotations[index + 1].time_stamp {
-                    return index as i32;
-                }
-            }
-            panic!("Incomplete animation.");
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn keys<T: Keyframe>(values: &[(f32, T)]) -> Track<T> {
+            Track::new(values.iter().map(|&(time, value)| Key { time, value }).collect())
         }
 
-        pub fn scale_index(&self, animation_time: f32) -> i32 {
-            for index in 0..self.scales.len() - 1 {
-                if animation_time < self.scales[index + 1].time_stamp {
-                    return index as i32;
-                }
-            }
-            panic!("Incomplete animation.");
+        // a bone moving along x through 0, 1, 4 at times 0, 1, 2, with a fixed rotation and scale
+        fn bone() -> Bone {
+            let positions = keys(&[(0.0, Vec3::ZERO), (1.0, Vec3::X), (2.0, 4.0 * Vec3::X)]);
+            let rotations = keys(&[(0.0, Quat::IDENTITY), (1.5, Quat::from_rotation_y(1.0))]);
+            let scales = keys(&[(0.0, Vec3::ONE)]);
+            Bone::from_tracks("bone".to_string(), 0, positions, rotations, scales)
         }
 
-        fn scale_factor(last_time_stamp: f32, next_time_stamp: f32, animation_time: f32) -> f32 {
-            let midway_length = animation_time - last_time_stamp;
-            let frames_diff = next_time_stamp - last_time_stamp;
+        fn reference(track: &Track<Vec3>, time: f32) -> usize {
+            track.keys.partition_point(|key| key.time <= time).clamp(1, track.keys.len() - 1) - 1
+        }
 
-            midway_length / frames_diff
+        fn close(a: Vec3, b: Vec3) -> bool {
+            a.distance(b) < 1e-5
         }
 
-        fn interpolate_position(&self, animation_time: f32) -> Mat4 {
-            if self.positions.len() == 1 {
-                return Mat4::from_translation(self.positions[0].position);
+        #[test]
+        fn cursor_agrees_with_partition_point() {
+            let times: Vec<(f32, Vec3)> = (0..40).map(|i| (i as f32 * 0.25 + if i % 3 == 0 { 0.1 } else { 0.0 }, Vec3::splat(i as f32))).collect();
+            let track = keys(&times);
+            let end = track.end_time();
+
+            let forward = (0..400).map(|i| i as f32 * 0.031 - 0.5);
+            let backward = (0..400).map(|i| end + 0.5 - i as f32 * 0.031);
+            let jumps = [3.0, 0.0, end, 7.3, 7.3, -1.0, end + 1.0, 0.25, 0.26, 9.9, 1.0];
+
+            for time in forward.chain(backward).chain(jumps) {
+                assert_eq!(track.segment(time), reference(&track, time), "at {}", time);
             }
+        }
 
-            let p0_index = self.position_index(animation_time);
-            let p1_index = p0_index + 1;
-            let scale_factor = Bone::scale_factor(self.positions[p0_index as usize].time_stamp, self.positions[p1_index as usize].time_stamp, animation_time);
-            let final_position = self.positions[p0_index as usize].position.lerp(self.positions[p1_index as usize].position, scale_factor);
-            Mat4::from_translation(final_position)
+        #[test]
+        fn duration_comes_from_the_last_key() {
+            let bone = bone();
+            assert_eq!(bone.end_time(), 2.0);
+            assert_eq!(keys::<Vec3>(&[]).end_time(), 0.0);
         }
 
-        fn interpolate_rotation(&self, animation_time: f32) -> Mat4 {
-            if self.rotations.len() == 1 {
-                return Mat4::from_quat(self.rotations[0].orientation);
-            }
+        #[test]
+        fn wrap_modes_at_and_past_the_last_key() {
+            let bone = bone();
+            let duration = bone.end_time();
+            let at = |wrap: WrapMode, time: f32| bone.sample(wrap.sample_time(wrap.advance(time, duration), duration)).translation;
+
+            // exactly on the last key loop restarts, the others hold it
+            assert!(close(at(WrapMode::Loop, 2.0), Vec3::ZERO));
+            assert!(close(at(WrapMode::Clamp, 2.0), 4.0 * Vec3::X));
+            assert!(close(at(WrapMode::PingPong, 2.0), 4.0 * Vec3::X));
+
+            assert!(close(at(WrapMode::Loop, 2.5), 0.5 * Vec3::X));
+            assert!(close(at(WrapMode::Clamp, 2.5), 4.0 * Vec3::X));
+            assert!(close(at(WrapMode::PingPong, 2.5), 2.5 * Vec3::X));
+            assert!(close(at(WrapMode::PingPong, 3.5), 0.5 * Vec3::X));
+            assert!(close(at(WrapMode::PingPong, 4.0), Vec3::ZERO));
+            assert!(close(at(WrapMode::Loop, -0.5), 2.5 * Vec3::X));
+            assert!(close(at(WrapMode::Clamp, -0.5), Vec3::ZERO));
 
-            let p0_index = self.rotation_index(animation_time);
-            let p1_index = p0_index + 1;
-            let scale_factor = Bone::scale_factor(self.rotations[p0_index as usize].time_stamp, self.rotations[p1_index as usize].time_stamp, animation_time);
-            let final_rotation = self.rotations[p0_index as usize]
-                .orientation
-                .slerp(self.rotations[p1_index as usize].orientation, scale_factor)
-                .normalize();
-            Mat4::from_quat(final_rotation)
+            assert_eq!(WrapMode::Loop.advance(1.0, 0.0), 0.0);
         }
 
-        fn interpolate_scaling(&self, animation_time: f32) -> Mat4 {
-            if self.scales.len() == 1 {
-                return Mat4::from_scale(self.scales[0].scale);
+        #[test]
+        fn interpolation_modes() {
+            let mut bone = bone();
+
+            bone.interpolation = Interpolation::Step;
+            assert!(close(bone.sample(0.99).translation, Vec3::ZERO));
+            assert!(close(bone.sample(1.0).translation, Vec3::X));
+            assert!(close(bone.sample(1.5).translation, Vec3::X));
+            assert!(close(bone.sample(2.0).translation, 4.0 * Vec3::X));
+
+            bone.interpolation = Interpolation::Linear;
+            assert!(close(bone.sample(0.5).translation, 0.5 * Vec3::X));
+            assert!(close(bone.sample(1.5).translation, 2.5 * Vec3::X));
+            assert!(bone.sample(0.75).rotation.angle_between(Quat::from_rotation_y(0.5)) < 1e-4);
+
+            // the spline passes through the keys and bends towards the next segment's slope
+            bone.interpolation = Interpolation::Cubic;
+            assert!(close(bone.sample(1.0).translation, Vec3::X));
+            assert!(close(bone.sample(2.0).translation, 4.0 * Vec3::X));
+            assert!(close(bone.sample(0.5).translation, 0.3125 * Vec3::X));
+            assert!(bone.sample(1.5).rotation.angle_between(Quat::from_rotation_y(1.0)) < 1e-4);
+
+            // evenly spaced keys on a line stay on it away from the ends, where the end key stands in for its missing neighbour
+            let line = keys(&[(0.0, Vec3::ZERO), (1.0, Vec3::Y), (2.0, 2.0 * Vec3::Y), (3.0, 3.0 * Vec3::Y)]);
+            for i in 10..=20 {
+                let time = i as f32 * 0.1;
+                assert!(close(line.sample(time, Interpolation::Cubic).unwrap(), time * Vec3::Y));
             }
 
-            let p0_index = self.scale_index(animation_time);
-            let p1_index = p0_index + 1;
-            let scale_factor = Bone::scale_factor(self.scales[p0_index as usize].time_stamp, self.scales[p1_index as usize].time_stamp, animation_time);
-            let final_scale = self.scales[p0_index as usize].scale.lerp(self.scales[p1_index as usize].scale, scale_factor);
-            Mat4::from_scale(final_scale)
+            // tracks without keys fall back to the identity, single keys hold
+            assert!(close(bone.sample(1.2).scale, Vec3::ONE));
+            let empty = Bone::from_tracks("empty".to_string(), 1, keys(&[]), keys(&[]), keys(&[]));
+            assert_eq!(empty.sample(1.0).to_matrix(), Mat4::IDENTITY);
         }
     }
 }
@@ -0,0 +1,393 @@
+pub mod ik {
+    use crate::{Animator, Pose, Skeleton};
+    use glam::{Mat4, Quat, Vec3};
+
+    const EPSILON: f32 = 1e-5;
+    // seconds a planted foot takes to blend in or out
+    const PLANT_FADE: f32 = 0.15;
+
+    // how far a joint may turn from its bind rotation, in its parent's space
+    #[derive(Clone, Copy, Debug)]
+    pub enum JointLimit {
+        // any axis, up to the angle
+        Cone { max_angle: f32 },
+        // only about the axis, e.g. knees and elbows
+        Hinge { axis: Vec3, min_angle: f32, max_angle: f32 },
+    }
+
+    impl JointLimit {
+        pub fn apply(&self, rotation: Quat, bind: Quat) -> Quat {
+            let relative = bind.inverse() * rotation;
+
+            let limited = match *self {
+                JointLimit::Cone { max_angle } => {
+                    let (axis, angle) = relative.to_axis_angle();
+                    let angle = wrap_angle(angle);
+
+                    if angle.abs() > max_angle {
+                        Quat::from_axis_angle(axis, max_angle.copysign(angle))
+                    } else {
+                        relative
+                    }
+                }
+                JointLimit::Hinge { axis, min_angle, max_angle } => {
+                    // keep the twist about the axis, drop the rest
+                    let axis = axis.normalize();
+                    let projected = axis * relative.xyz().dot(axis);
+                    let twist = Quat::from_xyzw(projected.x, projected.y, projected.z, relative.w);
+
+                    if twist.length_squared() < EPSILON {
+                        Quat::IDENTITY
+                    } else {
+                        let twist = twist.normalize();
+                        let angle = wrap_angle(2.0 * f32::atan2(twist.xyz().dot(axis), twist.w));
+                        Quat::from_axis_angle(axis, angle.clamp(min_angle, max_angle))
+                    }
+                }
+            };
+
+            (bind * limited).normalize()
+        }
+    }
+
+    fn wrap_angle(angle: f32) -> f32 {
+        (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub enum Solver {
+        // exactly three nodes, e.g. thigh, shin and foot; the middle one bends towards the pole, or the way it already bends
+        TwoBone { pole: Option<Vec3> },
+        Fabrik { iterations: usize, tolerance: f32 },
+        Ccd { iterations: usize, tolerance: f32 },
+        // turns the last node so its own axis points at the target, e.g. a head looking
+        Aim { axis: Vec3 },
+    }
+
+    // a chain of nodes, parent to child, whose tip is brought to the target in model space
+    pub struct IkGoal {
+        pub chain: Vec<usize>,
+        pub solver: Solver,
+        pub limits: Vec<Option<JointLimit>>,
+        pub target: Vec3,
+        // 0 keeps the animated pose, 1 the solved one
+        pub weight: f32,
+        // model space, where the animation put the tip before the last solve
+        animated_tip: Option<Vec3>,
+    }
+
+    impl IkGoal {
+        pub fn new(skeleton: &Skeleton, root: &str, tip: &str, solver: Solver) -> Option<Self> {
+            let chain = skeleton.chain(root, tip)?;
+
+            if matches!(solver, Solver::TwoBone { .. }) && chain.len() != 3 {
+                return None;
+            }
+
+            Some(IkGoal {
+                limits: vec![None; chain.len()],
+                chain,
+                solver,
+                target: Vec3::ZERO,
+                weight: 1.0,
+                animated_tip: None,
+            })
+        }
+
+        pub fn limit(mut self, skeleton: &Skeleton, name: &str, limit: JointLimit) -> Self {
+            match skeleton.find(name).and_then(|node| self.chain.iter().position(|&joint| joint == node)) {
+                Some(joint) => self.limits[joint] = Some(limit),
+                None => println!("No joint named {name} in the chain."),
+            }
+            self
+        }
+
+        pub fn animated_tip(&self) -> Option<Vec3> {
+            self.animated_tip
+        }
+
+        // after keyframe sampling, before the skinning matrices are built
+        pub fn solve(&mut self, skeleton: &Skeleton, pose: &mut Pose) {
+            let tip = *self.chain.last().unwrap();
+            self.animated_tip = Some(pose.globals(skeleton)[tip].w_axis.truncate());
+
+            if self.weight <= 0.0 {
+                return;
+            }
+
+            let mut solved = pose.clone();
+
+            let target = self.target;
+            match self.solver {
+                Solver::TwoBone { pole } => self.follow(skeleton, &mut solved, |joints| two_bone(joints, target, pole)),
+                Solver::Fabrik { iterations, tolerance } => self.follow(skeleton, &mut solved, |joints| fabrik(joints, target, iterations, tolerance)),
+                Solver::Ccd { iterations, tolerance } => self.follow(skeleton, &mut solved, |joints| ccd(joints, target, iterations, tolerance)),
+                Solver::Aim { axis } => self.aim(skeleton, &mut solved, axis),
+            }
+
+            for &node in &self.chain {
+                pose.locals[node] = pose.locals[node].lerp(&solved.locals[node], self.weight.min(1.0));
+            }
+        }
+
+        // turns each joint, root first, so its child lies the way the solver puts it
+        fn follow(&self, skeleton: &Skeleton, pose: &mut Pose, solve: impl Fn(&[Vec3]) -> Vec<Vec3>) {
+            let globals = pose.globals(skeleton);
+            let joints: Vec<Vec3> = self.chain.iter().map(|&node| globals[node].w_axis.truncate()).collect();
+            let positions = solve(&joints);
+
+            for joint in 0..self.chain.len() - 1 {
+                let globals = pose.globals(skeleton);
+                let (node, child) = (self.chain[joint], self.chain[joint + 1]);
+
+                let from = globals[child].w_axis.truncate() - globals[node].w_axis.truncate();
+                let to = positions[joint + 1] - positions[joint];
+
+                self.rotate(skeleton, pose, &globals, joint, from, to);
+            }
+        }
+
+        fn aim(&self, skeleton: &Skeleton, pose: &mut Pose, axis: Vec3) {
+            let globals = pose.globals(skeleton);
+            let joint = self.chain.len() - 1;
+            let node = &globals[self.chain[joint]];
+
+            let from = node.transform_vector3(axis);
+            let to = self.target - node.w_axis.truncate();
+
+            self.rotate(skeleton, pose, &globals, joint, from, to);
+        }
+
+        // turns a joint by the model space rotation taking from onto to, then limits it
+        fn rotate(&self, skeleton: &Skeleton, pose: &mut Pose, globals: &[Mat4], joint: usize, from: Vec3, to: Vec3) {
+            if from.length_squared() < EPSILON || to.length_squared() < EPSILON {
+                return;
+            }
+
+            let node = self.chain[joint];
+            let global = global_rotation(&globals[node]);
+            let delta = Quat::from_rotation_arc(from.normalize(), to.normalize());
+
+            let local = &mut pose.locals[node];
+            local.rotation = (local.rotation * global.inverse() * delta * global).normalize();
+
+            if let Some(limit) = &self.limits[joint] {
+                local.rotation = limit.apply(local.rotation, skeleton.nodes[node].bind.rotation);
+            }
+        }
+    }
+
+    // holds a two bone leg's foot where it came down until the animation lifts it again, by driving the goal's
+    // target and weight; the foot counts as down within margin of the lowest the animation has put it
+    pub struct FootPlant {
+        goal: usize,
+        margin: f32,
+        lowest: f32,
+        planted: Option<Vec3>,
+    }
+
+    impl FootPlant {
+        // margin is a fraction of the leg's length in the bind pose
+        pub fn new(animator: &Animator, goal: usize, margin: f32) -> Self {
+            let skeleton = animator.skeleton();
+            let globals = skeleton.bind_pose().globals(skeleton);
+            let joints: Vec<Vec3> = animator.ik(goal).chain.iter().map(|&node| globals[node].w_axis.truncate()).collect();
+
+            FootPlant {
+                goal,
+                margin: margin * lengths(&joints).iter().sum::<f32>(),
+                lowest: f32::MAX,
+                planted: None,
+            }
+        }
+
+        // before update_animation, with what the last one left
+        pub fn update(&mut self, animator: &mut Animator, enabled: bool, delta_time: f32) {
+            // the model moves by the root motion, so the spot moves back by it to stay put in the world
+            let displacement = animator.root_motion();
+            let goal = animator.ik_mut(self.goal);
+
+            let Some(animated) = goal.animated_tip() else {
+                return;
+            };
+            self.lowest = self.lowest.min(animated.y);
+            let down = enabled && animated.y <= self.lowest + self.margin;
+
+            match &mut self.planted {
+                Some(planted) => *planted -= displacement,
+                None if down => self.planted = Some(animated),
+                None => {}
+            }
+
+            let step = delta_time / PLANT_FADE;
+            goal.weight = if down { goal.weight + step } else { goal.weight - step }.clamp(0.0, 1.0);
+
+            // a lifted foot lets go of its spot once it has blended out
+            if !down && goal.weight == 0.0 {
+                self.planted = None;
+            }
+            if let Some(planted) = self.planted {
+                goal.target = planted;
+            }
+        }
+    }
+
+    fn global_rotation(matrix: &Mat4) -> Quat {
+        matrix.to_scale_rotation_translation().1
+    }
+
+    fn lengths(joints: &[Vec3]) -> Vec<f32> {
+        joints.windows(2).map(|pair| pair[0].distance(pair[1])).collect()
+    }
+
+    // law of cosines in the plane holding the target and the bend direction
+    pub fn two_bone(joints: &[Vec3], target: Vec3, pole: Option<Vec3>) -> Vec<Vec3> {
+        let (root, middle, tip) = (joints[0], joints[1], joints[2]);
+        let (upper, lower) = (root.distance(middle), middle.distance(tip));
+
+        let to_target = target - root;
+        if to_target.length_squared() < EPSILON || upper < EPSILON || lower < EPSILON {
+            return joints.to_vec();
+        }
+
+        let direction = to_target.normalize();
+        let distance = to_target.length().clamp((upper - lower).abs() + EPSILON, upper + lower - EPSILON);
+
+        let hint = pole.unwrap_or(middle) - root;
+        let bend = hint - direction * hint.dot(direction);
+        let bend = if bend.length_squared() < EPSILON { direction.any_orthonormal_vector() } else { bend.normalize() };
+
+        let cosine = ((upper * upper + distance * distance - lower * lower) / (2.0 * upper * distance)).clamp(-1.0, 1.0);
+        let sine = (1.0 - cosine * cosine).sqrt();
+
+        vec![root, root + upper * (direction * cosine + bend * sine), root + direction * distance]
+    }
+
+    // forward and backward reaching, bone lengths kept
+    pub fn fabrik(joints: &[Vec3], target: Vec3, iterations: usize, tolerance: f32) -> Vec<Vec3> {
+        let lengths = lengths(joints);
+        let mut positions = joints.to_vec();
+        let root = joints[0];
+        let last = positions.len() - 1;
+
+        // out of reach, straight at it
+        if root.distance(target) >= lengths.iter().sum::<f32>() {
+            let direction = (target - root).normalize_or_zero();
+            for i in 0..last {
+                positions[i + 1] = positions[i] + direction * lengths[i];
+            }
+            return positions;
+        }
+
+        for _ in 0..iterations {
+            if positions[last].distance(target) <= tolerance {
+                break;
+            }
+
+            positions[last] = target;
+            for i in (0..last).rev() {
+                positions[i] = positions[i + 1] + (positions[i] - positions[i + 1]).normalize_or_zero() * lengths[i];
+            }
+
+            positions[0] = root;
+            for i in 0..last {
+                positions[i + 1] = positions[i] + (positions[i + 1] - positions[i]).normalize_or_zero() * lengths[i];
+            }
+        }
+
+        positions
+    }
+
+    // cyclic coordinate descent, each joint from the tip back turns the tip towards the target
+    pub fn ccd(joints: &[Vec3], target: Vec3, iterations: usize, tolerance: f32) -> Vec<Vec3> {
+        let mut positions = joints.to_vec();
+        let last = positions.len() - 1;
+
+        for _ in 0..iterations {
+            if positions[last].distance(target) <= tolerance {
+                break;
+            }
+
+            for joint in (0..last).rev() {
+                let pivot = positions[joint];
+                let (from, to) = (positions[last] - pivot, target - pivot);
+
+                if from.length_squared() < EPSILON || to.length_squared() < EPSILON {
+                    continue;
+                }
+
+                let rotation = Quat::from_rotation_arc(from.normalize(), to.normalize());
+                for position in &mut positions[joint + 1..] {
+                    *position = pivot + rotation * (*position - pivot);
+                }
+            }
+        }
+
+        positions
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn chain(count: usize) -> Vec<Vec3> {
+            (0..count).map(|i| Vec3::new(0.0, i as f32, 0.0)).collect()
+        }
+
+        fn keeps_lengths(before: &[Vec3], after: &[Vec3]) -> bool {
+            lengths(before).iter().zip(lengths(after)).all(|(a, b)| (a - b).abs() < 1e-4)
+        }
+
+        #[test]
+        fn two_bone_reaches_and_keeps_lengths() {
+            let joints = chain(3);
+            let target = Vec3::new(1.0, 1.0, 0.0);
+            let solved = two_bone(&joints, target, Some(Vec3::new(-1.0, 1.0, 0.0)));
+
+            assert!(solved[2].distance(target) < 1e-3);
+            assert!(keeps_lengths(&joints, &solved));
+            // the knee bends towards the pole
+            assert!(solved[1].x < target.x);
+        }
+
+        #[test]
+        fn two_bone_stretches_towards_an_unreachable_target() {
+            let joints = chain(3);
+            let solved = two_bone(&joints, Vec3::new(5.0, 0.0, 0.0), None);
+
+            assert!(keeps_lengths(&joints, &solved));
+            assert!(solved[2].distance(Vec3::new(2.0, 0.0, 0.0)) < 1e-2);
+        }
+
+        #[test]
+        fn fabrik_converges_and_keeps_lengths() {
+            let joints = chain(4);
+            let target = Vec3::new(1.5, 1.0, 0.5);
+            let solved = fabrik(&joints, target, 20, 1e-3);
+
+            assert!(solved[3].distance(target) < 1e-3);
+            assert!(keeps_lengths(&joints, &solved));
+            assert_eq!(solved[0], joints[0]);
+        }
+
+        #[test]
+        fn fabrik_straightens_towards_an_unreachable_target() {
+            let joints = chain(4);
+            let solved = fabrik(&joints, Vec3::new(10.0, 0.0, 0.0), 20, 1e-3);
+
+            assert!(keeps_lengths(&joints, &solved));
+            assert!(solved[3].distance(Vec3::new(3.0, 0.0, 0.0)) < 1e-4);
+        }
+
+        #[test]
+        fn ccd_converges_and_keeps_lengths() {
+            let joints = chain(4);
+            let target = Vec3::new(1.5, 1.0, 0.5);
+            let solved = ccd(&joints, target, 20, 1e-3);
+
+            assert!(solved[3].distance(target) < 1e-3);
+            assert!(keeps_lengths(&joints, &solved));
+            assert_eq!(solved[0], joints[0]);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
 use stb_image::stb_image::bindgen;
 
@@ -6,19 +6,42 @@ const SCR_WIDTH: u32 = 800;
 const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
+const INSTANCES: usize = 3;
+const PALETTE_BINDING: u32 = 0;
+const MODEL_SCALE: f32 = 0.5;
+const ROOT_BONE: &str = "mixamorig_Hips";
+const HAND_BONE: &str = "mixamorig_RightHand";
+const NECK_BONE: &str = "mixamorig_Neck";
+const HEAD_BONE: &str = "mixamorig_Head";
+// thigh to foot, each side
+const LEGS: [(&str, &str); 2] = [("mixamorig_LeftUpLeg", "mixamorig_LeftFoot"), ("mixamorig_RightUpLeg", "mixamorig_RightFoot")];
 
 pub mod animation;
-use animation::animation::{Animation, Animator};
+use animation::animation::{Animation, Animator, Blend, Player};
 pub mod bone;
-use bone::bone::Bone;
+use bone::bone::{Bone, WrapMode};
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
+pub mod ik;
+use ik::ik::{FootPlant, IkGoal, JointLimit, Solver};
 pub mod camera;
 use camera::camera::{Camera, Movement};
 pub mod mesh;
 use mesh::mesh::{stbi_flip_vertical, Mesh, Texture};
+pub mod morph;
+use morph::morph::{MorphTarget, MorphTargets, MorphWeights};
 pub mod model;
 use model::model::{convert_matrix, BoneInfo, Model, Vertex};
+pub mod palette;
+use palette::palette::{BonePalette, Skinning, Storage};
+pub mod pose;
+use pose::pose::{BoneMask, Pose, Skeleton, Transform};
 pub mod shader;
 use shader::shader::Program;
+pub mod socket;
+use socket::socket::{Attachment, Socket};
+pub mod state_machine;
+use state_machine::state_machine::{Condition, Motion, State, StateMachine};
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
@@ -44,11 +67,83 @@ fn main() {
 
     ogl::depth_enable(true);
 
-    let shader = Program::new().link("src/anim_model.vs", "src/anim_model.fs");
-
     let vampire = Model::new(false).load_model("resources/objects/vampire/dancing_vampire.dae");
-    let dance_animation = Animation::new("resources/objects/vampire/dancing_vampire.dae", &vampire);
-    let mut animator = Animator::new(dance_animation);
+    let clips = Animation::load_all("resources/objects/vampire/dancing_vampire.dae", &vampire);
+    let dance = clips[0].name().to_string();
+    let planet = Model::new(false).load_model("resources/objects/planet/planet.obj");
+
+    // the palettes only need the bones the vertices reference
+    let bone_count = vampire.bone_counter as usize;
+    let linear_shader = Program::new().link_with_defines("src/anim_model.vs", "src/anim_model.fs", &Skinning::Linear.defines(bone_count));
+    let dual_quaternion_shader = Program::new().link_with_defines("src/anim_model.vs", "src/anim_model.fs", &Skinning::DualQuaternion.defines(bone_count));
+    // skeletons too big for a uniform block are fetched from a texture buffer instead, which has no block to bind
+    for (shader, skinning) in [(&linear_shader, Skinning::Linear), (&dual_quaternion_shader, Skinning::DualQuaternion)] {
+        if Storage::for_bones(bone_count, skinning) == Storage::UniformBlock {
+            shader.set_block("BonePalette", PALETTE_BINDING);
+        }
+    }
+    let mut palette = BonePalette::new(bone_count, INSTANCES, Skinning::Linear);
+    let mut dual_quaternion = Toggle { is_on: false, pressed: false };
+    let mut root_motion = Toggle { is_on: false, pressed: false };
+    let mut root_motion_enabled = false;
+    let mut look_at = Toggle { is_on: true, pressed: false };
+    let mut foot_planting = Toggle { is_on: true, pressed: false };
+
+    // every instance shares the mesh but animates on its own, started a little apart
+    let mut animators: Vec<Animator> = (0..INSTANCES)
+        .map(|i| {
+            let mut animator = Animator::new(clips.clone());
+            animator.update_animation(i as f32 * 0.4);
+            animator
+        })
+        .collect();
+    let mut state_machines: Vec<StateMachine> = animators.iter().map(|animator| dance_state_machine(&dance, animator)).collect();
+    let mut positions = vec![Vec3::ZERO; INSTANCES];
+    let mut morph_weights = vec![MorphWeights::new(&vampire); INSTANCES];
+
+    // a small planet held in each right hand
+    let props: Vec<Option<Attachment<&Model>>> = animators
+        .iter_mut()
+        .map(|animator| {
+            let socket = animator.add_socket("prop", HAND_BONE, Mat4::from_scale(Vec3::splat(0.1)));
+            socket.map(|socket| Attachment::new(socket, &planet))
+        })
+        .collect();
+    if props.iter().any(Option::is_none) {
+        println!("No bone named {HAND_BONE} to hold the prop.");
+    }
+
+    // heads turn to the camera, no further than a person could
+    let look_at_goals: Vec<Option<usize>> = animators
+        .iter_mut()
+        .map(|animator| {
+            let goal = IkGoal::new(animator.skeleton(), NECK_BONE, HEAD_BONE, Solver::Aim { axis: Vec3::Z })?;
+            let goal = goal.limit(animator.skeleton(), HEAD_BONE, JointLimit::Cone { max_angle: 70f32.to_radians() });
+            Some(animator.add_ik(goal))
+        })
+        .collect();
+    if look_at_goals.iter().any(Option::is_none) {
+        println!("No bones named {NECK_BONE} and {HEAD_BONE} to look with.");
+    }
+
+    // feet stay where they came down while the animation holds them low, instead of sliding with the hips
+    let mut foot_plants: Vec<Vec<FootPlant>> = animators
+        .iter_mut()
+        .map(|animator| {
+            LEGS.iter()
+                .filter_map(|&(thigh, foot)| {
+                    let mut goal = IkGoal::new(animator.skeleton(), thigh, foot, Solver::TwoBone { pole: None })?;
+                    // the plant fades it in once a foot comes down
+                    goal.weight = 0.0;
+                    let goal = animator.add_ik(goal);
+                    Some(FootPlant::new(animator, goal, 0.05))
+                })
+                .collect()
+        })
+        .collect();
+    if foot_plants.iter().any(|plants| plants.len() != LEGS.len()) {
+        println!("No legs named {LEGS:?} to plant.");
+    }
 
     //ogl::polygon_mode();
 
@@ -64,12 +159,48 @@ fn main() {
         let delta_time = current_frame - last_frame;
         last_frame = current_frame;
 
-        process_input(&mut camera, &mut window, delta_time);
-        animator.update_animation(delta_time);
+        process_input(&mut camera, &mut state_machines, [&mut dual_quaternion, &mut root_motion, &mut look_at, &mut foot_planting], &mut window, delta_time);
+
+        // the dancers start back in line whenever root motion is switched
+        if root_motion.is_on != root_motion_enabled {
+            root_motion_enabled = root_motion.is_on;
+            for animator in &mut animators {
+                if !root_motion_enabled {
+                    animator.disable_root_motion();
+                } else if !animator.enable_root_motion(ROOT_BONE, Vec3::new(1.0, 0.0, 1.0)) {
+                    println!("No bone named {ROOT_BONE} to take root motion from.");
+                }
+            }
+            positions.fill(Vec3::ZERO);
+        }
+
+        let skinning = if dual_quaternion.is_on { Skinning::DualQuaternion } else { Skinning::Linear };
+        if palette.skinning() != skinning {
+            palette = BonePalette::new(bone_count, INSTANCES, skinning);
+        }
+
+        for (i, (animator, state_machine)) in animators.iter_mut().zip(&mut state_machines).enumerate() {
+            if let Some(goal) = look_at_goals[i] {
+                let goal = animator.ik_mut(goal);
+                goal.target = instance_matrix(i, positions[i]).inverse().transform_point3(camera.position());
+                goal.weight = if look_at.is_on { 1.0 } else { 0.0 };
+            }
+            for plant in &mut foot_plants[i] {
+                plant.update(animator, foot_planting.is_on, delta_time);
+            }
+
+            state_machine.update(animator);
+            animator.update_animation(delta_time);
+            animator.update_morphs(&vampire, &mut morph_weights[i]);
+            positions[i] += MODEL_SCALE * animator.root_motion();
+            palette.update(i, animator.final_bone_matrices());
+        }
+        palette.upload();
 
         ogl::clear_color(0.05, 0.05, 0.05, 1.0);
         ogl::clear();
 
+        let shader = if dual_quaternion.is_on { &dual_quaternion_shader } else { &linear_shader };
         shader.apply();
 
         let projection = Mat4::perspective_lh(camera.zoom(), SCR_WIDTH as f32 / SCR_HEIGHT as f32, SCR_NEAR, SCR_FAR);
@@ -77,14 +208,18 @@ fn main() {
         shader.set_mat4("projection", projection);
         shader.set_mat4("view", view);
 
-        let transforms = animator.final_bone_matrices();
-        for i in 0..transforms.len() {
-            shader.set_mat4(&("finalBonesMatrices[".to_string() + &(i.to_string() + "]")), transforms[i]);
-        }
+        for i in 0..INSTANCES {
+            palette.bind(shader, i, PALETTE_BINDING);
 
-        let model = Mat4::from_translation(Vec3::new(0.0, -0.4, 0.0)) * Mat4::from_scale(Vec3::new(0.5, 0.5, 0.5));
-        shader.set_mat4("model", model);
-        vampire.draw(&shader);
+            let model = instance_matrix(i, positions[i]);
+            shader.set_mat4("model", model);
+            vampire.draw_morphed(shader, &morph_weights[i]);
+
+            if let Some(prop) = &props[i] {
+                shader.set_mat4("model", prop.transform(&animators[i], model));
+                prop.item.draw(shader);
+            }
+        }
 
         window.swap_buffers();
 
@@ -95,11 +230,38 @@ fn main() {
     }
 }
 
-fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
+// the instances stand in a row, offset by how far root motion has moved them
+fn instance_matrix(instance: usize, position: Vec3) -> Mat4 {
+    let x = instance as f32 - (INSTANCES - 1) as f32 * 0.5;
+    Mat4::from_translation(Vec3::new(x, -0.4, 0.0) + position) * Mat4::from_scale(Vec3::splat(MODEL_SCALE))
+}
+
+// the file only has the one dance, holding shift cross-fades to it at half speed
+fn dance_state_machine(dance: &str, animator: &Animator) -> StateMachine {
+    StateMachine::new(0)
+        .state(State::new("dance", Motion::Clip(dance.to_string())).transition("slow", 0.5, vec![Condition::IsSet("slow".to_string())]))
+        .state(State {
+            speed: 0.5,
+            ..State::new("slow", Motion::Clip(dance.to_string())).transition("dance", 0.5, vec![Condition::NotSet("slow".to_string())])
+        })
+        .build(animator)
+        .unwrap_or_else(|error| panic!("{error}"))
+}
+
+fn process_input(camera: &mut Camera, state_machines: &mut [StateMachine], toggles: [&mut Toggle; 4], window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
     }
 
+    for state_machine in state_machines {
+        state_machine.set_bool("slow", window.get_key(Key::LeftShift) == Action::Press);
+    }
+
+    // K switches between linear and dual quaternion skinning, R lets the hips move the dancers, L makes them look at the camera, F plants their feet
+    for (toggle, key) in toggles.into_iter().zip([Key::K, Key::R, Key::L, Key::F]) {
+        toggle.update(window.get_key(key) == Action::Press);
+    }
+
     if window.get_key(Key::W) == Action::Press {
         camera.process_keyboard(Movement::Forward, delta_time);
     }
@@ -162,3 +324,18 @@ mod ogl {
         }
     }
 }
+
+pub struct Toggle {
+    pub is_on: bool,
+    pub pressed: bool,
+}
+
+impl Toggle {
+    // flips once per key press
+    pub fn update(&mut self, pressed: bool) {
+        if pressed && !self.pressed {
+            self.is_on = !self.is_on;
+        }
+        self.pressed = pressed;
+    }
+}
@@ -1,5 +1,5 @@
 pub mod mesh {
-    use crate::{bindgen, Program, Vertex};
+    use crate::{bindgen, interleave, Attribute, MeshBuilder, MorphTargets, Program, Vertex};
     use glfw::with_c_str;
     use std::{fs::File, io::Read, mem::size_of, ptr, rc::Rc};
 
@@ -9,6 +9,9 @@ pub mod mesh {
     pub struct Mesh {
         indices: Vec<u32>,
         textures: Vec<Rc<Texture>>,
+        morph_targets: Option<MorphTargets>,
+        // the node it hangs from, which morph channels go by
+        pub node: String,
         pub vao: VertexArray,
     }
 
@@ -54,10 +57,35 @@ pub mod mesh {
                 gl::BindVertexArray(0);
             }
 
-            Mesh { indices, textures, vao }
+            Mesh {
+                indices,
+                textures,
+                morph_targets: None,
+                node: String::new(),
+                vao,
+            }
+        }
+
+        pub fn with_morph_targets(mut self, morph_targets: MorphTargets) -> Self {
+            self.morph_targets = Some(morph_targets);
+            self
+        }
+
+        pub fn morph_targets(&self) -> Option<&MorphTargets> {
+            self.morph_targets.as_ref()
         }
 
         pub fn draw(&self, shader: &Program) {
+            self.draw_morphed(shader, None)
+        }
+
+        // weights per morph target, None for the ones the file set
+        pub fn draw_morphed(&self, shader: &Program, weights: Option<&[f32]>) {
+            match &self.morph_targets {
+                Some(targets) => targets.bind(shader, weights.unwrap_or(targets.default_weights())),
+                None => MorphTargets::unbind(shader),
+            }
+
             let mut diffuse = 1u32;
             let mut specular = 1u32;
             let mut normal = 1u32;
@@ -113,16 +141,6 @@ pub mod mesh {
         }
     }
 
-    const INDEX_COUNT: usize = 36;
-    const INDICES: [u32; INDEX_COUNT] = [
-        0, 1, 2, 2, 1, 3, // first quad
-        4, 5, 6, 6, 5, 7, // second quad
-        8, 9, 10, 10, 9, 11, // third quad
-        12, 13, 14, 14, 13, 15, // fourth quad
-        16, 17, 18, 18, 17, 19, // fifth quad
-        20, 21, 22, 22, 21, 23, // sixth quad
-    ];
-
     pub struct VertexArray {
         vertex_array: gl::types::GLuint,
         vertex_buffer: gl::types::GLuint,
@@ -150,111 +168,44 @@ pub mod mesh {
             }
         }
 
-        pub fn new_quad() -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
+        // the attributes go to locations 0, 1, 2... in the order given
+        pub fn from_builder(builder: MeshBuilder, attributes: &[Attribute]) -> Self {
+            let mut vao = VertexArray::new();
 
-            let vertices: Vec<f32> = vec![-1.0, -1.0, 0.0, 0.0, 0.0, 1.0, -1.0, 0.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0];
-            let indices = Vec::from(&INDICES[..6]);
+            let (vertices, indices) = builder.build();
+            let vertices = interleave(&vertices, attributes);
+            let stride: usize = attributes.iter().map(|attribute| attribute.components()).sum();
 
             unsafe {
-                gl::GenVertexArrays(1, &mut vertex_array);
-                gl::GenBuffers(1, &mut vertex_buffer);
-                gl::GenBuffers(1, &mut index_buffer);
-
-                gl::BindVertexArray(vertex_array);
+                gl::BindVertexArray(vao.vertex_array);
 
-                gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vao.vertex_buffer);
                 gl::BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
 
-                gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ptr::null());
-                gl::EnableVertexAttribArray(0);
-
-                gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl::EnableVertexAttribArray(1);
+                let mut offset = 0;
+                for (location, attribute) in attributes.iter().enumerate() {
+                    let components = attribute.components();
+                    gl::VertexAttribPointer(location as u32, components as i32, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * stride) as i32, ((offset * FLOAT_SIZE) as *const usize).cast());
+                    gl::EnableVertexAttribArray(location as u32);
+                    offset += components;
+                }
 
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vao.index_buffer);
                 gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
 
                 gl::BindVertexArray(0);
             }
 
-            VertexArray {
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-            }
+            vao.index_count = indices.len();
+            vao
         }
 
-        pub fn new_cube() -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
-
-            let vertices: Vec<f32> = vec![
-                // back face
-                1.0, -1.0, -1.0, 1.0, 0.0, // bottom-right
-                -1.0, -1.0, -1.0, 0.0, 0.0, // bottom-left
-                1.0, 1.0, -1.0, 1.0, 1.0, // top-right
-                -1.0, 1.0, -1.0, 0.0, 1.0, // top-left
-                // front face
-                -1.0, -1.0, 1.0, 0.0, 0.0, // bottom-left
-                1.0, -1.0, 1.0, 1.0, 0.0, // bottom-right
-                -1.0, 1.0, 1.0, 0.0, 1.0, // top-left
-                1.0, 1.0, 1.0, 1.0, 1.0, // top-right
-                // left face
-                -1.0, -1.0, -1.0, 0.0, 1.0, // bottom-left
-                -1.0, -1.0, 1.0, 0.0, 0.0, // bottom-right
-                -1.0, 1.0, -1.0, 1.0, 1.0, // top-left
-                -1.0, 1.0, 1.0, 1.0, 0.0, // top-right
-                // right face
-                1.0, -1.0, 1.0, 0.0, 0.0, // bottom-left
-                1.0, -1.0, -1.0, 0.0, 1.0, // bottom-right
-                1.0, 1.0, 1.0, 1.0, 0.0, // top-left
-                1.0, 1.0, -1.0, 1.0, 1.0, // top-right
-                // bottom face
-                1.0, -1.0, 1.0, 1.0, 0.0, // bottom-left
-                -1.0, -1.0, 1.0, 0.0, 0.0, // bottom-right
-                1.0, -1.0, -1.0, 1.0, 1.0, // top-left
-                -1.0, -1.0, -1.0, 0.0, 1.0, // top-right
-                // top face
-                -1.0, 1.0, 1.0, 0.0, 0.0, // bottom-left
-                1.0, 1.0, 1.0, 1.0, 0.0, // bottom-right
-                -1.0, 1.0, -1.0, 0.0, 1.0, // top-left
-                1.0, 1.0, -1.0, 1.0, 1.0, // top-right
-            ];
-            let indices = Vec::from(&INDICES[..]);
-
-            unsafe {
-                gl::GenVertexArrays(1, &mut vertex_array);
-                gl::GenBuffers(1, &mut vertex_buffer);
-                gl::GenBuffers(1, &mut index_buffer);
-
-                gl::BindVertexArray(vertex_array);
-
-                gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
-                gl::BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ptr::null());
-                gl::EnableVertexAttribArray(0);
-
-                gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl::EnableVertexAttribArray(1);
-
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
-                gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl::BindVertexArray(0);
-            }
+        pub fn new_quad() -> Self {
+            VertexArray::from_builder(MeshBuilder::rectangle(2.0, 2.0), &[Attribute::Position, Attribute::TexCoords])
+        }
 
-            VertexArray {
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-            }
+        pub fn new_cube() -> Self {
+            VertexArray::from_builder(MeshBuilder::cube(2.0), &[Attribute::Position, Attribute::TexCoords])
         }
 
         pub fn bind(&self) {
@@ -1,5 +1,5 @@
 pub mod model {
-    use crate::{Mesh, Program, Texture};
+    use crate::{Mesh, MorphTarget, MorphTargets, MorphWeights, Program, Texture};
     use glam::{Mat4, Vec2, Vec3};
     use russimp::{
         material::Material,
@@ -68,7 +68,8 @@ pub mod model {
 
         fn process_node(&mut self, node: &Node, scene: &Scene) {
             for mesh in &node.meshes {
-                let mesh = self.process_mesh(scene.meshes.get(*mesh as usize).unwrap(), scene);
+                let mut mesh = self.process_mesh(scene.meshes.get(*mesh as usize).unwrap(), scene);
+                mesh.node = node.name.clone();
                 self.meshes.push(mesh);
             }
 
@@ -136,7 +137,15 @@ pub mod model {
 
             self.extract_bone_weight_for_vertices(&mut vertices, mesh);
 
-            Mesh::new(vertices, indices, textures)
+            let vertex_count = vertices.len();
+            let result = Mesh::new(vertices, indices, textures);
+
+            if mesh.anim_meshes.is_empty() {
+                result
+            } else {
+                let targets = mesh.anim_meshes.iter().map(|target| MorphTarget::from_anim_mesh(mesh, target)).collect();
+                result.with_morph_targets(MorphTargets::new(targets, vertex_count))
+            }
         }
 
         fn set_vertex_bone_data(vertex: &mut Vertex, bone_id: i32, weight: f32) {
@@ -199,6 +208,12 @@ pub mod model {
             }
         }
 
+        pub fn draw_morphed(&self, shader: &Program, weights: &MorphWeights) {
+            for (i, mesh) in self.meshes.iter().enumerate() {
+                mesh.draw_morphed(shader, weights.weights.get(i).map(Vec::as_slice))
+            }
+        }
+
         pub fn bind_texture(&self) {
             unsafe {
                 gl::ActiveTexture(gl::TEXTURE0);
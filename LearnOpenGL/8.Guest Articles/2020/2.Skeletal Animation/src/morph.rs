@@ -0,0 +1,181 @@
+pub mod morph {
+    use crate::{Model, Program};
+    use glam::Vec3;
+    use russimp::{
+        mesh::{AnimMesh, Mesh},
+        Vector3D,
+    };
+    use std::mem::size_of;
+
+    // the size of morphWeights in anim_model.vs
+    pub const MAX_MORPH_TARGETS: usize = 32;
+    // above the material textures a mesh binds
+    pub const MORPH_TEXTURE_UNIT: u32 = 15;
+    // position, normal and tangent delta, each an rgba32f texel; the stride of the fetch in anim_model.vs
+    const TEXELS_PER_VERTEX: usize = 3;
+
+    // how far each vertex moves at full weight
+    pub struct MorphTarget {
+        pub name: String,
+        pub positions: Vec<Vec3>,
+        pub normals: Vec<Vec3>,
+        pub tangents: Vec<Vec3>,
+        pub default_weight: f32,
+    }
+
+    impl MorphTarget {
+        // assimp keeps whole replacement attributes, missing ones don't move
+        pub fn from_anim_mesh(mesh: &Mesh, target: &AnimMesh) -> Self {
+            let deltas = |replaced: &Vec<Vector3D>, base: &Vec<Vector3D>| -> Vec<Vec3> {
+                (0..mesh.vertices.len())
+                    .map(|i| match (replaced.get(i), base.get(i)) {
+                        (Some(replaced), Some(base)) => Vec3::new(replaced.x - base.x, replaced.y - base.y, replaced.z - base.z),
+                        _ => Vec3::ZERO,
+                    })
+                    .collect()
+            };
+
+            MorphTarget {
+                name: target.name.clone(),
+                positions: deltas(&target.vertices, &mesh.vertices),
+                normals: deltas(&target.normals, &mesh.normals),
+                tangents: deltas(&target.tangents, &mesh.tangents),
+                default_weight: target.weight,
+            }
+        }
+    }
+
+    // every target's deltas of one mesh in a texture buffer the vertex shader fetches with gl_VertexID
+    pub struct MorphTargets {
+        targets: Vec<MorphTarget>,
+        default_weights: Vec<f32>,
+        vertex_count: usize,
+        buffer: gl::types::GLuint,
+        texture: gl::types::GLuint,
+    }
+
+    impl MorphTargets {
+        pub fn new(targets: Vec<MorphTarget>, vertex_count: usize) -> Self {
+            if targets.len() > MAX_MORPH_TARGETS {
+                println!("{} morph targets, only the first {MAX_MORPH_TARGETS} are used.", targets.len());
+            }
+
+            let mut data: Vec<f32> = Vec::with_capacity(targets.len() * vertex_count * TEXELS_PER_VERTEX * 4);
+            for target in &targets {
+                for i in 0..vertex_count {
+                    for delta in [target.positions[i], target.normals[i], target.tangents[i]] {
+                        data.extend_from_slice(&delta.extend(0.0).to_array());
+                    }
+                }
+            }
+
+            let mut max_texels = 0;
+            let (mut buffer, mut texture) = (0, 0);
+
+            unsafe {
+                gl::GetIntegerv(gl::MAX_TEXTURE_BUFFER_SIZE, &mut max_texels);
+            }
+
+            if data.len() / 4 > max_texels as usize {
+                println!("{} morph texels, over the {max_texels} a texture buffer can hold.", data.len() / 4);
+            }
+
+            unsafe {
+                gl::GenBuffers(1, &mut buffer);
+                gl::BindBuffer(gl::TEXTURE_BUFFER, buffer);
+                gl::BufferData(gl::TEXTURE_BUFFER, (data.len() * size_of::<f32>()) as isize, data.as_ptr().cast(), gl::STATIC_DRAW);
+
+                gl::GenTextures(1, &mut texture);
+                gl::BindTexture(gl::TEXTURE_BUFFER, texture);
+                gl::TexBuffer(gl::TEXTURE_BUFFER, gl::RGBA32F, buffer);
+
+                gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+                gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+            }
+
+            MorphTargets {
+                default_weights: targets.iter().map(|target| target.default_weight).collect(),
+                targets,
+                vertex_count,
+                buffer,
+                texture,
+            }
+        }
+
+        pub fn targets(&self) -> &[MorphTarget] {
+            &self.targets
+        }
+
+        pub fn find(&self, name: &str) -> Option<usize> {
+            self.targets.iter().position(|target| target.name == name)
+        }
+
+        pub fn default_weights(&self) -> &[f32] {
+            &self.default_weights
+        }
+
+        pub fn bind(&self, shader: &Program, weights: &[f32]) {
+            let count = self.targets.len().min(weights.len()).min(MAX_MORPH_TARGETS);
+
+            shader.set_int("morphCount", count as i32);
+            shader.set_int("morphVertexCount", self.vertex_count as i32);
+            shader.set_float_array("morphWeights", &weights[..count]);
+            shader.set_int("morphDeltas", MORPH_TEXTURE_UNIT as i32);
+
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + MORPH_TEXTURE_UNIT);
+                gl::BindTexture(gl::TEXTURE_BUFFER, self.texture);
+                gl::ActiveTexture(gl::TEXTURE0);
+            }
+        }
+
+        pub fn unbind(shader: &Program) {
+            shader.set_int("morphCount", 0);
+        }
+    }
+
+    impl Drop for MorphTargets {
+        fn drop(&mut self) {
+            unsafe {
+                gl::DeleteTextures(1, &self.texture);
+                gl::DeleteBuffers(1, &self.buffer);
+            }
+        }
+    }
+
+    // per mesh of a model, per target of that mesh; one per instance since the model is shared
+    #[derive(Clone, Debug, Default)]
+    pub struct MorphWeights {
+        pub weights: Vec<Vec<f32>>,
+    }
+
+    impl MorphWeights {
+        pub fn new(model: &Model) -> Self {
+            MorphWeights {
+                weights: model.meshes.iter().map(|mesh| mesh.morph_targets().map_or(vec![], |targets| targets.default_weights().to_vec())).collect(),
+            }
+        }
+
+        // on every mesh with a target of that name, returns false if there is none
+        pub fn set(&mut self, model: &Model, target: &str, weight: f32) -> bool {
+            let mut found = false;
+
+            for (mesh, weights) in model.meshes.iter().zip(&mut self.weights) {
+                if let Some(index) = mesh.morph_targets().and_then(|targets| targets.find(target)) {
+                    weights[index] = weight;
+                    found = true;
+                }
+            }
+
+            found
+        }
+
+        pub fn lerp(&mut self, other: &MorphWeights, t: f32) {
+            for (weights, others) in self.weights.iter_mut().zip(&other.weights) {
+                for (weight, other) in weights.iter_mut().zip(others) {
+                    *weight += (other - *weight) * t;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+pub mod palette {
+    use crate::Program;
+    use glam::{Mat4, Quat};
+    use std::{mem::size_of, ptr};
+
+    // below the morph deltas' unit, above the material textures a mesh binds
+    pub const PALETTE_TEXTURE_UNIT: u32 = 14;
+    // a texture buffer texel is a vec4
+    const TEXEL_SIZE: usize = 4 * size_of::<f32>();
+
+    #[derive(Clone, Copy, PartialEq)]
+    pub enum Skinning {
+        // blends the matrices, volume collapses around twisting joints
+        Linear,
+        // blends rotation and translation as dual quaternions, ignores bone scale
+        DualQuaternion,
+    }
+
+    impl Skinning {
+        // std140 size of one bone, a mat4 or a mat2x4
+        fn bone_size(self) -> usize {
+            match self {
+                Skinning::Linear => 16 * size_of::<f32>(),
+                Skinning::DualQuaternion => 8 * size_of::<f32>(),
+            }
+        }
+
+        pub fn defines(self, bone_count: usize) -> Vec<(&'static str, String)> {
+            let mut defines = vec![("MAX_BONES", bone_count.max(1).to_string())];
+            if self == Skinning::DualQuaternion {
+                defines.push(("DUAL_QUATERNION", "1".to_string()));
+            }
+            if Storage::for_bones(bone_count, self) == Storage::TextureBuffer {
+                defines.push(("BONE_TEXTURE", "1".to_string()));
+            }
+            defines
+        }
+    }
+
+    // a uniform block when one instance's bones fit in it, a texture buffer for bigger skeletons
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Storage {
+        UniformBlock,
+        TextureBuffer,
+    }
+
+    impl Storage {
+        pub fn for_bones(bone_count: usize, skinning: Skinning) -> Self {
+            let mut max_block_size = 0;
+
+            unsafe {
+                gl::GetIntegerv(gl::MAX_UNIFORM_BLOCK_SIZE, &mut max_block_size);
+            }
+
+            if bone_count.max(1) * skinning.bone_size() > max_block_size as usize {
+                Storage::TextureBuffer
+            } else {
+                Storage::UniformBlock
+            }
+        }
+
+        fn target(self) -> gl::types::GLenum {
+            match self {
+                Storage::UniformBlock => gl::UNIFORM_BUFFER,
+                Storage::TextureBuffer => gl::TEXTURE_BUFFER,
+            }
+        }
+    }
+
+    // one buffer holding a bone palette per animated instance, each bound to the block or fetched from in turn
+    pub struct BonePalette {
+        buffer: gl::types::GLuint,
+        // only for Storage::TextureBuffer
+        texture: gl::types::GLuint,
+        data: Vec<f32>,
+        skinning: Skinning,
+        storage: Storage,
+        bone_count: usize,
+        stride: usize,
+    }
+
+    impl BonePalette {
+        pub fn new(bone_count: usize, instances: usize, skinning: Skinning) -> Self {
+            let (mut buffer, mut texture) = (0, 0);
+
+            let size = bone_count.max(1) * skinning.bone_size();
+            let storage = Storage::for_bones(bone_count, skinning);
+
+            // bones are whole texels, so texture buffer palettes need no padding
+            let alignment = match storage {
+                Storage::UniformBlock => {
+                    let mut alignment = 0;
+                    unsafe {
+                        gl::GetIntegerv(gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT, &mut alignment);
+                    }
+                    alignment.max(1) as usize
+                }
+                Storage::TextureBuffer => TEXEL_SIZE,
+            };
+            let stride = size.div_ceil(alignment) * alignment;
+
+            unsafe {
+                gl::GenBuffers(1, &mut buffer);
+                gl::BindBuffer(storage.target(), buffer);
+                gl::BufferData(storage.target(), (stride * instances) as isize, ptr::null(), gl::DYNAMIC_DRAW);
+
+                if storage == Storage::TextureBuffer {
+                    gl::GenTextures(1, &mut texture);
+                    gl::BindTexture(gl::TEXTURE_BUFFER, texture);
+                    gl::TexBuffer(gl::TEXTURE_BUFFER, gl::RGBA32F, buffer);
+                    gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+                }
+
+                gl::BindBuffer(storage.target(), 0);
+            }
+
+            BonePalette {
+                buffer,
+                texture,
+                data: vec![0.0; stride * instances / size_of::<f32>()],
+                skinning,
+                storage,
+                bone_count,
+                stride,
+            }
+        }
+
+        pub fn skinning(&self) -> Skinning {
+            self.skinning
+        }
+
+        pub fn storage(&self) -> Storage {
+            self.storage
+        }
+
+        // only the first bone_count matrices are kept, bones no vertex uses aren't needed on the GPU
+        pub fn update(&mut self, instance: usize, matrices: &[Mat4]) {
+            let start = instance * self.stride / size_of::<f32>();
+            let bone_floats = self.skinning.bone_size() / size_of::<f32>();
+
+            for (i, matrix) in matrices.iter().take(self.bone_count).enumerate() {
+                let bone = &mut self.data[start + i * bone_floats..][..bone_floats];
+
+                match self.skinning {
+                    Skinning::Linear => bone.copy_from_slice(&matrix.to_cols_array()),
+                    Skinning::DualQuaternion => {
+                        let (_, rotation, translation) = matrix.to_scale_rotation_translation();
+                        let dual = Quat::from_xyzw(translation.x, translation.y, translation.z, 0.0) * rotation * 0.5;
+
+                        bone[..4].copy_from_slice(&rotation.to_array());
+                        bone[4..].copy_from_slice(&dual.to_array());
+                    }
+                }
+            }
+        }
+
+        // every instance in one call, after their update
+        pub fn upload(&self) {
+            let target = self.storage.target();
+
+            unsafe {
+                gl::BindBuffer(target, self.buffer);
+                gl::BufferSubData(target, 0, (self.data.len() * size_of::<f32>()) as isize, self.data.as_ptr().cast());
+                gl::BindBuffer(target, 0);
+            }
+        }
+
+        // the uniform block binding, or the texel the shader starts fetching this instance's bones from
+        pub fn bind(&self, shader: &Program, instance: usize, binding: u32) {
+            match self.storage {
+                Storage::UniformBlock => unsafe {
+                    gl::BindBufferRange(
+                        gl::UNIFORM_BUFFER,
+                        binding,
+                        self.buffer,
+                        (instance * self.stride) as isize,
+                        (self.bone_count.max(1) * self.skinning.bone_size()) as isize,
+                    );
+                },
+                Storage::TextureBuffer => {
+                    shader.set_int("bonePalette", PALETTE_TEXTURE_UNIT as i32);
+                    shader.set_int("bonePaletteOffset", (instance * self.stride / TEXEL_SIZE) as i32);
+
+                    unsafe {
+                        gl::ActiveTexture(gl::TEXTURE0 + PALETTE_TEXTURE_UNIT);
+                        gl::BindTexture(gl::TEXTURE_BUFFER, self.texture);
+                        gl::ActiveTexture(gl::TEXTURE0);
+                    }
+                }
+            }
+        }
+    }
+
+    impl Drop for BonePalette {
+        fn drop(&mut self) {
+            unsafe {
+                gl::DeleteTextures(1, &self.texture);
+                gl::DeleteBuffers(1, &self.buffer);
+            }
+        }
+    }
+}
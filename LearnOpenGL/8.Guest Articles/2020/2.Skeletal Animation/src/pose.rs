@@ -0,0 +1,169 @@
+pub mod pose {
+    use glam::{Mat4, Quat, Vec3};
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Transform {
+        pub translation: Vec3,
+        pub rotation: Quat,
+        pub scale: Vec3,
+    }
+
+    impl Transform {
+        pub const IDENTITY: Transform = Transform {
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+        };
+
+        pub fn from_matrix(matrix: Mat4) -> Self {
+            let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+            Transform { translation, rotation, scale }
+        }
+
+        pub fn to_matrix(&self) -> Mat4 {
+            Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+        }
+
+        pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+            Transform {
+                translation: self.translation.lerp(other.translation, t),
+                rotation: self.rotation.slerp(other.rotation, t).normalize(),
+                scale: self.scale.lerp(other.scale, t),
+            }
+        }
+
+        // what has to be applied on top of reference to get self
+        pub fn difference(&self, reference: &Transform) -> Transform {
+            Transform {
+                translation: self.translation - reference.translation,
+                rotation: (self.rotation * reference.rotation.inverse()).normalize(),
+                scale: self.scale / reference.scale,
+            }
+        }
+
+        pub fn add(&self, difference: &Transform, weight: f32) -> Transform {
+            Transform {
+                translation: self.translation + difference.translation * weight,
+                rotation: (Quat::IDENTITY.slerp(difference.rotation, weight) * self.rotation).normalize(),
+                scale: self.scale * Vec3::ONE.lerp(difference.scale, weight),
+            }
+        }
+    }
+
+    pub struct SkeletonNode {
+        pub name: String,
+        pub parent: Option<usize>,
+        pub bind: Transform,
+    }
+
+    // the node hierarchy flattened with every parent before its children
+    #[derive(Default)]
+    pub struct Skeleton {
+        pub nodes: Vec<SkeletonNode>,
+    }
+
+    impl Skeleton {
+        pub fn push(&mut self, name: String, parent: Option<usize>, bind: Mat4) -> usize {
+            self.nodes.push(SkeletonNode {
+                name,
+                parent,
+                bind: Transform::from_matrix(bind),
+            });
+            self.nodes.len() - 1
+        }
+
+        pub fn find(&self, name: &str) -> Option<usize> {
+            self.nodes.iter().position(|node| node.name == name)
+        }
+
+        // full weight on the named nodes and everything below them, e.g. ["mixamorig_Spine1"] for the upper body
+        pub fn mask(&self, roots: &[&str]) -> BoneMask {
+            let mut weights: Vec<f32> = Vec::with_capacity(self.nodes.len());
+
+            for node in &self.nodes {
+                let inherited = node.parent.map_or(0.0, |parent| weights[parent]);
+                weights.push(if roots.contains(&node.name.as_str()) { 1.0 } else { inherited });
+            }
+
+            BoneMask { weights }
+        }
+
+        // the nodes from root down to tip, None unless tip is below root
+        pub fn chain(&self, root: &str, tip: &str) -> Option<Vec<usize>> {
+            let root = self.find(root)?;
+            let mut chain = vec![self.find(tip)?];
+
+            while *chain.last().unwrap() != root {
+                chain.push(self.nodes[*chain.last().unwrap()].parent?);
+            }
+
+            chain.reverse();
+            Some(chain)
+        }
+
+        pub fn bind_pose(&self) -> Pose {
+            Pose {
+                locals: self.nodes.iter().map(|node| node.bind).collect(),
+            }
+        }
+    }
+
+    // per node weights a layer is scaled by
+    #[derive(Clone)]
+    pub struct BoneMask {
+        pub weights: Vec<f32>,
+    }
+
+    impl BoneMask {
+        pub fn inverted(&self) -> BoneMask {
+            BoneMask {
+                weights: self.weights.iter().map(|weight| 1.0 - weight).collect(),
+            }
+        }
+
+        fn weight(mask: Option<&BoneMask>, node: usize) -> f32 {
+            mask.map_or(1.0, |mask| mask.weights[node])
+        }
+    }
+
+    // local transforms, indexed like the skeleton's nodes
+    #[derive(Clone)]
+    pub struct Pose {
+        pub locals: Vec<Transform>,
+    }
+
+    impl Pose {
+        // model space transforms, indexed like the locals
+        pub fn globals(&self, skeleton: &Skeleton) -> Vec<Mat4> {
+            let mut globals: Vec<Mat4> = Vec::with_capacity(self.locals.len());
+
+            for (node, local) in skeleton.nodes.iter().zip(&self.locals) {
+                let parent = node.parent.map_or(Mat4::IDENTITY, |parent| globals[parent]);
+                globals.push(parent * local.to_matrix());
+            }
+
+            globals
+        }
+
+        pub fn blend(&mut self, other: &Pose, weight: f32, mask: Option<&BoneMask>) {
+            for (i, (local, other)) in self.locals.iter_mut().zip(&other.locals).enumerate() {
+                let weight = weight * BoneMask::weight(mask, i);
+
+                if weight > 0.0 {
+                    *local = local.lerp(other, weight);
+                }
+            }
+        }
+
+        // adds how far `pose` has moved from `reference`, as additive layers do
+        pub fn add(&mut self, pose: &Pose, reference: &Pose, weight: f32, mask: Option<&BoneMask>) {
+            for (i, local) in self.locals.iter_mut().enumerate() {
+                let weight = weight * BoneMask::weight(mask, i);
+
+                if weight > 0.0 {
+                    *local = local.add(&pose.locals[i].difference(&reference.locals[i]), weight);
+                }
+            }
+        }
+    }
+}
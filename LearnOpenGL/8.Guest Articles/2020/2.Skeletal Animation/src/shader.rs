@@ -63,15 +63,15 @@ pub mod shader {
         }
 
         pub fn link(self, vertex_file: &str, fragment_file: &str) -> Self {
-            let program = self.program;
+            self.link_with_defines(vertex_file, fragment_file, &[])
+        }
 
-            let mut vertex_source = Vec::new();
-            File::open(vertex_file).unwrap().read_to_end(&mut vertex_source).unwrap();
-            vertex_source.push(0);
+        // each (name, value) becomes a #define right after the #version line of both stages
+        pub fn link_with_defines(self, vertex_file: &str, fragment_file: &str, defines: &[(&str, String)]) -> Self {
+            let program = self.program;
 
-            let mut fragment_source = Vec::new();
-            File::open(fragment_file).unwrap().read_to_end(&mut fragment_source).unwrap();
-            fragment_source.push(0);
+            let vertex_source = Program::read_source(vertex_file, defines);
+            let fragment_source = Program::read_source(fragment_file, defines);
 
             let vertex_shader = Shader::new(gl::VERTEX_SHADER).compile(&vertex_source);
             let fragment_shader = Shader::new(gl::FRAGMENT_SHADER).compile(&fragment_source);
@@ -102,6 +102,18 @@ pub mod shader {
             self
         }
 
+        fn read_source(path: &str, defines: &[(&str, String)]) -> Vec<u8> {
+            let mut source = String::new();
+            File::open(path).unwrap().read_to_string(&mut source).unwrap();
+
+            let (version, body) = source.split_once('\n').unwrap_or((&source, ""));
+            let defines: String = defines.iter().map(|(name, value)| format!("#define {name} {value}\n")).collect();
+
+            let mut source = format!("{version}\n{defines}{body}").into_bytes();
+            source.push(0);
+            source
+        }
+
         pub fn apply(&self) {
             unsafe {
                 gl::UseProgram(self.program);
@@ -118,6 +130,12 @@ pub mod shader {
             });
         }
 
+        pub fn set_float_array(&self, name: &str, values: &[f32]) {
+            with_c_str(name, |locn| unsafe {
+                gl::Uniform1fv(gl::GetUniformLocation(self.program, locn), values.len() as i32, values.as_ptr());
+            });
+        }
+
         pub fn set_vec3(&self, name: &str, vector: Vec3) {
             let vectorray = [vector.x, vector.y, vector.z];
 
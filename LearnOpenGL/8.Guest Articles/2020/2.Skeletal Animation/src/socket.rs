@@ -0,0 +1,28 @@
+pub mod socket {
+    use crate::Animator;
+    use glam::Mat4;
+
+    // a named point on a bone props are held at
+    pub struct Socket {
+        pub name: String,
+        pub node: usize,
+        pub offset: Mat4,
+    }
+
+    // anything that follows a socket, a prop Model or what a scene graph Entity's transform is set from
+    pub struct Attachment<T> {
+        pub socket: usize,
+        pub item: T,
+    }
+
+    impl<T> Attachment<T> {
+        pub fn new(socket: usize, item: T) -> Self {
+            Attachment { socket, item }
+        }
+
+        // model is the animated model's own matrix
+        pub fn transform(&self, animator: &Animator, model: Mat4) -> Mat4 {
+            model * animator.socket_transform(self.socket)
+        }
+    }
+}
@@ -0,0 +1,202 @@
+pub mod state_machine {
+    use crate::{Animator, Blend, Player, WrapMode};
+    use std::collections::HashMap;
+
+    pub enum Motion {
+        Clip(String),
+        // clips placed along one parameter, the two either side of its value are mixed
+        Blend1D { parameter: String, clips: Vec<(f32, String)> },
+    }
+
+    pub enum Condition {
+        Above(String, f32),
+        Below(String, f32),
+        IsSet(String),
+        NotSet(String),
+        // every clip of a clamped state has reached its end
+        Finished,
+    }
+
+    pub struct Transition {
+        pub to: String,
+        pub conditions: Vec<Condition>,
+        pub fade_duration: f32,
+    }
+
+    pub struct State {
+        pub name: String,
+        pub motion: Motion,
+        pub speed: f32,
+        pub wrap: WrapMode,
+        pub transitions: Vec<Transition>,
+    }
+
+    impl State {
+        pub fn new(name: &str, motion: Motion) -> Self {
+            State {
+                name: name.to_string(),
+                motion,
+                speed: 1.0,
+                wrap: WrapMode::Loop,
+                transitions: vec![],
+            }
+        }
+
+        pub fn transition(mut self, to: &str, fade_duration: f32, conditions: Vec<Condition>) -> Self {
+            self.transitions.push(Transition {
+                to: to.to_string(),
+                conditions,
+                fade_duration,
+            });
+            self
+        }
+    }
+
+    // drives one animator layer, starting in the first state and taking the first transition whose conditions all hold
+    pub struct StateMachine {
+        layer: usize,
+        states: Vec<State>,
+        current: Option<usize>,
+        parameters: HashMap<String, f32>,
+    }
+
+    impl StateMachine {
+        pub fn new(layer: usize) -> Self {
+            StateMachine {
+                layer,
+                states: vec![],
+                current: None,
+                parameters: HashMap::new(),
+            }
+        }
+
+        pub fn state(mut self, state: State) -> Self {
+            self.states.push(state);
+            self
+        }
+
+        // checks every clip and transition target against the animator it will drive, once all states are added
+        pub fn build(self, animator: &Animator) -> Result<Self, String> {
+            for state in &self.states {
+                let clips = match &state.motion {
+                    Motion::Clip(name) => vec![name],
+                    Motion::Blend1D { clips, .. } => clips.iter().map(|(_, name)| name).collect(),
+                };
+
+                if let Some(name) = clips.into_iter().find(|name| animator.clip_index(name).is_none()) {
+                    return Err(format!("No animation clip named {name} in state {}.", state.name));
+                }
+
+                if let Some(transition) = state.transitions.iter().find(|transition| self.states.iter().all(|other| other.name != transition.to)) {
+                    return Err(format!("No animation state named {} to go to from {}.", transition.to, state.name));
+                }
+            }
+
+            Ok(self)
+        }
+
+        pub fn set_float(&mut self, name: &str, value: f32) {
+            self.parameters.insert(name.to_string(), value);
+        }
+
+        pub fn set_bool(&mut self, name: &str, value: bool) {
+            self.set_float(name, if value { 1.0 } else { 0.0 });
+        }
+
+        pub fn float(&self, name: &str) -> f32 {
+            self.parameters.get(name).copied().unwrap_or(0.0)
+        }
+
+        pub fn current_state(&self) -> Option<&str> {
+            self.current.map(|current| self.states[current].name.as_str())
+        }
+
+        // before Animator::update_animation, so a transition starts fading in the same frame
+        pub fn update(&mut self, animator: &mut Animator) {
+            let current = match self.current {
+                Some(current) => current,
+                None if self.states.is_empty() => return,
+                None => {
+                    self.enter(animator, 0, 0.0);
+                    0
+                }
+            };
+
+            let transition = self.states[current]
+                .transitions
+                .iter()
+                .find(|transition| transition.conditions.iter().all(|condition| self.holds(animator, condition)))
+                .map(|transition| (transition.to.clone(), transition.fade_duration));
+
+            if let Some((to, fade_duration)) = transition {
+                let next = self.states.iter().position(|state| state.name == to).unwrap();
+                self.enter(animator, next, fade_duration);
+            }
+
+            let current = self.current.unwrap();
+            if let Motion::Blend1D { parameter, clips } = &self.states[current].motion {
+                let weights = blend_weights(clips, self.float(parameter));
+
+                for (player, weight) in animator.layer_mut(self.layer).blend_mut().players.iter_mut().zip(weights) {
+                    player.weight = weight;
+                }
+            }
+        }
+
+        fn enter(&mut self, animator: &mut Animator, index: usize, fade_duration: f32) {
+            let state = &self.states[index];
+
+            let (names, weights): (Vec<&String>, Vec<f32>) = match &state.motion {
+                Motion::Clip(name) => (vec![name], vec![1.0]),
+                Motion::Blend1D { parameter, clips } => (clips.iter().map(|(_, name)| name).collect(), blend_weights(clips, self.float(parameter))),
+            };
+
+            // build has checked every name, so the players line up with the blend weights
+            let players = names
+                .into_iter()
+                .zip(weights)
+                .map(|(name, weight)| Player {
+                    speed: state.speed,
+                    weight,
+                    wrap: state.wrap,
+                    ..Player::new(animator.clip_index(name).unwrap())
+                })
+                .collect();
+
+            animator.layer_mut(self.layer).play(Blend { players }, fade_duration);
+            self.current = Some(index);
+        }
+
+        fn holds(&self, animator: &Animator, condition: &Condition) -> bool {
+            match condition {
+                Condition::Above(name, value) => self.float(name) > *value,
+                Condition::Below(name, value) => self.float(name) < *value,
+                Condition::IsSet(name) => self.float(name) != 0.0,
+                Condition::NotSet(name) => self.float(name) == 0.0,
+                Condition::Finished => animator.is_finished(self.layer),
+            }
+        }
+    }
+
+    // clips sorted by position, the value is clamped to the ends
+    fn blend_weights(clips: &[(f32, String)], value: f32) -> Vec<f32> {
+        let mut weights = vec![0.0; clips.len()];
+
+        match clips.iter().position(|(position, _)| value < *position) {
+            Some(0) => weights[0] = 1.0,
+            Some(next) => {
+                let (from, to) = (clips[next - 1].0, clips[next].0);
+                let t = (value - from) / (to - from);
+                weights[next - 1] = 1.0 - t;
+                weights[next] = t;
+            }
+            None => {
+                if let Some(last) = weights.last_mut() {
+                    *last = 1.0;
+                }
+            }
+        }
+
+        weights
+    }
+}
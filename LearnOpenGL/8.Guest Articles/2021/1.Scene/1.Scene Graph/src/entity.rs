@@ -29,11 +29,17 @@ pub mod entity {
         }
 
         pub fn compute_model_matrix(&mut self) {
-            self.model_matrix = self.get_local_model_matrix();
+            self.compute_relative_model_matrix(Mat4::IDENTITY);
         }
 
         pub fn compute_relative_model_matrix(&mut self, parent: Mat4) {
             self.model_matrix = parent * self.get_local_model_matrix();
+            self.is_dirty = false;
+        }
+
+        // the model matrix is recomputed on the next update even though nothing local changed
+        pub fn mark_dirty(&mut self) {
+            self.is_dirty = true;
         }
 
         pub fn set_local_position(&mut self, position: Vec3) {
@@ -322,10 +328,8 @@ pub mod entity {
         Sphere::new((max_aabb + min_aabb) * 0.5, (min_aabb - max_aabb).length())
     }
 
+    // what a scene node draws, its transform lives on the node
     pub struct Entity {
-        pub children: Vec<Entity>,
-        parent: Option<Transform>,
-        pub transform: Transform,
         pub p_model: Rc<Model>,
         bounding_volume: AABB,
     }
@@ -335,9 +339,6 @@ pub mod entity {
             let bounding_volume = generate_aabb(&value);
 
             Entity {
-                children: Vec::new(),
-                parent: None,
-                transform: Transform::new(),
                 p_model: Rc::clone(value),
                 bounding_volume,
             }
@@ -345,12 +346,12 @@ pub mod entity {
     }
 
     impl Entity {
-        pub fn get_global_aabb(&self) -> AABB {
-            let global_center = (self.transform.get_model_matrix() * self.bounding_volume.center.extend(1.0)).truncate();
+        pub fn get_global_aabb(&self, transform: &Transform) -> AABB {
+            let global_center = (transform.get_model_matrix() * self.bounding_volume.center.extend(1.0)).truncate();
 
-            let right = self.transform.get_right() * self.bounding_volume.extents.x;
-            let up = self.transform.get_up() * self.bounding_volume.extents.y;
-            let forward = self.transform.get_forward() * self.bounding_volume.extents.z;
+            let right = transform.get_right() * self.bounding_volume.extents.x;
+            let up = transform.get_up() * self.bounding_volume.extents.y;
+            let forward = transform.get_forward() * self.bounding_volume.extents.z;
 
             let new_ii = f32::abs(Vec3::X.dot(right)) + f32::abs(Vec3::X.dot(up)) + f32::abs(Vec3::X.dot(forward));
 
@@ -361,40 +362,19 @@ pub mod entity {
             AABB::new(global_center, Vec3::new(new_ii, new_ij, new_ik))
         }
 
-        pub fn add_child(&mut self, mut child: Entity) {
-            child.parent = Some(self.transform);
-            self.children.push(child);
-        }
-
-        pub fn update_self_and_child(&mut self) {
-            if self.transform.is_dirty() {
-                self.force_update_self_and_child();
-            }
-        }
-
-        fn force_update_self_and_child(&mut self) {
-            if let Some(transform) = self.parent {
-                self.transform.compute_relative_model_matrix(transform.get_model_matrix());
-            } else {
-                self.transform.compute_model_matrix();
-            }
-
-            for child in &mut self.children {
-                child.force_update_self_and_child();
-            }
+        pub fn draw(&self, transform: &Transform, shader: &Program) {
+            shader.set_mat4("model", transform.get_model_matrix());
+            self.p_model.draw(shader);
         }
 
-        pub fn draw_self_and_child(&self, frustum: &Frustum, shader: &Program, display: &mut u32, total: &mut u32) {
-            if self.bounding_volume.is_on_frustum_transform(frustum, &self.transform) {
-                shader.set_mat4("model", self.transform.get_model_matrix());
-                self.p_model.draw(shader);
-                *display += 1;
+        // returns false when culled
+        pub fn draw_culled(&self, transform: &Transform, frustum: &Frustum, shader: &Program) -> bool {
+            if !self.bounding_volume.is_on_frustum_transform(frustum, transform) {
+                return false;
             }
-            *total += 1;
 
-            for child in &self.children {
-                child.draw_self_and_child(frustum, shader, display, total);
-            }
+            self.draw(transform, shader);
+            true
         }
     }
 }
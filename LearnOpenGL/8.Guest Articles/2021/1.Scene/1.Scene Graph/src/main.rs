@@ -1,4 +1,4 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
 use stb_image::stb_image::bindgen;
 use std::rc::Rc;
@@ -8,14 +8,18 @@ const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::{Camera, Movement};
 pub mod entity;
-use entity::entity::Entity;
+use entity::entity::{Entity, Transform};
 pub mod mesh;
 use mesh::mesh::{stbi_flip_vertical, Mesh, Texture, VertexArray};
 pub mod model;
 use model::model::{Model, Vertex};
+pub mod scene;
+use scene::scene::Scene;
 pub mod shader;
 use shader::shader::Program;
 
@@ -49,23 +53,20 @@ fn main() {
     let shader = Program::new().link("src/1.model_loading.vs", "src/1.model_loading.fs");
 
     let planet = Rc::new(Model::new().load_model("resources/objects/planet/planet.obj"));
-    let mut entity = Entity::from(&planet);
-    entity.transform.set_local_position(Vec3::new(10.0, 0.0, 0.0));
+    let mut scene = Scene::new();
+    let root = scene.add("planet 0", Entity::from(&planet), None).unwrap();
+    scene.transform_mut(root).set_local_position(Vec3::new(10.0, 0.0, 0.0));
     let scale = 0.75;
-    entity.transform.set_local_scale(Vec3::splat(scale));
-
-    {
-        let mut last_entity = &mut entity;
-
-        for _ in 0..10 {
-            let mut child = Entity::from(&planet);
-            child.transform.set_local_position(Vec3::new(10.0, 0.0, 0.0));
-            child.transform.set_local_scale(Vec3::splat(scale));
-            last_entity.add_child(child);
-            last_entity = last_entity.children.last_mut().unwrap();
-        }
+    scene.transform_mut(root).set_local_scale(Vec3::splat(scale));
+
+    let mut last_entity = root;
+    for i in 1..=10 {
+        let child = scene.add(&format!("planet {i}"), Entity::from(&planet), Some(last_entity)).unwrap();
+        scene.transform_mut(child).set_local_position(Vec3::new(10.0, 0.0, 0.0));
+        scene.transform_mut(child).set_local_scale(Vec3::splat(scale));
+        last_entity = child;
     }
-    entity.update_self_and_child();
+    scene.update();
 
     //ogl::polygon_mode();
 
@@ -93,12 +94,11 @@ fn main() {
         shader.set_mat4("projection", projection);
         shader.set_mat4("view", view);
 
-        fix_draw(&shader, &entity, Mat4::IDENTITY);
+        scene.draw(&shader);
 
-        entity
-            .transform
-            .set_local_rotation(Vec3::new(0.0, entity.transform.get_local_rotation().y + 20.0 * delta_time, 0.0));
-        entity.update_self_and_child();
+        let rotation = scene.transform(root).get_local_rotation();
+        scene.transform_mut(root).set_local_rotation(Vec3::new(0.0, rotation.y + 20.0 * delta_time, 0.0));
+        scene.update();
 
         window.swap_buffers();
 
@@ -109,16 +109,6 @@ fn main() {
     }
 }
 
-fn fix_draw(shader: &Program, entity: &Entity, parent: Mat4) {
-    let new_parent = parent * entity.transform.get_model_matrix();
-    shader.set_mat4("model", new_parent);
-    entity.p_model.draw(&shader);
-
-    for child in &entity.children {
-        fix_draw(shader, child, new_parent);
-    }
-}
-
 fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
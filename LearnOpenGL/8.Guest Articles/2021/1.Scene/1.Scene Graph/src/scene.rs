@@ -0,0 +1,358 @@
+pub mod scene {
+    use crate::{Entity, Program, Transform};
+    use glam::Mat4;
+
+    // a slot in the arena and the generation it was filled in, so a removed node's id doesn't reach whatever reuses the slot
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct NodeId {
+        index: usize,
+        generation: u32,
+    }
+
+    pub struct Node {
+        pub name: String,
+        pub transform: Transform,
+        pub entity: Entity,
+        parent: Option<NodeId>,
+        children: Vec<NodeId>,
+    }
+
+    impl Node {
+        pub fn parent(&self) -> Option<NodeId> {
+            self.parent
+        }
+
+        pub fn children(&self) -> &[NodeId] {
+            &self.children
+        }
+    }
+
+    struct Slot {
+        generation: u32,
+        node: Option<Node>,
+    }
+
+    // nodes live in one arena and point at each other by id, parents are read at update time instead of copied
+    #[derive(Default)]
+    pub struct Scene {
+        slots: Vec<Slot>,
+        free: Vec<usize>,
+        roots: Vec<NodeId>,
+    }
+
+    impl Scene {
+        pub fn new() -> Self {
+            Scene::default()
+        }
+
+        // None adds a root, a parent that is gone adds nothing
+        pub fn add(&mut self, name: &str, entity: Entity, parent: Option<NodeId>) -> Option<NodeId> {
+            if parent.is_some_and(|parent| !self.contains(parent)) {
+                return None;
+            }
+
+            let node = Node {
+                name: name.to_string(),
+                transform: Transform::new(),
+                entity,
+                parent,
+                children: vec![],
+            };
+
+            let id = match self.free.pop() {
+                Some(index) => {
+                    let slot = &mut self.slots[index];
+                    slot.node = Some(node);
+                    NodeId { index, generation: slot.generation }
+                }
+                None => {
+                    self.slots.push(Slot { generation: 0, node: Some(node) });
+                    NodeId {
+                        index: self.slots.len() - 1,
+                        generation: 0,
+                    }
+                }
+            };
+
+            self.siblings_mut(parent).push(id);
+            Some(id)
+        }
+
+        // takes the whole subtree with it
+        pub fn remove(&mut self, id: NodeId) -> bool {
+            let parent = match self.get(id) {
+                Some(node) => node.parent,
+                None => return false,
+            };
+            self.siblings_mut(parent).retain(|&sibling| sibling != id);
+
+            let mut stack = vec![id];
+            while let Some(id) = stack.pop() {
+                let slot = &mut self.slots[id.index];
+                if let Some(node) = slot.node.take() {
+                    stack.extend(node.children);
+                    slot.generation += 1;
+                    self.free.push(id.index);
+                }
+            }
+
+            true
+        }
+
+        // keeps the local transform, so the node moves with its new parent; fails on a node below itself
+        pub fn reparent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+            if !self.contains(id) || parent.is_some_and(|parent| !self.contains(parent) || self.is_ancestor(id, parent)) {
+                return false;
+            }
+
+            let previous = self.slots[id.index].node.as_ref().unwrap().parent;
+            self.siblings_mut(previous).retain(|&sibling| sibling != id);
+            self.siblings_mut(parent).push(id);
+
+            let node = self.get_mut(id).unwrap();
+            node.parent = parent;
+            node.transform.mark_dirty();
+            true
+        }
+
+        // whether ancestor is node itself or above it
+        pub fn is_ancestor(&self, ancestor: NodeId, node: NodeId) -> bool {
+            let mut current = Some(node);
+
+            while let Some(id) = current {
+                if id == ancestor {
+                    return true;
+                }
+                current = self.get(id).and_then(Node::parent);
+            }
+
+            false
+        }
+
+        pub fn find(&self, name: &str) -> Option<NodeId> {
+            self.iter().find(|(_, node)| node.name == name).map(|(id, _)| id)
+        }
+
+        pub fn contains(&self, id: NodeId) -> bool {
+            self.get(id).is_some()
+        }
+
+        pub fn get(&self, id: NodeId) -> Option<&Node> {
+            self.slots.get(id.index).filter(|slot| slot.generation == id.generation).and_then(|slot| slot.node.as_ref())
+        }
+
+        pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+            self.slots.get_mut(id.index).filter(|slot| slot.generation == id.generation).and_then(|slot| slot.node.as_mut())
+        }
+
+        // panics on a removed node, like indexing
+        pub fn transform(&self, id: NodeId) -> &Transform {
+            &self.get(id).expect("No such scene node.").transform
+        }
+
+        pub fn transform_mut(&mut self, id: NodeId) -> &mut Transform {
+            &mut self.get_mut(id).expect("No such scene node.").transform
+        }
+
+        pub fn roots(&self) -> &[NodeId] {
+            &self.roots
+        }
+
+        // every live node, in no particular order
+        pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+            self.slots.iter().enumerate().filter_map(|(index, slot)| {
+                slot.node.as_ref().map(|node| {
+                    (
+                        NodeId {
+                            index,
+                            generation: slot.generation,
+                        },
+                        node,
+                    )
+                })
+            })
+        }
+
+        pub fn len(&self) -> usize {
+            self.slots.len() - self.free.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        // recomputes the model matrix of dirty nodes and everything below them, and clears their flag
+        pub fn update(&mut self) {
+            let mut stack: Vec<(NodeId, Mat4, bool)> = self.roots.iter().map(|&root| (root, Mat4::IDENTITY, false)).collect();
+
+            while let Some((id, parent, parent_changed)) = stack.pop() {
+                let node = self.slots[id.index].node.as_mut().unwrap();
+                let changed = parent_changed || node.transform.is_dirty();
+
+                if changed {
+                    node.transform.compute_relative_model_matrix(parent);
+                }
+
+                let model_matrix = node.transform.get_model_matrix();
+                stack.extend(node.children.iter().map(|&child| (child, model_matrix, changed)));
+            }
+        }
+
+        // after update, every node with the model matrix its parents give it
+        pub fn draw(&self, shader: &Program) {
+            for (_, node) in self.iter() {
+                node.entity.draw(&node.transform, shader);
+            }
+        }
+
+        fn siblings_mut(&mut self, parent: Option<NodeId>) -> &mut Vec<NodeId> {
+            match parent {
+                Some(parent) => &mut self.get_mut(parent).unwrap().children,
+                None => &mut self.roots,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::Model;
+        use glam::{EulerRot, Quat, Vec3};
+        use std::{f32::consts::PI, rc::Rc};
+
+        fn local(position: Vec3, rotation: Vec3, scale: Vec3) -> Mat4 {
+            let radians = rotation * PI / 180.0;
+            Mat4::from_scale_rotation_translation(scale, Quat::from_euler(EulerRot::XYZ, radians.x, radians.y, radians.z), position)
+        }
+
+        fn close(a: Mat4, b: Mat4) -> bool {
+            a.abs_diff_eq(b, 1e-4)
+        }
+
+        // root > arm > hand, plus a second root
+        fn scene() -> (Scene, [NodeId; 4]) {
+            let model = Rc::new(Model::new());
+            let mut scene = Scene::new();
+
+            let root = scene.add("root", Entity::from(&model), None).unwrap();
+            let arm = scene.add("arm", Entity::from(&model), Some(root)).unwrap();
+            let hand = scene.add("hand", Entity::from(&model), Some(arm)).unwrap();
+            let other = scene.add("other", Entity::from(&model), None).unwrap();
+
+            let transforms = [
+                (root, Vec3::new(1.0, 2.0, 3.0), Vec3::new(0.0, 90.0, 0.0), Vec3::splat(2.0)),
+                (arm, Vec3::new(0.0, 1.0, 0.0), Vec3::new(30.0, 0.0, 10.0), Vec3::ONE),
+                (hand, Vec3::new(0.5, 0.0, -1.0), Vec3::new(0.0, 0.0, 45.0), Vec3::new(1.0, 0.5, 1.0)),
+                (other, Vec3::new(-4.0, 0.0, 0.0), Vec3::ZERO, Vec3::ONE),
+            ];
+            for (id, position, rotation, scale) in transforms {
+                let transform = scene.transform_mut(id);
+                transform.set_local_position(position);
+                transform.set_local_rotation(rotation);
+                transform.set_local_scale(scale);
+            }
+
+            (scene, [root, arm, hand, other])
+        }
+
+        fn local_of(scene: &Scene, id: NodeId) -> Mat4 {
+            let transform = scene.transform(id);
+            local(transform.get_local_position(), transform.get_local_rotation(), transform.get_local_scale())
+        }
+
+        #[test]
+        fn world_is_parent_times_local() {
+            let (mut scene, [root, arm, hand, other]) = scene();
+            scene.update();
+
+            let root_world = local_of(&scene, root);
+            let arm_world = root_world * local_of(&scene, arm);
+            assert!(close(scene.transform(root).get_model_matrix(), root_world));
+            assert!(close(scene.transform(arm).get_model_matrix(), arm_world));
+            assert!(close(scene.transform(hand).get_model_matrix(), arm_world * local_of(&scene, hand)));
+            assert!(close(scene.transform(other).get_model_matrix(), local_of(&scene, other)));
+        }
+
+        #[test]
+        fn update_clears_dirty_flags_and_skips_clean_subtrees() {
+            let (mut scene, [root, arm, hand, other]) = scene();
+            scene.update();
+            assert!(scene.iter().all(|(_, node)| !node.transform.is_dirty()));
+
+            // planted matrices survive an update that has nothing to do
+            let planted = Mat4::from_translation(Vec3::splat(100.0));
+            scene.transform_mut(hand).compute_relative_model_matrix(planted);
+            scene.transform_mut(other).compute_relative_model_matrix(planted);
+            scene.update();
+            assert_eq!(scene.transform(hand).get_model_matrix(), planted * local_of(&scene, hand));
+
+            // moving the arm redoes the hand below it but not the other root
+            scene.transform_mut(arm).set_local_position(Vec3::new(0.0, 3.0, 0.0));
+            scene.update();
+            let arm_world = local_of(&scene, root) * local_of(&scene, arm);
+            assert!(close(scene.transform(hand).get_model_matrix(), arm_world * local_of(&scene, hand)));
+            assert_eq!(scene.transform(other).get_model_matrix(), planted * local_of(&scene, other));
+            assert!(scene.iter().all(|(_, node)| !node.transform.is_dirty()));
+        }
+
+        #[test]
+        fn reparent_keeps_the_hierarchy_consistent() {
+            let (mut scene, [root, arm, hand, other]) = scene();
+            scene.update();
+
+            // a node can't go below itself
+            assert!(!scene.reparent(root, Some(hand)));
+            assert!(!scene.reparent(arm, Some(arm)));
+
+            assert!(scene.reparent(arm, Some(other)));
+            assert_eq!(scene.get(arm).unwrap().parent(), Some(other));
+            assert!(scene.get(root).unwrap().children().is_empty());
+            assert_eq!(scene.get(other).unwrap().children(), &[arm]);
+            assert!(scene.is_ancestor(other, hand));
+            assert!(!scene.is_ancestor(root, hand));
+
+            scene.update();
+            let arm_world = local_of(&scene, other) * local_of(&scene, arm);
+            assert!(close(scene.transform(hand).get_model_matrix(), arm_world * local_of(&scene, hand)));
+
+            assert!(scene.reparent(hand, None));
+            assert!(scene.roots().contains(&hand));
+            assert!(scene.get(arm).unwrap().children().is_empty());
+            scene.update();
+            assert!(close(scene.transform(hand).get_model_matrix(), local_of(&scene, hand)));
+        }
+
+        #[test]
+        fn remove_drops_the_subtree() {
+            let (mut scene, [root, arm, hand, other]) = scene();
+
+            assert!(scene.remove(arm));
+            assert_eq!(scene.len(), 2);
+            assert!(!scene.contains(arm) && !scene.contains(hand));
+            assert!(scene.get(root).unwrap().children().is_empty());
+            assert!(!scene.remove(hand));
+
+            // reused slots don't bring the old ids back
+            let model = Rc::new(Model::new());
+            let new = scene.add("new", Entity::from(&model), Some(other)).unwrap();
+            assert!(!scene.contains(arm) && !scene.contains(hand));
+            assert!(scene.add("orphan", Entity::from(&model), Some(hand)).is_none());
+            assert_eq!(scene.get(other).unwrap().children(), &[new]);
+
+            assert!(scene.remove(root));
+            assert_eq!(scene.roots(), &[other]);
+            scene.update();
+        }
+
+        #[test]
+        fn find_by_name() {
+            let (mut scene, [_, arm, hand, _]) = scene();
+
+            assert_eq!(scene.find("hand"), Some(hand));
+            assert_eq!(scene.find("missing"), None);
+
+            scene.remove(arm);
+            assert_eq!(scene.find("hand"), None);
+        }
+    }
+}
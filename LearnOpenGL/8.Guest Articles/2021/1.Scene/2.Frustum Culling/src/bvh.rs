@@ -0,0 +1,804 @@
+pub mod bvh {
+    use crate::{Frustum, AABB};
+    use glam::Vec3;
+
+    // how much a leaf's box is grown past its entity's, so small moves don't touch the tree
+    const DEFAULT_MARGIN: f32 = 0.5;
+    // buckets the centroids are sorted into when looking for the cheapest split
+    const SAH_BINS: usize = 12;
+
+    // a world space box in corner form, cheaper to merge than center and extents
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct BoundingBox {
+        pub min: Vec3,
+        pub max: Vec3,
+    }
+
+    impl BoundingBox {
+        pub const EMPTY: BoundingBox = BoundingBox {
+            min: Vec3::splat(f32::MAX),
+            max: Vec3::splat(f32::MIN),
+        };
+
+        pub fn new(min: Vec3, max: Vec3) -> Self {
+            BoundingBox { min, max }
+        }
+
+        pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+            BoundingBox::new(self.min.min(other.min), self.max.max(other.max))
+        }
+
+        pub fn grow(&self, margin: f32) -> BoundingBox {
+            BoundingBox::new(self.min - Vec3::splat(margin), self.max + Vec3::splat(margin))
+        }
+
+        pub fn center(&self) -> Vec3 {
+            (self.min + self.max) * 0.5
+        }
+
+        pub fn extents(&self) -> Vec3 {
+            (self.max - self.min) * 0.5
+        }
+
+        // half the real surface area, only ever compared
+        pub fn area(&self) -> f32 {
+            let size = (self.max - self.min).max(Vec3::ZERO);
+            size.x * size.y + size.y * size.z + size.z * size.x
+        }
+
+        pub fn contains(&self, other: &BoundingBox) -> bool {
+            self.min.cmple(other.min).all() && self.max.cmpge(other.max).all()
+        }
+
+        // 0 inside the box
+        pub fn distance_to(&self, point: Vec3) -> f32 {
+            (self.min - point).max(point - self.max).max(Vec3::ZERO).length()
+        }
+
+        // where the ray enters, or its origin when it starts inside
+        pub fn ray_entry(&self, ray: &Ray, max_distance: f32) -> Option<f32> {
+            let t1 = (self.min - ray.origin) * ray.inverse_direction;
+            let t2 = (self.max - ray.origin) * ray.inverse_direction;
+
+            let near = t1.min(t2).max_element().max(0.0);
+            let far = t1.max(t2).min_element().min(max_distance);
+
+            (near <= far).then_some(near)
+        }
+
+        pub fn classify(&self, frustum: &Frustum) -> Visibility {
+            let (center, extents) = (self.center(), self.extents());
+            let mut visibility = Visibility::Inside;
+
+            for plan in frustum.planes() {
+                let normal = plan.get_normal();
+                let r = extents.dot(normal.abs());
+                let distance = plan.get_signed_distance_to_plan(center);
+
+                if distance < -r {
+                    return Visibility::Outside;
+                }
+                if distance < r {
+                    visibility = Visibility::Intersecting;
+                }
+            }
+
+            visibility
+        }
+    }
+
+    impl From<&AABB> for BoundingBox {
+        fn from(aabb: &AABB) -> Self {
+            BoundingBox::new(aabb.get_min(), aabb.get_max())
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Visibility {
+        Outside,
+        Intersecting,
+        Inside,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Ray {
+        pub origin: Vec3,
+        // unit length, hit distances are along it
+        pub direction: Vec3,
+        inverse_direction: Vec3,
+    }
+
+    impl Ray {
+        pub fn new(origin: Vec3, direction: Vec3) -> Self {
+            let direction = direction.normalize();
+            Ray {
+                origin,
+                direction,
+                inverse_direction: direction.recip(),
+            }
+        }
+
+        pub fn at(&self, distance: f32) -> Vec3 {
+            self.origin + self.direction * distance
+        }
+    }
+
+    // stays valid for the leaf's whole life, rebuilds included
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct ProxyId(usize);
+
+    enum Kind<T> {
+        Leaf(T),
+        Branch([usize; 2]),
+        Free,
+    }
+
+    struct BvhNode<T> {
+        // fattened for leaves, the union of the children for branches
+        bounds: BoundingBox,
+        parent: Option<usize>,
+        kind: Kind<T>,
+    }
+
+    // one item per leaf, inserted where it grows the surface area the least and rebuilt with the surface area heuristic once enough has moved
+    pub struct Bvh<T> {
+        nodes: Vec<BvhNode<T>>,
+        free: Vec<usize>,
+        root: Option<usize>,
+        margin: f32,
+        leaves: usize,
+        // leaves inserted or moved since the last rebuild
+        churn: usize,
+    }
+
+    impl<T: Copy> Default for Bvh<T> {
+        fn default() -> Self {
+            Bvh::new(DEFAULT_MARGIN)
+        }
+    }
+
+    impl<T: Copy> Bvh<T> {
+        pub fn new(margin: f32) -> Self {
+            Bvh {
+                nodes: vec![],
+                free: vec![],
+                root: None,
+                margin,
+                leaves: 0,
+                churn: 0,
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.leaves
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.leaves == 0
+        }
+
+        pub fn clear(&mut self) {
+            *self = Bvh::new(self.margin);
+        }
+
+        pub fn get(&self, proxy: ProxyId) -> Option<T> {
+            match self.nodes.get(proxy.0).map(|node| &node.kind) {
+                Some(Kind::Leaf(item)) => Some(*item),
+                _ => None,
+            }
+        }
+
+        pub fn bounds(&self, proxy: ProxyId) -> BoundingBox {
+            self.nodes[proxy.0].bounds
+        }
+
+        pub fn insert(&mut self, item: T, bounds: BoundingBox) -> ProxyId {
+            let leaf = self.allocate(BvhNode {
+                bounds: bounds.grow(self.margin),
+                parent: None,
+                kind: Kind::Leaf(item),
+            });
+
+            self.insert_leaf(leaf);
+            self.leaves += 1;
+            self.churn += 1;
+            ProxyId(leaf)
+        }
+
+        pub fn remove(&mut self, proxy: ProxyId) -> Option<T> {
+            let item = self.get(proxy)?;
+
+            self.remove_leaf(proxy.0);
+            self.release(proxy.0);
+            self.leaves -= 1;
+            Some(item)
+        }
+
+        // returns true when the box left the fattened one and the leaf was moved
+        pub fn update(&mut self, proxy: ProxyId, bounds: BoundingBox) -> bool {
+            if self.nodes[proxy.0].bounds.contains(&bounds) {
+                return false;
+            }
+
+            self.remove_leaf(proxy.0);
+            self.nodes[proxy.0].bounds = bounds.grow(self.margin);
+            self.insert_leaf(proxy.0);
+            self.churn += 1;
+            true
+        }
+
+        // once more than half the leaves were placed incrementally
+        pub fn needs_rebuild(&self) -> bool {
+            self.churn > self.leaves / 2
+        }
+
+        // top down over the leaves' centroids, leaves keep their slots so proxies survive
+        pub fn rebuild(&mut self) {
+            let mut leaves = vec![];
+
+            for index in 0..self.nodes.len() {
+                match self.nodes[index].kind {
+                    Kind::Leaf(_) => leaves.push(index),
+                    Kind::Branch(_) => self.release(index),
+                    Kind::Free => {}
+                }
+            }
+
+            self.root = if leaves.is_empty() { None } else { Some(self.build(&mut leaves)) };
+            if let Some(root) = self.root {
+                self.nodes[root].parent = None;
+            }
+            self.churn = 0;
+        }
+
+        // the total area of the branches over the root's, lower is a better tree
+        pub fn cost(&self) -> f32 {
+            let root = match self.root {
+                Some(root) => self.nodes[root].bounds.area(),
+                None => return 0.0,
+            };
+
+            let branches: f32 = self.nodes.iter().filter(|node| matches!(node.kind, Kind::Branch(_))).map(|node| node.bounds.area()).sum();
+            branches / root.max(f32::EPSILON)
+        }
+
+        // whole subtrees inside the frustum are taken without testing their leaves, returns how many nodes were tested
+        pub fn cull(&self, frustum: &Frustum, mut visit: impl FnMut(T, Visibility)) -> usize {
+            let mut tested = 0;
+            let mut stack: Vec<(usize, bool)> = self.root.map(|root| (root, false)).into_iter().collect();
+
+            while let Some((index, inside)) = stack.pop() {
+                let node = &self.nodes[index];
+
+                let visibility = if inside {
+                    Visibility::Inside
+                } else {
+                    tested += 1;
+                    node.bounds.classify(frustum)
+                };
+
+                if visibility == Visibility::Outside {
+                    continue;
+                }
+
+                match node.kind {
+                    Kind::Leaf(item) => visit(item, visibility),
+                    Kind::Branch(children) => stack.extend(children.map(|child| (child, visibility == Visibility::Inside))),
+                    Kind::Free => {}
+                }
+            }
+
+            tested
+        }
+
+        // the nearest leaf along the ray; hit gets the distance the fattened box is entered at and returns the exact one, or None for a miss
+        pub fn ray_cast(&self, ray: &Ray, max_distance: f32, mut hit: impl FnMut(T, f32) -> Option<f32>) -> Option<(T, f32)> {
+            let mut best: Option<(T, f32)> = None;
+            let mut stack: Vec<(usize, f32)> = vec![];
+
+            if let Some(root) = self.root {
+                if let Some(entry) = self.nodes[root].bounds.ray_entry(ray, max_distance) {
+                    stack.push((root, entry));
+                }
+            }
+
+            while let Some((index, entry)) = stack.pop() {
+                let limit = best.map_or(max_distance, |(_, distance)| distance);
+                if entry > limit {
+                    continue;
+                }
+
+                match self.nodes[index].kind {
+                    Kind::Leaf(item) => {
+                        if let Some(distance) = hit(item, entry).filter(|&distance| distance <= limit) {
+                            best = Some((item, distance));
+                        }
+                    }
+                    Kind::Branch(children) => {
+                        let mut entries: Vec<(usize, f32)> = children
+                            .iter()
+                            .filter_map(|&child| self.nodes[child].bounds.ray_entry(ray, limit).map(|entry| (child, entry)))
+                            .collect();
+
+                        // the nearer child is popped first
+                        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+                        stack.extend(entries);
+                    }
+                    Kind::Free => {}
+                }
+            }
+
+            best
+        }
+
+        pub fn segment_cast(&self, start: Vec3, end: Vec3, hit: impl FnMut(T, f32) -> Option<f32>) -> Option<(T, f32)> {
+            let length = start.distance(end);
+            // a point, whatever it is inside of
+            if length <= f32::EPSILON {
+                return self.sphere_query(start, 0.0, hit);
+            }
+
+            self.ray_cast(&Ray::new(start, end - start), length, hit)
+        }
+
+        // the leaf nearest to center among those overlapping the sphere; hit gets the distance to the fattened box and returns the exact one
+        pub fn sphere_query(&self, center: Vec3, radius: f32, mut hit: impl FnMut(T, f32) -> Option<f32>) -> Option<(T, f32)> {
+            let mut best: Option<(T, f32)> = None;
+            let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+            while let Some(index) = stack.pop() {
+                let node = &self.nodes[index];
+                let distance = node.bounds.distance_to(center);
+                let limit = best.map_or(radius, |(_, best)| best);
+
+                if distance > limit {
+                    continue;
+                }
+
+                match node.kind {
+                    Kind::Leaf(item) => {
+                        if let Some(distance) = hit(item, distance).filter(|&distance| distance <= limit) {
+                            best = Some((item, distance));
+                        }
+                    }
+                    Kind::Branch(children) => stack.extend(children),
+                    Kind::Free => {}
+                }
+            }
+
+            best
+        }
+
+        // every leaf whose fattened box overlaps the sphere
+        pub fn overlap_sphere(&self, center: Vec3, radius: f32, found: &mut Vec<T>) {
+            let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+            while let Some(index) = stack.pop() {
+                let node = &self.nodes[index];
+                if node.bounds.distance_to(center) > radius {
+                    continue;
+                }
+
+                match node.kind {
+                    Kind::Leaf(item) => found.push(item),
+                    Kind::Branch(children) => stack.extend(children),
+                    Kind::Free => {}
+                }
+            }
+        }
+
+        fn allocate(&mut self, node: BvhNode<T>) -> usize {
+            match self.free.pop() {
+                Some(index) => {
+                    self.nodes[index] = node;
+                    index
+                }
+                None => {
+                    self.nodes.push(node);
+                    self.nodes.len() - 1
+                }
+            }
+        }
+
+        fn release(&mut self, index: usize) {
+            self.nodes[index].kind = Kind::Free;
+            self.nodes[index].parent = None;
+            self.free.push(index);
+        }
+
+        fn children(&self, index: usize) -> [usize; 2] {
+            match self.nodes[index].kind {
+                Kind::Branch(children) => children,
+                _ => unreachable!("Not a branch."),
+            }
+        }
+
+        // walks down to the sibling whose box grows the least, counting what every ancestor grows by too
+        fn insert_leaf(&mut self, leaf: usize) {
+            let root = match self.root {
+                Some(root) => root,
+                None => {
+                    self.root = Some(leaf);
+                    self.nodes[leaf].parent = None;
+                    return;
+                }
+            };
+
+            let bounds = self.nodes[leaf].bounds;
+            let mut sibling = root;
+
+            while let Kind::Branch(children) = self.nodes[sibling].kind {
+                let area = self.nodes[sibling].bounds.area();
+                let combined = self.nodes[sibling].bounds.union(&bounds).area();
+
+                // a new parent here, or the extra area every level below pays
+                let cost = 2.0 * combined;
+                let inheritance = 2.0 * (combined - area);
+
+                let descend = |child: usize| {
+                    let node = &self.nodes[child];
+                    let grown = node.bounds.union(&bounds).area();
+                    match node.kind {
+                        Kind::Leaf(_) => grown + inheritance,
+                        _ => grown - node.bounds.area() + inheritance,
+                    }
+                };
+
+                let (left, right) = (descend(children[0]), descend(children[1]));
+                if cost < left && cost < right {
+                    break;
+                }
+                sibling = if left < right { children[0] } else { children[1] };
+            }
+
+            let old_parent = self.nodes[sibling].parent;
+            let parent = self.allocate(BvhNode {
+                bounds: self.nodes[sibling].bounds.union(&bounds),
+                parent: old_parent,
+                kind: Kind::Branch([sibling, leaf]),
+            });
+
+            match old_parent {
+                Some(old_parent) => {
+                    let mut children = self.children(old_parent);
+                    children[if children[0] == sibling { 0 } else { 1 }] = parent;
+                    self.nodes[old_parent].kind = Kind::Branch(children);
+                }
+                None => self.root = Some(parent),
+            }
+
+            self.nodes[sibling].parent = Some(parent);
+            self.nodes[leaf].parent = Some(parent);
+            self.refit(old_parent);
+        }
+
+        // the sibling takes the parent's place
+        fn remove_leaf(&mut self, leaf: usize) {
+            let parent = match self.nodes[leaf].parent {
+                Some(parent) => parent,
+                None => {
+                    self.root = None;
+                    return;
+                }
+            };
+
+            let children = self.children(parent);
+            let sibling = if children[0] == leaf { children[1] } else { children[0] };
+            let grandparent = self.nodes[parent].parent;
+
+            match grandparent {
+                Some(grandparent) => {
+                    let mut children = self.children(grandparent);
+                    children[if children[0] == parent { 0 } else { 1 }] = sibling;
+                    self.nodes[grandparent].kind = Kind::Branch(children);
+                }
+                None => self.root = Some(sibling),
+            }
+
+            self.nodes[sibling].parent = grandparent;
+            self.nodes[leaf].parent = None;
+            self.release(parent);
+            self.refit(grandparent);
+        }
+
+        fn refit(&mut self, mut current: Option<usize>) {
+            while let Some(index) = current {
+                let [left, right] = self.children(index);
+                self.nodes[index].bounds = self.nodes[left].bounds.union(&self.nodes[right].bounds);
+                current = self.nodes[index].parent;
+            }
+        }
+
+        fn build(&mut self, leaves: &mut [usize]) -> usize {
+            if leaves.len() == 1 {
+                return leaves[0];
+            }
+
+            let split = self.split(leaves);
+            let (left, right) = leaves.split_at_mut(split);
+            let children = [self.build(left), self.build(right)];
+
+            let bounds = self.nodes[children[0]].bounds.union(&self.nodes[children[1]].bounds);
+            let branch = self.allocate(BvhNode {
+                bounds,
+                parent: None,
+                kind: Kind::Branch(children),
+            });
+
+            for child in children {
+                self.nodes[child].parent = Some(branch);
+            }
+            branch
+        }
+
+        // sorts the leaves around the cheapest binned split and returns where it is, the middle when the centroids all coincide
+        fn split(&self, leaves: &mut [usize]) -> usize {
+            let centroid = |leaf: usize| self.nodes[leaf].bounds.center();
+            let centroids = leaves.iter().fold(BoundingBox::EMPTY, |bounds, &leaf| bounds.union(&BoundingBox::new(centroid(leaf), centroid(leaf))));
+            let size = centroids.max - centroids.min;
+
+            let mut best: Option<(f32, usize, f32)> = None;
+
+            for axis in 0..3 {
+                if size[axis] <= f32::EPSILON {
+                    continue;
+                }
+
+                let bin_of = |leaf: usize| (((centroid(leaf)[axis] - centroids.min[axis]) / size[axis] * SAH_BINS as f32) as usize).min(SAH_BINS - 1);
+
+                let mut bins = [(BoundingBox::EMPTY, 0usize); SAH_BINS];
+                for &leaf in leaves.iter() {
+                    let bin = &mut bins[bin_of(leaf)];
+                    bin.0 = bin.0.union(&self.nodes[leaf].bounds);
+                    bin.1 += 1;
+                }
+
+                // areas and counts left of each boundary, then right of it
+                let mut left = [(0.0, 0usize); SAH_BINS - 1];
+                let mut accumulated = (BoundingBox::EMPTY, 0);
+                for i in 0..SAH_BINS - 1 {
+                    accumulated = (accumulated.0.union(&bins[i].0), accumulated.1 + bins[i].1);
+                    left[i] = (accumulated.0.area(), accumulated.1);
+                }
+
+                let mut accumulated = (BoundingBox::EMPTY, 0);
+                for i in (1..SAH_BINS).rev() {
+                    accumulated = (accumulated.0.union(&bins[i].0), accumulated.1 + bins[i].1);
+                    let (left_area, left_count) = left[i - 1];
+
+                    if left_count == 0 || accumulated.1 == 0 {
+                        continue;
+                    }
+
+                    let cost = left_area * left_count as f32 + accumulated.0.area() * accumulated.1 as f32;
+                    if best.is_none_or(|(best, _, _)| cost < best) {
+                        best = Some((cost, axis, centroids.min[axis] + size[axis] * i as f32 / SAH_BINS as f32));
+                    }
+                }
+            }
+
+            let (axis, position) = match best {
+                Some((_, axis, position)) => (axis, position),
+                None => return leaves.len() / 2,
+            };
+
+            let mut split = 0;
+            for i in 0..leaves.len() {
+                if centroid(leaves[i])[axis] < position {
+                    leaves.swap(i, split);
+                    split += 1;
+                }
+            }
+
+            if split == 0 || split == leaves.len() {
+                leaves.len() / 2
+            } else {
+                split
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::ClipDepth;
+        use glam::Mat4;
+        use std::collections::HashSet;
+
+        // xorshift, so every run builds the same trees
+        struct Random(u32);
+
+        impl Random {
+            fn next(&mut self) -> f32 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 17;
+                self.0 ^= self.0 << 5;
+                self.0 as f32 / u32::MAX as f32
+            }
+
+            fn range(&mut self, min: f32, max: f32) -> f32 {
+                min + (max - min) * self.next()
+            }
+
+            fn vec3(&mut self, min: f32, max: f32) -> Vec3 {
+                Vec3::new(self.range(min, max), self.range(min, max), self.range(min, max))
+            }
+
+            fn bounds(&mut self) -> BoundingBox {
+                let center = self.vec3(-50.0, 50.0);
+                let extents = self.vec3(0.1, 2.0);
+                BoundingBox::new(center - extents, center + extents)
+            }
+        }
+
+        fn frustums() -> Vec<Frustum> {
+            let eyes = [(Vec3::new(0.0, 5.0, -60.0), Vec3::ZERO), (Vec3::new(10.0, 0.0, 0.0), Vec3::new(40.0, 10.0, -20.0)), (Vec3::new(-30.0, 40.0, 30.0), Vec3::new(-20.0, 0.0, 20.0))];
+
+            eyes.iter()
+                .map(|&(eye, target)| {
+                    let view_projection = Mat4::perspective_lh(0.8, 16.0 / 9.0, 0.1, 60.0) * Mat4::look_at_lh(eye, target, Vec3::Y);
+                    Frustum::from_matrix(view_projection, ClipDepth::ZeroToOne)
+                })
+                .collect()
+        }
+
+        // parent links both ways, branch boxes around their children, every live leaf reached once from the root
+        fn check(bvh: &Bvh<usize>) {
+            let mut leaves = 0;
+            let mut stack: Vec<usize> = bvh.root.into_iter().collect();
+
+            if let Some(root) = bvh.root {
+                assert_eq!(bvh.nodes[root].parent, None);
+            }
+
+            while let Some(index) = stack.pop() {
+                match bvh.nodes[index].kind {
+                    Kind::Leaf(_) => leaves += 1,
+                    Kind::Branch(children) => {
+                        for child in children {
+                            assert_eq!(bvh.nodes[child].parent, Some(index));
+                            assert!(bvh.nodes[index].bounds.contains(&bvh.nodes[child].bounds));
+                        }
+                        stack.extend(children);
+                    }
+                    Kind::Free => panic!("a free node is still in the tree"),
+                }
+            }
+
+            assert_eq!(leaves, bvh.len());
+        }
+
+        // the leaves the tree returns are exactly those whose fattened box a linear scan would keep, and never miss an exact box
+        fn check_cull(bvh: &Bvh<usize>, items: &[(ProxyId, BoundingBox)]) {
+            for frustum in frustums() {
+                let mut found = HashSet::new();
+                bvh.cull(&frustum, |item, _| assert!(found.insert(item)));
+
+                let fattened: HashSet<usize> = items
+                    .iter()
+                    .filter(|(proxy, _)| bvh.bounds(*proxy).classify(&frustum) != Visibility::Outside)
+                    .map(|(proxy, _)| bvh.get(*proxy).unwrap())
+                    .collect();
+                assert_eq!(found, fattened);
+
+                for (proxy, bounds) in items {
+                    if bounds.classify(&frustum) != Visibility::Outside {
+                        assert!(found.contains(&bvh.get(*proxy).unwrap()));
+                    }
+                }
+            }
+        }
+
+        fn filled(random: &mut Random, count: usize) -> (Bvh<usize>, Vec<(ProxyId, BoundingBox)>) {
+            let mut bvh = Bvh::default();
+            let items = (0..count)
+                .map(|item| {
+                    let bounds = random.bounds();
+                    (bvh.insert(item, bounds), bounds)
+                })
+                .collect();
+            (bvh, items)
+        }
+
+        #[test]
+        fn build_keeps_every_leaf_and_proxy() {
+            let mut random = Random(3);
+            let (mut bvh, items) = filled(&mut random, 300);
+            check(&bvh);
+            check_cull(&bvh, &items);
+
+            let incremental = bvh.cost();
+            assert!(bvh.needs_rebuild());
+            bvh.rebuild();
+            assert!(!bvh.needs_rebuild());
+            check(&bvh);
+            check_cull(&bvh, &items);
+
+            for (item, (proxy, bounds)) in items.iter().enumerate() {
+                assert_eq!(bvh.get(*proxy), Some(item));
+                assert!(bvh.bounds(*proxy).contains(bounds));
+            }
+            assert!(bvh.cost() <= incremental * 1.5, "rebuilt {} against incremental {incremental}", bvh.cost());
+        }
+
+        #[test]
+        fn refit_after_a_move() {
+            let mut random = Random(5);
+            let (mut bvh, mut items) = filled(&mut random, 200);
+            bvh.rebuild();
+
+            // inside the margin nothing changes, past it the leaf is moved and the branches above it grow to fit
+            let (proxy, bounds) = items[0];
+            let nudged = BoundingBox::new(bounds.min + Vec3::splat(DEFAULT_MARGIN * 0.5), bounds.max + Vec3::splat(DEFAULT_MARGIN * 0.5));
+            assert!(!bvh.update(proxy, nudged));
+            items[0].1 = nudged;
+
+            for (proxy, bounds) in items.iter_mut() {
+                let offset = random.vec3(-20.0, 20.0);
+                let moved = BoundingBox::new(bounds.min + offset, bounds.max + offset);
+                assert!(bvh.update(*proxy, moved));
+                assert!(bvh.bounds(*proxy).contains(&moved));
+                *bounds = moved;
+            }
+
+            check(&bvh);
+            check_cull(&bvh, &items);
+            assert!(bvh.needs_rebuild());
+
+            bvh.rebuild();
+            check(&bvh);
+            check_cull(&bvh, &items);
+        }
+
+        #[test]
+        fn insert_and_remove() {
+            let mut random = Random(9);
+            let (mut bvh, mut items) = filled(&mut random, 150);
+
+            let removed: Vec<(ProxyId, BoundingBox)> = items.iter().copied().enumerate().filter(|(i, _)| i % 3 != 0).map(|(_, item)| item).collect();
+            items.retain(|item| !removed.contains(item));
+
+            for (proxy, _) in &removed {
+                assert!(bvh.remove(*proxy).is_some());
+                assert_eq!(bvh.get(*proxy), None);
+                assert_eq!(bvh.remove(*proxy), None);
+            }
+            assert_eq!(bvh.len(), items.len());
+            check(&bvh);
+            check_cull(&bvh, &items);
+
+            // freed slots are reused without disturbing the proxies still held
+            for item in 1000..1100 {
+                let bounds = random.bounds();
+                let proxy = bvh.insert(item, bounds);
+                items.push((proxy, bounds));
+            }
+            assert_eq!(bvh.len(), items.len());
+            check(&bvh);
+            check_cull(&bvh, &items);
+
+            for (proxy, _) in items.drain(..) {
+                bvh.remove(proxy).unwrap();
+            }
+            assert!(bvh.is_empty() && bvh.root.is_none());
+            assert_eq!(bvh.cull(&frustums()[0], |_, _| panic!("the tree is empty")), 0);
+        }
+
+        #[test]
+        fn ray_cast_finds_the_nearest_box() {
+            let mut random = Random(13);
+            let (mut bvh, items) = filled(&mut random, 300);
+            bvh.rebuild();
+
+            let exact: Vec<BoundingBox> = items.iter().map(|(_, bounds)| *bounds).collect();
+
+            for _ in 0..200 {
+                let ray = Ray::new(random.vec3(-60.0, 60.0), random.vec3(-1.0, 1.0));
+                let expected = exact.iter().enumerate().filter_map(|(item, bounds)| bounds.ray_entry(&ray, 100.0).map(|distance| (item, distance))).min_by(|a, b| a.1.total_cmp(&b.1));
+
+                let found = bvh.ray_cast(&ray, 100.0, |item, _| exact[item].ray_entry(&ray, 100.0));
+                assert_eq!(found.map(|(_, distance)| distance), expected.map(|(_, distance)| distance));
+            }
+        }
+    }
+}
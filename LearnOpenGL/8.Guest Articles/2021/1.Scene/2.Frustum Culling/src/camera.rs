@@ -164,5 +164,30 @@ pub mod camera {
         pub fn zoom(&self) -> f32 {
             self.zoom
         }
+
+        pub fn yaw(&self) -> f32 {
+            self.yaw
+        }
+
+        pub fn pitch(&self) -> f32 {
+            self.pitch
+        }
+
+        pub fn set_view(&mut self, position: Vec3, yaw: f32, pitch: f32) {
+            self.position = position;
+            self.yaw = yaw;
+            self.pitch = pitch.min(PI / 2.0 - BIAS).max(-PI / 2.0 + BIAS);
+            self.calculate_vectors();
+        }
+
+        // where the next process_mouse measures from, after the cursor was let go and taken back
+        pub fn set_cursor(&mut self, x_pos: f32, y_pos: f32) {
+            self.last_x = x_pos;
+            self.last_y = y_pos;
+        }
+
+        pub fn set_zoom(&mut self, zoom: f32) {
+            self.zoom = zoom.max(PI / 180.0).min(PI / 4.0);
+        }
     }
 }
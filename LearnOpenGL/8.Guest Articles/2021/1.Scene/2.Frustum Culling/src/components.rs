@@ -0,0 +1,222 @@
+pub mod components {
+    use crate::{
+        generate_aabb, generate_obb, generate_sphere, positions, BoundingBox, BoundingVolume, Camera, ClipDepth, EntityId, Frustum, Json, LodSettings, Model, Program,
+        ProxyId, Sphere, Transform, AABB, OBB,
+    };
+    use glam::{Mat4, Vec3};
+    use std::rc::Rc;
+
+    // what the entity is called in the scene file and in messages, not unique
+    pub struct Name(pub String);
+
+    // where the entity hangs in the scene graph, children are kept in the order they were added
+    #[derive(Default)]
+    pub struct Hierarchy {
+        parent: Option<EntityId>,
+        children: Vec<EntityId>,
+    }
+
+    impl Hierarchy {
+        pub fn new(parent: Option<EntityId>) -> Self {
+            Hierarchy { parent, children: vec![] }
+        }
+
+        pub fn parent(&self) -> Option<EntityId> {
+            self.parent
+        }
+
+        pub fn children(&self) -> &[EntityId] {
+            &self.children
+        }
+
+        // only the link on this side, the scene keeps both sides matching
+        pub fn set_parent(&mut self, parent: Option<EntityId>) {
+            self.parent = parent;
+        }
+
+        pub fn add_child(&mut self, child: EntityId) {
+            self.children.push(child);
+        }
+
+        pub fn remove_child(&mut self, child: EntityId) {
+            self.children.retain(|&id| id != child);
+        }
+    }
+
+    // draws a model with the entity's transform, picking its level of detail from the entity's bounds
+    pub struct MeshRenderer {
+        pub model: Rc<Model>,
+        // a name from the scene file's materials table
+        pub material: Option<String>,
+    }
+
+    impl MeshRenderer {
+        pub fn new(model: &Rc<Model>) -> Self {
+            MeshRenderer {
+                model: Rc::clone(model),
+                material: None,
+            }
+        }
+
+        // for what is already known to be in view
+        pub fn draw(&self, transform: &Transform, bounds: &Bounds, view: &RenderView, shader: &Program) {
+            shader.set_mat4("model", transform.get_model_matrix());
+
+            match self.model.lod_settings() {
+                Some(settings) => {
+                    let (level, blend) = select_lod(bounds.world(), view, settings);
+
+                    if blend > 0.0 {
+                        self.model.draw_lod(shader, level, blend);
+                        self.model.draw_lod(shader, level + 1, -blend);
+                    } else {
+                        self.model.draw_lod(shader, level, 0.0);
+                    }
+                }
+                None => self.model.draw(shader),
+            }
+        }
+    }
+
+    // the level for the bounding sphere's height on screen, and how far it has faded into the next one
+    fn select_lod(bounds: BoundingBox, view: &RenderView, settings: &LodSettings) -> (usize, f32) {
+        let radius = bounds.extents().length();
+        let distance = (bounds.center() - view.eye).length();
+
+        if distance <= radius {
+            return (0, 0.0);
+        }
+        let screen_size = radius / (distance * f32::tan(view.fov * 0.5));
+
+        let mut level = 0;
+        for (i, &threshold) in settings.screen_sizes.iter().enumerate() {
+            let half_range = threshold * settings.fade_range * 0.5;
+
+            if screen_size >= threshold + half_range {
+                break;
+            }
+            if screen_size > threshold - half_range {
+                return (i, (threshold + half_range - screen_size) / (2.0 * half_range));
+            }
+            level = i + 1;
+        }
+
+        (level, 0.0)
+    }
+
+    // the model space volumes of what the entity draws, and the world box they make under its transform
+    pub struct Bounds {
+        aabb: AABB,
+        // the sphere rejects most of what is out of view cheaply, the box decides the rest
+        sphere: Sphere,
+        obb: OBB,
+        world: BoundingBox,
+        // the model matrix world was computed with, None until the first update
+        matrix: Option<Mat4>,
+        // its leaf in the scene's bvh, kept by bounds_system
+        pub proxy: Option<ProxyId>,
+    }
+
+    impl From<&Model> for Bounds {
+        fn from(model: &Model) -> Self {
+            let points = positions(model);
+            let aabb = generate_aabb(model);
+
+            Bounds {
+                aabb,
+                sphere: generate_sphere(&points),
+                obb: generate_obb(&points),
+                world: BoundingBox::from(&aabb),
+                matrix: None,
+                proxy: None,
+            }
+        }
+    }
+
+    impl Bounds {
+        // as of the last follow
+        pub fn world(&self) -> BoundingBox {
+            self.world
+        }
+
+        // the oriented box in world space, the one is_visible tests
+        pub fn world_obb(&self, transform: &Transform) -> OBB {
+            self.obb.transformed(transform.get_model_matrix())
+        }
+
+        pub fn is_visible(&self, transform: &Transform, frustum: &Frustum) -> bool {
+            self.sphere.is_on_frustum_transform(frustum, transform) && self.obb.is_on_frustum_transform(frustum, transform)
+        }
+
+        // recomputes the world box if the transform moved since, returns whether it did
+        pub fn follow(&mut self, transform: &Transform) -> bool {
+            let matrix = transform.get_model_matrix();
+            if self.matrix == Some(matrix) {
+                return false;
+            }
+            self.matrix = Some(matrix);
+
+            self.world = BoundingBox::from(&self.aabb.transformed(transform));
+            true
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum LightKind {
+        Directional { direction: Vec3 },
+        Point { position: Vec3, range: f32 },
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Light {
+        pub kind: LightKind,
+        pub color: Vec3,
+        pub intensity: f32,
+    }
+
+    // scene file components the engine doesn't read itself, kept so a saved scene holds them again
+    pub struct Extras(pub Vec<(String, Json)>);
+
+    // the entity whose Camera the frame is drawn from
+    #[derive(Clone, Copy, Debug)]
+    pub struct ActiveCamera(pub EntityId);
+
+    // the framebuffer the frame is drawn to, and the depth range
+    #[derive(Clone, Copy, Debug)]
+    pub struct Viewport {
+        pub width: i32,
+        pub height: i32,
+        pub near: f32,
+        pub far: f32,
+    }
+
+    // what camera_system makes of the active camera for the systems after it
+    pub struct RenderView {
+        pub projection: Mat4,
+        pub view: Mat4,
+        pub frustum: Frustum,
+        pub eye: Vec3,
+        // vertical field of view in radians
+        pub fov: f32,
+    }
+
+    impl RenderView {
+        pub fn new(camera: &Camera, viewport: &Viewport) -> Self {
+            let aspect = viewport.width.max(1) as f32 / viewport.height.max(1) as f32;
+            let projection = Mat4::perspective_lh(camera.zoom(), aspect, viewport.near, viewport.far);
+            let view = camera.view_matrix();
+
+            RenderView {
+                projection,
+                view,
+                frustum: Frustum::from_matrix(projection * view, ClipDepth::ZeroToOne),
+                eye: camera.position(),
+                fov: camera.zoom(),
+            }
+        }
+
+        pub fn view_projection(&self) -> Mat4 {
+            self.projection * self.view
+        }
+    }
+}
@@ -0,0 +1,576 @@
+pub mod ecs {
+    use std::{
+        any::{type_name, Any, TypeId},
+        cell::{Ref, RefCell, RefMut},
+        collections::HashMap,
+    };
+
+    // a slot and the generation it was handed out in, so a despawned entity's id doesn't reach whatever reuses the slot
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct EntityId {
+        index: u32,
+        generation: u32,
+    }
+
+    impl EntityId {
+        pub fn index(&self) -> usize {
+            self.index as usize
+        }
+    }
+
+    // one component type for every entity, indexed by slot; the generation is kept so a stale id finds nothing
+    pub struct Storage<T> {
+        slots: Vec<Option<(u32, T)>>,
+        len: usize,
+    }
+
+    impl<T> Default for Storage<T> {
+        fn default() -> Self {
+            Storage { slots: vec![], len: 0 }
+        }
+    }
+
+    impl<T> Storage<T> {
+        pub fn get(&self, id: EntityId) -> Option<&T> {
+            match self.slots.get(id.index())? {
+                Some((generation, component)) if *generation == id.generation => Some(component),
+                _ => None,
+            }
+        }
+
+        pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+            match self.slots.get_mut(id.index())? {
+                Some((generation, component)) if *generation == id.generation => Some(component),
+                _ => None,
+            }
+        }
+
+        pub fn contains(&self, id: EntityId) -> bool {
+            self.get(id).is_some()
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        // in slot order
+        pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+            self.slots.iter().enumerate().filter_map(|(index, slot)| {
+                slot.as_ref().map(|(generation, component)| {
+                    (
+                        EntityId {
+                            index: index as u32,
+                            generation: *generation,
+                        },
+                        component,
+                    )
+                })
+            })
+        }
+
+        pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
+            self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+                slot.as_mut().map(|(generation, component)| {
+                    (
+                        EntityId {
+                            index: index as u32,
+                            generation: *generation,
+                        },
+                        component,
+                    )
+                })
+            })
+        }
+
+        pub fn ids(&self) -> Vec<EntityId> {
+            self.iter().map(|(id, _)| id).collect()
+        }
+
+        fn insert(&mut self, id: EntityId, component: T) -> Option<T> {
+            if self.slots.len() <= id.index() {
+                self.slots.resize_with(id.index() + 1, || None);
+            }
+
+            let previous = self.slots[id.index()].replace((id.generation, component));
+            match previous {
+                Some((generation, component)) if generation == id.generation => Some(component),
+                Some(_) => None,
+                None => {
+                    self.len += 1;
+                    None
+                }
+            }
+        }
+
+        fn remove(&mut self, id: EntityId) -> Option<T> {
+            let slot = self.slots.get_mut(id.index())?;
+            if !matches!(slot, Some((generation, _)) if *generation == id.generation) {
+                return None;
+            }
+
+            self.len -= 1;
+            slot.take().map(|(_, component)| component)
+        }
+    }
+
+    // what the world needs of a storage without knowing its component type
+    trait AnyStorage {
+        fn remove_entity(&self, id: EntityId);
+        fn contains_entity(&self, id: EntityId) -> bool;
+        fn as_any(&self) -> &dyn Any;
+    }
+
+    impl<T: 'static> AnyStorage for RefCell<Storage<T>> {
+        fn remove_entity(&self, id: EntityId) {
+            self.borrow_mut().remove(id);
+        }
+
+        fn contains_entity(&self, id: EntityId) -> bool {
+            self.borrow().contains(id)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    // entities are ids, what they are is whichever components they have; every storage and resource borrows on its own,
+    // so a system can read one component type while writing another
+    #[derive(Default)]
+    pub struct World {
+        generations: Vec<u32>,
+        alive: Vec<bool>,
+        free: Vec<u32>,
+        storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+        // one value per type that belongs to no entity, like the active camera or the bvh
+        resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+    }
+
+    impl World {
+        pub fn new() -> Self {
+            World::default()
+        }
+
+        pub fn spawn(&mut self) -> EntityId {
+            match self.free.pop() {
+                Some(index) => {
+                    self.alive[index as usize] = true;
+                    EntityId {
+                        index,
+                        generation: self.generations[index as usize],
+                    }
+                }
+                None => {
+                    self.generations.push(0);
+                    self.alive.push(true);
+                    EntityId {
+                        index: self.generations.len() as u32 - 1,
+                        generation: 0,
+                    }
+                }
+            }
+        }
+
+        // drops every component the entity has
+        pub fn despawn(&mut self, id: EntityId) -> bool {
+            if !self.is_alive(id) {
+                return false;
+            }
+
+            for storage in self.storages.values() {
+                storage.remove_entity(id);
+            }
+
+            self.alive[id.index()] = false;
+            self.generations[id.index()] += 1;
+            self.free.push(id.index);
+            true
+        }
+
+        pub fn is_alive(&self, id: EntityId) -> bool {
+            self.alive.get(id.index()) == Some(&true) && self.generations[id.index()] == id.generation
+        }
+
+        pub fn len(&self) -> usize {
+            self.alive.len() - self.free.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        // every live entity, in slot order
+        pub fn entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+            self.alive.iter().enumerate().filter(|(_, &alive)| alive).map(|(index, _)| EntityId {
+                index: index as u32,
+                generation: self.generations[index],
+            })
+        }
+
+        // a storage has to exist before it can be read, inserting a component registers its type too
+        pub fn register<T: 'static>(&mut self) {
+            self.storages.entry(TypeId::of::<T>()).or_insert_with(|| Box::new(RefCell::new(Storage::<T>::default())));
+        }
+
+        pub fn is_registered<T: 'static>(&self) -> bool {
+            self.storages.contains_key(&TypeId::of::<T>())
+        }
+
+        // replaces and returns the entity's component of that type, a despawned entity gets nothing
+        pub fn insert<T: 'static>(&mut self, id: EntityId, component: T) -> Option<T> {
+            if !self.is_alive(id) {
+                return None;
+            }
+
+            self.register::<T>();
+            self.write::<T>().insert(id, component)
+        }
+
+        pub fn remove<T: 'static>(&mut self, id: EntityId) -> Option<T> {
+            if !self.is_registered::<T>() {
+                return None;
+            }
+            self.write::<T>().remove(id)
+        }
+
+        pub fn has<T: 'static>(&self, id: EntityId) -> bool {
+            self.storages.get(&TypeId::of::<T>()).is_some_and(|storage| storage.contains_entity(id))
+        }
+
+        // the component borrowed on its own, panics if the storage is already written elsewhere
+        pub fn get<T: 'static>(&self, id: EntityId) -> Option<Ref<'_, T>> {
+            if !self.is_registered::<T>() {
+                return None;
+            }
+            Ref::filter_map(self.read::<T>(), |storage| storage.get(id)).ok()
+        }
+
+        pub fn get_mut<T: 'static>(&self, id: EntityId) -> Option<RefMut<'_, T>> {
+            if !self.is_registered::<T>() {
+                return None;
+            }
+            RefMut::filter_map(self.write::<T>(), |storage| storage.get_mut(id)).ok()
+        }
+
+        // the whole storage, for systems that go over every component of a type
+        pub fn read<T: 'static>(&self) -> Ref<'_, Storage<T>> {
+            self.storage::<T>().borrow()
+        }
+
+        pub fn write<T: 'static>(&self) -> RefMut<'_, Storage<T>> {
+            self.storage::<T>().borrow_mut()
+        }
+
+        fn storage<T: 'static>(&self) -> &RefCell<Storage<T>> {
+            self.storages
+                .get(&TypeId::of::<T>())
+                .unwrap_or_else(|| panic!("Component {} was never registered.", type_name::<T>()))
+                .as_any()
+                .downcast_ref()
+                .unwrap()
+        }
+
+        // the entities that have everything in Q, see Fetch
+        pub fn query<Q: Fetch>(&self) -> Query<'_, Q> {
+            let storages = Q::borrow(self);
+            let ids = Q::ids(&storages);
+            Query { storages, ids }
+        }
+
+        pub fn insert_resource<R: 'static>(&mut self, resource: R) -> Option<R> {
+            self.resources
+                .insert(TypeId::of::<R>(), RefCell::new(Box::new(resource)))
+                .map(|previous| *previous.into_inner().downcast().unwrap())
+        }
+
+        pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+            self.resources.remove(&TypeId::of::<R>()).map(|resource| *resource.into_inner().downcast().unwrap())
+        }
+
+        pub fn has_resource<R: 'static>(&self) -> bool {
+            self.resources.contains_key(&TypeId::of::<R>())
+        }
+
+        // panics when the resource was never inserted, like indexing
+        pub fn resource<R: 'static>(&self) -> Ref<'_, R> {
+            Ref::map(self.resource_cell::<R>().borrow(), |resource| resource.downcast_ref().unwrap())
+        }
+
+        pub fn resource_mut<R: 'static>(&self) -> RefMut<'_, R> {
+            RefMut::map(self.resource_cell::<R>().borrow_mut(), |resource| resource.downcast_mut().unwrap())
+        }
+
+        fn resource_cell<R: 'static>(&self) -> &RefCell<Box<dyn Any>> {
+            self.resources
+                .get(&TypeId::of::<R>())
+                .unwrap_or_else(|| panic!("Resource {} was never inserted.", type_name::<R>()))
+        }
+    }
+
+    // what a query asks for: &T reads a component, &mut T writes it, and tuples of those want all of them at once
+    pub trait Fetch {
+        type Storages<'w>;
+        type Item<'s>;
+
+        fn borrow(world: &World) -> Self::Storages<'_>;
+        // the entities worth looking at, the first storage's
+        fn ids(storages: &Self::Storages<'_>) -> Vec<EntityId>;
+        fn fetch<'s>(storages: &'s mut Self::Storages<'_>, id: EntityId) -> Option<Self::Item<'s>>;
+    }
+
+    impl<T: 'static> Fetch for &T {
+        type Storages<'w> = Ref<'w, Storage<T>>;
+        type Item<'s> = &'s T;
+
+        fn borrow(world: &World) -> Self::Storages<'_> {
+            world.read::<T>()
+        }
+
+        fn ids(storages: &Self::Storages<'_>) -> Vec<EntityId> {
+            storages.ids()
+        }
+
+        fn fetch<'s>(storages: &'s mut Self::Storages<'_>, id: EntityId) -> Option<Self::Item<'s>> {
+            storages.get(id)
+        }
+    }
+
+    impl<T: 'static> Fetch for &mut T {
+        type Storages<'w> = RefMut<'w, Storage<T>>;
+        type Item<'s> = &'s mut T;
+
+        fn borrow(world: &World) -> Self::Storages<'_> {
+            world.write::<T>()
+        }
+
+        fn ids(storages: &Self::Storages<'_>) -> Vec<EntityId> {
+            storages.ids()
+        }
+
+        fn fetch<'s>(storages: &'s mut Self::Storages<'_>, id: EntityId) -> Option<Self::Item<'s>> {
+            storages.get_mut(id)
+        }
+    }
+
+    macro_rules! fetch_tuple {
+        ($first:ident $(, $rest:ident)*) => {
+            #[allow(non_snake_case)]
+            impl<$first: Fetch $(, $rest: Fetch)*> Fetch for ($first, $($rest,)*) {
+                type Storages<'w> = ($first::Storages<'w>, $($rest::Storages<'w>,)*);
+                type Item<'s> = ($first::Item<'s>, $($rest::Item<'s>,)*);
+
+                fn borrow(world: &World) -> Self::Storages<'_> {
+                    ($first::borrow(world), $($rest::borrow(world),)*)
+                }
+
+                fn ids(storages: &Self::Storages<'_>) -> Vec<EntityId> {
+                    $first::ids(&storages.0)
+                }
+
+                fn fetch<'s>(storages: &'s mut Self::Storages<'_>, id: EntityId) -> Option<Self::Item<'s>> {
+                    let ($first, $($rest,)*) = storages;
+                    Some(($first::fetch($first, id)?, $($rest::fetch($rest, id)?,)*))
+                }
+            }
+        };
+    }
+
+    fetch_tuple!(A);
+    fetch_tuple!(A, B);
+    fetch_tuple!(A, B, C);
+    fetch_tuple!(A, B, C, D);
+    fetch_tuple!(A, B, C, D, E);
+
+    // the storages stay borrowed for as long as the query lives
+    pub struct Query<'w, Q: Fetch> {
+        storages: Q::Storages<'w>,
+        ids: Vec<EntityId>,
+    }
+
+    impl<'w, Q: Fetch> Query<'w, Q> {
+        // in slot order
+        pub fn for_each(&mut self, mut visit: impl FnMut(EntityId, Q::Item<'_>)) {
+            for &id in &self.ids {
+                if let Some(item) = Q::fetch(&mut self.storages, id) {
+                    visit(id, item);
+                }
+            }
+        }
+
+        pub fn get(&mut self, id: EntityId) -> Option<Q::Item<'_>> {
+            Q::fetch(&mut self.storages, id)
+        }
+
+        // the entities that have every component asked for
+        pub fn ids(&mut self) -> Vec<EntityId> {
+            let candidates = self.ids.clone();
+            candidates.into_iter().filter(|&id| Q::fetch(&mut self.storages, id).is_some()).collect()
+        }
+    }
+
+    pub trait System {
+        fn run(&mut self, world: &mut World);
+    }
+
+    impl<F: FnMut(&mut World)> System for F {
+        fn run(&mut self, world: &mut World) {
+            self(world)
+        }
+    }
+
+    // systems by name, run in the order they were added
+    #[derive(Default)]
+    pub struct Schedule {
+        systems: Vec<(String, Box<dyn System>)>,
+    }
+
+    impl Schedule {
+        pub fn new() -> Self {
+            Schedule::default()
+        }
+
+        pub fn with(mut self, name: &str, system: impl System + 'static) -> Self {
+            self.systems.push((name.to_string(), Box::new(system)));
+            self
+        }
+
+        pub fn names(&self) -> impl Iterator<Item = &str> {
+            self.systems.iter().map(|(name, _)| name.as_str())
+        }
+
+        pub fn run(&mut self, world: &mut World) {
+            for (_, system) in &mut self.systems {
+                system.run(world);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[derive(Debug, PartialEq)]
+        struct Position(f32);
+
+        #[derive(Debug, PartialEq)]
+        struct Velocity(f32);
+
+        #[test]
+        fn stale_ids_find_nothing_after_despawn_and_respawn() {
+            let mut world = World::new();
+            let stale = world.spawn();
+            world.insert(stale, Position(1.0));
+
+            assert!(world.despawn(stale));
+            assert!(!world.despawn(stale));
+            assert!(!world.is_alive(stale));
+            assert!(world.get::<Position>(stale).is_none());
+
+            // the slot is reused under a new generation
+            let fresh = world.spawn();
+            assert_eq!(fresh.index(), stale.index());
+            assert_ne!(fresh, stale);
+            assert!(!world.has::<Position>(fresh));
+
+            world.insert(fresh, Position(2.0));
+            assert_eq!(world.insert(stale, Position(3.0)), None);
+            assert!(world.get::<Position>(stale).is_none());
+            assert!(world.remove::<Position>(stale).is_none());
+            assert_eq!(*world.get::<Position>(fresh).unwrap(), Position(2.0));
+            assert_eq!(world.entities().collect::<Vec<_>>(), vec![fresh]);
+            assert_eq!(world.len(), 1);
+        }
+
+        #[test]
+        fn storage_insert_replaces_and_remove_takes() {
+            let mut world = World::new();
+            let (a, b) = (world.spawn(), world.spawn());
+
+            assert_eq!(world.insert(a, Position(1.0)), None);
+            assert_eq!(world.insert(a, Position(2.0)), Some(Position(1.0)));
+            world.insert(b, Position(3.0));
+            assert_eq!(world.read::<Position>().len(), 2);
+
+            assert_eq!(world.remove::<Position>(a), Some(Position(2.0)));
+            assert_eq!(world.remove::<Position>(a), None);
+            assert_eq!(world.remove::<Velocity>(a), None);
+            assert_eq!(world.read::<Position>().ids(), vec![b]);
+
+            // despawning drops the components with it
+            world.despawn(b);
+            assert!(world.read::<Position>().is_empty());
+        }
+
+        #[test]
+        fn queries_skip_entities_missing_a_component() {
+            let mut world = World::new();
+            let both = world.spawn();
+            let position_only = world.spawn();
+            let velocity_only = world.spawn();
+            let also_both = world.spawn();
+
+            for (id, position, velocity) in [
+                (both, Some(1.0), Some(10.0)),
+                (position_only, Some(2.0), None),
+                (velocity_only, None, Some(30.0)),
+                (also_both, Some(4.0), Some(40.0)),
+            ] {
+                if let Some(position) = position {
+                    world.insert(id, Position(position));
+                }
+                if let Some(velocity) = velocity {
+                    world.insert(id, Velocity(velocity));
+                }
+            }
+
+            let mut query = world.query::<(&mut Position, &Velocity)>();
+            let mut visited = vec![];
+            query.for_each(|id, (position, velocity)| {
+                position.0 += velocity.0;
+                visited.push(id);
+            });
+            assert_eq!(visited, vec![both, also_both]);
+            assert_eq!(query.ids(), vec![both, also_both]);
+            assert!(query.get(position_only).is_none());
+            assert!(query.get(velocity_only).is_none());
+            drop(query);
+
+            assert_eq!(*world.get::<Position>(both).unwrap(), Position(11.0));
+            assert_eq!(*world.get::<Position>(position_only).unwrap(), Position(2.0));
+        }
+
+        #[test]
+        fn resources_insert_replace_and_remove() {
+            let mut world = World::new();
+            assert!(!world.has_resource::<Position>());
+
+            assert_eq!(world.insert_resource(Position(1.0)), None);
+            assert_eq!(world.insert_resource(Position(2.0)), Some(Position(1.0)));
+            world.resource_mut::<Position>().0 += 1.0;
+            assert_eq!(*world.resource::<Position>(), Position(3.0));
+
+            assert_eq!(world.remove_resource::<Position>(), Some(Position(3.0)));
+            assert_eq!(world.remove_resource::<Position>(), None);
+            assert!(!world.has_resource::<Position>());
+        }
+
+        #[test]
+        fn schedule_runs_systems_in_the_order_they_were_added() {
+            let mut world = World::new();
+            world.insert_resource(Vec::<&str>::new());
+
+            let mut schedule = Schedule::new()
+                .with("first", |world: &mut World| world.resource_mut::<Vec<&str>>().push("first"))
+                .with("second", |world: &mut World| world.resource_mut::<Vec<&str>>().push("second"));
+            schedule.run(&mut world);
+            schedule.run(&mut world);
+
+            assert_eq!(schedule.names().collect::<Vec<_>>(), vec!["first", "second"]);
+            assert_eq!(*world.resource::<Vec<&str>>(), vec!["first", "second", "first", "second"]);
+        }
+    }
+}
@@ -1,7 +1,6 @@
 pub mod entity {
-    use crate::{Camera, Model, Program};
-    use glam::{EulerRot, Mat4, Quat, Vec3};
-    use std::rc::Rc;
+    use crate::{Camera, Model};
+    use glam::{EulerRot, Mat3, Mat4, Quat, Vec3, Vec4};
 
     #[derive(Clone, Copy)]
     pub struct Transform {
@@ -29,11 +28,17 @@ pub mod entity {
         }
 
         pub fn compute_model_matrix(&mut self) {
-            self.model_matrix = self.get_local_model_matrix();
+            self.compute_relative_model_matrix(Mat4::IDENTITY);
         }
 
         pub fn compute_relative_model_matrix(&mut self, parent: Mat4) {
             self.model_matrix = parent * self.get_local_model_matrix();
+            self.is_dirty = false;
+        }
+
+        // the model matrix is recomputed on the next update even though nothing local changed
+        pub fn mark_dirty(&mut self) {
+            self.is_dirty = true;
         }
 
         pub fn set_local_position(&mut self, position: Vec3) {
@@ -96,6 +101,12 @@ pub mod entity {
         }
     }
 
+    impl Default for Transform {
+        fn default() -> Self {
+            Transform::new()
+        }
+    }
+
     pub struct Plan {
         normal: Vec3,
         distance: f32,
@@ -116,9 +127,29 @@ pub mod entity {
             Plan { normal, distance: normal.dot(p1) }
         }
 
+        // a x + b y + c z + d = 0, scaled to a unit normal
+        pub fn from_equation(equation: Vec4) -> Self {
+            let length = equation.truncate().length();
+            Plan {
+                normal: equation.truncate() / length,
+                distance: -equation.w / length,
+            }
+        }
+
         pub fn get_signed_distance_to_plan(&self, point: Vec3) -> f32 {
             self.normal.dot(point) - self.distance
         }
+
+        pub fn get_normal(&self) -> Vec3 {
+            self.normal
+        }
+    }
+
+    // the depth range a projection maps to, glam's perspective_lh and orthographic_lh use 0 to 1, the _gl ones -1 to 1
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ClipDepth {
+        ZeroToOne,
+        NegativeOneToOne,
     }
 
     pub struct Frustum {
@@ -130,6 +161,30 @@ pub mod entity {
         pub near_face: Plan,
     }
 
+    impl Frustum {
+        // gribb and hartmann, every plane is the last row of the matrix plus or minus another, normals point inside;
+        // works for any projection, perspective or orthographic, camera or light
+        pub fn from_matrix(view_projection: Mat4, depth: ClipDepth) -> Self {
+            let (x, y, z, w) = (view_projection.row(0), view_projection.row(1), view_projection.row(2), view_projection.row(3));
+
+            Frustum {
+                left_face: Plan::from_equation(w + x),
+                right_face: Plan::from_equation(w - x),
+                bottom_face: Plan::from_equation(w + y),
+                top_face: Plan::from_equation(w - y),
+                near_face: Plan::from_equation(match depth {
+                    ClipDepth::ZeroToOne => z,
+                    ClipDepth::NegativeOneToOne => w + z,
+                }),
+                far_face: Plan::from_equation(w - z),
+            }
+        }
+
+        pub fn planes(&self) -> [&Plan; 6] {
+            [&self.left_face, &self.right_face, &self.top_face, &self.bottom_face, &self.near_face, &self.far_face]
+        }
+    }
+
     pub trait BoundingVolume {
         fn is_on_or_forward_plan(&self, plan: &Plan) -> bool;
 
@@ -145,7 +200,7 @@ pub mod entity {
         }
     }
 
-    #[derive(Default)]
+    #[derive(Clone, Copy, Debug, Default)]
     pub struct Sphere {
         center: Vec3,
         radius: f32,
@@ -157,10 +212,9 @@ pub mod entity {
         }
 
         fn is_on_frustum_transform(&self, cam_frustum: &Frustum, transform: &Transform) -> bool {
-            let global_scale = transform.get_global_scale();
-            let global_center = (transform.get_model_matrix() * self.center.extend(1.0)).truncate();
-            let max_scale = f32::max(f32::max(global_scale.x, global_scale.y), global_scale.z);
-            let global_sphere = Sphere::new(global_center, self.radius * max_scale * 0.5);
+            let matrix = transform.get_model_matrix();
+            let global_center = matrix.transform_point3(self.center);
+            let global_sphere = Sphere::new(global_center, self.radius * max_stretch(matrix));
 
             global_sphere.is_on_frustum(cam_frustum)
         }
@@ -170,6 +224,18 @@ pub mod entity {
         pub fn new(center: Vec3, radius: f32) -> Self {
             Sphere { center, radius }
         }
+
+        pub fn get_center(&self) -> Vec3 {
+            self.center
+        }
+
+        pub fn get_radius(&self) -> f32 {
+            self.radius
+        }
+
+        fn contains(&self, point: Vec3) -> bool {
+            point.distance_squared(self.center) <= self.radius * self.radius * (1.0 + 1e-5) + 1e-7
+        }
     }
 
     #[derive(Default)]
@@ -186,22 +252,9 @@ pub mod entity {
         }
 
         fn is_on_frustum_transform(&self, cam_frustum: &Frustum, transform: &Transform) -> bool {
-            let global_center = (transform.get_model_matrix() * self.center.extend(1.0)).truncate();
-
-            let right = transform.get_right() * self.extent;
-            let up = transform.get_up() * self.extent;
-            let forward = transform.get_forward() * self.extent;
-
-            let new_ii = f32::abs(Vec3::X.dot(right)) + f32::abs(Vec3::X.dot(up)) + f32::abs(Vec3::X.dot(forward));
-
-            let new_ij = f32::abs(Vec3::Y.dot(right)) + f32::abs(Vec3::Y.dot(up)) + f32::abs(Vec3::Y.dot(forward));
-
-            let new_ik = f32::abs(Vec3::Z.dot(right)) + f32::abs(Vec3::Z.dot(up)) + f32::abs(Vec3::Z.dot(forward));
-
-            let max_scale = f32::max(f32::max(new_ii, new_ij), new_ik);
-            let global_aabb = SquareAABB::new(global_center, max_scale);
-
-            global_aabb.is_on_frustum(cam_frustum)
+            OBB::from(&AABB::new(self.center, Vec3::splat(self.extent)))
+                .transformed(transform.get_model_matrix())
+                .is_on_frustum(cam_frustum)
         }
     }
 
@@ -211,7 +264,7 @@ pub mod entity {
         }
     }
 
-    #[derive(Default)]
+    #[derive(Clone, Copy, Debug, Default)]
     pub struct AABB {
         center: Vec3,
         extents: Vec3,
@@ -224,22 +277,9 @@ pub mod entity {
             -r <= plan.get_signed_distance_to_plan(self.center)
         }
 
+        // the transformed box itself, not the world box around it
         fn is_on_frustum_transform(&self, cam_frustum: &Frustum, transform: &Transform) -> bool {
-            let global_center = (transform.get_model_matrix() * self.center.extend(1.0)).truncate();
-
-            let right = transform.get_right() * self.extents.x;
-            let up = transform.get_up() * self.extents.y;
-            let forward = transform.get_forward() * self.extents.z;
-
-            let new_ii = f32::abs(Vec3::X.dot(right)) + f32::abs(Vec3::X.dot(up)) + f32::abs(Vec3::X.dot(forward));
-
-            let new_ij = f32::abs(Vec3::Y.dot(right)) + f32::abs(Vec3::Y.dot(up)) + f32::abs(Vec3::Y.dot(forward));
-
-            let new_ik = f32::abs(Vec3::Z.dot(right)) + f32::abs(Vec3::Z.dot(up)) + f32::abs(Vec3::Z.dot(forward));
-
-            let global_aabb = AABB::new(global_center, Vec3::new(new_ii, new_ij, new_ik));
-
-            global_aabb.is_on_frustum(cam_frustum)
+            OBB::from(self).transformed(transform.get_model_matrix()).is_on_frustum(cam_frustum)
         }
     }
 
@@ -255,6 +295,31 @@ pub mod entity {
             AABB { center, extents }
         }
 
+        pub fn get_min(&self) -> Vec3 {
+            self.center - self.extents
+        }
+
+        pub fn get_max(&self) -> Vec3 {
+            self.center + self.extents
+        }
+
+        // the axis aligned box around this one once the transform has moved it
+        pub fn transformed(&self, transform: &Transform) -> AABB {
+            let global_center = (transform.get_model_matrix() * self.center.extend(1.0)).truncate();
+
+            let right = transform.get_right() * self.extents.x;
+            let up = transform.get_up() * self.extents.y;
+            let forward = transform.get_forward() * self.extents.z;
+
+            let new_ii = f32::abs(Vec3::X.dot(right)) + f32::abs(Vec3::X.dot(up)) + f32::abs(Vec3::X.dot(forward));
+
+            let new_ij = f32::abs(Vec3::Y.dot(right)) + f32::abs(Vec3::Y.dot(up)) + f32::abs(Vec3::Y.dot(forward));
+
+            let new_ik = f32::abs(Vec3::Z.dot(right)) + f32::abs(Vec3::Z.dot(up)) + f32::abs(Vec3::Z.dot(forward));
+
+            AABB::new(global_center, Vec3::new(new_ii, new_ij, new_ik))
+        }
+
         pub fn get_vertice(&self) -> Vec<Vec3> {
             vec![
                 Vec3::new(self.center.x - self.extents.x, self.center.y - self.extents.y, self.center.z - self.extents.z),
@@ -269,22 +334,130 @@ pub mod entity {
         }
     }
 
+    // a box along any three axes; each half axis carries its extent, and after a shearing transform they may stop
+    // being perpendicular, which the plane test doesn't mind
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct OBB {
+        center: Vec3,
+        half_axes: [Vec3; 3],
+    }
+
+    impl BoundingVolume for OBB {
+        // the box's reach along the normal, exactly where its farthest corner is
+        fn is_on_or_forward_plan(&self, plan: &Plan) -> bool {
+            let r: f32 = self.half_axes.iter().map(|axis| axis.dot(plan.normal).abs()).sum();
+
+            -r <= plan.get_signed_distance_to_plan(self.center)
+        }
+
+        fn is_on_frustum_transform(&self, cam_frustum: &Frustum, transform: &Transform) -> bool {
+            self.transformed(transform.get_model_matrix()).is_on_frustum(cam_frustum)
+        }
+    }
+
+    impl From<&AABB> for OBB {
+        fn from(aabb: &AABB) -> Self {
+            OBB::new(aabb.center, [Vec3::X, Vec3::Y, Vec3::Z], aabb.extents)
+        }
+    }
+
+    impl OBB {
+        pub fn new(center: Vec3, axes: [Vec3; 3], extents: Vec3) -> Self {
+            OBB {
+                center,
+                half_axes: [axes[0] * extents.x, axes[1] * extents.y, axes[2] * extents.z],
+            }
+        }
+
+        pub fn transformed(&self, matrix: Mat4) -> OBB {
+            OBB {
+                center: matrix.transform_point3(self.center),
+                half_axes: self.half_axes.map(|axis| matrix.transform_vector3(axis)),
+            }
+        }
+
+        pub fn get_center(&self) -> Vec3 {
+            self.center
+        }
+
+        pub fn get_half_axes(&self) -> [Vec3; 3] {
+            self.half_axes
+        }
+
+        pub fn get_vertice(&self) -> Vec<Vec3> {
+            let [a, b, c] = self.half_axes;
+            (0..8)
+                .map(|i| {
+                    let sign = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+                    self.center + a * sign(1) + b * sign(2) + c * sign(4)
+                })
+                .collect()
+        }
+
+        pub fn volume(&self) -> f32 {
+            8.0 * self.half_axes[0].dot(self.half_axes[1].cross(self.half_axes[2])).abs()
+        }
+    }
+
+    // the camera's own projection, through the same plane extraction as any other matrix
     pub fn create_frustum_from_camera(cam: &Camera, aspect: f32, fov_y: f32, z_near: f32, z_far: f32) -> Frustum {
-        let half_v_side = z_far * f32::tan(fov_y * 0.5);
-        let half_h_side = half_v_side * aspect;
-        let front_mult_far = z_far * cam.front();
+        Frustum::from_matrix(Mat4::perspective_lh(fov_y, aspect, z_near, z_far) * cam.view_matrix(), ClipDepth::ZeroToOne)
+    }
+
+    // how far the matrix stretches any direction at most, its largest singular value
+    pub fn max_stretch(matrix: Mat4) -> f32 {
+        let linear = Mat3::from_mat4(matrix);
+        let (values, _) = symmetric_eigen(linear.transpose() * linear);
+        values.max_element().max(0.0).sqrt()
+    }
+
+    // jacobi rotations until the off-diagonal is gone, the eigenvectors are the columns
+    fn symmetric_eigen(matrix: Mat3) -> (Vec3, Mat3) {
+        let mut a = matrix.to_cols_array_2d();
+        let mut v = Mat3::IDENTITY.to_cols_array_2d();
+
+        for _ in 0..32 {
+            let off = a[0][1] * a[0][1] + a[0][2] * a[0][2] + a[1][2] * a[1][2];
+            if off < 1e-12 {
+                break;
+            }
 
-        Frustum {
-            near_face: Plan::new(cam.position() + z_near * cam.front(), cam.front()),
-            far_face: Plan::new(cam.position() + front_mult_far, -cam.front()),
-            right_face: Plan::new(cam.position(), cam.up().cross(front_mult_far + cam.right() * half_h_side)),
-            left_face: Plan::new(cam.position(), (front_mult_far - cam.right() * half_h_side).cross(cam.up())),
-            top_face: Plan::new(cam.position(), cam.right().cross(front_mult_far - cam.up() * half_v_side)),
-            bottom_face: Plan::new(cam.position(), (front_mult_far + cam.up() * half_v_side).cross(cam.right())),
+            for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+                if a[p][q].abs() < 1e-12 {
+                    continue;
+                }
+
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let (c, s) = (1.0 / (t * t + 1.0).sqrt(), t / (t * t + 1.0).sqrt());
+
+                for row in &mut a {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                for (k, (apk, aqk)) in a[p].into_iter().zip(a[q]).enumerate() {
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for row in &mut v {
+                    let (vp, vq) = (row[p], row[q]);
+                    row[p] = c * vp - s * vq;
+                    row[q] = s * vp + c * vq;
+                }
+            }
         }
+
+        // v was built as columns of the rotation, rows of the array are its columns
+        let vectors = Mat3::from_cols_array_2d(&v).transpose();
+        (Vec3::new(a[0][0], a[1][1], a[2][2]), vectors)
     }
 
-    fn generate_aabb(model: &Model) -> AABB {
+    pub fn positions(model: &Model) -> Vec<Vec3> {
+        model.meshes.iter().flat_map(|mesh| mesh.vertices.iter().map(|vertex| vertex.position)).collect()
+    }
+
+    pub fn generate_aabb(model: &Model) -> AABB {
         let mut min_aabb = Vec3::splat(f32::MAX);
         let mut max_aabb = Vec3::splat(f32::MIN);
 
@@ -303,98 +476,388 @@ pub mod entity {
         AABB::new_minmax(min_aabb, max_aabb)
     }
 
-    fn generate_sphere(model: &Model) -> Sphere {
-        let mut min_aabb = Vec3::splat(f32::MAX);
-        let mut max_aabb = Vec3::splat(f32::MIN);
+    // along the principal axes of the vertices, or the axis aligned box when that one is smaller
+    pub fn generate_obb(points: &[Vec3]) -> OBB {
+        if points.is_empty() {
+            return OBB::default();
+        }
 
-        for mesh in &model.meshes {
-            for vertex in &mesh.vertices {
-                min_aabb.x = f32::min(min_aabb.x, vertex.position.x);
-                min_aabb.y = f32::min(min_aabb.y, vertex.position.y);
-                min_aabb.z = f32::min(min_aabb.z, vertex.position.z);
+        let mean = points.iter().copied().sum::<Vec3>() / points.len() as f32;
+        let mut covariance = Mat3::ZERO;
+        for &point in points {
+            let d = point - mean;
+            covariance += Mat3::from_cols(d * d.x, d * d.y, d * d.z);
+        }
 
-                max_aabb.x = f32::max(max_aabb.x, vertex.position.x);
-                max_aabb.y = f32::max(max_aabb.y, vertex.position.y);
-                max_aabb.z = f32::max(max_aabb.z, vertex.position.z);
+        let (_, vectors) = symmetric_eigen(covariance * (1.0 / points.len() as f32));
+        let axes = [vectors.x_axis.normalize_or_zero(), vectors.y_axis.normalize_or_zero(), vectors.z_axis.normalize_or_zero()];
+
+        let (mut min, mut max) = (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN));
+        for &point in points {
+            let local = Vec3::new(point.dot(axes[0]), point.dot(axes[1]), point.dot(axes[2]));
+            min = min.min(local);
+            max = max.max(local);
+        }
+
+        let middle = (min + max) * 0.5;
+        let principal = OBB::new(axes[0] * middle.x + axes[1] * middle.y + axes[2] * middle.z, axes, (max - min) * 0.5);
+
+        let (min, max) = points.iter().fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), &point| (min.min(point), max.max(point)));
+        let aligned = OBB::from(&AABB::new_minmax(min, max));
+
+        if principal.volume() < aligned.volume() {
+            principal
+        } else {
+            aligned
+        }
+    }
+
+    // the smallest sphere around the points, welzl's incremental form: each point outside the sphere so far is on the new one's surface
+    pub fn generate_sphere(points: &[Vec3]) -> Sphere {
+        if points.is_empty() {
+            return Sphere::default();
+        }
+
+        // a fixed shuffle keeps the expected linear time on meshes stored in spatial order
+        let mut points = points.to_vec();
+        let mut seed = 0x2545_f491u32;
+        for i in (1..points.len()).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            points.swap(i, seed as usize % (i + 1));
+        }
+
+        let mut sphere = Sphere::new(points[0], 0.0);
+        for i in 1..points.len() {
+            if sphere.contains(points[i]) {
+                continue;
+            }
+
+            sphere = Sphere::new(points[i], 0.0);
+            for j in 0..i {
+                if sphere.contains(points[j]) {
+                    continue;
+                }
+
+                sphere = sphere_through(&[points[i], points[j]]);
+                for k in 0..j {
+                    if sphere.contains(points[k]) {
+                        continue;
+                    }
+
+                    sphere = sphere_through(&[points[i], points[j], points[k]]);
+                    for l in 0..k {
+                        if !sphere.contains(points[l]) {
+                            sphere = sphere_through(&[points[i], points[j], points[k], points[l]]);
+                        }
+                    }
+                }
             }
         }
 
-        Sphere::new((max_aabb + min_aabb) * 0.5, (min_aabb - max_aabb).length())
+        sphere
     }
 
-    pub struct Entity {
-        pub children: Vec<Entity>,
-        parent: Option<Transform>,
-        pub transform: Transform,
-        pub p_model: Rc<Model>,
-        bounding_volume: AABB,
+    // the smallest sphere with every point on it, falling back to the smallest through fewer when they are degenerate
+    fn sphere_through(points: &[Vec3]) -> Sphere {
+        match *points {
+            [a] => Sphere::new(a, 0.0),
+            [a, b] => Sphere::new((a + b) * 0.5, a.distance(b) * 0.5),
+            [a, b, c] => {
+                let (ab, ac) = (b - a, c - a);
+                let normal = ab.cross(ac);
+                let denominator = 2.0 * normal.length_squared();
+
+                if denominator < 1e-12 {
+                    return widest(points);
+                }
+
+                let center = a + (normal.cross(ab) * ac.length_squared() + ac.cross(normal) * ab.length_squared()) / denominator;
+                Sphere::new(center, center.distance(a))
+            }
+            [a, b, c, d] => {
+                let rows = Mat3::from_cols(b - a, c - a, d - a).transpose();
+                if rows.determinant().abs() < 1e-9 {
+                    return widest(points);
+                }
+
+                let right = Vec3::new((b - a).length_squared(), (c - a).length_squared(), (d - a).length_squared()) * 0.5;
+                let center = a + rows.inverse() * right;
+                Sphere::new(center, center.distance(a))
+            }
+            _ => unreachable!("Spheres go through one to four points."),
+        }
     }
 
-    impl From<&Rc<Model>> for Entity {
-        fn from(value: &Rc<Model>) -> Self {
-            let bounding_volume = generate_aabb(&value);
+    // the smallest of the spheres through fewer of the points that still holds them all
+    fn widest(points: &[Vec3]) -> Sphere {
+        let mut best: Option<Sphere> = None;
+
+        for skip in 0..points.len() {
+            let fewer: Vec<Vec3> = points.iter().enumerate().filter(|&(i, _)| i != skip).map(|(_, &point)| point).collect();
+            let sphere = sphere_through(&fewer);
 
-            Entity {
-                children: Vec::new(),
-                parent: None,
-                transform: Transform::new(),
-                p_model: Rc::clone(value),
-                bounding_volume,
+            if points.iter().all(|&point| sphere.contains(point)) && best.is_none_or(|best| sphere.radius < best.radius) {
+                best = Some(sphere);
             }
         }
+
+        best.unwrap_or_else(|| sphere_through(&points[..2]))
     }
 
-    impl Entity {
-        pub fn get_global_aabb(&self) -> AABB {
-            let global_center = (self.transform.get_model_matrix() * self.bounding_volume.center.extend(1.0)).truncate();
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-            let right = self.transform.get_right() * self.bounding_volume.extents.x;
-            let up = self.transform.get_up() * self.bounding_volume.extents.y;
-            let forward = self.transform.get_forward() * self.bounding_volume.extents.z;
+        // xorshift, so every run tests the same boxes
+        struct Random(u32);
 
-            let new_ii = f32::abs(Vec3::X.dot(right)) + f32::abs(Vec3::X.dot(up)) + f32::abs(Vec3::X.dot(forward));
+        impl Random {
+            fn next(&mut self) -> f32 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 17;
+                self.0 ^= self.0 << 5;
+                self.0 as f32 / u32::MAX as f32
+            }
 
-            let new_ij = f32::abs(Vec3::Y.dot(right)) + f32::abs(Vec3::Y.dot(up)) + f32::abs(Vec3::Y.dot(forward));
+            fn range(&mut self, min: f32, max: f32) -> f32 {
+                min + (max - min) * self.next()
+            }
 
-            let new_ik = f32::abs(Vec3::Z.dot(right)) + f32::abs(Vec3::Z.dot(up)) + f32::abs(Vec3::Z.dot(forward));
+            fn vec3(&mut self, min: f32, max: f32) -> Vec3 {
+                Vec3::new(self.range(min, max), self.range(min, max), self.range(min, max))
+            }
+        }
 
-            AABB::new(global_center, Vec3::new(new_ii, new_ij, new_ik))
+        // z' = 2 z - w, what the same projection looks like with a -1 to 1 depth range
+        const ZERO_TO_ONE_TO_GL: Mat4 = Mat4::from_cols(Vec4::X, Vec4::Y, Vec4::new(0.0, 0.0, 2.0, 0.0), Vec4::new(0.0, 0.0, -1.0, 1.0));
+
+        // every projection the samples cull with, as (matrix, depth range) with the same volume under both ranges
+        fn frustums() -> Vec<(Mat4, ClipDepth)> {
+            let view = Mat4::look_at_lh(Vec3::new(1.0, 2.0, -6.0), Vec3::new(0.0, 0.5, 0.0), Vec3::Y);
+            let perspective = Mat4::perspective_lh(1.0, 16.0 / 9.0, 0.5, 30.0) * view;
+            let orthographic = Mat4::orthographic_lh(-4.0, 6.0, -3.0, 2.0, 1.0, 20.0) * view;
+
+            // a cascade: an orthographic box around part of the camera frustum, seen from the light
+            let center = Vec3::new(0.5, 0.0, 2.0);
+            let light_view = Mat4::look_at_lh(center + Vec3::new(20.0, 50.0, 20.0).normalize(), center, Vec3::Y);
+            let light = Mat4::orthographic_lh(-7.0, 7.0, -5.0, 5.0, -40.0, 4.0) * light_view;
+
+            [perspective, orthographic, light]
+                .into_iter()
+                .flat_map(|matrix| [(matrix, ClipDepth::ZeroToOne), (ZERO_TO_ONE_TO_GL * matrix, ClipDepth::NegativeOneToOne)])
+                .collect()
+        }
+
+        fn random_obb(random: &mut Random) -> OBB {
+            let rotation = Quat::from_euler(EulerRot::XYZ, random.range(0.0, 6.3), random.range(0.0, 6.3), random.range(0.0, 6.3));
+            let axes = [rotation * Vec3::X, rotation * Vec3::Y, rotation * Vec3::Z];
+            OBB::new(random.vec3(-15.0, 15.0), axes, random.vec3(0.05, 3.0))
+        }
+
+        // whether some corner is in front of the plane, None when one is too close to call
+        fn farthest(corners: &[Vec3], plan: &Plan) -> f32 {
+            corners.iter().map(|&corner| plan.get_signed_distance_to_plan(corner)).fold(f32::MIN, f32::max)
+        }
+
+        fn corners_forward(corners: &[Vec3], plan: &Plan) -> Option<bool> {
+            let farthest = farthest(corners, plan);
+            (farthest.abs() > 1e-3).then_some(farthest > 0.0)
+        }
+
+        // spread evenly over the unit sphere, no point of it is more than about 0.07 radians from one of them
+        fn sphere_points(count: usize) -> Vec<Vec3> {
+            let golden = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+            (0..count)
+                .map(|i| {
+                    let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+                    let ring = (1.0 - y * y).sqrt();
+                    let angle = golden * i as f32;
+                    Vec3::new(ring * angle.cos(), y, ring * angle.sin())
+                })
+                .collect()
+        }
+
+        #[test]
+        fn extracted_planes_hold_exactly_the_clip_volume() {
+            for (matrix, depth) in frustums() {
+                let frustum = Frustum::from_matrix(matrix, depth);
+                let inverse = matrix.inverse();
+                let near = if depth == ClipDepth::ZeroToOne { 0.0 } else { -1.0 };
+                let mut random = Random(7);
+
+                for _ in 0..500 {
+                    let ndc = Vec3::new(random.range(-1.2, 1.2), random.range(-1.2, 1.2), random.range(near - 0.2 * (1.0 - near), 1.2));
+                    let inside = ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && (near..=1.0).contains(&ndc.z);
+                    let margin = (1.0 - ndc.x.abs()).abs().min((1.0 - ndc.y.abs()).abs()).min((ndc.z - near).abs()).min((1.0 - ndc.z).abs());
+                    if margin < 1e-2 {
+                        continue;
+                    }
+
+                    let point = inverse.project_point3(ndc);
+                    let in_planes = frustum.planes().iter().all(|plan| plan.get_signed_distance_to_plan(point) >= 0.0);
+                    assert_eq!(in_planes, inside, "{:?} at ndc {}", depth, ndc);
+                }
+            }
         }
 
-        pub fn add_child(&mut self, mut child: Entity) {
-            child.parent = Some(self.transform);
-            self.children.push(child);
+        #[test]
+        fn obb_plane_test_matches_its_corners() {
+            let mut random = Random(11);
+
+            for (matrix, depth) in frustums() {
+                let frustum = Frustum::from_matrix(matrix, depth);
+
+                for _ in 0..300 {
+                    let obb = random_obb(&mut random);
+                    let corners = obb.get_vertice();
+
+                    for plan in frustum.planes() {
+                        if let Some(expected) = corners_forward(&corners, plan) {
+                            assert_eq!(obb.is_on_or_forward_plan(plan), expected);
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn transformed_obb_matches_its_transformed_corners() {
+            let mut random = Random(13);
+
+            for (matrix, depth) in frustums() {
+                let frustum = Frustum::from_matrix(matrix, depth);
+
+                for _ in 0..200 {
+                    let aabb = AABB::new(random.vec3(-1.0, 1.0), random.vec3(0.1, 2.0));
+                    let mut transform = Transform::new();
+                    transform.set_local_position(random.vec3(-12.0, 12.0));
+                    transform.set_local_rotation(random.vec3(0.0, 6.3));
+                    transform.set_local_scale(random.vec3(0.2, 4.0));
+                    transform.compute_model_matrix();
+
+                    let model = transform.get_model_matrix();
+                    let corners: Vec<Vec3> = aabb.get_vertice().into_iter().map(|corner| model.transform_point3(corner)).collect();
+                    let calls: Vec<Option<bool>> = frustum.planes().iter().map(|plan| corners_forward(&corners, plan)).collect();
+                    if calls.contains(&None) {
+                        continue;
+                    }
+
+                    let expected = calls.iter().all(|&call| call == Some(true));
+                    assert_eq!(aabb.is_on_frustum_transform(&frustum, &transform), expected);
+                }
+            }
         }
 
-        pub fn update_self_and_child(&mut self) {
-            if self.transform.is_dirty() {
-                self.force_update_self_and_child();
+        #[test]
+        fn sphere_plane_test_matches_its_surface_and_never_rejects_a_corner_in_front() {
+            let mut random = Random(17);
+            let unit = sphere_points(1024);
+
+            for (matrix, depth) in frustums() {
+                let frustum = Frustum::from_matrix(matrix, depth);
+
+                for _ in 0..300 {
+                    let corners = random_obb(&mut random).get_vertice();
+                    let sphere = generate_sphere(&corners);
+                    let surface: Vec<Vec3> = unit.iter().map(|&point| sphere.get_center() + point * sphere.get_radius()).collect();
+                    // how far short of the sphere the points can fall, 1 - cos(0.07) with room to spare
+                    let spacing = 0.01 * sphere.get_radius();
+
+                    for plan in frustum.planes() {
+                        let expected = match corners_forward(&surface, plan) {
+                            // the sphere may still reach past the plane between the points
+                            Some(false) if farthest(&surface, plan) > -spacing => continue,
+                            Some(expected) => expected,
+                            None => continue,
+                        };
+                        assert_eq!(sphere.is_on_or_forward_plan(plan), expected);
+
+                        if corners_forward(&corners, plan) == Some(true) {
+                            assert!(sphere.is_on_or_forward_plan(plan));
+                        }
+                    }
+                }
             }
         }
 
-        fn force_update_self_and_child(&mut self) {
-            if let Some(transform) = self.parent {
-                self.transform.compute_relative_model_matrix(transform.get_model_matrix());
-            } else {
-                self.transform.compute_model_matrix();
+        #[test]
+        fn scaled_sphere_holds_the_scaled_corners() {
+            let mut random = Random(19);
+
+            for _ in 0..200 {
+                let corners = AABB::new(random.vec3(-1.0, 1.0), random.vec3(0.1, 2.0)).get_vertice();
+                let sphere = generate_sphere(&corners);
+
+                let mut transform = Transform::new();
+                transform.set_local_position(random.vec3(-5.0, 5.0));
+                transform.set_local_rotation(random.vec3(0.0, 6.3));
+                transform.set_local_scale(random.vec3(0.1, 5.0));
+                transform.compute_model_matrix();
+
+                let model = transform.get_model_matrix();
+                let scaled = Sphere::new(model.transform_point3(sphere.get_center()), sphere.get_radius() * max_stretch(model));
+                for corner in corners {
+                    assert!(scaled.contains(model.transform_point3(corner)));
+                }
             }
+        }
+
+        #[test]
+        fn welzl_sphere_holds_every_point_and_beats_the_box() {
+            let mut random = Random(23);
+
+            for n in [1, 2, 3, 4, 5, 17, 200, 1000] {
+                let stretch = random.vec3(0.1, 5.0);
+                let points: Vec<Vec3> = (0..n).map(|_| random.vec3(-1.0, 1.0) * stretch + Vec3::new(3.0, -2.0, 1.0)).collect();
+                let sphere = generate_sphere(&points);
+
+                for &point in &points {
+                    assert!(sphere.contains(point), "{} points, {} outside", n, point);
+                }
 
-            for child in &mut self.children {
-                child.force_update_self_and_child();
+                let (min, max) = points.iter().fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), &point| (min.min(point), max.max(point)));
+                assert!(sphere.get_radius() <= (max - min).length() * 0.5 * (1.0 + 1e-5) + 1e-6);
             }
+
+            // all on a line and all in a plane take the degenerate paths
+            let line: Vec<Vec3> = (0..20).map(|i| Vec3::new(i as f32, 2.0 * i as f32, 0.0)).collect();
+            let sphere = generate_sphere(&line);
+            assert!((sphere.get_radius() - line[0].distance(line[19]) * 0.5).abs() < 1e-4);
+
+            let square = [Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 0.0, -1.0), Vec3::new(1.0, 0.0, 1.0), Vec3::new(-1.0, 0.0, 1.0)];
+            assert!((generate_sphere(&square).get_radius() - 2f32.sqrt()).abs() < 1e-5);
         }
 
-        pub fn draw_self_and_child(&self, frustum: &Frustum, shader: &Program, display: &mut u32, total: &mut u32) {
-            if self.bounding_volume.is_on_frustum_transform(frustum, &self.transform) {
-                shader.set_mat4("model", self.transform.get_model_matrix());
-                self.p_model.draw(shader);
-                *display += 1;
+        #[test]
+        fn generated_obb_holds_every_point() {
+            let mut random = Random(29);
+            let rotation = Quat::from_euler(EulerRot::XYZ, 0.3, 1.1, -0.7);
+            let points: Vec<Vec3> = (0..300).map(|_| rotation * (random.vec3(-1.0, 1.0) * Vec3::new(6.0, 1.0, 0.3))).collect();
+            let obb = generate_obb(&points);
+
+            let (min, max) = points.iter().fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), &point| (min.min(point), max.max(point)));
+            assert!(obb.volume() <= OBB::from(&AABB::new_minmax(min, max)).volume());
+
+            for &point in &points {
+                let local = point - obb.get_center();
+                for axis in obb.half_axes {
+                    assert!(local.dot(axis).abs() <= axis.length_squared() * (1.0 + 1e-4) + 1e-4);
+                }
             }
-            *total += 1;
+        }
+
+        #[test]
+        fn symmetric_eigen_rebuilds_the_matrix() {
+            let a = Mat3::from_cols(Vec3::new(4.0, 1.0, -2.0), Vec3::new(1.0, 3.0, 0.5), Vec3::new(-2.0, 0.5, 1.0));
+            let (values, vectors) = symmetric_eigen(a);
+            let rebuilt = vectors * Mat3::from_diagonal(values) * vectors.transpose();
 
-            for child in &self.children {
-                child.draw_self_and_child(frustum, shader, display, total);
+            for (x, y) in rebuilt.to_cols_array().iter().zip(a.to_cols_array()) {
+                assert!((x - y).abs() < 1e-4);
             }
+            assert!((max_stretch(Mat4::from_scale(Vec3::new(0.5, 3.0, 2.0))) - 3.0).abs() < 1e-5);
         }
     }
 }
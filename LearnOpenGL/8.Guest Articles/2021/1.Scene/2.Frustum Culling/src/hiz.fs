@@ -0,0 +1,38 @@
+#version 330 core
+out float Depth;
+
+// its base and max level are both the one read from
+uniform sampler2D source;
+uniform ivec2 sourceSize;
+// the first pass copies the depth buffer as it is, later ones keep the farthest of each 2x2 block
+uniform bool copy;
+
+float fetch(ivec2 texel)
+{
+    return texelFetch(source, min(texel, sourceSize - 1), 0).r;
+}
+
+void main()
+{
+    ivec2 texel = ivec2(gl_FragCoord.xy);
+    if (copy)
+    {
+        Depth = fetch(texel);
+        return;
+    }
+
+    ivec2 base = texel * 2;
+    float depth = max(max(fetch(base), fetch(base + ivec2(1, 0))), max(fetch(base + ivec2(0, 1)), fetch(base + ivec2(1, 1))));
+
+    // an odd column or row left over folds into the last texel
+    bool extraX = (sourceSize.x & 1) == 1 && base.x + 3 == sourceSize.x;
+    bool extraY = (sourceSize.y & 1) == 1 && base.y + 3 == sourceSize.y;
+    if (extraX)
+        depth = max(depth, max(fetch(base + ivec2(2, 0)), fetch(base + ivec2(2, 1))));
+    if (extraY)
+        depth = max(depth, max(fetch(base + ivec2(0, 2)), fetch(base + ivec2(1, 2))));
+    if (extraX && extraY)
+        depth = max(depth, fetch(base + ivec2(2, 2)));
+
+    Depth = depth;
+}
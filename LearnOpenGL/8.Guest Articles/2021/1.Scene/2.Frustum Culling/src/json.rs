@@ -0,0 +1,352 @@
+pub mod json {
+    use std::fmt::{self, Display, Formatter, Write};
+
+    // objects keep their keys in file order so a saved file diffs cleanly against the loaded one
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Json {
+        Null,
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Json>),
+        Object(Vec<(String, Json)>),
+    }
+
+    #[derive(Debug)]
+    pub struct ParseError {
+        pub line: usize,
+        pub column: usize,
+        pub message: String,
+    }
+
+    impl Display for ParseError {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+        }
+    }
+
+    impl Json {
+        pub fn parse(text: &str) -> Result<Json, ParseError> {
+            let mut parser = Parser {
+                chars: text.chars().collect(),
+                position: 0,
+            };
+
+            let value = parser.value()?;
+            parser.skip_whitespace();
+
+            if parser.position < parser.chars.len() {
+                return Err(parser.error("trailing characters after the value"));
+            }
+            Ok(value)
+        }
+
+        // through the shortest decimal that reads back as the same f32, so 0.1 isn't written as 0.10000000149011612
+        pub fn from_f32(number: f32) -> Json {
+            Json::Number(number.to_string().parse().unwrap_or(number as f64))
+        }
+
+        pub fn get(&self, key: &str) -> Option<&Json> {
+            match self {
+                Json::Object(entries) => entries.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+                _ => None,
+            }
+        }
+
+        pub fn as_f64(&self) -> Option<f64> {
+            match self {
+                Json::Number(number) => Some(*number),
+                _ => None,
+            }
+        }
+
+        pub fn as_str(&self) -> Option<&str> {
+            match self {
+                Json::String(string) => Some(string),
+                _ => None,
+            }
+        }
+
+        pub fn as_array(&self) -> Option<&[Json]> {
+            match self {
+                Json::Array(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_object(&self) -> Option<&[(String, Json)]> {
+            match self {
+                Json::Object(entries) => Some(entries),
+                _ => None,
+            }
+        }
+
+        // numbers and short arrays of them stay on one line, everything else is indented by two spaces a level
+        pub fn pretty(&self) -> String {
+            let mut out = String::new();
+            self.write(&mut out, 0);
+            out.push('\n');
+            out
+        }
+
+        fn is_scalar(&self) -> bool {
+            !matches!(self, Json::Array(_) | Json::Object(_))
+        }
+
+        fn write(&self, out: &mut String, indent: usize) {
+            let pad = |out: &mut String, indent: usize| out.extend(std::iter::repeat_n(' ', indent * 2));
+
+            match self {
+                Json::Null => out.push_str("null"),
+                Json::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+                Json::Number(number) if number.is_finite() => write!(out, "{number}").unwrap(),
+                Json::Number(_) => out.push_str("null"),
+                Json::String(string) => write_string(out, string),
+                Json::Array(values) if values.is_empty() => out.push_str("[]"),
+                Json::Array(values) if values.iter().all(Json::is_scalar) => {
+                    out.push('[');
+                    for (i, value) in values.iter().enumerate() {
+                        if i > 0 {
+                            out.push_str(", ");
+                        }
+                        value.write(out, indent);
+                    }
+                    out.push(']');
+                }
+                Json::Array(values) => {
+                    out.push_str("[\n");
+                    for (i, value) in values.iter().enumerate() {
+                        pad(out, indent + 1);
+                        value.write(out, indent + 1);
+                        out.push_str(if i + 1 < values.len() { ",\n" } else { "\n" });
+                    }
+                    pad(out, indent);
+                    out.push(']');
+                }
+                Json::Object(entries) if entries.is_empty() => out.push_str("{}"),
+                Json::Object(entries) => {
+                    out.push_str("{\n");
+                    for (i, (key, value)) in entries.iter().enumerate() {
+                        pad(out, indent + 1);
+                        write_string(out, key);
+                        out.push_str(": ");
+                        value.write(out, indent + 1);
+                        out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+                    }
+                    pad(out, indent);
+                    out.push('}');
+                }
+            }
+        }
+    }
+
+    fn write_string(out: &mut String, string: &str) {
+        out.push('"');
+        for c in string.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+
+    struct Parser {
+        chars: Vec<char>,
+        position: usize,
+    }
+
+    impl Parser {
+        fn error(&self, message: &str) -> ParseError {
+            let before = &self.chars[..self.position.min(self.chars.len())];
+            let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+            let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+
+            ParseError {
+                line,
+                column,
+                message: message.to_string(),
+            }
+        }
+
+        fn peek(&self) -> Option<char> {
+            self.chars.get(self.position).copied()
+        }
+
+        fn skip_whitespace(&mut self) {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.position += 1;
+            }
+        }
+
+        fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+            self.skip_whitespace();
+            if self.peek() == Some(expected) {
+                self.position += 1;
+                Ok(())
+            } else {
+                Err(self.error(&format!("expected '{expected}'")))
+            }
+        }
+
+        fn keyword(&mut self, word: &str, value: Json) -> Result<Json, ParseError> {
+            if self.chars[self.position..].iter().take(word.len()).copied().eq(word.chars()) {
+                self.position += word.len();
+                Ok(value)
+            } else {
+                Err(self.error("unexpected character"))
+            }
+        }
+
+        fn value(&mut self) -> Result<Json, ParseError> {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None => Err(self.error("unexpected end of file")),
+                Some('{') => self.object(),
+                Some('[') => self.array(),
+                Some('"') => Ok(Json::String(self.string()?)),
+                Some('t') => self.keyword("true", Json::Bool(true)),
+                Some('f') => self.keyword("false", Json::Bool(false)),
+                Some('n') => self.keyword("null", Json::Null),
+                Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+                Some(_) => Err(self.error("unexpected character")),
+            }
+        }
+
+        fn object(&mut self) -> Result<Json, ParseError> {
+            self.expect('{')?;
+            let mut entries = vec![];
+
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                self.position += 1;
+                return Ok(Json::Object(entries));
+            }
+
+            loop {
+                self.skip_whitespace();
+                if self.peek() != Some('"') {
+                    return Err(self.error("expected a key"));
+                }
+                let key = self.string()?;
+
+                if entries.iter().any(|(name, _)| *name == key) {
+                    return Err(self.error(&format!("duplicate key \"{key}\"")));
+                }
+
+                self.expect(':')?;
+                entries.push((key, self.value()?));
+
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(',') => self.position += 1,
+                    Some('}') => {
+                        self.position += 1;
+                        return Ok(Json::Object(entries));
+                    }
+                    _ => return Err(self.error("expected ',' or '}'")),
+                }
+            }
+        }
+
+        fn array(&mut self) -> Result<Json, ParseError> {
+            self.expect('[')?;
+            let mut values = vec![];
+
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.position += 1;
+                return Ok(Json::Array(values));
+            }
+
+            loop {
+                values.push(self.value()?);
+
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(',') => self.position += 1,
+                    Some(']') => {
+                        self.position += 1;
+                        return Ok(Json::Array(values));
+                    }
+                    _ => return Err(self.error("expected ',' or ']'")),
+                }
+            }
+        }
+
+        fn string(&mut self) -> Result<String, ParseError> {
+            self.position += 1;
+            let mut string = String::new();
+
+            loop {
+                let c = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                self.position += 1;
+
+                match c {
+                    '"' => return Ok(string),
+                    '\\' => {
+                        let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                        self.position += 1;
+
+                        string.push(match escaped {
+                            '"' => '"',
+                            '\\' => '\\',
+                            '/' => '/',
+                            'b' => '\u{8}',
+                            'f' => '\u{c}',
+                            'n' => '\n',
+                            'r' => '\r',
+                            't' => '\t',
+                            'u' => self.unicode()?,
+                            _ => return Err(self.error("unknown escape")),
+                        });
+                    }
+                    c => string.push(c),
+                }
+            }
+        }
+
+        fn unicode(&mut self) -> Result<char, ParseError> {
+            let code = self.hex4()?;
+
+            // surrogate pairs, for characters past the first plane
+            if (0xD800..0xDC00).contains(&code) && self.chars[self.position..].starts_with(&['\\', 'u']) {
+                self.position += 2;
+                let low = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(self.error("bad surrogate pair"));
+                }
+                return char::from_u32(0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)).ok_or_else(|| self.error("bad surrogate pair"));
+            }
+
+            char::from_u32(code).ok_or_else(|| self.error("bad \\u escape"))
+        }
+
+        fn hex4(&mut self) -> Result<u32, ParseError> {
+            // from_str_radix would also take a sign or fewer than four digits
+            let digits = &self.chars[self.position..];
+            if digits.len() < 4 || !digits[..4].iter().all(char::is_ascii_hexdigit) {
+                return Err(self.error("bad \\u escape"));
+            }
+            let code = digits[..4].iter().fold(0, |code, digit| code * 16 + digit.to_digit(16).unwrap());
+            self.position += 4;
+            Ok(code)
+        }
+
+        fn number(&mut self) -> Result<Json, ParseError> {
+            let start = self.position;
+            while self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+                self.position += 1;
+            }
+
+            let text: String = self.chars[start..self.position].iter().collect();
+            text.parse().map(Json::Number).map_err(|_| self.error(&format!("bad number {text}")))
+        }
+    }
+}
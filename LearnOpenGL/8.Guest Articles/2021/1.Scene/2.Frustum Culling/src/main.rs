@@ -1,23 +1,52 @@
-use glam::{Mat4, Vec3};
-use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
+use glam::{Vec2, Vec3, Vec4};
+use glfw::{Action, Context, CursorMode, Key, MouseButton, Window, WindowEvent, WindowHint};
 use stb_image::stb_image::bindgen;
-use std::rc::Rc;
+use std::{collections::HashSet, rc::Rc};
 
 const SCR_WIDTH: u32 = 800;
 const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
-
+const SCENE_PATH: &str = "resources/scenes/planets.json";
+const OUTLINE_SCALE: f32 = 1.05;
+const KEPT_COLOR: Vec4 = Vec4::new(0.2, 0.9, 0.3, 1.0);
+const CULLED_COLOR: Vec4 = Vec4::new(0.9, 0.2, 0.2, 1.0);
+const SELECTED_COLOR: Vec4 = Vec4::new(1.0, 0.8, 0.1, 1.0);
+
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
+pub mod bvh;
+use bvh::bvh::{BoundingBox, Bvh, ProxyId, Ray, Visibility};
 pub mod camera;
 use camera::camera::{Camera, Movement};
+pub mod components;
+use components::components::{ActiveCamera, Bounds, Extras, Hierarchy, Light, LightKind, MeshRenderer, Name, RenderView, Viewport};
+pub mod debug_draw;
+use debug_draw::debug_draw::DebugDraw;
+pub mod ecs;
+use ecs::ecs::{EntityId, Schedule, System, World};
+pub mod font;
+use font::font::{strokes, GLYPH_ADVANCE};
 pub mod entity;
-use entity::entity::{create_frustum_from_camera, Entity};
+use entity::entity::{generate_aabb, generate_obb, generate_sphere, positions, BoundingVolume, ClipDepth, Frustum, Sphere, Transform, AABB, OBB};
+pub mod json;
+use json::json::{Json, ParseError};
 pub mod mesh;
 use mesh::mesh::{stbi_flip_vertical, Mesh, Texture, VertexArray};
 pub mod model;
-use model::model::{Model, Vertex};
+use model::model::{LodSettings, Model, Vertex};
+pub mod occlusion;
+use occlusion::occlusion::{CullStats, OcclusionCuller, OcclusionMode};
+pub mod picking;
+use picking::picking::{cursor_ray, draw_outline, pick, IdBuffer};
+pub mod scene;
+use scene::scene::{bounds_system, camera_system, transform_system, NodeId};
+pub mod scene_file;
+use scene_file::scene_file::{ModelLibrary, NodeDescription, SceneDescription, SceneError};
 pub mod shader;
 use shader::shader::Program;
+pub mod simplify;
+use simplify::simplify::simplify;
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
@@ -43,72 +72,263 @@ fn main() {
 
     ogl::depth_enable(true);
 
-    let _quad = VertexArray::new_quad();
-    let _cube = VertexArray::new_cube();
-
-    let shader = Program::new().link("src/1.model_loading.vs", "src/1.model_loading.fs");
-
-    let planet = Rc::new(Model::new().load_model("resources/objects/planet/planet.obj"));
-    let mut entity = Entity::from(&planet);
-    entity.transform.set_local_position(Vec3::new(0.0, 0.0, 0.0));
-    let scale = 1.0;
-    entity.transform.set_local_scale(Vec3::splat(scale));
-
-    for x in 0..20 {
-        for z in 0..20 {
-            let mut child = Entity::from(&planet);
-            child.transform.set_local_position(Vec3::new(x as f32 * 10.0 - 100.0, 0.0, z as f32 * 10.0 - 100.0));
-            entity.add_child(child);
+    let quad = VertexArray::new_quad();
+    let cube = VertexArray::new_cube();
+
+    let shader = Rc::new(Program::new().link("src/1.model_loading.vs", "src/1.model_loading.fs"));
+    let picking_shader = Program::new().link("src/1.model_loading.vs", "src/picking.fs");
+    // also what the occlusion query boxes are drawn with
+    let outline_shader = Rc::new(Program::new().link("src/1.model_loading.vs", "src/outline.fs"));
+    let debug_draw_shader = Program::new().link("src/debug_draw.vs", "src/debug_draw.fs");
+    let mut debug = DebugDraw::new();
+
+    let (fb_width, fb_height) = window.get_framebuffer_size();
+    let mut id_buffer = IdBuffer::new(fb_width, fb_height);
+
+    let mut description = match SceneDescription::load(SCENE_PATH) {
+        Ok(description) => description,
+        Err(SceneError::Io { error, .. }) if error.kind() == std::io::ErrorKind::NotFound => {
+            let description = planet_grid();
+            if let Err(error) = std::fs::create_dir_all("resources/scenes").map_err(|error| error.to_string()).and_then(|_| description.save(SCENE_PATH).map_err(|error| error.to_string())) {
+                println!("Failed to write the default scene: {error}");
+            }
+            description
+        }
+        Err(error) => {
+            println!("Failed to load the scene, using the default one: {error}");
+            planet_grid()
         }
+    };
+
+    let mut world = World::new_scene();
+    let mut library = ModelLibrary::new();
+    if let Err(error) = description.instantiate(&mut world, &mut library, |path| Model::new().lods(LodSettings::default()).load_model(path)) {
+        panic!("Failed to build the scene: {error}");
     }
-    entity.update_self_and_child();
 
     //ogl::polygon_mode();
 
     glfw.poll_events();
 
+    // the first camera in the scene file is the one looked through, a default one is added when there is none
     let (x_pos, y_pos) = window.get_cursor_pos();
-    let mut camera = Camera::new(Vec3::new(0.0, 10.0, 0.0), x_pos as f32, y_pos as f32);
+    let first_camera = world.read::<Camera>().ids().first().copied();
+    let camera_id = match first_camera {
+        Some(id) => id,
+        None => {
+            let id = world.spawn();
+            world.insert(id, Name("main".to_string()));
+            world.insert(id, Camera::new(Vec3::new(0.0, 10.0, 0.0), x_pos as f32, y_pos as f32));
+            id
+        }
+    };
+    {
+        let mut camera = world.get_mut::<Camera>(camera_id).unwrap();
+        camera.speed = 20.0;
+        camera.set_cursor(x_pos as f32, y_pos as f32);
+    }
     let mut camera_spy = Camera::new(Vec3::new(0.0, 10.0, 0.0), x_pos as f32, y_pos as f32);
-    camera.speed = 20.0;
+
+    world.insert_resource(ActiveCamera(camera_id));
+    world.insert_resource(Viewport {
+        width: fb_width,
+        height: fb_height,
+        near: SCR_NEAR,
+        far: SCR_FAR,
+    });
+    // o cycles between no occlusion culling, hi-z and occlusion queries
+    world.insert_resource(OcclusionMode::HiZ);
+
+    // the frame as systems, each reads what the ones before it left in the world
+    let mut update = Schedule::new()
+        .with("camera", camera_system)
+        .with("transforms", transform_system)
+        .with("bounds", bounds_system);
+    let mut render = Schedule::new().with("render", OcclusionCuller::new(Rc::clone(&shader), Rc::clone(&outline_shader), cube, quad));
 
     let mut last_frame = 0.0;
+    let mut save_held = false;
+
+    // tab frees the cursor to click on planets, i picks through the id buffer instead of ray casts
+    let mut free_cursor = Toggle::default();
+    let mut id_picking = Toggle::default();
+    let mut click_held = false;
+    let mut selected = None;
+
+    let mut cycle_occlusion = Toggle::default();
+    // c prints what each culling stage took out of the frame
+    let mut print_stats = Toggle::default();
+    // b draws the world boxes, green where the frustum keeps them and red where it culls
+    let mut show_bounds = Toggle::default();
 
     while !window.should_close() {
         let current_frame = glfw.get_time() as f32;
         let delta_time = current_frame - last_frame;
         last_frame = current_frame;
 
-        process_input(&mut camera, &mut window, delta_time);
+        process_input(&mut world.get_mut::<Camera>(camera_id).unwrap(), &mut window, delta_time);
+
+        let save = window.get_key(Key::F5) == Action::Press;
+        if save && !save_held {
+            save_scene(&mut description, &world, &library);
+        }
+        save_held = save;
+
+        let was_free = free_cursor.is_on;
+        free_cursor.update(window.get_key(Key::Tab) == Action::Press);
+        id_picking.update(window.get_key(Key::I) == Action::Press);
+
+        let was_on = cycle_occlusion.is_on;
+        cycle_occlusion.update(window.get_key(Key::O) == Action::Press);
+        if cycle_occlusion.is_on != was_on {
+            let mut mode = world.resource_mut::<OcclusionMode>();
+            *mode = mode.next();
+            println!("Occlusion culling: {:?}", *mode);
+        }
+        if free_cursor.is_on != was_free {
+            window.set_cursor_mode(if free_cursor.is_on { CursorMode::Normal } else { CursorMode::Disabled });
+            let (x_pos, y_pos) = window.get_cursor_pos();
+            world.get_mut::<Camera>(camera_id).unwrap().set_cursor(x_pos as f32, y_pos as f32);
+        }
+
+        let (fb_width, fb_height) = window.get_framebuffer_size();
+        {
+            let mut viewport = world.resource_mut::<Viewport>();
+            viewport.width = fb_width;
+            viewport.height = fb_height;
+        }
+
+        //world.get_mut::<Transform>(root).unwrap().set_local_rotation(Vec3::new(0.0, world.get::<Transform>(root).unwrap().get_local_rotation().y + PI / 9.0 * delta_time, 0.0));
+        update.run(&mut world);
 
         ogl::clear_color(0.05, 0.05, 0.05, 1.0);
         ogl::clear();
 
-        shader.apply();
+        camera_spy.process_mouse(2.0, 0.0, true);
 
-        let projection = Mat4::perspective_lh(camera.zoom(), SCR_WIDTH as f32 / SCR_HEIGHT as f32, SCR_NEAR, SCR_FAR);
-        let cam_frustum = create_frustum_from_camera(&camera, SCR_WIDTH as f32 / SCR_HEIGHT as f32, camera.zoom(), SCR_NEAR, SCR_FAR);
+        let (projection, view) = {
+            let render_view = world.resource::<RenderView>();
+            (render_view.projection, render_view.view)
+        };
 
-        camera_spy.process_mouse(2.0, 0.0, true);
+        let click = window.get_mouse_button(MouseButton::Button1) == Action::Press;
+        if click && !click_held && free_cursor.is_on {
+            let (x_pos, y_pos) = window.get_cursor_pos();
+            let (width, height) = window.get_size();
+
+            let picked = if id_picking.is_on {
+                id_buffer.resize(fb_width, fb_height);
+                id_buffer.render(&world, &world.resource::<RenderView>().frustum, &picking_shader, projection, view);
+                ogl::viewport(fb_width, fb_height);
+
+                id_buffer.read((x_pos * fb_width as f64 / width as f64) as i32, (y_pos * fb_height as f64 / height as f64) as i32)
+            } else {
+                let ray = cursor_ray(x_pos as f32, y_pos as f32, width as f32, height as f32, projection, view);
+                pick(&world, &ray, SCR_FAR).map(|(id, _)| id)
+            };
+
+            selected = picked;
+            match picked.and_then(|id| world.get::<Name>(id)) {
+                Some(name) => println!("Picked {}.", name.0),
+                None => println!("Picked nothing."),
+            }
+        }
+        click_held = click;
 
-        let view = camera.view_matrix();
-        shader.set_mat4("projection", projection);
-        shader.set_mat4("view", view);
+        render.run(&mut world);
 
-        let mut total = 0;
-        let mut display = 0;
-        entity.draw_self_and_child(&cam_frustum, &shader, &mut display, &mut total);
-        println!("Total process in CPU : {} / Total send to GPU : {}", total, display);
+        let was_printed = print_stats.is_on;
+        print_stats.update(window.get_key(Key::C) == Action::Press);
+        if print_stats.is_on != was_printed {
+            println!("{}", *world.resource::<CullStats>());
+        }
+
+        if let Some(id) = selected {
+            outline_shader.apply();
+            outline_shader.set_mat4("projection", projection);
+            outline_shader.set_mat4("view", view);
+
+            draw_outline(&world, id, &shader, &outline_shader, OUTLINE_SCALE);
+        }
 
-        //entity.transform.set_local_rotation(Vec3::new(0.0, entity.transform.get_local_rotation().y + PI / 9.0 * delta_time, 0.0));
-        entity.update_self_and_child();
+        show_bounds.update(window.get_key(Key::B) == Action::Press);
+        if show_bounds.is_on {
+            draw_bounds(&mut debug, &world, selected);
+        }
+        debug.flush(&debug_draw_shader, projection, view);
 
         window.swap_buffers();
 
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut camera, &mut window, event);
+            handle_window_event(&mut world.get_mut::<Camera>(camera_id).unwrap(), &mut window, event, !free_cursor.is_on);
+        }
+    }
+}
+
+// a root planet with a 20 by 20 grid of planets below it, what the sample shows when there is no scene file
+fn planet_grid() -> SceneDescription {
+    let mut root = NodeDescription::new("root", "planet");
+
+    for x in 0..20 {
+        for z in 0..20 {
+            let mut child = NodeDescription::new(&format!("planet {x} {z}"), "planet");
+            child.position = Vec3::new(x as f32 * 10.0 - 100.0, 0.0, z as f32 * 10.0 - 100.0);
+            root.children.push(child);
+        }
+    }
+
+    SceneDescription {
+        models: vec![("planet".to_string(), "resources/objects/planet/planet.obj".to_string())],
+        nodes: vec![root],
+        ..Default::default()
+    }
+}
+
+// the selected entity also gets the oriented box the frustum test decides with
+fn draw_bounds(debug: &mut DebugDraw, world: &World, selected: Option<EntityId>) {
+    let visible: HashSet<NodeId> = {
+        let render_view = world.resource::<RenderView>();
+        world.visible(&render_view.frustum, render_view.eye).into_iter().collect()
+    };
+
+    world.query::<(&Transform, &Bounds)>().for_each(|id, (transform, bounds)| {
+        let world_box = bounds.world();
+        debug.aabb(world_box.min, world_box.max, if visible.contains(&id) { KEPT_COLOR } else { CULLED_COLOR });
+
+        if selected == Some(id) {
+            let obb = bounds.world_obb(transform);
+            debug.obb(obb.get_center(), obb.get_half_axes(), SELECTED_COLOR);
+        }
+    });
+}
+
+// writes the world as it is now over the file it was loaded from, cameras included
+fn save_scene(description: &mut SceneDescription, world: &World, library: &ModelLibrary) {
+    if let Err(error) = description.capture(world, library) {
+        println!("Failed to capture the scene: {error}");
+        return;
+    }
+
+    match description.save(SCENE_PATH) {
+        Ok(()) => println!("Saved the scene to {SCENE_PATH}."),
+        Err(error) => println!("Failed to save the scene: {error}"),
+    }
+}
+
+#[derive(Default)]
+pub struct Toggle {
+    pub is_on: bool,
+    pub pressed: bool,
+}
+
+impl Toggle {
+    // flips once per key press
+    pub fn update(&mut self, pressed: bool) {
+        if pressed && !self.pressed {
+            self.is_on = !self.is_on;
         }
+        self.pressed = pressed;
     }
 }
 
@@ -131,11 +351,11 @@ fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     }
 }
 
-fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, event: glfw::WindowEvent) {
+fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, event: glfw::WindowEvent, look: bool) {
     match event {
         WindowEvent::FramebufferSize(width, height) => ogl::viewport(width, height),
         WindowEvent::Scroll(_, y_offset) => camera.process_scroll(y_offset as f32),
-        WindowEvent::CursorPos(_x_pos, _y_pos) => {
+        WindowEvent::CursorPos(_x_pos, _y_pos) if look => {
             let (x_pos, y_pos) = window.get_cursor_pos();
 
             camera.process_mouse(x_pos as f32, y_pos as f32, true);
@@ -1,21 +1,48 @@
 pub mod mesh {
-    use crate::{bindgen, Program, Vertex};
+    use crate::{bindgen, interleave, Attribute, MeshBuilder, Program, Vertex};
     use glfw::with_c_str;
     use std::{fs::File, io::Read, mem::size_of, ptr, rc::Rc};
 
     const FLOAT_SIZE: usize = size_of::<f32>();
     const U32_SIZE: usize = size_of::<u32>();
 
+    // a range of the element buffer, every level indexes the same vertices
+    #[derive(Clone, Copy)]
+    pub struct Lod {
+        pub offset: usize,
+        pub count: usize,
+        pub error: f32,
+    }
+
     pub struct Mesh {
         pub vertices: Vec<Vertex>,
+        // the most detailed level, kept for picking against triangles
         indices: Vec<u32>,
+        lods: Vec<Lod>,
         textures: Vec<Rc<Texture>>,
         vao: VertexArray,
     }
 
     impl Mesh {
         pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, textures: Vec<Rc<Texture>>) -> Self {
+            Mesh::with_lods(vertices, vec![(indices, 0.0)], textures)
+        }
+
+        // levels from the most detailed down, with the error each one was simplified to
+        pub fn with_lods(vertices: Vec<Vertex>, levels: Vec<(Vec<u32>, f32)>, textures: Vec<Rc<Texture>>) -> Self {
             let vao = VertexArray::new();
+            let full = levels.first().map_or(vec![], |(level, _)| level.clone());
+
+            let mut lods = vec![];
+            let mut indices = vec![];
+            for (level, error) in levels {
+                lods.push(Lod {
+                    offset: indices.len(),
+                    count: level.len(),
+                    error,
+                });
+                indices.extend(level);
+            }
 
             unsafe {
                 gl::BindVertexArray(vao.vertex_array);
@@ -55,10 +82,31 @@ pub mod mesh {
                 gl::BindVertexArray(0);
             }
 
-            Mesh { vertices, indices, textures, vao }
+            Mesh {
+                vertices,
+                indices: full,
+                lods,
+                textures,
+                vao,
+            }
+        }
+
+        pub fn lods(&self) -> &[Lod] {
+            &self.lods
+        }
+
+        pub fn indices(&self) -> &[u32] {
+            &self.indices
         }
 
         pub fn draw(&self, shader: &Program) {
+            self.draw_lod(shader, 0);
+        }
+
+        // levels past the last one this mesh has draw its coarsest
+        pub fn draw_lod(&self, shader: &Program, level: usize) {
+            let lod = self.lods[level.min(self.lods.len() - 1)];
+
             let mut diffuse = 1u32;
             let mut specular = 1u32;
             let mut normal = 1u32;
@@ -99,31 +147,23 @@ pub mod mesh {
                 }
 
                 gl::BindVertexArray(self.vao.vertex_array);
-                gl::DrawElements(gl::TRIANGLES, self.indices.len() as i32, gl::UNSIGNED_INT, ptr::null());
+                gl::DrawElements(gl::TRIANGLES, lod.count as i32, gl::UNSIGNED_INT, ((lod.offset * U32_SIZE) as *const usize).cast());
 
                 gl::ActiveTexture(gl::TEXTURE0);
             }
         }
 
-        pub fn draw_instanced(&self, amount: i32) {
+        pub fn draw_instanced(&self, level: usize, amount: i32) {
+            let lod = self.lods[level.min(self.lods.len() - 1)];
+
             unsafe {
                 gl::BindVertexArray(self.vao.vertex_array);
-                gl::DrawElementsInstanced(gl::TRIANGLES, self.indices.len() as i32, gl::UNSIGNED_INT, ptr::null(), amount);
+                gl::DrawElementsInstanced(gl::TRIANGLES, lod.count as i32, gl::UNSIGNED_INT, ((lod.offset * U32_SIZE) as *const usize).cast(), amount);
                 gl::BindVertexArray(0);
             }
         }
     }
 
-    const INDEX_COUNT: usize = 36;
-    const INDICES: [u32; INDEX_COUNT] = [
-        0, 1, 2, 2, 1, 3, // first quad
-        4, 5, 6, 6, 5, 7, // second quad
-        8, 9, 10, 10, 9, 11, // third quad
-        12, 13, 14, 14, 13, 15, // fourth quad
-        16, 17, 18, 18, 17, 19, // fifth quad
-        20, 21, 22, 22, 21, 23, // sixth quad
-    ];
-
     pub struct VertexArray {
         vertex_array: gl::types::GLuint,
         vertex_buffer: gl::types::GLuint,
@@ -151,111 +191,44 @@ pub mod mesh {
             }
         }
 
-        pub fn new_quad() -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
+        // the attributes go to locations 0, 1, 2... in the order given
+        pub fn from_builder(builder: MeshBuilder, attributes: &[Attribute]) -> Self {
+            let mut vao = VertexArray::new();
 
-            let vertices: Vec<f32> = vec![-1.0, -1.0, 0.0, 0.0, 0.0, 1.0, -1.0, 0.0, 1.0, 0.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0];
-            let indices = Vec::from(&INDICES[..6]);
+            let (vertices, indices) = builder.build();
+            let vertices = interleave(&vertices, attributes);
+            let stride: usize = attributes.iter().map(|attribute| attribute.components()).sum();
 
             unsafe {
-                gl::GenVertexArrays(1, &mut vertex_array);
-                gl::GenBuffers(1, &mut vertex_buffer);
-                gl::GenBuffers(1, &mut index_buffer);
-
-                gl::BindVertexArray(vertex_array);
+                gl::BindVertexArray(vao.vertex_array);
 
-                gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vao.vertex_buffer);
                 gl::BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
 
-                gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ptr::null());
-                gl::EnableVertexAttribArray(0);
-
-                gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl::EnableVertexAttribArray(1);
+                let mut offset = 0;
+                for (location, attribute) in attributes.iter().enumerate() {
+                    let components = attribute.components();
+                    gl::VertexAttribPointer(location as u32, components as i32, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * stride) as i32, ((offset * FLOAT_SIZE) as *const usize).cast());
+                    gl::EnableVertexAttribArray(location as u32);
+                    offset += components;
+                }
 
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vao.index_buffer);
                 gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
 
                 gl::BindVertexArray(0);
             }
 
-            VertexArray {
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-            }
+            vao.index_count = indices.len();
+            vao
         }
 
-        pub fn new_cube() -> Self {
-            let mut vertex_array = 0;
-            let mut vertex_buffer = 0;
-            let mut index_buffer = 0;
-
-            let vertices: Vec<f32> = vec![
-                // back face
-                1.0, -1.0, -1.0, 1.0, 0.0, // bottom-right
-                -1.0, -1.0, -1.0, 0.0, 0.0, // bottom-left
-                1.0, 1.0, -1.0, 1.0, 1.0, // top-right
-                -1.0, 1.0, -1.0, 0.0, 1.0, // top-left
-                // front face
-                -1.0, -1.0, 1.0, 0.0, 0.0, // bottom-left
-                1.0, -1.0, 1.0, 1.0, 0.0, // bottom-right
-                -1.0, 1.0, 1.0, 0.0, 1.0, // top-left
-                1.0, 1.0, 1.0, 1.0, 1.0, // top-right
-                // left face
-                -1.0, -1.0, -1.0, 0.0, 1.0, // bottom-left
-                -1.0, -1.0, 1.0, 0.0, 0.0, // bottom-right
-                -1.0, 1.0, -1.0, 1.0, 1.0, // top-left
-                -1.0, 1.0, 1.0, 1.0, 0.0, // top-right
-                // right face
-                1.0, -1.0, 1.0, 0.0, 0.0, // bottom-left
-                1.0, -1.0, -1.0, 0.0, 1.0, // bottom-right
-                1.0, 1.0, 1.0, 1.0, 0.0, // top-left
-                1.0, 1.0, -1.0, 1.0, 1.0, // top-right
-                // bottom face
-                1.0, -1.0, 1.0, 1.0, 0.0, // bottom-left
-                -1.0, -1.0, 1.0, 0.0, 0.0, // bottom-right
-                1.0, -1.0, -1.0, 1.0, 1.0, // top-left
-                -1.0, -1.0, -1.0, 0.0, 1.0, // top-right
-                // top face
-                -1.0, 1.0, 1.0, 0.0, 0.0, // bottom-left
-                1.0, 1.0, 1.0, 1.0, 0.0, // bottom-right
-                -1.0, 1.0, -1.0, 0.0, 1.0, // top-left
-                1.0, 1.0, -1.0, 1.0, 1.0, // top-right
-            ];
-            let indices = Vec::from(&INDICES[..]);
-
-            unsafe {
-                gl::GenVertexArrays(1, &mut vertex_array);
-                gl::GenBuffers(1, &mut vertex_buffer);
-                gl::GenBuffers(1, &mut index_buffer);
-
-                gl::BindVertexArray(vertex_array);
-
-                gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
-                gl::BufferData(gl::ARRAY_BUFFER, (FLOAT_SIZE * vertices.len()) as isize, vertices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ptr::null());
-                gl::EnableVertexAttribArray(0);
-
-                gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, (FLOAT_SIZE * 5) as i32, ((3 * FLOAT_SIZE) as *const usize).cast());
-                gl::EnableVertexAttribArray(1);
-
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer);
-                gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (indices.len() * U32_SIZE) as isize, indices.as_ptr().cast(), gl::STATIC_DRAW);
-
-                gl::BindVertexArray(0);
-            }
+        pub fn new_quad() -> Self {
+            VertexArray::from_builder(MeshBuilder::rectangle(2.0, 2.0), &[Attribute::Position, Attribute::TexCoords])
+        }
 
-            VertexArray {
-                vertex_array,
-                vertex_buffer,
-                index_buffer,
-                index_count: indices.len(),
-            }
+        pub fn new_cube() -> Self {
+            VertexArray::from_builder(MeshBuilder::cube(2.0), &[Attribute::Position, Attribute::TexCoords])
         }
 
         pub fn bind(&self) {
@@ -1,5 +1,5 @@
 pub mod model {
-    use crate::{Mesh, Program, Texture};
+    use crate::{simplify, Mesh, Program, Texture};
     use glam::{Vec2, Vec3};
     use russimp::{
         material::Material,
@@ -23,10 +23,35 @@ pub mod model {
         pub weights: [f32; MAX_BONE_INFLUENCE],
     }
 
+    pub struct LodSettings {
+        // levels after the first, each keeping this fraction of the previous one's triangles
+        pub levels: usize,
+        pub reduction: f32,
+        // relative to the mesh's extent, the chain ends early at the first level that can't get smaller within it
+        pub max_error: f32,
+        // projected bounding sphere diameter over screen height below which each level after the first is drawn
+        pub screen_sizes: Vec<f32>,
+        // fraction of each screen size around it over which the two levels cross-fade, 0 switches at once
+        pub fade_range: f32,
+    }
+
+    impl Default for LodSettings {
+        fn default() -> Self {
+            LodSettings {
+                levels: 3,
+                reduction: 0.5,
+                max_error: 0.05,
+                screen_sizes: vec![0.4, 0.2, 0.1],
+                fade_range: 0.2,
+            }
+        }
+    }
+
     pub struct Model {
         textures_loaded: Vec<Rc<Texture>>,
         pub meshes: Vec<Mesh>,
         directory: String,
+        lod_settings: Option<LodSettings>,
     }
 
     impl Model {
@@ -35,9 +60,20 @@ pub mod model {
                 textures_loaded: vec![],
                 meshes: vec![],
                 directory: "".to_string(),
+                lod_settings: None,
             }
         }
 
+        // set before load_model, which then simplifies every mesh into a chain of levels
+        pub fn lods(mut self, settings: LodSettings) -> Self {
+            self.lod_settings = Some(settings);
+            self
+        }
+
+        pub fn lod_settings(&self) -> Option<&LodSettings> {
+            self.lod_settings.as_ref()
+        }
+
         pub fn load_model(mut self, path: &str) -> Self {
             let scene = Scene::from_file(
                 path,
@@ -129,7 +165,16 @@ pub mod model {
             let mut height_maps = self.load_material_textures(material, TextureType::Ambient, "texture_height");
             textures.append(&mut height_maps);
 
-            Mesh::new(vertices, indices, textures)
+            match &self.lod_settings {
+                Some(settings) => {
+                    let levels = generate_lods(&vertices, indices, settings);
+                    let triangles: Vec<String> = levels.iter().map(|(level, _)| (level.len() / 3).to_string()).collect();
+                    println!("{}: LOD triangles {}", mesh.name, triangles.join(" / "));
+
+                    Mesh::with_lods(vertices, levels, textures)
+                }
+                None => Mesh::new(vertices, indices, textures),
+            }
         }
 
         fn load_material_textures(&mut self, material: &Material, tex_type: TextureType, type_name: &str) -> Vec<Rc<Texture>> {
@@ -160,6 +205,17 @@ pub mod model {
             }
         }
 
+        // fade > 0 dithers out that fraction of the pixels, fade < 0 draws only the pixels -fade would have removed
+        pub fn draw_lod(&self, shader: &Program, level: usize, fade: f32) {
+            shader.set_float("lodFade", fade);
+
+            for mesh in &self.meshes {
+                mesh.draw_lod(shader, level)
+            }
+
+            shader.set_float("lodFade", 0.0);
+        }
+
         pub fn bind_texture(&self) {
             unsafe {
                 gl::ActiveTexture(gl::TEXTURE0);
@@ -167,4 +223,22 @@ pub mod model {
             }
         }
     }
+
+    // every level is simplified from the full mesh, so errors don't pile up down the chain
+    fn generate_lods(vertices: &[Vertex], indices: Vec<u32>, settings: &LodSettings) -> Vec<(Vec<u32>, f32)> {
+        let mut target = indices.len() as f32;
+        let mut levels = vec![(indices, 0.0)];
+
+        for _ in 0..settings.levels {
+            target *= settings.reduction;
+
+            let (level, error) = simplify(vertices, &levels[0].0, (target as usize) / 3 * 3, settings.max_error);
+            if level.len() >= levels.last().unwrap().0.len() {
+                break;
+            }
+            levels.push((level, error));
+        }
+
+        levels
+    }
 }
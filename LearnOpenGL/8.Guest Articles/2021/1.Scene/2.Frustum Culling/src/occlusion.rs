@@ -0,0 +1,585 @@
+pub mod occlusion {
+    use crate::{BoundingBox, Bounds, MeshRenderer, NodeId, Program, RenderView, System, Transform, VertexArray, Viewport, World};
+    use glam::{Mat4, Vec3};
+    use std::{collections::HashMap, fmt, ptr, rc::Rc};
+
+    // the gpu builds the pyramid down to this level, which is read back and reduced further on the cpu
+    const READBACK_LEVEL: usize = 2;
+    // boxes this close to the camera plane can't be projected, they are always drawn
+    const MIN_W: f32 = 1e-4;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum OcclusionMode {
+        Off,
+        // tests boxes against last frame's depth, on the cpu
+        HiZ,
+        // draws each box into an any samples passed query and renders the node on its result
+        Queries,
+    }
+
+    impl OcclusionMode {
+        pub fn next(self) -> Self {
+            match self {
+                OcclusionMode::Off => OcclusionMode::HiZ,
+                OcclusionMode::HiZ => OcclusionMode::Queries,
+                OcclusionMode::Queries => OcclusionMode::Off,
+            }
+        }
+    }
+
+    // what each stage took out of one frame
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct CullStats {
+        pub total: u32,
+        pub frustum_rejected: u32,
+        pub hiz_rejected: u32,
+        // from the frame before, query results are read once they are ready
+        pub query_rejected: u32,
+        pub drawn: u32,
+    }
+
+    impl fmt::Display for CullStats {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "total {} / frustum rejected {} / hi-z rejected {} / queries rejected {} / sent to GPU {}",
+                self.total, self.frustum_rejected, self.hiz_rejected, self.query_rejected, self.drawn
+            )
+        }
+    }
+
+    struct Level {
+        width: usize,
+        height: usize,
+        depths: Vec<f32>,
+    }
+
+    impl Level {
+        fn max(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> f32 {
+            let mut depth = 0.0f32;
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    depth = depth.max(self.depths[y * self.width + x]);
+                }
+            }
+            depth
+        }
+
+        // the same 2x2 max, odd leftovers folded into the last texel, as hiz.fs
+        fn reduce(&self) -> Level {
+            let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+            let mut depths = vec![0.0; width * height];
+
+            for y in 0..height {
+                for x in 0..width {
+                    let x1 = if x + 1 == width { self.width - 1 } else { 2 * x + 1 };
+                    let y1 = if y + 1 == height { self.height - 1 } else { 2 * y + 1 };
+                    depths[y * width + x] = self.max(2 * x, 2 * y, x1, y1);
+                }
+            }
+
+            Level { width, height, depths }
+        }
+    }
+
+    // last frame's depth on the cpu, from the read back level down to 1x1
+    #[derive(Default)]
+    struct DepthPyramid {
+        levels: Vec<Level>,
+        // the view projection the depth was drawn with
+        view_projection: Mat4,
+    }
+
+    impl DepthPyramid {
+        fn new(base: Level, view_projection: Mat4) -> Self {
+            let mut levels = vec![base];
+            while levels.last().is_some_and(|level| level.width > 1 || level.height > 1) {
+                let next = levels.last().unwrap().reduce();
+                levels.push(next);
+            }
+
+            DepthPyramid { levels, view_projection }
+        }
+
+        // conservative, anything it can't tell is visible
+        fn is_occluded(&self, bounds: &BoundingBox) -> bool {
+            if self.levels.is_empty() {
+                return false;
+            }
+
+            let (mut min, mut max) = (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN));
+            for i in 0..8 {
+                let corner = Vec3::new(
+                    if i & 1 == 0 { bounds.min.x } else { bounds.max.x },
+                    if i & 2 == 0 { bounds.min.y } else { bounds.max.y },
+                    if i & 4 == 0 { bounds.min.z } else { bounds.max.z },
+                );
+
+                let clip = self.view_projection * corner.extend(1.0);
+                if clip.w < MIN_W {
+                    return false;
+                }
+
+                let ndc = clip.truncate() / clip.w;
+                min = min.min(ndc);
+                max = max.max(ndc);
+            }
+
+            // the pyramid holds window depth, what gl makes of the -1 to 1 ndc depth
+            let nearest = min.z * 0.5 + 0.5;
+
+            // off last frame's screen there is nothing to test against
+            if max.x < -1.0 || max.y < -1.0 || min.x > 1.0 || min.y > 1.0 || nearest <= 0.0 {
+                return false;
+            }
+
+            let base = &self.levels[0];
+            let to_texel = |ndc: f32, size: usize| (((ndc.clamp(-1.0, 1.0) * 0.5 + 0.5) * size as f32) as usize).min(size - 1);
+            let (mut x0, mut y0) = (to_texel(min.x, base.width), to_texel(min.y, base.height));
+            let (mut x1, mut y1) = (to_texel(max.x, base.width), to_texel(max.y, base.height));
+
+            // down the pyramid until the rectangle is a couple of texels wide
+            let mut level = 0;
+            while level + 1 < self.levels.len() && (x1 - x0 > 1 || y1 - y0 > 1) {
+                level += 1;
+                let (width, height) = (self.levels[level].width, self.levels[level].height);
+                x0 = (x0 / 2).min(width - 1);
+                y0 = (y0 / 2).min(height - 1);
+                x1 = (x1 / 2).min(width - 1);
+                y1 = (y1 / 2).min(height - 1);
+            }
+
+            nearest > self.levels[level].max(x0, y0, x1, y1)
+        }
+    }
+
+    // a max depth pyramid of the last frame, boxes behind everything in their screen rectangle are occluded
+    pub struct HiZ {
+        depth_framebuffer: gl::types::GLuint,
+        depth_texture: gl::types::GLuint,
+        pyramid_framebuffer: gl::types::GLuint,
+        pyramid: gl::types::GLuint,
+        width: i32,
+        height: i32,
+        shader: Program,
+        depths: DepthPyramid,
+    }
+
+    impl HiZ {
+        pub fn new() -> Self {
+            let mut hiz = HiZ {
+                depth_framebuffer: 0,
+                depth_texture: 0,
+                pyramid_framebuffer: 0,
+                pyramid: 0,
+                width: 0,
+                height: 0,
+                shader: Program::new().link("src/hiz.vs", "src/hiz.fs"),
+                depths: DepthPyramid::default(),
+            };
+
+            unsafe {
+                gl::GenFramebuffers(1, &mut hiz.depth_framebuffer);
+                gl::GenTextures(1, &mut hiz.depth_texture);
+                gl::GenFramebuffers(1, &mut hiz.pyramid_framebuffer);
+                gl::GenTextures(1, &mut hiz.pyramid);
+            }
+
+            hiz
+        }
+
+        fn resize(&mut self, width: i32, height: i32) {
+            self.width = width;
+            self.height = height;
+            self.depths.levels.clear();
+
+            unsafe {
+                // the default framebuffer's format, blits between depth buffers need them to match
+                gl::BindTexture(gl::TEXTURE_2D, self.depth_texture);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::DEPTH24_STENCIL8 as i32, width, height, 0, gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8, ptr::null());
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.depth_framebuffer);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::TEXTURE_2D, self.depth_texture, 0);
+                gl::DrawBuffer(gl::NONE);
+                gl::ReadBuffer(gl::NONE);
+
+                gl::BindTexture(gl::TEXTURE_2D, self.pyramid);
+                for level in 0..=READBACK_LEVEL {
+                    let (level_width, level_height) = ((width >> level).max(1), (height >> level).max(1));
+                    gl::TexImage2D(gl::TEXTURE_2D, level as i32, gl::R32F as i32, level_width, level_height, 0, gl::RED, gl::FLOAT, ptr::null());
+                }
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST_MIPMAP_NEAREST as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, READBACK_LEVEL as i32);
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            }
+        }
+
+        // after the frame is drawn, before it is swapped
+        pub fn capture(&mut self, view_projection: Mat4, width: i32, height: i32, quad: &VertexArray) {
+            if width <= 0 || height <= 0 {
+                return;
+            }
+            if width != self.width || height != self.height {
+                self.resize(width, height);
+            }
+
+            unsafe {
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.depth_framebuffer);
+                gl::BlitFramebuffer(0, 0, width, height, 0, 0, width, height, gl::DEPTH_BUFFER_BIT, gl::NEAREST);
+
+                gl::Disable(gl::DEPTH_TEST);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.pyramid_framebuffer);
+                gl::ActiveTexture(gl::TEXTURE0);
+            }
+
+            self.shader.apply();
+            self.shader.set_int("source", 0);
+            quad.bind();
+
+            for level in 0..=READBACK_LEVEL {
+                let (source, source_level) = if level == 0 { (self.depth_texture, 0) } else { (self.pyramid, level - 1) };
+                let (source_width, source_height) = ((width >> source_level).max(1), (height >> source_level).max(1));
+
+                unsafe {
+                    gl::BindTexture(gl::TEXTURE_2D, source);
+                    // only the level read from, so the one written isn't sampled at the same time; texelFetch counts
+                    // its lod from the base level, so hiz.fs always reads lod 0
+                    if level > 0 {
+                        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_BASE_LEVEL, source_level as i32);
+                        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, source_level as i32);
+                    }
+
+                    gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.pyramid, level as i32);
+                    gl::Viewport(0, 0, (width >> level).max(1), (height >> level).max(1));
+                }
+
+                self.shader.set_int("copy", (level == 0) as i32);
+                self.shader.set_ivec2("sourceSize", source_width, source_height);
+                quad.draw();
+            }
+
+            let (base_width, base_height) = (((width >> READBACK_LEVEL).max(1)) as usize, ((height >> READBACK_LEVEL).max(1)) as usize);
+            let mut depths = vec![0.0f32; base_width * base_height];
+
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, self.pyramid);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_BASE_LEVEL, 0);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, READBACK_LEVEL as i32);
+
+                // waits for the gpu, the pyramid being a frame old already is what keeps this from costing more
+                gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+                gl::GetTexImage(gl::TEXTURE_2D, READBACK_LEVEL as i32, gl::RED, gl::FLOAT, depths.as_mut_ptr().cast());
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+
+                gl::BindVertexArray(0);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                gl::Viewport(0, 0, width, height);
+                gl::Enable(gl::DEPTH_TEST);
+            }
+
+            let base = Level {
+                width: base_width,
+                height: base_height,
+                depths,
+            };
+            self.depths = DepthPyramid::new(base, view_projection);
+        }
+
+        // after the mode changed, old depth from another view would hide the wrong things
+        pub fn reset(&mut self) {
+            self.depths.levels.clear();
+        }
+
+        pub fn is_occluded(&self, bounds: &BoundingBox) -> bool {
+            self.depths.is_occluded(bounds)
+        }
+    }
+
+    impl Default for HiZ {
+        fn default() -> Self {
+            HiZ::new()
+        }
+    }
+
+    impl Drop for HiZ {
+        fn drop(&mut self) {
+            unsafe {
+                gl::DeleteFramebuffers(1, &self.depth_framebuffer);
+                gl::DeleteTextures(1, &self.depth_texture);
+                gl::DeleteFramebuffers(1, &self.pyramid_framebuffer);
+                gl::DeleteTextures(1, &self.pyramid);
+            }
+        }
+    }
+
+    // a query per node, kept across frames
+    #[derive(Default)]
+    pub struct OcclusionQueries {
+        queries: HashMap<NodeId, gl::types::GLuint>,
+        // issued last frame, counted once the gpu has them
+        pending: Vec<gl::types::GLuint>,
+        rejected: u32,
+    }
+
+    impl OcclusionQueries {
+        pub fn new() -> Self {
+            OcclusionQueries::default()
+        }
+
+        fn query(&mut self, id: NodeId) -> gl::types::GLuint {
+            *self.queries.entry(id).or_insert_with(|| {
+                let mut query = 0;
+                unsafe {
+                    gl::GenQueries(1, &mut query);
+                }
+                query
+            })
+        }
+
+        // counts last frame's results that are in, and lets go of the queries of removed nodes
+        fn collect(&mut self, world: &World) {
+            self.rejected = 0;
+
+            for query in self.pending.drain(..) {
+                let (mut available, mut passed) = (0, 0);
+                unsafe {
+                    gl::GetQueryObjectuiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+                    if available != 0 {
+                        gl::GetQueryObjectuiv(query, gl::QUERY_RESULT, &mut passed);
+                    }
+                }
+
+                if available != 0 && passed == 0 {
+                    self.rejected += 1;
+                }
+            }
+
+            self.queries.retain(|&id, query| {
+                let alive = world.is_alive(id);
+                if !alive {
+                    unsafe {
+                        gl::DeleteQueries(1, query);
+                    }
+                }
+                alive
+            });
+        }
+
+        // nearest first so what is drawn earlier hides what comes after; box_shader draws the -1 to 1 cube under model
+        pub fn draw(&mut self, world: &World, visible: &[NodeId], view: &RenderView, shader: &Program, box_shader: &Program, cube: &VertexArray) {
+            let mut nodes = world.query::<(&Transform, &Bounds, &MeshRenderer)>();
+
+            for &id in visible {
+                let (transform, bounds, renderer) = match nodes.get(id) {
+                    Some(components) => components,
+                    None => continue,
+                };
+                let world_bounds = bounds.world();
+
+                // from inside the box its faces are behind the near plane and nothing would pass
+                if world_bounds.grow(0.1).contains(&BoundingBox::new(view.eye, view.eye)) {
+                    shader.apply();
+                    renderer.draw(transform, bounds, view, shader);
+                    continue;
+                }
+
+                let query = self.query(id);
+
+                box_shader.apply();
+                box_shader.set_mat4("model", Mat4::from_translation(world_bounds.center()) * Mat4::from_scale(world_bounds.extents()));
+
+                unsafe {
+                    gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+                    gl::DepthMask(gl::FALSE);
+                    gl::BeginQuery(gl::ANY_SAMPLES_PASSED, query);
+                }
+
+                cube.bind();
+                cube.draw();
+
+                unsafe {
+                    gl::EndQuery(gl::ANY_SAMPLES_PASSED);
+                    gl::BindVertexArray(0);
+                    gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                    gl::DepthMask(gl::TRUE);
+                    // draws anyway while the result isn't in, so the cpu never waits on the box
+                    gl::BeginConditionalRender(query, gl::QUERY_NO_WAIT);
+                }
+
+                shader.apply();
+                renderer.draw(transform, bounds, view, shader);
+
+                unsafe {
+                    gl::EndConditionalRender();
+                }
+                self.pending.push(query);
+            }
+        }
+    }
+
+    impl Drop for OcclusionQueries {
+        fn drop(&mut self) {
+            for query in self.queries.values() {
+                unsafe {
+                    gl::DeleteQueries(1, query);
+                }
+            }
+        }
+    }
+
+    // the render system: frustum culling through the bvh, then whichever occlusion test the OcclusionMode resource picks,
+    // then whatever is left drawn with the RenderView; leaves its CullStats in the world
+    pub struct OcclusionCuller {
+        shader: Rc<Program>,
+        // occlusion query boxes are drawn with color writes off, so any fragment shader does
+        box_shader: Rc<Program>,
+        cube: VertexArray,
+        quad: VertexArray,
+        hiz: HiZ,
+        queries: OcclusionQueries,
+    }
+
+    impl OcclusionCuller {
+        // shader and box_shader take the model, view and projection of 1.model_loading.vs
+        pub fn new(shader: Rc<Program>, box_shader: Rc<Program>, cube: VertexArray, quad: VertexArray) -> Self {
+            OcclusionCuller {
+                shader,
+                box_shader,
+                cube,
+                quad,
+                hiz: HiZ::new(),
+                queries: OcclusionQueries::new(),
+            }
+        }
+
+        fn draw(&mut self, world: &World, mode: OcclusionMode, view: &RenderView) -> CullStats {
+            let visible = world.visible(&view.frustum, view.eye);
+            let total = world.drawable_count();
+
+            let mut stats = CullStats {
+                total: total as u32,
+                frustum_rejected: (total - visible.len()) as u32,
+                ..Default::default()
+            };
+
+            match mode {
+                OcclusionMode::Off | OcclusionMode::HiZ => {
+                    let mut nodes = world.query::<(&Transform, &Bounds, &MeshRenderer)>();
+
+                    self.shader.apply();
+                    for &id in &visible {
+                        let (transform, bounds, renderer) = match nodes.get(id) {
+                            Some(components) => components,
+                            None => continue,
+                        };
+
+                        if mode == OcclusionMode::HiZ && self.hiz.is_occluded(&bounds.world()) {
+                            stats.hiz_rejected += 1;
+                            continue;
+                        }
+
+                        renderer.draw(transform, bounds, view, &self.shader);
+                        stats.drawn += 1;
+                    }
+                }
+                OcclusionMode::Queries => {
+                    self.queries.collect(world);
+                    self.queries.draw(world, &visible, view, &self.shader, &self.box_shader, &self.cube);
+
+                    stats.query_rejected = self.queries.rejected;
+                    stats.drawn = visible.len() as u32;
+                }
+            }
+
+            stats
+        }
+    }
+
+    // after transform_system, bounds_system and camera_system; the depth the frame leaves is what the next one is tested against
+    impl System for OcclusionCuller {
+        fn run(&mut self, world: &mut World) {
+            let mode = *world.resource::<OcclusionMode>();
+            let viewport = *world.resource::<Viewport>();
+
+            let stats = {
+                let view = world.resource::<RenderView>();
+
+                for program in [&*self.shader, &*self.box_shader] {
+                    program.apply();
+                    program.set_mat4("projection", view.projection);
+                    program.set_mat4("view", view.view);
+                }
+
+                let stats = self.draw(world, mode, &view);
+
+                if mode == OcclusionMode::HiZ {
+                    self.hiz.capture(view.view_projection(), viewport.width, viewport.height, &self.quad);
+                } else {
+                    self.hiz.reset();
+                }
+                stats
+            };
+
+            world.insert_resource(stats);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn level(width: usize, height: usize, depth: f32) -> Level {
+            Level {
+                width,
+                height,
+                depths: vec![depth; width * height],
+            }
+        }
+
+        // a box at window depths near to far over the ndc rectangle, with an identity view projection
+        fn ndc_box(min: (f32, f32), max: (f32, f32), near: f32, far: f32) -> BoundingBox {
+            BoundingBox::new(Vec3::new(min.0, min.1, near * 2.0 - 1.0), Vec3::new(max.0, max.1, far * 2.0 - 1.0))
+        }
+
+        #[test]
+        fn reduce_keeps_the_farthest_and_folds_odd_leftovers_into_the_last_texel() {
+            let mut source = level(5, 3, 0.1);
+            source.depths[5 + 1] = 0.5;
+            // the leftover column and row only reach the output through the last texel
+            source.depths[2 * 5 + 4] = 0.9;
+
+            let reduced = source.reduce();
+            assert_eq!((reduced.width, reduced.height), (2, 1));
+            assert_eq!(reduced.depths, vec![0.5, 0.9]);
+
+            let pyramid = DepthPyramid::new(source, Mat4::IDENTITY);
+            let sizes: Vec<(usize, usize)> = pyramid.levels.iter().map(|level| (level.width, level.height)).collect();
+            assert_eq!(sizes, vec![(5, 3), (2, 1), (1, 1)]);
+            assert_eq!(pyramid.levels[2].depths, vec![0.9]);
+        }
+
+        #[test]
+        fn boxes_behind_every_texel_they_cover_are_occluded() {
+            // a wall at window depth 0.5, with a hole to the far plane in the top right texel
+            let mut base = level(4, 4, 0.5);
+            base.depths[3 * 4 + 3] = 1.0;
+            let pyramid = DepthPyramid::new(base, Mat4::IDENTITY);
+
+            assert!(pyramid.is_occluded(&ndc_box((-0.9, -0.9), (-0.6, -0.6), 0.6, 0.7)));
+            // in front of the wall
+            assert!(!pyramid.is_occluded(&ndc_box((-0.9, -0.9), (-0.6, -0.6), 0.3, 0.7)));
+            // seen through the hole, directly and from a coarser level
+            assert!(!pyramid.is_occluded(&ndc_box((0.6, 0.6), (0.9, 0.9), 0.6, 0.7)));
+            assert!(!pyramid.is_occluded(&ndc_box((-0.9, -0.9), (0.9, 0.9), 0.6, 0.7)));
+            // off last frame's screen
+            assert!(!pyramid.is_occluded(&ndc_box((1.1, -0.9), (1.5, 0.9), 0.6, 0.7)));
+            assert!(!DepthPyramid::default().is_occluded(&ndc_box((-0.9, -0.9), (-0.6, -0.6), 0.6, 0.7)));
+        }
+    }
+}
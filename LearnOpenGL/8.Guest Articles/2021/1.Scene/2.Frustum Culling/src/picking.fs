@@ -0,0 +1,10 @@
+#version 330 core
+out uint FragId;
+
+// 0 is left for the background
+uniform uint entityId;
+
+void main()
+{
+    FragId = entityId;
+}
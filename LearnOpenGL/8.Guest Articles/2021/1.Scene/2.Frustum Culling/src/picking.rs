@@ -0,0 +1,250 @@
+pub mod picking {
+    use crate::{Bvh, Frustum, MeshRenderer, Model, NodeId, Program, Ray, Transform, World};
+    use glam::{Mat4, Vec3, Vec4};
+    use std::rc::Rc;
+
+    const EPSILON: f32 = 1e-7;
+
+    // through the cursor, from the near plane out; x and y in window pixels from the top left
+    pub fn cursor_ray(x: f32, y: f32, width: f32, height: f32, projection: Mat4, view: Mat4) -> Ray {
+        let ndc_x = 2.0 * x / width - 1.0;
+        let ndc_y = 1.0 - 2.0 * y / height;
+
+        let inverse = (projection * view).inverse();
+        let unproject = |depth: f32| {
+            let point = inverse * Vec4::new(ndc_x, ndc_y, depth, 1.0);
+            point.truncate() / point.w
+        };
+
+        let near = unproject(0.0);
+        Ray::new(near, unproject(1.0) - near)
+    }
+
+    // moller-trumbore, both faces count
+    pub fn ray_triangle(origin: Vec3, direction: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        let (edge1, edge2) = (b - a, c - a);
+        let p = direction.cross(edge2);
+        let determinant = edge1.dot(p);
+
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let t = origin - a;
+        let u = t.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = t.cross(edge1);
+        let v = direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge2.dot(q) * inverse;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    // the nearest triangle of the most detailed level, tested in model space; the distance is in world space along the ray
+    pub fn ray_model(model: &Model, model_matrix: Mat4, ray: &Ray) -> Option<f32> {
+        let inverse = model_matrix.inverse();
+        let origin = inverse.transform_point3(ray.origin);
+        let direction = inverse.transform_vector3(ray.direction);
+
+        let mut nearest: Option<f32> = None;
+        for mesh in &model.meshes {
+            for triangle in mesh.indices().chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize].position);
+
+                if let Some(t) = ray_triangle(origin, direction, a, b, c) {
+                    if nearest.is_none_or(|nearest| t < nearest) {
+                        nearest = Some(t);
+                    }
+                }
+            }
+        }
+
+        // t is in units of the model space direction, which the model matrix carries back onto the world ray
+        nearest.map(|t| model_matrix.transform_point3(origin + direction * t).distance(ray.origin))
+    }
+
+    // bounds through the bvh first, then the triangles of whatever the ray got into
+    pub fn pick(world: &World, ray: &Ray, max_distance: f32) -> Option<(NodeId, f32)> {
+        let mut nodes = world.query::<(&Transform, &MeshRenderer)>();
+
+        world.resource::<Bvh<NodeId>>().ray_cast(ray, max_distance, |id, _| {
+            world.world_bounds(id)?.ray_entry(ray, max_distance)?;
+            let (transform, renderer) = nodes.get(id)?;
+            ray_model(&renderer.model, transform.get_model_matrix(), ray)
+        })
+    }
+
+    // every visible node drawn with its own id into an integer texture, read back under the cursor
+    pub struct IdBuffer {
+        framebuffer: gl::types::GLuint,
+        texture: gl::types::GLuint,
+        depth: gl::types::GLuint,
+        width: i32,
+        height: i32,
+        // what each id drawn last stands for, 0 is the background
+        ids: Vec<NodeId>,
+    }
+
+    impl IdBuffer {
+        pub fn new(width: i32, height: i32) -> Self {
+            let mut buffer = IdBuffer {
+                framebuffer: 0,
+                texture: 0,
+                depth: 0,
+                width: 0,
+                height: 0,
+                ids: vec![],
+            };
+
+            unsafe {
+                gl::GenFramebuffers(1, &mut buffer.framebuffer);
+                gl::GenTextures(1, &mut buffer.texture);
+                gl::GenRenderbuffers(1, &mut buffer.depth);
+            }
+
+            buffer.resize(width, height);
+            buffer
+        }
+
+        pub fn resize(&mut self, width: i32, height: i32) {
+            if width == self.width && height == self.height || width <= 0 || height <= 0 {
+                return;
+            }
+            self.width = width;
+            self.height = height;
+
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, self.texture);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R32UI as i32, width, height, 0, gl::RED_INTEGER, gl::UNSIGNED_INT, std::ptr::null());
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+
+                gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth);
+                gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.texture, 0);
+                gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER, self.depth);
+
+                if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                    println!("ERROR::FRAMEBUFFER:: Id buffer is not complete!");
+                }
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            }
+        }
+
+        // shader takes the model, view and projection of 1.model_loading.vs and a uint entityId
+        pub fn render(&mut self, world: &World, frustum: &Frustum, shader: &Program, projection: Mat4, view: Mat4) {
+            self.ids.clear();
+
+            unsafe {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+                gl::Viewport(0, 0, self.width, self.height);
+                gl::ClearBufferuiv(gl::COLOR, 0, [0u32; 4].as_ptr());
+                gl::Clear(gl::DEPTH_BUFFER_BIT);
+            }
+
+            shader.apply();
+            shader.set_mat4("projection", projection);
+            shader.set_mat4("view", view);
+
+            let mut nodes = world.query::<(&Transform, &MeshRenderer)>();
+            world.resource::<Bvh<NodeId>>().cull(frustum, |id, _| {
+                if let Some((transform, renderer)) = nodes.get(id) {
+                    self.ids.push(id);
+
+                    shader.set_uint("entityId", self.ids.len() as u32);
+                    shader.set_mat4("model", transform.get_model_matrix());
+                    renderer.model.draw(shader);
+                }
+            });
+
+            unsafe {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            }
+        }
+
+        // x and y in framebuffer pixels from the top left, as of the last render
+        pub fn read(&self, x: i32, y: i32) -> Option<NodeId> {
+            if x < 0 || y < 0 || x >= self.width || y >= self.height {
+                return None;
+            }
+
+            let mut id = 0u32;
+            unsafe {
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.framebuffer);
+                gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+                gl::ReadPixels(x, self.height - 1 - y, 1, 1, gl::RED_INTEGER, gl::UNSIGNED_INT, (&mut id as *mut u32).cast());
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            }
+
+            id.checked_sub(1).and_then(|index| self.ids.get(index as usize).copied())
+        }
+    }
+
+    impl Drop for IdBuffer {
+        fn drop(&mut self) {
+            unsafe {
+                gl::DeleteFramebuffers(1, &self.framebuffer);
+                gl::DeleteTextures(1, &self.texture);
+                gl::DeleteRenderbuffers(1, &self.depth);
+            }
+        }
+    }
+
+    // the stencil outline of 2.Stencil Testing: the node marks the stencil buffer, then a slightly larger copy is drawn wherever it didn't
+    pub fn draw_outline(world: &World, id: NodeId, shader: &Program, outline: &Program, scale: f32) {
+        let (model, model_matrix, bounds) = match (world.get::<MeshRenderer>(id), world.get::<Transform>(id), world.world_bounds(id)) {
+            (Some(renderer), Some(transform), Some(bounds)) => (Rc::clone(&renderer.model), transform.get_model_matrix(), bounds),
+            _ => return,
+        };
+
+        unsafe {
+            gl::Enable(gl::STENCIL_TEST);
+            gl::StencilOp(gl::KEEP, gl::KEEP, gl::REPLACE);
+            gl::Clear(gl::STENCIL_BUFFER_BIT);
+            gl::StencilFunc(gl::ALWAYS, 1, 0xFF);
+            gl::StencilMask(0xFF);
+        }
+
+        // the node is already in the depth buffer at the same depth
+        unsafe {
+            gl::DepthFunc(gl::LEQUAL);
+        }
+
+        shader.apply();
+        shader.set_mat4("model", model_matrix);
+        model.draw(shader);
+
+        // grown about the middle of its bounds, so off-center models stay in place
+        let center = bounds.center();
+        let grown = Mat4::from_translation(center) * Mat4::from_scale(Vec3::splat(scale)) * Mat4::from_translation(-center) * model_matrix;
+
+        unsafe {
+            gl::DepthFunc(gl::LESS);
+            gl::StencilFunc(gl::NOTEQUAL, 1, 0xFF);
+            gl::StencilMask(0x00);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        outline.apply();
+        outline.set_mat4("model", grown);
+        model.draw(outline);
+
+        unsafe {
+            gl::StencilMask(0xFF);
+            gl::StencilFunc(gl::ALWAYS, 0, 0xFF);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Disable(gl::STENCIL_TEST);
+        }
+    }
+}
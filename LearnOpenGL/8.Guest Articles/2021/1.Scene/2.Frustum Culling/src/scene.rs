@@ -0,0 +1,407 @@
+pub mod scene {
+    use crate::{
+        ActiveCamera, BoundingBox, Bounds, Bvh, Camera, EntityId, Extras, Frustum, Hierarchy, Light, MeshRenderer, Model, Name, Ray, RenderView, Transform, Viewport,
+        Visibility, World,
+    };
+    use glam::{Mat4, Vec3};
+    use std::rc::Rc;
+
+    // a scene node is an entity with a Name, a Transform and a Hierarchy, the ones that draw something add a MeshRenderer and Bounds
+    pub type NodeId = EntityId;
+
+    // the scene graph kept in a world; parents are read by transform_system instead of copied into their children
+    impl World {
+        // the built-in components registered and an empty bvh for bounds_system to fill
+        pub fn new_scene() -> World {
+            let mut world = World::new();
+
+            world.register::<Name>();
+            world.register::<Transform>();
+            world.register::<Hierarchy>();
+            world.register::<MeshRenderer>();
+            world.register::<Bounds>();
+            world.register::<Light>();
+            world.register::<Camera>();
+            world.register::<Extras>();
+            world.insert_resource(Bvh::<NodeId>::default());
+
+            world
+        }
+
+        // None adds a root, a parent that is gone adds nothing
+        pub fn add_node(&mut self, name: &str, parent: Option<NodeId>) -> Option<NodeId> {
+            if parent.is_some_and(|parent| !self.has::<Hierarchy>(parent)) {
+                return None;
+            }
+
+            let id = self.spawn();
+            self.insert(id, Name(name.to_string()));
+            self.insert(id, Transform::new());
+            self.insert(id, Hierarchy::new(parent));
+
+            if let Some(parent) = parent {
+                self.get_mut::<Hierarchy>(parent).unwrap().add_child(id);
+            }
+            Some(id)
+        }
+
+        // a node that draws model, its bounding volumes are fitted to the vertices once here
+        pub fn add_model_node(&mut self, name: &str, model: &Rc<Model>, parent: Option<NodeId>) -> Option<NodeId> {
+            let id = self.add_node(name, parent)?;
+            self.insert(id, Bounds::from(model.as_ref()));
+            self.insert(id, MeshRenderer::new(model));
+            Some(id)
+        }
+
+        // takes the whole subtree with it
+        pub fn remove_node(&mut self, id: NodeId) -> bool {
+            let parent = match self.get::<Hierarchy>(id) {
+                Some(hierarchy) => hierarchy.parent(),
+                None => return false,
+            };
+            if let Some(parent) = parent {
+                self.get_mut::<Hierarchy>(parent).unwrap().remove_child(id);
+            }
+
+            let mut stack = vec![id];
+            while let Some(id) = stack.pop() {
+                if let Some(hierarchy) = self.remove::<Hierarchy>(id) {
+                    stack.extend_from_slice(hierarchy.children());
+                }
+
+                let proxy = self.get::<Bounds>(id).and_then(|bounds| bounds.proxy);
+                if let Some(proxy) = proxy {
+                    self.resource_mut::<Bvh<NodeId>>().remove(proxy);
+                }
+
+                self.despawn(id);
+            }
+
+            true
+        }
+
+        // keeps the local transform, so the node moves with its new parent; fails on a node below itself
+        pub fn reparent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+            if !self.has::<Hierarchy>(id) || parent.is_some_and(|parent| !self.has::<Hierarchy>(parent) || self.is_ancestor(id, parent)) {
+                return false;
+            }
+
+            let previous = self.get::<Hierarchy>(id).unwrap().parent();
+            if let Some(previous) = previous {
+                self.get_mut::<Hierarchy>(previous).unwrap().remove_child(id);
+            }
+            if let Some(parent) = parent {
+                self.get_mut::<Hierarchy>(parent).unwrap().add_child(id);
+            }
+
+            self.get_mut::<Hierarchy>(id).unwrap().set_parent(parent);
+            if let Some(mut transform) = self.get_mut::<Transform>(id) {
+                transform.mark_dirty();
+            }
+            true
+        }
+
+        // whether ancestor is node itself or above it
+        pub fn is_ancestor(&self, ancestor: NodeId, node: NodeId) -> bool {
+            let mut current = Some(node);
+
+            while let Some(id) = current {
+                if id == ancestor {
+                    return true;
+                }
+                current = self.get::<Hierarchy>(id).and_then(|hierarchy| hierarchy.parent());
+            }
+
+            false
+        }
+
+        // the first entity with that name, nodes or not
+        pub fn find(&self, name: &str) -> Option<EntityId> {
+            self.read::<Name>().iter().find(|(_, entry)| entry.0 == name).map(|(id, _)| id)
+        }
+
+        // the nodes without a parent, in slot order
+        pub fn roots(&self) -> Vec<NodeId> {
+            self.read::<Hierarchy>()
+                .iter()
+                .filter(|(_, hierarchy)| hierarchy.parent().is_none())
+                .map(|(id, _)| id)
+                .collect()
+        }
+
+        pub fn node_count(&self) -> usize {
+            self.read::<Hierarchy>().len()
+        }
+
+        pub fn drawable_count(&self) -> usize {
+            self.read::<Bounds>().len()
+        }
+
+        // as of the last bounds_system
+        pub fn world_bounds(&self, id: NodeId) -> Option<BoundingBox> {
+            self.get::<Bounds>(id).map(|bounds| bounds.world())
+        }
+
+        // what the frustum doesn't cull, nearest first so it can hide what is behind
+        pub fn visible(&self, frustum: &Frustum, eye: Vec3) -> Vec<NodeId> {
+            let mut query = self.query::<(&Transform, &Bounds)>();
+            let mut visible = vec![];
+
+            self.resource::<Bvh<NodeId>>().cull(frustum, |id, visibility| {
+                if let Some((transform, bounds)) = query.get(id) {
+                    if visibility == Visibility::Inside || bounds.is_visible(transform, frustum) {
+                        visible.push((id, bounds.world().distance_to(eye)));
+                    }
+                }
+            });
+
+            visible.sort_by(|a, b| a.1.total_cmp(&b.1));
+            visible.into_iter().map(|(id, _)| id).collect()
+        }
+
+        // the nearest node whose world box the ray goes through, and how far along it
+        pub fn ray_cast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<(NodeId, f32)> {
+            let ray = Ray::new(origin, direction);
+            self.resource::<Bvh<NodeId>>()
+                .ray_cast(&ray, max_distance, |id, _| self.world_bounds(id)?.ray_entry(&ray, max_distance))
+        }
+
+        pub fn segment_cast(&self, start: Vec3, end: Vec3) -> Option<(NodeId, f32)> {
+            let ray = Ray::new(start, end - start);
+            let length = start.distance(end);
+
+            self.resource::<Bvh<NodeId>>().segment_cast(start, end, |id, _| {
+                let bounds = self.world_bounds(id)?;
+                if length <= f32::EPSILON {
+                    (bounds.distance_to(start) <= 0.0).then_some(0.0)
+                } else {
+                    bounds.ray_entry(&ray, length)
+                }
+            })
+        }
+
+        // the node whose world box is nearest to center, if any is within radius
+        pub fn sphere_query(&self, center: Vec3, radius: f32) -> Option<(NodeId, f32)> {
+            self.resource::<Bvh<NodeId>>()
+                .sphere_query(center, radius, |id, _| self.world_bounds(id).map(|bounds| bounds.distance_to(center)).filter(|&distance| distance <= radius))
+        }
+
+        // every node whose world box overlaps the sphere
+        pub fn overlap_sphere(&self, center: Vec3, radius: f32) -> Vec<NodeId> {
+            let mut found = vec![];
+            self.resource::<Bvh<NodeId>>().overlap_sphere(center, radius, &mut found);
+            found.retain(|&id| self.world_bounds(id).is_some_and(|bounds| bounds.distance_to(center) <= radius));
+            found
+        }
+    }
+
+    // the projection, view and frustum of the ActiveCamera for the Viewport, as a RenderView
+    pub fn camera_system(world: &mut World) {
+        let viewport = *world.resource::<Viewport>();
+        let active = world.resource::<ActiveCamera>().0;
+
+        let view = match world.get::<Camera>(active) {
+            Some(camera) => RenderView::new(&camera, &viewport),
+            None => return,
+        };
+        world.insert_resource(view);
+    }
+
+    // recomputes the model matrix of dirty nodes and everything below them, and clears their flag
+    pub fn transform_system(world: &mut World) {
+        let hierarchy = world.read::<Hierarchy>();
+        let mut transforms = world.write::<Transform>();
+
+        let mut stack: Vec<(NodeId, Mat4, bool)> = hierarchy
+            .iter()
+            .filter(|(_, node)| node.parent().is_none())
+            .map(|(id, _)| (id, Mat4::IDENTITY, false))
+            .collect();
+
+        while let Some((id, parent, parent_changed)) = stack.pop() {
+            let transform = match transforms.get_mut(id) {
+                Some(transform) => transform,
+                None => continue,
+            };
+            let changed = parent_changed || transform.is_dirty();
+
+            if changed {
+                transform.compute_relative_model_matrix(parent);
+            }
+
+            let model_matrix = transform.get_model_matrix();
+            if let Some(node) = hierarchy.get(id) {
+                stack.extend(node.children().iter().map(|&child| (child, model_matrix, changed)));
+            }
+        }
+    }
+
+    // after transform_system, moves the world boxes and bvh leaves of whatever moved
+    pub fn bounds_system(world: &mut World) {
+        let mut bvh = world.resource_mut::<Bvh<NodeId>>();
+
+        world.query::<(&Transform, &mut Bounds)>().for_each(|id, (transform, bounds)| {
+            if !bounds.follow(transform) {
+                return;
+            }
+
+            match bounds.proxy {
+                Some(proxy) => {
+                    bvh.update(proxy, bounds.world());
+                }
+                None => bounds.proxy = Some(bvh.insert(id, bounds.world())),
+            }
+        });
+
+        if bvh.needs_rebuild() {
+            bvh.rebuild();
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use glam::{EulerRot, Quat};
+
+        fn local(transform: &Transform) -> Mat4 {
+            let rotation = transform.get_local_rotation();
+            let rotation = Quat::from_euler(EulerRot::XYZ, rotation.x, rotation.y, rotation.z);
+            Mat4::from_scale_rotation_translation(transform.get_local_scale(), rotation, transform.get_local_position())
+        }
+
+        fn local_of(world: &World, id: NodeId) -> Mat4 {
+            local(&world.get::<Transform>(id).unwrap())
+        }
+
+        fn model_of(world: &World, id: NodeId) -> Mat4 {
+            world.get::<Transform>(id).unwrap().get_model_matrix()
+        }
+
+        fn close(a: Mat4, b: Mat4) -> bool {
+            a.abs_diff_eq(b, 1e-4)
+        }
+
+        fn children(world: &World, id: NodeId) -> Vec<NodeId> {
+            world.get::<Hierarchy>(id).unwrap().children().to_vec()
+        }
+
+        // root > arm > hand, plus a second root
+        fn scene() -> (World, [NodeId; 4]) {
+            let mut world = World::new_scene();
+
+            let root = world.add_node("root", None).unwrap();
+            let arm = world.add_node("arm", Some(root)).unwrap();
+            let hand = world.add_node("hand", Some(arm)).unwrap();
+            let other = world.add_node("other", None).unwrap();
+
+            let transforms = [
+                (root, Vec3::new(1.0, 2.0, 3.0), Vec3::new(0.0, 1.5, 0.0), Vec3::splat(2.0)),
+                (arm, Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.5, 0.0, 0.2), Vec3::ONE),
+                (hand, Vec3::new(0.5, 0.0, -1.0), Vec3::new(0.0, 0.0, 0.8), Vec3::new(1.0, 0.5, 1.0)),
+                (other, Vec3::new(-4.0, 0.0, 0.0), Vec3::ZERO, Vec3::ONE),
+            ];
+            for (id, position, rotation, scale) in transforms {
+                let mut transform = world.get_mut::<Transform>(id).unwrap();
+                transform.set_local_position(position);
+                transform.set_local_rotation(rotation);
+                transform.set_local_scale(scale);
+            }
+
+            (world, [root, arm, hand, other])
+        }
+
+        #[test]
+        fn world_is_parent_times_local() {
+            let (mut world, [root, arm, hand, other]) = scene();
+            transform_system(&mut world);
+
+            let arm_world = local_of(&world, root) * local_of(&world, arm);
+            assert!(close(model_of(&world, root), local_of(&world, root)));
+            assert!(close(model_of(&world, arm), arm_world));
+            assert!(close(model_of(&world, hand), arm_world * local_of(&world, hand)));
+            assert!(close(model_of(&world, other), local_of(&world, other)));
+        }
+
+        #[test]
+        fn transform_system_clears_dirty_flags_and_skips_clean_subtrees() {
+            let (mut world, [root, arm, hand, other]) = scene();
+            transform_system(&mut world);
+            assert!(world.read::<Transform>().iter().all(|(_, transform)| !transform.is_dirty()));
+
+            // planted matrices survive a pass that has nothing to do
+            let planted = Mat4::from_translation(Vec3::splat(100.0));
+            world.get_mut::<Transform>(hand).unwrap().compute_relative_model_matrix(planted);
+            world.get_mut::<Transform>(other).unwrap().compute_relative_model_matrix(planted);
+            transform_system(&mut world);
+            assert_eq!(model_of(&world, hand), planted * local_of(&world, hand));
+
+            // moving the arm redoes the hand below it but not the other root
+            world.get_mut::<Transform>(arm).unwrap().set_local_position(Vec3::new(0.0, 3.0, 0.0));
+            transform_system(&mut world);
+            let arm_world = local_of(&world, root) * local_of(&world, arm);
+            assert!(close(model_of(&world, hand), arm_world * local_of(&world, hand)));
+            assert_eq!(model_of(&world, other), planted * local_of(&world, other));
+            assert!(world.read::<Transform>().iter().all(|(_, transform)| !transform.is_dirty()));
+        }
+
+        #[test]
+        fn reparent_keeps_the_hierarchy_consistent() {
+            let (mut world, [root, arm, hand, other]) = scene();
+            transform_system(&mut world);
+
+            // a node can't go below itself
+            assert!(!world.reparent(root, Some(hand)));
+            assert!(!world.reparent(arm, Some(arm)));
+
+            assert!(world.reparent(arm, Some(other)));
+            assert_eq!(world.get::<Hierarchy>(arm).unwrap().parent(), Some(other));
+            assert!(children(&world, root).is_empty());
+            assert_eq!(children(&world, other), vec![arm]);
+            assert!(world.is_ancestor(other, hand));
+            assert!(!world.is_ancestor(root, hand));
+
+            transform_system(&mut world);
+            let arm_world = local_of(&world, other) * local_of(&world, arm);
+            assert!(close(model_of(&world, hand), arm_world * local_of(&world, hand)));
+
+            assert!(world.reparent(hand, None));
+            assert!(world.roots().contains(&hand));
+            assert!(children(&world, arm).is_empty());
+            transform_system(&mut world);
+            assert!(close(model_of(&world, hand), local_of(&world, hand)));
+        }
+
+        #[test]
+        fn remove_drops_the_subtree() {
+            let (mut world, [root, arm, hand, other]) = scene();
+
+            assert!(world.remove_node(arm));
+            assert_eq!(world.node_count(), 2);
+            assert!(!world.is_alive(arm) && !world.is_alive(hand));
+            assert!(!world.has::<Transform>(hand) && !world.has::<Name>(hand));
+            assert!(children(&world, root).is_empty());
+            assert!(!world.remove_node(hand));
+
+            // reused slots don't bring the old ids back
+            let new = world.add_node("new", Some(other)).unwrap();
+            assert!(!world.is_alive(arm) && !world.is_alive(hand));
+            assert!(world.add_node("orphan", Some(hand)).is_none());
+            assert_eq!(children(&world, other), vec![new]);
+
+            assert!(world.remove_node(root));
+            assert_eq!(world.roots(), vec![other]);
+            transform_system(&mut world);
+        }
+
+        #[test]
+        fn find_by_name() {
+            let (mut world, [_, arm, hand, _]) = scene();
+
+            assert_eq!(world.find("hand"), Some(hand));
+            assert_eq!(world.find("missing"), None);
+
+            world.remove_node(arm);
+            assert_eq!(world.find("hand"), None);
+        }
+    }
+}
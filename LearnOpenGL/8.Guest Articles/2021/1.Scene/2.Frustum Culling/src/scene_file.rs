@@ -0,0 +1,778 @@
+pub mod scene_file {
+    use crate::{Camera, Extras, Hierarchy, Json, Light, LightKind, MeshRenderer, Model, Name, NodeId, ParseError, Transform, World};
+    use glam::{EulerRot, Quat, Vec3};
+    use std::{
+        fmt::{self, Display, Formatter},
+        fs, io,
+        path::Path,
+        rc::Rc,
+    };
+
+    // bumped whenever a field changes meaning, older files are read as long as the version is known
+    pub const SCENE_VERSION: u32 = 1;
+
+    #[derive(Debug)]
+    pub enum SceneError {
+        Io { path: String, error: io::Error },
+        Parse { path: String, error: ParseError },
+        Version { found: f64 },
+        // where in the file, what was wrong
+        Invalid { context: String, message: String },
+        UnknownModel { node: String, model: String },
+        UnknownMaterial { node: String, material: String },
+        MissingModelFile { model: String, path: String },
+        // a model drawn by a node that no entry of the library holds
+        UncapturedModel { node: String },
+    }
+
+    impl Display for SceneError {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            match self {
+                SceneError::Io { path, error } => write!(f, "{path}: {error}"),
+                SceneError::Parse { path, error } => write!(f, "{path}: {error}"),
+                SceneError::Version { found } => write!(f, "scene version {found} is not supported, {SCENE_VERSION} is the newest known"),
+                SceneError::Invalid { context, message } => write!(f, "{context}: {message}"),
+                SceneError::UnknownModel { node, model } => write!(f, "node \"{node}\" uses model \"{model}\", which the models table doesn't list"),
+                SceneError::UnknownMaterial { node, material } => {
+                    write!(f, "node \"{node}\" uses material \"{material}\", which the materials table doesn't list")
+                }
+                SceneError::MissingModelFile { model, path } => write!(f, "model \"{model}\" points at {path}, which doesn't exist"),
+                SceneError::UncapturedModel { node } => write!(f, "node \"{node}\" draws a model the library doesn't name"),
+            }
+        }
+    }
+
+    fn invalid(context: &str, message: &str) -> SceneError {
+        SceneError::Invalid {
+            context: context.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Rotation {
+        // radians, applied x then y then z like Transform does
+        Euler(Vec3),
+        Quaternion(Quat),
+    }
+
+    impl Rotation {
+        pub fn to_euler(self) -> Vec3 {
+            match self {
+                Rotation::Euler(euler) => euler,
+                Rotation::Quaternion(quaternion) => Vec3::from(quaternion.to_euler(EulerRot::XYZ)),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct MaterialDescription {
+        pub name: String,
+        pub diffuse: Vec3,
+        pub specular: Vec3,
+        pub shininess: f32,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct LightDescription {
+        pub name: String,
+        pub kind: LightKind,
+        pub color: Vec3,
+        pub intensity: f32,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct CameraDescription {
+        pub name: String,
+        pub position: Vec3,
+        pub yaw: f32,
+        pub pitch: f32,
+        pub zoom: f32,
+    }
+
+    impl CameraDescription {
+        pub fn from_camera(name: &str, camera: &Camera) -> Self {
+            CameraDescription {
+                name: name.to_string(),
+                position: camera.position(),
+                yaw: camera.yaw(),
+                pitch: camera.pitch(),
+                zoom: camera.zoom(),
+            }
+        }
+
+        pub fn apply(&self, camera: &mut Camera) {
+            camera.set_view(self.position, self.yaw, self.pitch);
+            camera.set_zoom(self.zoom);
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct NodeDescription {
+        pub name: String,
+        pub model: String,
+        pub material: Option<String>,
+        pub position: Vec3,
+        pub rotation: Rotation,
+        pub scale: Vec3,
+        // anything the engine itself doesn't read, kept as written
+        pub components: Vec<(String, Json)>,
+        pub children: Vec<NodeDescription>,
+    }
+
+    impl NodeDescription {
+        pub fn new(name: &str, model: &str) -> Self {
+            NodeDescription {
+                name: name.to_string(),
+                model: model.to_string(),
+                material: None,
+                position: Vec3::ZERO,
+                rotation: Rotation::Euler(Vec3::ZERO),
+                scale: Vec3::ONE,
+                components: vec![],
+                children: vec![],
+            }
+        }
+    }
+
+    // models by the name nodes refer to them with, and the file each came from
+    #[derive(Default)]
+    pub struct ModelLibrary {
+        entries: Vec<(String, String, Rc<Model>)>,
+    }
+
+    impl ModelLibrary {
+        pub fn new() -> Self {
+            ModelLibrary::default()
+        }
+
+        pub fn insert(&mut self, name: &str, path: &str, model: Rc<Model>) {
+            self.entries.retain(|(entry, _, _)| entry != name);
+            self.entries.push((name.to_string(), path.to_string(), model));
+        }
+
+        pub fn get(&self, name: &str) -> Option<&Rc<Model>> {
+            self.entries.iter().find(|(entry, _, _)| entry == name).map(|(_, _, model)| model)
+        }
+
+        // the name and path of a model, by identity
+        pub fn name_of(&self, model: &Rc<Model>) -> Option<(&str, &str)> {
+            self.entries
+                .iter()
+                .find(|(_, _, entry)| Rc::ptr_eq(entry, model))
+                .map(|(name, path, _)| (name.as_str(), path.as_str()))
+        }
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct SceneDescription {
+        // name and path, in file order
+        pub models: Vec<(String, String)>,
+        pub materials: Vec<MaterialDescription>,
+        pub lights: Vec<LightDescription>,
+        pub cameras: Vec<CameraDescription>,
+        pub nodes: Vec<NodeDescription>,
+    }
+
+    impl SceneDescription {
+        pub fn load(path: &str) -> Result<Self, SceneError> {
+            let text = fs::read_to_string(path).map_err(|error| SceneError::Io {
+                path: path.to_string(),
+                error,
+            })?;
+            let json = Json::parse(&text).map_err(|error| SceneError::Parse {
+                path: path.to_string(),
+                error,
+            })?;
+
+            SceneDescription::from_json(&json)
+        }
+
+        pub fn save(&self, path: &str) -> Result<(), SceneError> {
+            fs::write(path, self.to_json().pretty()).map_err(|error| SceneError::Io {
+                path: path.to_string(),
+                error,
+            })
+        }
+
+        pub fn from_json(json: &Json) -> Result<Self, SceneError> {
+            let version = number(json, "version", "scene")?;
+            if version.fract() != 0.0 || version < 1.0 || version > SCENE_VERSION as f64 {
+                return Err(SceneError::Version { found: version });
+            }
+
+            let models = optional_object(json, "models", "scene")?
+                .iter()
+                .map(|(name, path)| match path.as_str() {
+                    Some(path) => Ok((name.clone(), path.to_string())),
+                    None => Err(invalid(&format!("model \"{name}\""), "expected a file path")),
+                })
+                .collect::<Result<_, _>>()?;
+
+            let materials = optional_array(json, "materials", "scene")?
+                .iter()
+                .map(|material| {
+                    let name = string(material, "name", "material")?;
+                    let context = format!("material \"{name}\"");
+
+                    Ok(MaterialDescription {
+                        diffuse: vec3_or(material, "diffuse", &context, Vec3::ONE)?,
+                        specular: vec3_or(material, "specular", &context, Vec3::splat(0.5))?,
+                        shininess: number_or(material, "shininess", &context, 32.0)? as f32,
+                        name,
+                    })
+                })
+                .collect::<Result<_, SceneError>>()?;
+
+            let lights = optional_array(json, "lights", "scene")?
+                .iter()
+                .map(|light| {
+                    let name = string(light, "name", "light")?;
+                    let context = format!("light \"{name}\"");
+
+                    let kind = match string(light, "type", &context)?.as_str() {
+                        "directional" => LightKind::Directional {
+                            direction: vec3(light, "direction", &context)?,
+                        },
+                        "point" => LightKind::Point {
+                            position: vec3(light, "position", &context)?,
+                            range: number_or(light, "range", &context, 10.0)? as f32,
+                        },
+                        other => return Err(invalid(&context, &format!("unknown light type \"{other}\", expected \"directional\" or \"point\""))),
+                    };
+
+                    Ok(LightDescription {
+                        kind,
+                        color: vec3_or(light, "color", &context, Vec3::ONE)?,
+                        intensity: number_or(light, "intensity", &context, 1.0)? as f32,
+                        name,
+                    })
+                })
+                .collect::<Result<_, SceneError>>()?;
+
+            let cameras = optional_array(json, "cameras", "scene")?
+                .iter()
+                .map(|camera| {
+                    let name = string(camera, "name", "camera")?;
+                    let context = format!("camera \"{name}\"");
+
+                    Ok(CameraDescription {
+                        position: vec3(camera, "position", &context)?,
+                        yaw: number_or(camera, "yaw", &context, -90f64.to_radians())? as f32,
+                        pitch: number_or(camera, "pitch", &context, 0.0)? as f32,
+                        zoom: number_or(camera, "zoom", &context, 45f64.to_radians())? as f32,
+                        name,
+                    })
+                })
+                .collect::<Result<_, SceneError>>()?;
+
+            let nodes = optional_array(json, "nodes", "scene")?.iter().map(node_from_json).collect::<Result<_, _>>()?;
+
+            let description = SceneDescription {
+                models,
+                materials,
+                lights,
+                cameras,
+                nodes,
+            };
+            description.validate()?;
+            Ok(description)
+        }
+
+        pub fn to_json(&self) -> Json {
+            let mut entries = vec![("version".to_string(), Json::Number(SCENE_VERSION as f64))];
+
+            entries.push((
+                "models".to_string(),
+                Json::Object(self.models.iter().map(|(name, path)| (name.clone(), Json::String(path.clone()))).collect()),
+            ));
+
+            if !self.materials.is_empty() {
+                let materials = self
+                    .materials
+                    .iter()
+                    .map(|material| {
+                        Json::Object(vec![
+                            ("name".to_string(), Json::String(material.name.clone())),
+                            ("diffuse".to_string(), vec3_json(material.diffuse)),
+                            ("specular".to_string(), vec3_json(material.specular)),
+                            ("shininess".to_string(), Json::from_f32(material.shininess)),
+                        ])
+                    })
+                    .collect();
+                entries.push(("materials".to_string(), Json::Array(materials)));
+            }
+
+            if !self.lights.is_empty() {
+                let lights = self
+                    .lights
+                    .iter()
+                    .map(|light| {
+                        let mut fields = vec![("name".to_string(), Json::String(light.name.clone()))];
+                        match light.kind {
+                            LightKind::Directional { direction } => {
+                                fields.push(("type".to_string(), Json::String("directional".to_string())));
+                                fields.push(("direction".to_string(), vec3_json(direction)));
+                            }
+                            LightKind::Point { position, range } => {
+                                fields.push(("type".to_string(), Json::String("point".to_string())));
+                                fields.push(("position".to_string(), vec3_json(position)));
+                                fields.push(("range".to_string(), Json::from_f32(range)));
+                            }
+                        }
+                        fields.push(("color".to_string(), vec3_json(light.color)));
+                        fields.push(("intensity".to_string(), Json::from_f32(light.intensity)));
+                        Json::Object(fields)
+                    })
+                    .collect();
+                entries.push(("lights".to_string(), Json::Array(lights)));
+            }
+
+            if !self.cameras.is_empty() {
+                let cameras = self
+                    .cameras
+                    .iter()
+                    .map(|camera| {
+                        Json::Object(vec![
+                            ("name".to_string(), Json::String(camera.name.clone())),
+                            ("position".to_string(), vec3_json(camera.position)),
+                            ("yaw".to_string(), Json::from_f32(camera.yaw)),
+                            ("pitch".to_string(), Json::from_f32(camera.pitch)),
+                            ("zoom".to_string(), Json::from_f32(camera.zoom)),
+                        ])
+                    })
+                    .collect();
+                entries.push(("cameras".to_string(), Json::Array(cameras)));
+            }
+
+            entries.push(("nodes".to_string(), Json::Array(self.nodes.iter().map(node_to_json).collect())));
+            Json::Object(entries)
+        }
+
+        // every model and material a node names has to be in the tables
+        pub fn validate(&self) -> Result<(), SceneError> {
+            let mut stack: Vec<&NodeDescription> = self.nodes.iter().collect();
+
+            while let Some(node) = stack.pop() {
+                if !self.models.iter().any(|(name, _)| *name == node.model) {
+                    return Err(SceneError::UnknownModel {
+                        node: node.name.clone(),
+                        model: node.model.clone(),
+                    });
+                }
+
+                if let Some(material) = &node.material {
+                    if !self.materials.iter().any(|entry| entry.name == *material) {
+                        return Err(SceneError::UnknownMaterial {
+                            node: node.name.clone(),
+                            material: material.clone(),
+                        });
+                    }
+                }
+
+                stack.extend(&node.children);
+            }
+
+            Ok(())
+        }
+
+        // models not in the library yet are loaded with load; lights and cameras become entities of their own, returns the root nodes added
+        pub fn instantiate(&self, world: &mut World, library: &mut ModelLibrary, load: impl Fn(&str) -> Model) -> Result<Vec<NodeId>, SceneError> {
+            self.validate()?;
+
+            for (name, path) in &self.models {
+                if library.get(name).is_none() {
+                    if !Path::new(path).exists() {
+                        return Err(SceneError::MissingModelFile {
+                            model: name.clone(),
+                            path: path.clone(),
+                        });
+                    }
+                    library.insert(name, path, Rc::new(load(path)));
+                }
+            }
+
+            let mut roots = vec![];
+            let mut stack: Vec<(&NodeDescription, Option<NodeId>)> = self.nodes.iter().rev().map(|node| (node, None)).collect();
+
+            while let Some((description, parent)) = stack.pop() {
+                let model = library.get(&description.model).unwrap();
+                let id = world.add_model_node(&description.name, model, parent).unwrap();
+
+                {
+                    let mut transform = world.get_mut::<Transform>(id).unwrap();
+                    transform.set_local_position(description.position);
+                    transform.set_local_rotation(description.rotation.to_euler());
+                    transform.set_local_scale(description.scale);
+                }
+                world.get_mut::<MeshRenderer>(id).unwrap().material = description.material.clone();
+                if !description.components.is_empty() {
+                    world.insert(id, Extras(description.components.clone()));
+                }
+
+                if parent.is_none() {
+                    roots.push(id);
+                }
+                stack.extend(description.children.iter().rev().map(|child| (child, Some(id))));
+            }
+
+            for light in &self.lights {
+                let id = world.spawn();
+                world.insert(id, Name(light.name.clone()));
+                world.insert(
+                    id,
+                    Light {
+                        kind: light.kind,
+                        color: light.color,
+                        intensity: light.intensity,
+                    },
+                );
+            }
+
+            for view in &self.cameras {
+                let mut camera = Camera::new(view.position, 0.0, 0.0);
+                view.apply(&mut camera);
+
+                let id = world.spawn();
+                world.insert(id, Name(view.name.clone()));
+                world.insert(id, camera);
+            }
+
+            Ok(roots)
+        }
+
+        // replaces the nodes, models, lights and cameras with what the world holds now, materials stay
+        pub fn capture(&mut self, world: &World, library: &ModelLibrary) -> Result<(), SceneError> {
+            let mut models: Vec<(String, String)> = vec![];
+
+            fn capture_node(world: &World, library: &ModelLibrary, id: NodeId, models: &mut Vec<(String, String)>) -> Result<NodeDescription, SceneError> {
+                let name = world.get::<Name>(id).map_or_else(String::new, |name| name.0.clone());
+                let renderer = world.get::<MeshRenderer>(id).ok_or_else(|| SceneError::UncapturedModel { node: name.clone() })?;
+                let (model, path) = library.name_of(&renderer.model).ok_or_else(|| SceneError::UncapturedModel { node: name.clone() })?;
+
+                if !models.iter().any(|(name, _)| name == model) {
+                    models.push((model.to_string(), path.to_string()));
+                }
+
+                let transform = world.get::<Transform>(id).map_or_else(Transform::new, |transform| *transform);
+                let children = world.get::<Hierarchy>(id).map_or_else(Vec::new, |hierarchy| hierarchy.children().to_vec());
+
+                Ok(NodeDescription {
+                    name,
+                    model: model.to_string(),
+                    material: renderer.material.clone(),
+                    position: transform.get_local_position(),
+                    rotation: Rotation::Euler(transform.get_local_rotation()),
+                    scale: transform.get_local_scale(),
+                    components: world.get::<Extras>(id).map_or_else(Vec::new, |extras| extras.0.clone()),
+                    children: children.into_iter().map(|child| capture_node(world, library, child, models)).collect::<Result<_, _>>()?,
+                })
+            }
+
+            let nodes = world.roots().into_iter().map(|root| capture_node(world, library, root, &mut models)).collect::<Result<_, _>>()?;
+
+            let mut lights = vec![];
+            world.query::<(&Name, &Light)>().for_each(|_, (name, light)| {
+                lights.push(LightDescription {
+                    name: name.0.clone(),
+                    kind: light.kind,
+                    color: light.color,
+                    intensity: light.intensity,
+                })
+            });
+
+            let mut cameras = vec![];
+            world.query::<(&Name, &Camera)>().for_each(|_, (name, camera)| cameras.push(CameraDescription::from_camera(&name.0, camera)));
+
+            self.models = models;
+            self.nodes = nodes;
+            self.lights = lights;
+            self.cameras = cameras;
+            Ok(())
+        }
+    }
+
+    fn node_from_json(json: &Json) -> Result<NodeDescription, SceneError> {
+        let name = string(json, "name", "node")?;
+        let context = format!("node \"{name}\"");
+
+        let rotation = match (json.get("rotation"), json.get("quaternion")) {
+            (Some(_), Some(_)) => return Err(invalid(&context, "has both \"rotation\" and \"quaternion\"")),
+            (_, Some(_)) => {
+                let quaternion = Quat::from_array(numbers::<4>(json, "quaternion", &context)?);
+                if quaternion.length() == 0.0 {
+                    return Err(invalid(&context, "\"quaternion\" has zero length"));
+                }
+                Rotation::Quaternion(quaternion.normalize())
+            }
+            _ => Rotation::Euler(vec3_or(json, "rotation", &context, Vec3::ZERO)?),
+        };
+
+        let material = match json.get("material") {
+            None | Some(Json::Null) => None,
+            Some(_) => Some(string(json, "material", &context)?),
+        };
+
+        Ok(NodeDescription {
+            model: string(json, "model", &context)?,
+            material,
+            position: vec3_or(json, "position", &context, Vec3::ZERO)?,
+            rotation,
+            scale: vec3_or(json, "scale", &context, Vec3::ONE)?,
+            components: optional_object(json, "components", &context)?.to_vec(),
+            children: optional_array(json, "children", &context)?.iter().map(node_from_json).collect::<Result<_, _>>()?,
+            name,
+        })
+    }
+
+    fn node_to_json(node: &NodeDescription) -> Json {
+        let mut fields = vec![
+            ("name".to_string(), Json::String(node.name.clone())),
+            ("model".to_string(), Json::String(node.model.clone())),
+        ];
+
+        if let Some(material) = &node.material {
+            fields.push(("material".to_string(), Json::String(material.clone())));
+        }
+
+        fields.push(("position".to_string(), vec3_json(node.position)));
+        match node.rotation {
+            Rotation::Euler(euler) => fields.push(("rotation".to_string(), vec3_json(euler))),
+            Rotation::Quaternion(quaternion) => fields.push(("quaternion".to_string(), Json::Array(quaternion.to_array().iter().map(|&v| Json::from_f32(v)).collect()))),
+        }
+        fields.push(("scale".to_string(), vec3_json(node.scale)));
+
+        if !node.components.is_empty() {
+            fields.push(("components".to_string(), Json::Object(node.components.clone())));
+        }
+        if !node.children.is_empty() {
+            fields.push(("children".to_string(), Json::Array(node.children.iter().map(node_to_json).collect())));
+        }
+
+        Json::Object(fields)
+    }
+
+    fn vec3_json(vector: Vec3) -> Json {
+        Json::Array(vector.to_array().iter().map(|&v| Json::from_f32(v)).collect())
+    }
+
+    fn number(json: &Json, field: &str, context: &str) -> Result<f64, SceneError> {
+        match json.get(field) {
+            Some(value) => value.as_f64().ok_or_else(|| invalid(context, &format!("\"{field}\" should be a number"))),
+            None => Err(invalid(context, &format!("\"{field}\" is missing"))),
+        }
+    }
+
+    fn number_or(json: &Json, field: &str, context: &str, default: f64) -> Result<f64, SceneError> {
+        match json.get(field) {
+            Some(_) => number(json, field, context),
+            None => Ok(default),
+        }
+    }
+
+    fn string(json: &Json, field: &str, context: &str) -> Result<String, SceneError> {
+        match json.get(field) {
+            Some(value) => value.as_str().map(str::to_string).ok_or_else(|| invalid(context, &format!("\"{field}\" should be a string"))),
+            None => Err(invalid(context, &format!("\"{field}\" is missing"))),
+        }
+    }
+
+    fn numbers<const N: usize>(json: &Json, field: &str, context: &str) -> Result<[f32; N], SceneError> {
+        let wrong = || invalid(context, &format!("\"{field}\" should be an array of {N} numbers"));
+        let values = json.get(field).and_then(Json::as_array).ok_or_else(wrong)?;
+
+        if values.len() != N {
+            return Err(wrong());
+        }
+
+        let mut numbers = [0.0; N];
+        for (number, value) in numbers.iter_mut().zip(values) {
+            *number = value.as_f64().ok_or_else(wrong)? as f32;
+        }
+        Ok(numbers)
+    }
+
+    fn vec3(json: &Json, field: &str, context: &str) -> Result<Vec3, SceneError> {
+        numbers::<3>(json, field, context).map(Vec3::from)
+    }
+
+    fn vec3_or(json: &Json, field: &str, context: &str, default: Vec3) -> Result<Vec3, SceneError> {
+        match json.get(field) {
+            Some(_) => vec3(json, field, context),
+            None => Ok(default),
+        }
+    }
+
+    fn optional_array<'a>(json: &'a Json, field: &str, context: &str) -> Result<&'a [Json], SceneError> {
+        match json.get(field) {
+            Some(value) => value.as_array().ok_or_else(|| invalid(context, &format!("\"{field}\" should be an array"))),
+            None => Ok(&[]),
+        }
+    }
+
+    fn optional_object<'a>(json: &'a Json, field: &str, context: &str) -> Result<&'a [(String, Json)], SceneError> {
+        match json.get(field) {
+            Some(value) => value.as_object().ok_or_else(|| invalid(context, &format!("\"{field}\" should be an object"))),
+            None => Ok(&[]),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // one of everything the file can hold
+        fn description() -> SceneDescription {
+            let mut moon = NodeDescription::new("moon", "rock");
+            moon.position = Vec3::new(2.5, 0.0, -1.0);
+            moon.rotation = Rotation::Quaternion(Quat::from_xyzw(0.5, 0.5, 0.5, 0.5));
+            moon.scale = Vec3::splat(0.25);
+
+            let mut planet = NodeDescription::new("planet", "planet");
+            planet.material = Some("stone".to_string());
+            planet.rotation = Rotation::Euler(Vec3::new(0.1, 1.25, -0.5));
+            planet.components = vec![("spin".to_string(), Json::Number(0.75)), ("tag".to_string(), Json::String("home \u{1F30D}".to_string()))];
+            planet.children.push(moon);
+
+            SceneDescription {
+                models: vec![
+                    ("planet".to_string(), "resources/objects/planet/planet.obj".to_string()),
+                    ("rock".to_string(), "resources/objects/rock/rock.obj".to_string()),
+                ],
+                materials: vec![MaterialDescription {
+                    name: "stone".to_string(),
+                    diffuse: Vec3::new(0.5, 0.4, 0.3),
+                    specular: Vec3::splat(0.125),
+                    shininess: 16.0,
+                }],
+                lights: vec![
+                    LightDescription {
+                        name: "sun".to_string(),
+                        kind: LightKind::Directional { direction: Vec3::new(0.0, -1.0, 0.0) },
+                        color: Vec3::new(1.0, 0.9, 0.8),
+                        intensity: 2.0,
+                    },
+                    LightDescription {
+                        name: "lamp".to_string(),
+                        kind: LightKind::Point {
+                            position: Vec3::new(1.0, 2.0, 3.0),
+                            range: 7.5,
+                        },
+                        color: Vec3::ONE,
+                        intensity: 0.5,
+                    },
+                ],
+                cameras: vec![CameraDescription {
+                    name: "main".to_string(),
+                    position: Vec3::new(0.0, 10.0, 0.0),
+                    yaw: -1.5,
+                    pitch: 0.25,
+                    zoom: 0.75,
+                }],
+                nodes: vec![planet, NodeDescription::new("lone rock", "rock")],
+            }
+        }
+
+        fn temp_path(name: &str) -> String {
+            std::env::temp_dir().join(format!("scene_file_{}_{name}.json", std::process::id())).to_string_lossy().into_owned()
+        }
+
+        fn parse(text: &str) -> Result<SceneDescription, SceneError> {
+            let json = Json::parse(text).map_err(|error| SceneError::Parse {
+                path: "test".to_string(),
+                error,
+            })?;
+            SceneDescription::from_json(&json)
+        }
+
+        #[test]
+        fn load_save_load_round_trips() {
+            let path = temp_path("round_trip");
+            let original = description();
+
+            original.save(&path).unwrap();
+            let loaded = SceneDescription::load(&path).unwrap();
+            assert_eq!(loaded, original);
+
+            // saving what was loaded writes the same file again
+            let first = fs::read_to_string(&path).unwrap();
+            loaded.save(&path).unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), first);
+            assert_eq!(SceneDescription::load(&path).unwrap(), loaded);
+
+            fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn defaults_fill_in_missing_fields() {
+            let loaded = parse(r#"{"version": 1, "models": {"rock": "rock.obj"}, "nodes": [{"name": "a", "model": "rock"}]}"#).unwrap();
+
+            assert_eq!(loaded.nodes, vec![NodeDescription::new("a", "rock")]);
+            assert!(loaded.materials.is_empty() && loaded.lights.is_empty() && loaded.cameras.is_empty());
+        }
+
+        #[test]
+        fn missing_file_is_an_io_error() {
+            let error = SceneDescription::load(&temp_path("missing")).unwrap_err();
+            assert!(matches!(error, SceneError::Io { error, .. } if error.kind() == io::ErrorKind::NotFound));
+        }
+
+        #[test]
+        fn malformed_files_are_rejected() {
+            let cases = [
+                // not json at all
+                (r#"{"version": 1,"#, "Parse"),
+                (r#"{"version": 1} trailing"#, "Parse"),
+                (r#"{"version": 1, "nodes": [{"name": "\uD800A", "model": "rock"}]}"#, "Parse"),
+                (r#"{"version": 1, "nodes": [{"name": "\u+041", "model": "rock"}]}"#, "Parse"),
+                (r#"{"version": 1, "version": 1}"#, "Parse"),
+                // json, but not a scene
+                (r#"{"nodes": []}"#, "Invalid"),
+                (r#"{"version": 2}"#, "Version"),
+                (r#"{"version": 1.5}"#, "Version"),
+                (r#"{"version": 1, "nodes": {}}"#, "Invalid"),
+                (r#"{"version": 1, "models": {"rock": 3}}"#, "Invalid"),
+                (r#"{"version": 1, "models": {"rock": "rock.obj"}, "nodes": [{"model": "rock"}]}"#, "Invalid"),
+                (r#"{"version": 1, "models": {"rock": "rock.obj"}, "nodes": [{"name": "a", "model": "rock", "position": [1, 2]}]}"#, "Invalid"),
+                (r#"{"version": 1, "models": {"rock": "rock.obj"}, "nodes": [{"name": "a", "model": "rock", "rotation": [0, 0, 0], "quaternion": [0, 0, 0, 1]}]}"#, "Invalid"),
+                (r#"{"version": 1, "models": {"rock": "rock.obj"}, "nodes": [{"name": "a", "model": "rock", "quaternion": [0, 0, 0, 0]}]}"#, "Invalid"),
+                (r#"{"version": 1, "lights": [{"name": "l", "type": "spot"}]}"#, "Invalid"),
+                (r#"{"version": 1, "cameras": [{"name": "c"}]}"#, "Invalid"),
+                // bad references
+                (r#"{"version": 1, "nodes": [{"name": "a", "model": "rock"}]}"#, "UnknownModel"),
+                (r#"{"version": 1, "models": {"rock": "rock.obj"}, "nodes": [{"name": "a", "model": "rock", "children": [{"name": "b", "model": "tree"}]}]}"#, "UnknownModel"),
+                (r#"{"version": 1, "models": {"rock": "rock.obj"}, "nodes": [{"name": "a", "model": "rock", "material": "gold"}]}"#, "UnknownMaterial"),
+            ];
+
+            for (text, expected) in cases {
+                let error = parse(text).unwrap_err();
+                let kind = match error {
+                    SceneError::Io { .. } => "Io",
+                    SceneError::Parse { .. } => "Parse",
+                    SceneError::Version { .. } => "Version",
+                    SceneError::Invalid { .. } => "Invalid",
+                    SceneError::UnknownModel { .. } => "UnknownModel",
+                    SceneError::UnknownMaterial { .. } => "UnknownMaterial",
+                    SceneError::MissingModelFile { .. } => "MissingModelFile",
+                    SceneError::UncapturedModel { .. } => "UncapturedModel",
+                };
+                assert_eq!(kind, expected, "{text}: {error}");
+            }
+        }
+
+        #[test]
+        fn parse_errors_point_at_the_problem() {
+            let path = temp_path("parse_error");
+            fs::write(&path, "{\n  \"version\": 1,\n  \"nodes\": [\n    {\"name\": \"\\uD800\\u0041\"}\n  ]\n}\n").unwrap();
+
+            match SceneDescription::load(&path).unwrap_err() {
+                SceneError::Parse { error, .. } => {
+                    assert_eq!((error.line, error.message.as_str()), (4, "bad surrogate pair"));
+                }
+                error => panic!("expected a parse error, got {error}"),
+            }
+
+            fs::remove_file(&path).unwrap();
+        }
+    }
+}
@@ -112,6 +112,14 @@ pub mod shader {
             with_c_str(name, |locn| unsafe { gl::Uniform1i(gl::GetUniformLocation(self.program, locn), value) });
         }
 
+        pub fn set_uint(&self, name: &str, value: u32) {
+            with_c_str(name, |locn| unsafe { gl::Uniform1ui(gl::GetUniformLocation(self.program, locn), value) });
+        }
+
+        pub fn set_ivec2(&self, name: &str, x: i32, y: i32) {
+            with_c_str(name, |locn| unsafe { gl::Uniform2i(gl::GetUniformLocation(self.program, locn), x, y) });
+        }
+
         pub fn set_float(&self, name: &str, value: f32) {
             with_c_str(name, |locn| unsafe {
                 gl::Uniform1f(gl::GetUniformLocation(self.program, locn), value);
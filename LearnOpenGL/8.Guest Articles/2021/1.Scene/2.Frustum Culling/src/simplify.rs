@@ -0,0 +1,336 @@
+pub mod simplify {
+    use crate::Vertex;
+    use glam::DVec3;
+    use std::collections::{HashMap, HashSet};
+
+    // a collapse may turn a triangle by at most ~75 degrees
+    const MIN_NORMAL_COSINE: f64 = 0.25;
+
+    // sum of squared distances to the planes of the triangles merged into a vertex, weighted by their area
+    #[derive(Clone, Copy, Default)]
+    struct Quadric {
+        xx: f64,
+        yy: f64,
+        zz: f64,
+        xy: f64,
+        xz: f64,
+        yz: f64,
+        b: DVec3,
+        c: f64,
+        weight: f64,
+    }
+
+    impl Quadric {
+        fn from_plane(normal: DVec3, distance: f64, weight: f64) -> Self {
+            Quadric {
+                xx: weight * normal.x * normal.x,
+                yy: weight * normal.y * normal.y,
+                zz: weight * normal.z * normal.z,
+                xy: weight * normal.x * normal.y,
+                xz: weight * normal.x * normal.z,
+                yz: weight * normal.y * normal.z,
+                b: normal * distance * weight,
+                c: distance * distance * weight,
+                weight,
+            }
+        }
+
+        fn add(&self, other: &Quadric) -> Quadric {
+            Quadric {
+                xx: self.xx + other.xx,
+                yy: self.yy + other.yy,
+                zz: self.zz + other.zz,
+                xy: self.xy + other.xy,
+                xz: self.xz + other.xz,
+                yz: self.yz + other.yz,
+                b: self.b + other.b,
+                c: self.c + other.c,
+                weight: self.weight + other.weight,
+            }
+        }
+
+        // root mean square distance from the point to the merged planes
+        fn error(&self, p: DVec3) -> f64 {
+            let ap = DVec3::new(
+                self.xx * p.x + self.xy * p.y + self.xz * p.z,
+                self.xy * p.x + self.yy * p.y + self.yz * p.z,
+                self.xz * p.x + self.yz * p.y + self.zz * p.z,
+            );
+            let error = p.dot(ap) + 2.0 * p.dot(self.b) + self.c;
+
+            if self.weight > 0.0 {
+                (error.max(0.0) / self.weight).sqrt()
+            } else {
+                0.0
+            }
+        }
+    }
+
+    // collapses edges into one of their ends in order of quadric error until the index count reaches the target or
+    // the error would pass max_error, both relative to the mesh's extent. vertices on borders and on seams, where
+    // several vertices share a position, never move so the mesh doesn't open up and attributes stay valid.
+    // returns the new indices into the same vertices, and the error they reached
+    pub fn simplify(vertices: &[Vertex], indices: &[u32], target_index_count: usize, max_error: f32) -> (Vec<u32>, f32) {
+        let positions: Vec<DVec3> = vertices.iter().map(|vertex| vertex.position.as_dvec3()).collect();
+
+        let (min, max) = positions.iter().fold((DVec3::splat(f64::MAX), DVec3::splat(f64::MIN)), |(min, max), &p| (min.min(p), max.max(p)));
+        let extent = (max - min).max_element();
+        if positions.is_empty() || extent <= 0.0 {
+            return (indices.to_vec(), 0.0);
+        }
+
+        // the first vertex at each position stands for all of them
+        let mut first = HashMap::new();
+        let wedge: Vec<usize> = positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| *first.entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]).or_insert(i))
+            .collect();
+
+        let mut wedge_sizes = vec![0; vertices.len()];
+        for &w in &wedge {
+            wedge_sizes[w] += 1;
+        }
+
+        let mut quadrics = vec![Quadric::default(); vertices.len()];
+        let mut edges: HashMap<(usize, usize), u32> = HashMap::new();
+
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| wedge[triangle[k] as usize]);
+            let normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+            let length = normal.length();
+
+            if length > 0.0 {
+                let normal = normal / length;
+                let quadric = Quadric::from_plane(normal, -normal.dot(positions[a]), length * 0.5);
+
+                for w in [a, b, c] {
+                    quadrics[w] = quadrics[w].add(&quadric);
+                }
+            }
+
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                *edges.entry((from.min(to), from.max(to))).or_insert(0) += 1;
+            }
+        }
+
+        let mut locked: Vec<bool> = wedge.iter().map(|&w| wedge_sizes[w] > 1).collect();
+        for (&(a, b), &count) in &edges {
+            if count == 1 {
+                locked[a] = true;
+                locked[b] = true;
+            }
+        }
+
+        let mut indices = indices.to_vec();
+        let mut error = 0.0;
+
+        // each pass sorts every candidate collapse once, and applies the cheapest ones that don't share triangles
+        while indices.len() > target_index_count {
+            let mut adjacency: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+            for (t, triangle) in indices.chunks_exact(3).enumerate() {
+                for &v in triangle {
+                    adjacency[wedge[v as usize]].push(t);
+                }
+            }
+
+            let mut candidates = vec![];
+            for triangle in indices.chunks_exact(3) {
+                for k in 0..3 {
+                    let (a, b) = (triangle[k] as usize, triangle[(k + 1) % 3] as usize);
+
+                    for (from, to) in [(a, b), (b, a)] {
+                        if !locked[from] {
+                            let cost = quadrics[from].add(&quadrics[wedge[to]]).error(positions[to]) / extent;
+                            candidates.push((cost, from, to));
+                        }
+                    }
+                }
+            }
+            candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let triangles_to_remove = (indices.len() - target_index_count) / 3;
+            let mut removed = 0;
+            let mut touched = vec![false; vertices.len()];
+            let mut remap: Vec<u32> = (0..vertices.len() as u32).collect();
+
+            for (cost, from, to) in candidates {
+                if cost > max_error as f64 || removed >= triangles_to_remove {
+                    break;
+                }
+
+                // an unlocked vertex is alone at its position, so it is its own wedge
+                let target = wedge[to];
+                if touched[from] || touched[target] || !can_collapse(&positions, &wedge, &indices, &adjacency, from, to) {
+                    continue;
+                }
+
+                remap[from] = to as u32;
+                quadrics[target] = quadrics[target].add(&quadrics[from]);
+                error = f64::max(error, cost);
+
+                for &t in &adjacency[from] {
+                    let triangle = &indices[t * 3..t * 3 + 3];
+
+                    if triangle.iter().any(|&v| wedge[v as usize] == target) {
+                        removed += 1;
+                    }
+                    for &v in triangle {
+                        touched[wedge[v as usize]] = true;
+                    }
+                }
+            }
+
+            if removed == 0 {
+                break;
+            }
+
+            indices = indices
+                .chunks_exact(3)
+                .map(|triangle| [0, 1, 2].map(|k| remap[triangle[k] as usize]))
+                .filter(|[a, b, c]| {
+                    let [a, b, c] = [a, b, c].map(|&v| wedge[v as usize]);
+                    a != b && b != c && c != a
+                })
+                .flatten()
+                .collect();
+        }
+
+        (indices, error as f32)
+    }
+
+    fn can_collapse(positions: &[DVec3], wedge: &[usize], indices: &[u32], adjacency: &[Vec<usize>], from: usize, to: usize) -> bool {
+        let target = wedge[to];
+
+        // the edge may only be shared by its two triangles, or the collapse pinches the surface
+        let neighbours = |w: usize| -> HashSet<usize> {
+            adjacency[w]
+                .iter()
+                .flat_map(|&t| indices[t * 3..t * 3 + 3].iter().map(|&v| wedge[v as usize]))
+                .filter(|&other| other != w)
+                .collect()
+        };
+        if neighbours(from).intersection(&neighbours(target)).count() > 2 {
+            return false;
+        }
+
+        // the triangles that survive mustn't fold over or collapse to a sliver
+        adjacency[from].iter().all(|&t| {
+            let triangle: [u32; 3] = indices[t * 3..t * 3 + 3].try_into().unwrap();
+            if triangle.iter().any(|&v| wedge[v as usize] == target) {
+                return true;
+            }
+
+            let corners = triangle.map(|v| positions[v as usize]);
+            let moved = triangle.map(|v| if v as usize == from { positions[to] } else { positions[v as usize] });
+
+            let before = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+            let after = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+
+            before.dot(after) > MIN_NORMAL_COSINE * before.length() * after.length()
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use glam::{Vec2, Vec3};
+
+        fn vertex(position: Vec3) -> Vertex {
+            Vertex {
+                position,
+                normal: Vec3::Y,
+                tex_coords: Vec2::new(position.x, position.z),
+                tangent: Vec3::X,
+                bitangent: Vec3::Z,
+                bone_ids: [0; 4],
+                weights: [0.0; 4],
+            }
+        }
+
+        // a size by size grid of quads over the unit square, lifted by `height`
+        fn grid(size: u32, height: impl Fn(f32, f32) -> f32) -> (Vec<Vertex>, Vec<u32>) {
+            let mut vertices = vec![];
+            for z in 0..=size {
+                for x in 0..=size {
+                    let (x, z) = (x as f32 / size as f32, z as f32 / size as f32);
+                    vertices.push(vertex(Vec3::new(x, height(x, z), z)));
+                }
+            }
+
+            let mut indices = vec![];
+            for z in 0..size {
+                for x in 0..size {
+                    let corner = z * (size + 1) + x;
+                    let [a, b, c, d] = [corner, corner + 1, corner + size + 1, corner + size + 2];
+                    indices.extend([a, c, b, b, c, d]);
+                }
+            }
+
+            (vertices, indices)
+        }
+
+        fn bump(x: f32, z: f32) -> f32 {
+            0.05 * (x * 6.0).sin() * (z * 5.0).cos()
+        }
+
+        // edges used by a single triangle, with their ends in order
+        fn border_edges(indices: &[u32]) -> HashSet<(u32, u32)> {
+            let mut counts: HashMap<(u32, u32), u32> = HashMap::new();
+            for triangle in indices.chunks_exact(3) {
+                for k in 0..3 {
+                    let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+                    *counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                }
+            }
+
+            counts.into_iter().filter(|&(_, count)| count == 1).map(|(edge, _)| edge).collect()
+        }
+
+        #[test]
+        fn reduces_triangle_count() {
+            for (vertices, indices) in [grid(16, |_, _| 0.0), grid(16, bump)] {
+                let target = indices.len() / 2 / 3 * 3;
+                let (simplified, error) = simplify(&vertices, &indices, target, 0.05);
+
+                assert!(simplified.len() <= target, "{} indices left of {}", simplified.len(), indices.len());
+                assert!(error <= 0.05);
+            }
+
+            // a flat grid simplifies without error
+            let (vertices, indices) = grid(16, |_, _| 0.0);
+            let (simplified, error) = simplify(&vertices, &indices, 0, 0.0);
+            assert!(simplified.len() < indices.len() / 4);
+            assert!(error < 1e-6);
+        }
+
+        #[test]
+        fn indices_stay_valid() {
+            let (vertices, indices) = grid(12, bump);
+
+            for target in [indices.len(), indices.len() / 2, indices.len() / 8, 0] {
+                let (simplified, _) = simplify(&vertices, &indices, target / 3 * 3, 0.1);
+
+                assert_eq!(simplified.len() % 3, 0);
+                for triangle in simplified.chunks_exact(3) {
+                    assert!(triangle.iter().all(|&v| (v as usize) < vertices.len()));
+                    assert!(triangle[0] != triangle[1] && triangle[1] != triangle[2] && triangle[2] != triangle[0]);
+
+                    // still facing up, nothing folded over
+                    let [a, b, c] = [0, 1, 2].map(|k| vertices[triangle[k] as usize].position);
+                    assert!((b - a).cross(c - a).y > 0.0);
+                }
+            }
+        }
+
+        #[test]
+        fn keeps_border_edges() {
+            for (vertices, indices) in [grid(10, |_, _| 0.0), grid(10, bump)] {
+                let (simplified, _) = simplify(&vertices, &indices, 0, 0.1);
+
+                assert!(simplified.len() < indices.len());
+                assert_eq!(border_edges(&simplified), border_edges(&indices));
+            }
+        }
+    }
+}
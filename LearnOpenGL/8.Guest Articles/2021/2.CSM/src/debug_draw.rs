@@ -0,0 +1,309 @@
+pub mod debug_draw {
+    use crate::{strokes, Program, GLYPH_ADVANCE};
+    use glam::{Mat4, Vec3, Vec4};
+    use std::{f32::consts::PI, mem::size_of, ptr};
+
+    // segments a circle or sphere is drawn with
+    const CIRCLE_SEGMENTS: usize = 32;
+    // of the arrow's length, the head is this long
+    const ARROW_HEAD: f32 = 0.15;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct DebugVertex {
+        position: Vec3,
+        color: Vec4,
+    }
+
+    struct Label {
+        position: Vec3,
+        text: String,
+        height: f32,
+        color: Vec4,
+        depth_test: bool,
+    }
+
+    // collects lines through the frame and draws them all at once in flush; shapes added while depth_test is off
+    // show through the scene. the shader is debug_draw.vs and debug_draw.fs
+    pub struct DebugDraw {
+        vertex_array: gl::types::GLuint,
+        vertex_buffer: gl::types::GLuint,
+        // in vertices
+        capacity: usize,
+        tested: Vec<DebugVertex>,
+        overlay: Vec<DebugVertex>,
+        labels: Vec<Label>,
+        pub depth_test: bool,
+    }
+
+    impl DebugDraw {
+        pub fn new() -> Self {
+            let mut vertex_array = 0;
+            let mut vertex_buffer = 0;
+            let stride = size_of::<DebugVertex>() as i32;
+
+            unsafe {
+                gl::GenVertexArrays(1, &mut vertex_array);
+                gl::GenBuffers(1, &mut vertex_buffer);
+
+                gl::BindVertexArray(vertex_array);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+
+                gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+                gl::EnableVertexAttribArray(0);
+
+                gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, stride, (size_of::<Vec3>() as *const usize).cast());
+                gl::EnableVertexAttribArray(1);
+
+                gl::BindVertexArray(0);
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            }
+
+            DebugDraw {
+                vertex_array,
+                vertex_buffer,
+                capacity: 0,
+                tested: vec![],
+                overlay: vec![],
+                labels: vec![],
+                depth_test: true,
+            }
+        }
+
+        pub fn line(&mut self, from: Vec3, to: Vec3, color: Vec4) {
+            let lines = if self.depth_test { &mut self.tested } else { &mut self.overlay };
+            lines.push(DebugVertex { position: from, color });
+            lines.push(DebugVertex { position: to, color });
+        }
+
+        // the head is four strokes back from the tip
+        pub fn arrow(&mut self, from: Vec3, to: Vec3, color: Vec4) {
+            self.line(from, to, color);
+
+            let length = from.distance(to);
+            if length <= f32::EPSILON {
+                return;
+            }
+
+            let direction = (to - from) / length;
+            let (side, up) = perpendiculars(direction);
+            let head = length * ARROW_HEAD;
+            let base = to - direction * head;
+
+            for offset in [side, -side, up, -up] {
+                self.line(to, base + offset * head * 0.5, color);
+            }
+        }
+
+        // a small three axis cross
+        pub fn point(&mut self, position: Vec3, size: f32, color: Vec4) {
+            for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                self.line(position - axis * size * 0.5, position + axis * size * 0.5, color);
+            }
+        }
+
+        pub fn aabb(&mut self, min: Vec3, max: Vec3, color: Vec4) {
+            let center = (min + max) * 0.5;
+            let half = (max - min) * 0.5;
+            self.obb(center, [Vec3::X * half.x, Vec3::Y * half.y, Vec3::Z * half.z], color);
+        }
+
+        // half_axes go from the center to the middle of three faces
+        pub fn obb(&mut self, center: Vec3, half_axes: [Vec3; 3], color: Vec4) {
+            let matrix = Mat4::from_cols(half_axes[0].extend(0.0), half_axes[1].extend(0.0), half_axes[2].extend(0.0), center.extend(1.0));
+            self.cube(matrix, color);
+        }
+
+        // the -1 to 1 cube under matrix, what a model matrix does to VertexArray::new_cube
+        pub fn cube(&mut self, matrix: Mat4, color: Vec4) {
+            let corners = cube_corners().map(|corner| matrix.transform_point3(corner));
+            self.box_edges(&corners, color);
+        }
+
+        // what view_projection keeps after clipping, the -1 to 1 cube of normalized device coordinates taken back to world space
+        pub fn frustum(&mut self, view_projection: Mat4, color: Vec4) {
+            let inverse = view_projection.inverse();
+            let corners = cube_corners().map(|corner| {
+                let point = inverse * corner.extend(1.0);
+                point.truncate() / point.w
+            });
+            self.box_edges(&corners, color);
+        }
+
+        pub fn circle(&mut self, center: Vec3, normal: Vec3, radius: f32, color: Vec4) {
+            let (u, v) = perpendiculars(normal.normalize());
+            let at = |i: usize| {
+                let angle = i as f32 / CIRCLE_SEGMENTS as f32 * 2.0 * PI;
+                center + (u * angle.cos() + v * angle.sin()) * radius
+            };
+
+            for i in 0..CIRCLE_SEGMENTS {
+                self.line(at(i), at(i + 1), color);
+            }
+        }
+
+        // three great circles
+        pub fn sphere(&mut self, center: Vec3, radius: f32, color: Vec4) {
+            for normal in [Vec3::X, Vec3::Y, Vec3::Z] {
+                self.circle(center, normal, radius, color);
+            }
+        }
+
+        // cells of size spacing on the xz plane around center
+        pub fn grid(&mut self, center: Vec3, cells: u32, spacing: f32, color: Vec4) {
+            let half = cells as f32 * spacing * 0.5;
+
+            for i in 0..=cells {
+                let offset = i as f32 * spacing - half;
+                self.line(center + Vec3::new(offset, 0.0, -half), center + Vec3::new(offset, 0.0, half), color);
+                self.line(center + Vec3::new(-half, 0.0, offset), center + Vec3::new(half, 0.0, offset), color);
+            }
+        }
+
+        // the x, y and z of matrix from its origin, in red, green and blue
+        pub fn axes(&mut self, matrix: Mat4, length: f32) {
+            let origin = matrix.transform_point3(Vec3::ZERO);
+            let colors = [Vec4::new(1.0, 0.0, 0.0, 1.0), Vec4::new(0.0, 1.0, 0.0, 1.0), Vec4::new(0.0, 0.0, 1.0, 1.0)];
+
+            for (axis, color) in [Vec3::X, Vec3::Y, Vec3::Z].into_iter().zip(colors) {
+                self.arrow(origin, origin + matrix.transform_vector3(axis).normalize_or_zero() * length, color);
+            }
+        }
+
+        // world space text facing the camera, its bottom left at position; height is in world units
+        pub fn text(&mut self, position: Vec3, text: &str, height: f32, color: Vec4) {
+            self.labels.push(Label {
+                position,
+                text: text.to_string(),
+                height,
+                color,
+                depth_test: self.depth_test,
+            });
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.tested.is_empty() && self.overlay.is_empty() && self.labels.is_empty()
+        }
+
+        // drops what was added without drawing it
+        pub fn clear(&mut self) {
+            self.tested.clear();
+            self.overlay.clear();
+            self.labels.clear();
+        }
+
+        // draws everything added since the last flush in one upload, leaves depth testing and blending as it found them
+        pub fn flush(&mut self, shader: &Program, projection: Mat4, view: Mat4) {
+            self.lay_out_labels(view);
+
+            let count = self.tested.len() + self.overlay.len();
+            if count == 0 {
+                return;
+            }
+
+            unsafe {
+                gl::BindVertexArray(self.vertex_array);
+                gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer);
+
+                let stride = size_of::<DebugVertex>();
+                if count > self.capacity {
+                    self.capacity = count.next_power_of_two();
+                    gl::BufferData(gl::ARRAY_BUFFER, (self.capacity * stride) as isize, ptr::null(), gl::STREAM_DRAW);
+                }
+                gl::BufferSubData(gl::ARRAY_BUFFER, 0, (self.tested.len() * stride) as isize, self.tested.as_ptr().cast());
+                gl::BufferSubData(gl::ARRAY_BUFFER, (self.tested.len() * stride) as isize, (self.overlay.len() * stride) as isize, self.overlay.as_ptr().cast());
+            }
+
+            shader.apply();
+            shader.set_mat4("projection", projection);
+            shader.set_mat4("view", view);
+
+            unsafe {
+                let depth_was_on = gl::IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+                let blend_was_on = gl::IsEnabled(gl::BLEND) == gl::TRUE;
+
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+                gl::Enable(gl::DEPTH_TEST);
+                gl::DrawArrays(gl::LINES, 0, self.tested.len() as i32);
+                gl::Disable(gl::DEPTH_TEST);
+                gl::DrawArrays(gl::LINES, self.tested.len() as i32, self.overlay.len() as i32);
+
+                if depth_was_on {
+                    gl::Enable(gl::DEPTH_TEST);
+                }
+                if !blend_was_on {
+                    gl::Disable(gl::BLEND);
+                }
+
+                gl::BindVertexArray(0);
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            }
+
+            self.tested.clear();
+            self.overlay.clear();
+        }
+
+        // labels turn into lines once the camera is known
+        fn lay_out_labels(&mut self, view: Mat4) {
+            let camera = view.inverse();
+            let right = camera.transform_vector3(Vec3::X).normalize_or_zero();
+            let up = camera.transform_vector3(Vec3::Y).normalize_or_zero();
+
+            let depth_test = self.depth_test;
+            for label in std::mem::take(&mut self.labels) {
+                self.depth_test = label.depth_test;
+
+                for (i, c) in label.text.chars().enumerate() {
+                    let origin = label.position + right * (i as f32 * GLYPH_ADVANCE * label.height);
+
+                    for (x0, y0, x1, y1) in strokes(c) {
+                        let at = |x: f32, y: f32| origin + right * (x * label.height) + up * (y * label.height);
+                        self.line(at(x0, y0), at(x1, y1), label.color);
+                    }
+                }
+            }
+            self.depth_test = depth_test;
+        }
+
+        // corners in the order cube_corners gives them
+        fn box_edges(&mut self, corners: &[Vec3; 8], color: Vec4) {
+            for (a, b) in [(0, 1), (2, 3), (4, 5), (6, 7), (0, 2), (1, 3), (4, 6), (5, 7), (0, 4), (1, 5), (2, 6), (3, 7)] {
+                self.line(corners[a], corners[b], color);
+            }
+        }
+    }
+
+    impl Default for DebugDraw {
+        fn default() -> Self {
+            DebugDraw::new()
+        }
+    }
+
+    impl Drop for DebugDraw {
+        fn drop(&mut self) {
+            unsafe {
+                gl::DeleteVertexArrays(1, &self.vertex_array);
+                gl::DeleteBuffers(1, &self.vertex_buffer);
+            }
+        }
+    }
+
+    // bit 0 of the index is z, bit 1 is y and bit 2 is x, so edges join indices one bit apart
+    fn cube_corners() -> [Vec3; 8] {
+        let mut corners = [Vec3::ZERO; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let sign = |bit: usize| if i & bit != 0 { 1.0 } else { -1.0 };
+            *corner = Vec3::new(sign(4), sign(2), sign(1));
+        }
+        corners
+    }
+
+    // two unit vectors at right angles to direction and each other
+    fn perpendiculars(direction: Vec3) -> (Vec3, Vec3) {
+        let helper = if direction.y.abs() < 0.99 { Vec3::Y } else { Vec3::X };
+        let side = direction.cross(helper).normalize();
+        (side, side.cross(direction))
+    }
+}
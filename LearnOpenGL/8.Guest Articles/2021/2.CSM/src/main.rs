@@ -1,4 +1,4 @@
-use glam::{Mat4, Quat, Vec3, Vec4};
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
 use glfw::{Action, Context, CursorMode, Key, Window, WindowEvent, WindowHint};
 use std::{f32::consts::PI, mem::size_of, ptr};
 
@@ -9,13 +9,28 @@ const CAMERA_NEAR: f32 = 0.1;
 const CAMERA_FAR: f32 = 500.0;
 const SHADOW_CASCADE_LEVELS: [f32; 4] = [CAMERA_FAR / 50.0, CAMERA_FAR / 25.0, CAMERA_FAR / 10.0, CAMERA_FAR / 2.0];
 const DEPTH_MAP_RESOLUTION: i32 = 4096;
+const SCENE_PATH: &str = "resources/scenes/cubes.json";
 
+const CASCADE_COLORS: [Vec4; 3] = [Vec4::new(1.0, 0.0, 0.0, 0.5), Vec4::new(0.0, 1.0, 0.0, 0.5), Vec4::new(0.0, 0.0, 1.0, 0.5)];
+
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::{Camera, Movement};
+pub mod debug_draw;
+use debug_draw::debug_draw::DebugDraw;
+pub mod font;
+use font::font::{strokes, GLYPH_ADVANCE};
+pub mod json;
+use json::json::{Json, ParseError};
 pub mod mesh;
 use mesh::mesh::{Texture, VertexArray};
+pub mod scene_file;
+use scene_file::scene_file::{NodeDescription, Primitive, Rotation, SceneDescription, SceneError};
 pub mod shader;
 use shader::shader::Program;
+pub mod ui;
+use ui::ui::Ui;
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
@@ -33,6 +48,8 @@ fn main() {
     window.set_framebuffer_size_polling(true);
     window.set_cursor_pos_polling(true);
     window.set_scroll_polling(true);
+    window.set_key_polling(true);
+    window.set_mouse_button_polling(true);
     window.focus();
 
     gl::load_with(|s| window.get_proc_address(s) as *const _);
@@ -45,7 +62,10 @@ fn main() {
     let shader = Program::new().link("src/10.shadow_mapping.vs", "src/10.shadow_mapping.fs", None);
     let simple_depth_shader = Program::new().link("src/10.shadow_mapping_depth.vs", "src/10.shadow_mapping_depth.fs", Some("src/10.shadow_mapping_depth.gs"));
     let debug_depth_quad = Program::new().link("src/10.debug_quad.vs", "src/10.debug_quad_depth.fs", None);
-    let debug_cascade_shader = Program::new().link("src/10.debug_cascade.vs", "src/10.debug_cascade.fs", None);
+    let debug_draw_shader = Program::new().link("src/debug_draw.vs", "src/debug_draw.fs", None);
+    let mut debug = DebugDraw::new();
+    let ui_shader = Program::new().link("src/ui.vs", "src/ui.fs", None);
+    let mut ui = Ui::new();
 
     let plane = VertexArray::new_plane();
 
@@ -63,21 +83,31 @@ fn main() {
 
     glfw.poll_events();
 
-    let mut model_matrices = Vec::new();
-    for _ in 0..10 {
-        let scale = Vec3::new(fastrand::f32() + 1.0, fastrand::f32() + 1.0, fastrand::f32() + 1.0);
-        let rotation = Quat::from_axis_angle(Vec3::new(1.0, 0.0, 1.0), fastrand::f32() * PI);
-        let translation = Vec3::new(fastrand::f32() * 20.0 - 10.0, fastrand::f32() * 20.0, fastrand::f32() * 20.0 - 10.0);
-        model_matrices.push(Mat4::from_scale_rotation_translation(scale, rotation, translation));
-    }
+    let description = match SceneDescription::load(SCENE_PATH) {
+        Ok(description) => description,
+        Err(SceneError::Io { error, .. }) if error.kind() == std::io::ErrorKind::NotFound => {
+            let description = random_cubes();
+            if let Err(error) = std::fs::create_dir_all("resources/scenes").map_err(|error| error.to_string()).and_then(|_| description.save(SCENE_PATH).map_err(|error| error.to_string())) {
+                println!("Failed to write the default scene: {error}");
+            }
+            description
+        }
+        Err(error) => {
+            println!("Failed to load the scene, using a random one: {error}");
+            random_cubes()
+        }
+    };
+    let nodes: Vec<(Primitive, Mat4)> = description.nodes.iter().map(|node| (node.model, node.model_matrix())).collect();
     let mut cache = Vec::new();
-    let mut input_state = InputState {
+    let mut parameters = Parameters {
         show_quad: false,
-        c_press: Action::Release,
-        f_press: Action::Release,
-        plus_press: Action::Release,
+        show_debug: false,
         debug_layer: 0,
-        light_dir: Vec3::new(20.0, 50.0, 20.0).normalize(),
+        // towards (20, 50, 20)
+        light_azimuth: PI / 4.0,
+        light_elevation: f32::atan2(50.0, 800.0f32.sqrt()),
+        cascade_levels: SHADOW_CASCADE_LEVELS,
+        ui_focus: false,
     };
 
     let (x_pos, y_pos) = window.get_cursor_pos();
@@ -90,12 +120,17 @@ fn main() {
         let delta_time = current_frame - last_frame;
         last_frame = current_frame;
 
-        process_input(&mut camera, &mut window, &mut input_state, &mut cache, delta_time);
+        process_input(&mut camera, &mut window, delta_time);
+
+        let (width, height) = window.get_size();
+        ui.begin_frame(width, height);
+        parameters_window(&mut ui, &mut parameters, &mut camera, &mut cache);
+        let light_dir = parameters.light_dir();
 
         ogl::clear_color(0.1, 0.1, 0.1, 1.0);
         ogl::clear();
 
-        let light_matrices = get_light_space_matrices(&mut camera, input_state.light_dir);
+        let light_matrices = get_light_space_matrices(&mut camera, light_dir, &parameters.cascade_levels);
         matrices_ubo.bind();
         for i in 0..light_matrices.len() {
             unsafe {
@@ -115,7 +150,7 @@ fn main() {
         ogl::viewport(DEPTH_MAP_RESOLUTION, DEPTH_MAP_RESOLUTION);
         ogl::clear();
         ogl::cull_front_face(true);
-        render_scene(&simple_depth_shader, &mut model_matrices, &plane, &cube);
+        render_scene(&simple_depth_shader, &nodes, &plane, &cube);
         ogl::cull_front_face(false);
         ogl::unbind_framebuffer();
 
@@ -129,117 +164,135 @@ fn main() {
         shader.set_mat4("projection", projection);
         shader.set_mat4("view", view);
         shader.set_vec3("viewPos", camera.position());
-        shader.set_vec3("lightDir", input_state.light_dir);
+        shader.set_vec3("lightDir", light_dir);
         shader.set_float("farPlane", CAMERA_FAR);
-        shader.set_int("cascadeCount", SHADOW_CASCADE_LEVELS.len() as i32);
-        for i in 0..SHADOW_CASCADE_LEVELS.len() {
-            shader.set_float(&("cascadePlaneDistances[".to_string() + &(i.to_string() + "]")), SHADOW_CASCADE_LEVELS[i]);
+        shader.set_int("cascadeCount", parameters.cascade_levels.len() as i32);
+        for i in 0..parameters.cascade_levels.len() {
+            shader.set_float(&("cascadePlaneDistances[".to_string() + &(i.to_string() + "]")), parameters.cascade_levels[i]);
         }
         ogl::active_texture(0);
         wood_tex.bind();
         ogl::active_texture(1);
         light_fbo.bind_texture();
-        render_scene(&shader, &mut model_matrices, &plane, &cube);
-
-        if cache.len() > 0 {
-            ogl::blend_enable(true);
-            ogl::blend_func();
-            debug_cascade_shader.apply();
-            debug_cascade_shader.set_mat4("projection", projection);
-            debug_cascade_shader.set_mat4("view", view);
-            draw_cascade_volume_visualizers(&cache, &debug_cascade_shader);
-            ogl::blend_enable(false);
+        render_scene(&shader, &nodes, &plane, &cube);
+
+        for (i, light_matrix) in cache.iter().enumerate() {
+            debug.frustum(*light_matrix, CASCADE_COLORS[i % 3]);
         }
 
+        if parameters.show_debug {
+            debug.grid(Vec3::new(0.0, -1.99, 0.0), 50, 1.0, Vec4::new(0.5, 0.5, 0.5, 0.5));
+            debug.axes(Mat4::IDENTITY, 2.0);
+            for (_, model) in nodes.iter().filter(|(primitive, _)| *primitive == Primitive::Cube) {
+                debug.cube(*model, Vec4::new(1.0, 1.0, 0.0, 1.0));
+                debug.axes(*model, 1.0);
+            }
+
+            debug.depth_test = false;
+            let light_position = Vec3::new(0.0, 5.0, 0.0);
+            debug.arrow(light_position + light_dir * 4.0, light_position, Vec4::new(1.0, 1.0, 1.0, 1.0));
+            debug.text(light_position + light_dir * 4.0, "light", 0.5, Vec4::new(1.0, 1.0, 1.0, 1.0));
+            debug.depth_test = true;
+        }
+
+        debug.flush(&debug_draw_shader, projection, view);
+
         debug_depth_quad.apply();
-        debug_depth_quad.set_int("layer", input_state.debug_layer);
+        debug_depth_quad.set_int("layer", parameters.debug_layer);
         ogl::active_texture(0);
         light_fbo.bind_texture();
-        if input_state.show_quad {
+        if parameters.show_quad {
             quad.bind();
             quad.draw();
         }
 
+        ui.end_frame(&ui_shader);
+
         window.swap_buffers();
 
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut camera, &mut window, event);
+            handle_window_event(&mut camera, &mut window, &mut ui, &mut parameters.ui_focus, event);
         }
     }
 }
 
-fn render_scene(shader: &Program, model_matrices: &mut Vec<Mat4>, plane: &VertexArray, cube: &VertexArray) {
-    shader.set_mat4("model", Mat4::IDENTITY);
-    plane.bind();
-    plane.draw();
-
-    cube.bind();
-    for model in model_matrices {
+fn render_scene(shader: &Program, nodes: &[(Primitive, Mat4)], plane: &VertexArray, cube: &VertexArray) {
+    for (primitive, model) in nodes {
+        let vertex_array = match primitive {
+            Primitive::Plane => plane,
+            Primitive::Cube => cube,
+        };
         shader.set_mat4("model", *model);
-        cube.draw()
+        vertex_array.bind();
+        vertex_array.draw();
     }
 }
 
-fn draw_cascade_volume_visualizers(light_matrices: &Vec<Mat4>, shader: &Program) {
-    const INDICES: [u32; 36] = [0, 2, 3, 0, 3, 1, 4, 6, 2, 4, 2, 0, 5, 7, 6, 5, 6, 4, 1, 3, 7, 1, 7, 5, 6, 7, 3, 6, 3, 2, 1, 5, 4, 0, 1, 4];
-
-    const COLORS: [Vec4; 3] = [Vec4::new(1.0, 0.0, 0.0, 0.5), Vec4::new(0.0, 1.0, 0.0, 0.5), Vec4::new(0.0, 0.0, 1.0, 0.5)];
+// the floor and ten cubes scattered above it, what the sample shows when there is no scene file
+fn random_cubes() -> SceneDescription {
+    let mut nodes = vec![NodeDescription::new("floor", Primitive::Plane)];
 
-    let mut visualizer_vaos = Vec::new();
-    let mut visualizer_vbos = Vec::new();
-    let mut visualizer_ebos = Vec::new();
-
-    for i in 0..light_matrices.len() {
-        let corners = get_frustum_corners_world_space(light_matrices[i]);
-        let mut vec3s = Vec::new();
-        for v in corners {
-            vec3s.push(v.truncate());
-        }
-
-        visualizer_vaos.push(0);
-        visualizer_vbos.push(0);
-        visualizer_ebos.push(0);
+    for i in 0..10 {
+        let mut cube = NodeDescription::new(&format!("cube {i}"), Primitive::Cube);
+        cube.scale = Vec3::new(fastrand::f32() + 1.0, fastrand::f32() + 1.0, fastrand::f32() + 1.0);
+        cube.rotation = Rotation::Quaternion(Quat::from_axis_angle(Vec3::new(1.0, 0.0, 1.0).normalize(), fastrand::f32() * PI));
+        cube.position = Vec3::new(fastrand::f32() * 20.0 - 10.0, fastrand::f32() * 20.0, fastrand::f32() * 20.0 - 10.0);
+        nodes.push(cube);
+    }
 
-        unsafe {
-            gl::GenVertexArrays(1, &mut visualizer_vaos[i]);
-            gl::GenBuffers(1, &mut visualizer_vbos[i]);
-            gl::GenBuffers(1, &mut visualizer_ebos[i]);
+    SceneDescription { nodes }
+}
 
-            gl::BindVertexArray(visualizer_vaos[i]);
+// what the ui edits
+struct Parameters {
+    show_quad: bool,
+    show_debug: bool,
+    debug_layer: i32,
+    light_azimuth: f32,
+    light_elevation: f32,
+    cascade_levels: [f32; 4],
+    // whether the cursor is free for the ui instead of turning the camera
+    ui_focus: bool,
+}
 
-            gl::BindBuffer(gl::ARRAY_BUFFER, visualizer_vbos[i]);
-            gl::BufferData(gl::ARRAY_BUFFER, (vec3s.len() * size_of::<Vec3>()) as isize, vec3s.as_ptr().cast(), gl::STATIC_DRAW);
+impl Parameters {
+    // from the scene towards the light
+    fn light_dir(&self) -> Vec3 {
+        let (sin_elevation, cos_elevation) = self.light_elevation.sin_cos();
+        Vec3::new(cos_elevation * self.light_azimuth.cos(), sin_elevation, cos_elevation * self.light_azimuth.sin())
+    }
+}
 
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, visualizer_ebos[i]);
-            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (36 * size_of::<u32>()) as isize, INDICES.as_ptr().cast(), gl::STATIC_DRAW);
+fn parameters_window(ui: &mut Ui, parameters: &mut Parameters, camera: &mut Camera, cache: &mut Vec<Mat4>) {
+    ui.window("cascaded shadows", Vec2::new(10.0, 10.0), 320.0, |ui| {
+        ui.label(if parameters.ui_focus { "tab: back to the camera" } else { "tab: use the mouse here" });
 
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, size_of::<Vec3>() as i32, ptr::null());
+        ui.slider("light azimuth", &mut parameters.light_azimuth, 0.0..=2.0 * PI);
+        ui.slider("light elevation", &mut parameters.light_elevation, 0.1..=PI / 2.0);
 
-            gl::BindVertexArray(visualizer_vaos[i]);
-            shader.set_vec4("color", COLORS[i % 3]);
-            gl::DrawElements(gl::TRIANGLES, 36, gl::UNSIGNED_INT, ptr::null());
+        // each split stays past the one before it
+        let mut near = CAMERA_NEAR;
+        for (i, level) in parameters.cascade_levels.iter_mut().enumerate() {
+            ui.slider(&format!("split {i}"), level, near..=CAMERA_FAR);
+            *level = level.max(near + 0.1);
+            near = *level;
+        }
 
-            gl::DeleteBuffers(1, &visualizer_vbos[i]);
-            gl::DeleteBuffers(1, &visualizer_ebos[i]);
-            gl::DeleteVertexArrays(1, &visualizer_vaos[i]);
+        ui.checkbox("depth map", &mut parameters.show_quad);
+        ui.slider_int("depth map layer", &mut parameters.debug_layer, 0..=parameters.cascade_levels.len() as i32);
+        ui.checkbox("debug draw", &mut parameters.show_debug);
 
-            gl::BindVertexArray(0);
+        if ui.button("freeze cascades") {
+            *cache = get_light_space_matrices(camera, parameters.light_dir(), &parameters.cascade_levels);
         }
-    }
-}
-
-pub struct InputState {
-    show_quad: bool,
-    c_press: Action,
-    f_press: Action,
-    plus_press: Action,
-    debug_layer: i32,
-    light_dir: Vec3,
+        if ui.button("clear frozen cascades") {
+            cache.clear();
+        }
+    });
 }
 
-fn process_input(camera: &mut Camera, window: &mut Window, input_state: &mut InputState, cache: &mut Vec<Mat4>, delta_time: f32) {
+fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
     }
@@ -258,31 +311,32 @@ fn process_input(camera: &mut Camera, window: &mut Window, input_state: &mut Inp
     if window.get_key(Key::D) == Action::Press {
         camera.process_keyboard(Movement::Right, delta_time);
     }
+}
 
-    if window.get_key(Key::F) == Action::Release && input_state.f_press == Action::Press {
-        input_state.show_quad = !input_state.show_quad;
-    }
-    input_state.f_press = window.get_key(Key::F);
-
-    if window.get_key(Key::KpAdd) == Action::Release && input_state.plus_press == Action::Press {
-        input_state.debug_layer = (input_state.debug_layer + 1) % SHADOW_CASCADE_LEVELS.len() as i32;
+fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, ui: &mut Ui, ui_focus: &mut bool, event: glfw::WindowEvent) {
+    if *ui_focus {
+        ui.handle_event(&event);
     }
-    input_state.plus_press = window.get_key(Key::KpAdd);
 
-    if window.get_key(Key::C) == Action::Release && input_state.c_press == Action::Press {
-        *cache = get_light_space_matrices(camera, input_state.light_dir);
-    }
-    input_state.c_press = window.get_key(Key::C);
-}
-
-fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, event: glfw::WindowEvent) {
     match event {
+        WindowEvent::Key(Key::Tab, _, Action::Release, _) => {
+            *ui_focus = !*ui_focus;
+            window.set_cursor_mode(if *ui_focus { CursorMode::Normal } else { CursorMode::Disabled });
+        }
         WindowEvent::FramebufferSize(width, height) => ogl::viewport(width, height),
-        WindowEvent::Scroll(_, y_offset) => camera.process_scroll(y_offset as f32),
+        WindowEvent::Scroll(_, y_offset) => {
+            if !(*ui_focus && ui.wants_mouse()) {
+                camera.process_scroll(y_offset as f32);
+            }
+        }
         WindowEvent::CursorPos(_x_pos, _y_pos) => {
             let (x_pos, y_pos) = window.get_cursor_pos();
 
-            camera.process_mouse(x_pos as f32, y_pos as f32, true);
+            if *ui_focus {
+                camera.set_cursor(x_pos as f32, y_pos as f32);
+            } else {
+                camera.process_mouse(x_pos as f32, y_pos as f32, true);
+            }
         }
         _ => {}
     }
@@ -350,15 +404,15 @@ fn get_light_space_matrix(camera: &mut Camera, light_dir: Vec3, near_plane: f32,
     light_projection * light_view
 }
 
-fn get_light_space_matrices(camera: &mut Camera, light_dir: Vec3) -> Vec<Mat4> {
+fn get_light_space_matrices(camera: &mut Camera, light_dir: Vec3, cascade_levels: &[f32]) -> Vec<Mat4> {
     let mut ret = Vec::new();
-    for i in 0..=SHADOW_CASCADE_LEVELS.len() {
+    for i in 0..=cascade_levels.len() {
         if i == 0 {
-            ret.push(get_light_space_matrix(camera, light_dir, CAMERA_NEAR, SHADOW_CASCADE_LEVELS[i]));
-        } else if i < SHADOW_CASCADE_LEVELS.len() {
-            ret.push(get_light_space_matrix(camera, light_dir, SHADOW_CASCADE_LEVELS[i - 1], SHADOW_CASCADE_LEVELS[i]));
+            ret.push(get_light_space_matrix(camera, light_dir, CAMERA_NEAR, cascade_levels[i]));
+        } else if i < cascade_levels.len() {
+            ret.push(get_light_space_matrix(camera, light_dir, cascade_levels[i - 1], cascade_levels[i]));
         } else {
-            ret.push(get_light_space_matrix(camera, light_dir, SHADOW_CASCADE_LEVELS[i - 1], CAMERA_FAR));
+            ret.push(get_light_space_matrix(camera, light_dir, cascade_levels[i - 1], CAMERA_FAR));
         }
     }
     ret
@@ -412,22 +466,6 @@ mod ogl {
         }
     }
 
-    pub fn blend_enable(on: bool) {
-        unsafe {
-            if on {
-                gl::Enable(gl::BLEND);
-            } else {
-                gl::Disable(gl::BLEND);
-            }
-        }
-    }
-
-    pub fn blend_func() {
-        unsafe {
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-        }
-    }
-
     pub fn unbind_ubo() {
         unsafe {
             gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
@@ -0,0 +1,213 @@
+pub mod scene_file {
+    use crate::{Json, ParseError};
+    use glam::{EulerRot, Mat4, Quat, Vec3};
+    use std::{
+        fmt::{self, Display, Formatter},
+        fs, io,
+    };
+
+    // the same layout the frustum culling sample reads, with the sample's primitives standing in for model files
+    pub const SCENE_VERSION: u32 = 1;
+
+    #[derive(Debug)]
+    pub enum SceneError {
+        Io { path: String, error: io::Error },
+        Parse { path: String, error: ParseError },
+        Version { found: f64 },
+        // where in the file, what was wrong
+        Invalid { context: String, message: String },
+        UnknownModel { node: String, model: String },
+    }
+
+    impl Display for SceneError {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            match self {
+                SceneError::Io { path, error } => write!(f, "{path}: {error}"),
+                SceneError::Parse { path, error } => write!(f, "{path}: {error}"),
+                SceneError::Version { found } => write!(f, "scene version {found} is not supported, {SCENE_VERSION} is the newest known"),
+                SceneError::Invalid { context, message } => write!(f, "{context}: {message}"),
+                SceneError::UnknownModel { node, model } => write!(f, "node \"{node}\" uses model \"{model}\", expected \"plane\" or \"cube\""),
+            }
+        }
+    }
+
+    fn invalid(context: &str, message: &str) -> SceneError {
+        SceneError::Invalid {
+            context: context.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Primitive {
+        Plane,
+        Cube,
+    }
+
+    impl Primitive {
+        fn name(self) -> &'static str {
+            match self {
+                Primitive::Plane => "plane",
+                Primitive::Cube => "cube",
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Rotation {
+        // radians, applied x then y then z
+        Euler(Vec3),
+        Quaternion(Quat),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct NodeDescription {
+        pub name: String,
+        pub model: Primitive,
+        pub position: Vec3,
+        pub rotation: Rotation,
+        pub scale: Vec3,
+    }
+
+    impl NodeDescription {
+        pub fn new(name: &str, model: Primitive) -> Self {
+            NodeDescription {
+                name: name.to_string(),
+                model,
+                position: Vec3::ZERO,
+                rotation: Rotation::Euler(Vec3::ZERO),
+                scale: Vec3::ONE,
+            }
+        }
+
+        pub fn model_matrix(&self) -> Mat4 {
+            let rotation = match self.rotation {
+                Rotation::Euler(euler) => Quat::from_euler(EulerRot::XYZ, euler.x, euler.y, euler.z),
+                Rotation::Quaternion(quaternion) => quaternion,
+            };
+            Mat4::from_scale_rotation_translation(self.scale, rotation, self.position)
+        }
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct SceneDescription {
+        pub nodes: Vec<NodeDescription>,
+    }
+
+    impl SceneDescription {
+        pub fn load(path: &str) -> Result<Self, SceneError> {
+            let text = fs::read_to_string(path).map_err(|error| SceneError::Io {
+                path: path.to_string(),
+                error,
+            })?;
+            let json = Json::parse(&text).map_err(|error| SceneError::Parse {
+                path: path.to_string(),
+                error,
+            })?;
+
+            SceneDescription::from_json(&json)
+        }
+
+        pub fn save(&self, path: &str) -> Result<(), SceneError> {
+            fs::write(path, self.to_json().pretty()).map_err(|error| SceneError::Io {
+                path: path.to_string(),
+                error,
+            })
+        }
+
+        pub fn from_json(json: &Json) -> Result<Self, SceneError> {
+            let version = json.get("version").ok_or_else(|| invalid("scene", "\"version\" is missing"))?;
+            let version = version.as_f64().ok_or_else(|| invalid("scene", "\"version\" should be a number"))?;
+            if version.fract() != 0.0 || version < 1.0 || version > SCENE_VERSION as f64 {
+                return Err(SceneError::Version { found: version });
+            }
+
+            let nodes = match json.get("nodes") {
+                Some(nodes) => nodes.as_array().ok_or_else(|| invalid("scene", "\"nodes\" should be an array"))?,
+                None => &[],
+            };
+
+            Ok(SceneDescription {
+                nodes: nodes.iter().map(node_from_json).collect::<Result<_, _>>()?,
+            })
+        }
+
+        pub fn to_json(&self) -> Json {
+            Json::Object(vec![
+                ("version".to_string(), Json::Number(SCENE_VERSION as f64)),
+                ("nodes".to_string(), Json::Array(self.nodes.iter().map(node_to_json).collect())),
+            ])
+        }
+    }
+
+    fn node_from_json(json: &Json) -> Result<NodeDescription, SceneError> {
+        let name = json.get("name").and_then(Json::as_str).ok_or_else(|| invalid("node", "\"name\" should be a string"))?.to_string();
+        let context = format!("node \"{name}\"");
+
+        let model = json.get("model").and_then(Json::as_str).ok_or_else(|| invalid(&context, "\"model\" should be a string"))?;
+        let model = match model {
+            "plane" => Primitive::Plane,
+            "cube" => Primitive::Cube,
+            _ => {
+                return Err(SceneError::UnknownModel {
+                    node: name,
+                    model: model.to_string(),
+                })
+            }
+        };
+
+        let rotation = match (json.get("rotation"), json.get("quaternion")) {
+            (Some(_), Some(_)) => return Err(invalid(&context, "has both \"rotation\" and \"quaternion\"")),
+            (_, Some(_)) => {
+                let quaternion = Quat::from_array(numbers::<4>(json, "quaternion", &context)?.unwrap());
+                if quaternion.length() == 0.0 {
+                    return Err(invalid(&context, "\"quaternion\" has zero length"));
+                }
+                Rotation::Quaternion(quaternion.normalize())
+            }
+            _ => Rotation::Euler(numbers::<3>(json, "rotation", &context)?.map_or(Vec3::ZERO, Vec3::from)),
+        };
+
+        Ok(NodeDescription {
+            model,
+            position: numbers::<3>(json, "position", &context)?.map_or(Vec3::ZERO, Vec3::from),
+            rotation,
+            scale: numbers::<3>(json, "scale", &context)?.map_or(Vec3::ONE, Vec3::from),
+            name,
+        })
+    }
+
+    fn node_to_json(node: &NodeDescription) -> Json {
+        let numbers = |values: &[f32]| Json::Array(values.iter().map(|&v| Json::from_f32(v)).collect());
+
+        let rotation = match node.rotation {
+            Rotation::Euler(euler) => ("rotation".to_string(), numbers(&euler.to_array())),
+            Rotation::Quaternion(quaternion) => ("quaternion".to_string(), numbers(&quaternion.to_array())),
+        };
+
+        Json::Object(vec![
+            ("name".to_string(), Json::String(node.name.clone())),
+            ("model".to_string(), Json::String(node.model.name().to_string())),
+            ("position".to_string(), numbers(&node.position.to_array())),
+            rotation,
+            ("scale".to_string(), numbers(&node.scale.to_array())),
+        ])
+    }
+
+    // None when the field is left out
+    fn numbers<const N: usize>(json: &Json, field: &str, context: &str) -> Result<Option<[f32; N]>, SceneError> {
+        let values = match json.get(field) {
+            Some(values) => values,
+            None => return Ok(None),
+        };
+
+        let wrong = || invalid(context, &format!("\"{field}\" should be an array of {N} numbers"));
+        let values = values.as_array().filter(|values| values.len() == N).ok_or_else(wrong)?;
+
+        let mut numbers = [0.0; N];
+        for (number, value) in numbers.iter_mut().zip(values) {
+            *number = value.as_f64().ok_or_else(wrong)? as f32;
+        }
+        Ok(Some(numbers))
+    }
+}
@@ -0,0 +1,90 @@
+pub mod font {
+    // of the text height, each character is this wide and advances this far
+    pub const GLYPH_WIDTH: f32 = 0.6;
+    pub const GLYPH_ADVANCE: f32 = 0.85;
+
+    // the stroke font's segments on a 1 by 1 cell, y up, named a to r for the glyph table
+    const SEGMENTS: [(f32, f32, f32, f32); 18] = [
+        (0.0, 1.0, 0.5, 1.0),   // a, top left half
+        (0.5, 1.0, 1.0, 1.0),   // b, top right half
+        (1.0, 1.0, 1.0, 0.5),   // c, upper right
+        (1.0, 0.5, 1.0, 0.0),   // d, lower right
+        (1.0, 0.0, 0.5, 0.0),   // e, bottom right half
+        (0.5, 0.0, 0.0, 0.0),   // f, bottom left half
+        (0.0, 0.0, 0.0, 0.5),   // g, lower left
+        (0.0, 0.5, 0.0, 1.0),   // h, upper left
+        (0.0, 0.5, 0.5, 0.5),   // i, middle left half
+        (0.5, 0.5, 1.0, 0.5),   // j, middle right half
+        (0.0, 1.0, 0.5, 0.5),   // k, upper left diagonal
+        (0.5, 1.0, 0.5, 0.5),   // l, upper middle
+        (1.0, 1.0, 0.5, 0.5),   // m, upper right diagonal
+        (0.0, 0.0, 0.5, 0.5),   // n, lower left diagonal
+        (0.5, 0.0, 0.5, 0.5),   // o, lower middle
+        (1.0, 0.0, 0.5, 0.5),   // p, lower right diagonal
+        (0.4, 0.0, 0.6, 0.0),   // q, dot
+        (0.4, 0.75, 0.6, 0.75), // r, upper dot
+    ];
+
+    // lowercase is drawn as uppercase, anything missing leaves a gap
+    fn glyph(c: char) -> &'static str {
+        match c.to_ascii_uppercase() {
+            '0' => "abcdefghmn",
+            '1' => "cd",
+            '2' => "abcjigef",
+            '3' => "abcdefj",
+            '4' => "hcdij",
+            '5' => "abhijdef",
+            '6' => "abhgefdij",
+            '7' => "abcd",
+            '8' => "abcdefghij",
+            '9' => "abhcdijef",
+            'A' => "abcdghij",
+            'B' => "abcdefloj",
+            'C' => "abghef",
+            'D' => "abcdeflo",
+            'E' => "abghefi",
+            'F' => "abghi",
+            'G' => "abghefdj",
+            'H' => "ghcdij",
+            'I' => "abloef",
+            'J' => "cdefg",
+            'K' => "ghimp",
+            'L' => "ghef",
+            'M' => "ghcdkm",
+            'N' => "ghcdkp",
+            'O' => "abcdefgh",
+            'P' => "abcghij",
+            'Q' => "abcdefghp",
+            'R' => "abcghijp",
+            'S' => "abhijdef",
+            'T' => "ablo",
+            'U' => "ghcdef",
+            'V' => "ghnm",
+            'W' => "ghcdnp",
+            'X' => "kmnp",
+            'Y' => "kmo",
+            'Z' => "abmnef",
+            '-' => "ij",
+            '+' => "ijlo",
+            '=' => "ijef",
+            '_' => "ef",
+            '/' => "mn",
+            '\\' => "kp",
+            '(' | '<' => "mp",
+            ')' | '>' => "kn",
+            '.' | ',' => "q",
+            ':' => "qr",
+            '|' => "lo",
+            '%' => "mnhd",
+            _ => "",
+        }
+    }
+
+    // a character's strokes as (x0, y0, x1, y1), on a cell GLYPH_WIDTH wide and 1 high with y up
+    pub fn strokes(c: char) -> impl Iterator<Item = (f32, f32, f32, f32)> {
+        glyph(c).bytes().map(|name| {
+            let (x0, y0, x1, y1) = SEGMENTS[(name - b'a') as usize];
+            (x0 * GLYPH_WIDTH, y0, x1 * GLYPH_WIDTH, y1)
+        })
+    }
+}
@@ -1,3 +1,4 @@
+use glam::Vec2;
 use glfw::{Context, WindowEvent, WindowHint};
 
 const SCR_WIDTH: u32 = 800;
@@ -5,10 +6,16 @@ const SCR_HEIGHT: u32 = 600;
 const TEX_WIDTH: i32 = 1000;
 const TEX_HEIGHT: i32 = 1000;
 
+pub mod font;
+use font::font::{strokes, GLYPH_ADVANCE};
 pub mod mesh;
 use mesh::mesh::{Texture, VertexArray};
+pub mod profiler;
+use profiler::profiler::Profiler;
 pub mod shader;
 use shader::shader::Program;
+pub mod ui;
+use ui::ui::Ui;
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
@@ -23,6 +30,8 @@ fn main() {
 
     window.make_current();
     window.set_framebuffer_size_polling(true);
+    window.set_cursor_pos_polling(true);
+    window.set_mouse_button_polling(true);
     glfw.set_swap_interval(glfw::SwapInterval::None);
     window.focus();
 
@@ -65,47 +74,67 @@ fn main() {
     ogl::active_texture(0);
     texture.bind();
 
-    let mut f_counter = 0;
+    let ui_shader = Program::new().link("src/ui.vs", "src/ui.fs");
+    let mut ui = Ui::new();
 
-    glfw.poll_events();
+    let mut profiler = Profiler::new();
 
-    let mut last_frame = 0.0;
+    glfw.poll_events();
 
     while !window.should_close() {
         let current_frame = glfw.get_time() as f32;
-        let delta_time = current_frame - last_frame;
-        last_frame = current_frame;
-
-        if f_counter > 500 {
-            println!("FPS: {}", 1.0 / delta_time);
-            f_counter = 0;
-        } else {
-            f_counter += 1;
-        }
 
+        profiler.begin_frame();
+
+        let (width, height) = window.get_size();
+        ui.begin_frame(width, height);
+        profiler.overlay(&mut ui, Vec2::new(10.0, 10.0));
+
+        profiler.begin_gpu("compute");
         compute_shader.apply();
         compute_shader.set_float("t", current_frame);
 
         ogl::dispatch_compute();
 
         ogl::memory_barrier();
+        profiler.end();
 
+        profiler.begin_gpu("screen quad");
         ogl::clear();
         screen_quad.apply();
 
         quad.bind();
         quad.draw();
+        profiler.end();
+
+        profiler.begin_gpu("ui");
+        ui.end_frame(&ui_shader);
+        profiler.end();
+
+        profiler.end_frame();
+        match profiler.save_capture("profile.json") {
+            Ok(true) => println!("Wrote profile.json"),
+            Ok(false) => {}
+            Err(error) => println!("Failed to write profile.json: {}", error),
+        }
 
         window.swap_buffers();
 
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut window, event);
+            handle_window_event(&mut window, &mut ui, event);
         }
     }
+
+    println!("Average and 95th percentile times(ms) of the last frames:");
+    for summary in profiler.summaries() {
+        println!("{}", summary);
+    }
 }
 
-fn handle_window_event(_window: &mut glfw::Window, event: glfw::WindowEvent) {
+fn handle_window_event(_window: &mut glfw::Window, ui: &mut Ui, event: glfw::WindowEvent) {
+    ui.handle_event(&event);
+
     match event {
         WindowEvent::FramebufferSize(width, height) => ogl::viewport(width, height),
         _ => {}
@@ -1,4 +1,5 @@
 pub mod shader {
+    use glam::Mat4;
     use glfw::with_c_str;
     use std::{fs::File, io::Read, ptr, str};
 
@@ -146,6 +147,12 @@ pub mod shader {
             });
         }
 
+        pub fn set_mat4(&self, name: &str, matrix: Mat4) {
+            with_c_str(name, |locn| unsafe {
+                gl::UniformMatrix4fv(gl::GetUniformLocation(self.program, locn), 1, gl::FALSE, matrix.to_cols_array().as_ptr());
+            });
+        }
+
         pub fn program(&self) -> gl::types::GLuint {
             self.program
         }
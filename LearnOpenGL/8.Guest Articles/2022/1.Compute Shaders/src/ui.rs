@@ -0,0 +1,763 @@
+pub mod ui {
+    use crate::{strokes, Program, GLYPH_ADVANCE};
+    use glam::{Mat4, Vec2, Vec3, Vec4};
+    use glfw::{Action, MouseButton, WindowEvent};
+    use std::{
+        collections::{hash_map::DefaultHasher, HashMap},
+        hash::{Hash, Hasher},
+        mem::size_of,
+        ops::RangeInclusive,
+        ptr,
+    };
+
+    // sizes are in window coordinates, the ones cursor positions come in
+    const TEXT_HEIGHT: f32 = 9.0;
+    const STROKE: f32 = 1.25;
+    const ROW_HEIGHT: f32 = 20.0;
+    const SPACING: f32 = 4.0;
+    const PADDING: f32 = 8.0;
+    const TITLE_HEIGHT: f32 = 20.0;
+    const PLOT_HEIGHT: f32 = 60.0;
+    const PICKER_SIZE: f32 = 128.0;
+    const HUE_WIDTH: f32 = 16.0;
+    // of a row, what the widget takes before its label
+    const WIDGET_SHARE: f32 = 0.6;
+
+    const WINDOW_COLOR: Vec4 = Vec4::new(0.08, 0.08, 0.1, 0.9);
+    const TITLE_COLOR: Vec4 = Vec4::new(0.16, 0.29, 0.48, 1.0);
+    const TITLE_HOVERED_COLOR: Vec4 = Vec4::new(0.2, 0.36, 0.6, 1.0);
+    const FRAME_COLOR: Vec4 = Vec4::new(0.2, 0.22, 0.27, 1.0);
+    const FRAME_HOVERED_COLOR: Vec4 = Vec4::new(0.28, 0.31, 0.38, 1.0);
+    const FRAME_ACTIVE_COLOR: Vec4 = Vec4::new(0.33, 0.37, 0.46, 1.0);
+    const FILL_COLOR: Vec4 = Vec4::new(0.26, 0.59, 0.98, 1.0);
+    const TEXT_COLOR: Vec4 = Vec4::new(0.92, 0.92, 0.92, 1.0);
+
+    fn text_width(text: &str) -> f32 {
+        text.chars().count() as f32 * GLYPH_ADVANCE * TEXT_HEIGHT
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct UiVertex {
+        position: Vec2,
+        color: Vec4,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Rect {
+        min: Vec2,
+        max: Vec2,
+    }
+
+    impl Rect {
+        fn new(position: Vec2, size: Vec2) -> Self {
+            Rect {
+                min: position,
+                max: position + size,
+            }
+        }
+
+        fn size(&self) -> Vec2 {
+            self.max - self.min
+        }
+
+        fn contains(&self, point: Vec2) -> bool {
+            point.cmpge(self.min).all() && point.cmplt(self.max).all()
+        }
+
+        // where on the rectangle point is, 0 to 1 on each axis
+        fn fraction(&self, point: Vec2) -> Vec2 {
+            ((point - self.min) / self.size().max(Vec2::ONE)).clamp(Vec2::ZERO, Vec2::ONE)
+        }
+    }
+
+    // kept between frames, so a window stays where it was dragged
+    #[derive(Clone, Copy)]
+    struct WindowState {
+        position: Vec2,
+        width: f32,
+        height: f32,
+        collapsed: bool,
+    }
+
+    impl WindowState {
+        fn rect(&self) -> Rect {
+            Rect::new(self.position, Vec2::new(self.width, self.height))
+        }
+    }
+
+    // where the next widget of the window being filled goes
+    struct Layout {
+        id: u64,
+        cursor: Vec2,
+        width: f32,
+    }
+
+    // the one open combo list or color picker, it takes the mouse from whatever it covers
+    struct Popup {
+        owner: u64,
+        rect: Rect,
+        opener: Rect,
+        // a color picker's hue, kept for when saturation or value leave none in the color
+        hue: f32,
+        drawn: bool,
+    }
+
+    // an immediate mode interface: widgets are declared again every frame between begin_frame and end_frame, and return
+    // whether the user changed their value. the shader is ui.vs and ui.fs
+    pub struct Ui {
+        vertex_array: gl::types::GLuint,
+        vertex_buffer: gl::types::GLuint,
+        // in vertices
+        capacity: usize,
+        vertices: Vec<UiVertex>,
+        // drawn after everything else
+        overlay: Vec<UiVertex>,
+        drawing_overlay: bool,
+        screen: Vec2,
+        windows: HashMap<u64, WindowState>,
+        layout: Option<Layout>,
+        popup: Option<Popup>,
+        mouse: Vec2,
+        mouse_down: bool,
+        // since the last frame, so a click shorter than a frame still counts
+        pressed: bool,
+        released: bool,
+        // the widget the mouse went down on, it has the mouse until it goes up
+        active: Option<u64>,
+        drag_offset: Vec2,
+    }
+
+    impl Ui {
+        pub fn new() -> Self {
+            let mut vertex_array = 0;
+            let mut vertex_buffer = 0;
+            let stride = size_of::<UiVertex>() as i32;
+
+            unsafe {
+                gl::GenVertexArrays(1, &mut vertex_array);
+                gl::GenBuffers(1, &mut vertex_buffer);
+
+                gl::BindVertexArray(vertex_array);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+
+                gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, ptr::null());
+                gl::EnableVertexAttribArray(0);
+
+                gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, stride, (size_of::<Vec2>() as *const usize).cast());
+                gl::EnableVertexAttribArray(1);
+
+                gl::BindVertexArray(0);
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            }
+
+            Ui {
+                vertex_array,
+                vertex_buffer,
+                capacity: 0,
+                vertices: vec![],
+                overlay: vec![],
+                drawing_overlay: false,
+                screen: Vec2::ONE,
+                windows: HashMap::new(),
+                layout: None,
+                popup: None,
+                mouse: Vec2::splat(-1.0),
+                mouse_down: false,
+                pressed: false,
+                released: false,
+                active: None,
+                drag_offset: Vec2::ZERO,
+            }
+        }
+
+        // cursor positions and the left button, the rest is ignored
+        pub fn handle_event(&mut self, event: &WindowEvent) {
+            match *event {
+                WindowEvent::CursorPos(x_pos, y_pos) => self.mouse = Vec2::new(x_pos as f32, y_pos as f32),
+                WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
+                    self.mouse_down = true;
+                    self.pressed = true;
+                }
+                WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => {
+                    self.mouse_down = false;
+                    self.released = true;
+                }
+                _ => {}
+            }
+        }
+
+        // whether the mouse is over the interface or dragging something in it, so the sample shouldn't use it too
+        pub fn wants_mouse(&self) -> bool {
+            self.active.is_some()
+                || self.popup.as_ref().is_some_and(|popup| popup.rect.contains(self.mouse))
+                || self.windows.values().any(|window| window.rect().contains(self.mouse))
+        }
+
+        // the window's size as glfw reports it, not the framebuffer's
+        pub fn begin_frame(&mut self, width: i32, height: i32) {
+            self.screen = Vec2::new(width.max(1) as f32, height.max(1) as f32);
+            self.vertices.clear();
+            self.overlay.clear();
+
+            if let Some(popup) = &mut self.popup {
+                popup.drawn = false;
+            }
+        }
+
+        // draws the frame's widgets over what is there, and leaves depth testing, culling, blending and the polygon mode
+        // as it found them
+        pub fn end_frame(&mut self, shader: &Program) {
+            // a popup closes when its widget is gone or the mouse goes down elsewhere
+            if let Some(popup) = &self.popup {
+                if !popup.drawn || (self.pressed && !popup.rect.contains(self.mouse) && !popup.opener.contains(self.mouse)) {
+                    self.popup = None;
+                }
+            }
+
+            self.draw(shader);
+
+            self.pressed = false;
+            self.released = false;
+            if !self.mouse_down {
+                self.active = None;
+            }
+        }
+
+        fn draw(&mut self, shader: &Program) {
+            let count = self.vertices.len() + self.overlay.len();
+            if count == 0 {
+                return;
+            }
+
+            unsafe {
+                gl::BindVertexArray(self.vertex_array);
+                gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer);
+
+                let stride = size_of::<UiVertex>();
+                if count > self.capacity {
+                    self.capacity = count.next_power_of_two();
+                    gl::BufferData(gl::ARRAY_BUFFER, (self.capacity * stride) as isize, ptr::null(), gl::STREAM_DRAW);
+                }
+                gl::BufferSubData(gl::ARRAY_BUFFER, 0, (self.vertices.len() * stride) as isize, self.vertices.as_ptr().cast());
+                gl::BufferSubData(gl::ARRAY_BUFFER, (self.vertices.len() * stride) as isize, (self.overlay.len() * stride) as isize, self.overlay.as_ptr().cast());
+            }
+
+            shader.apply();
+            shader.set_mat4("projection", Mat4::orthographic_rh_gl(0.0, self.screen.x, self.screen.y, 0.0, -1.0, 1.0));
+
+            unsafe {
+                let depth_was_on = gl::IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+                let cull_was_on = gl::IsEnabled(gl::CULL_FACE) == gl::TRUE;
+                let blend_was_on = gl::IsEnabled(gl::BLEND) == gl::TRUE;
+                let mut polygon_mode = [gl::FILL as i32; 2];
+                gl::GetIntegerv(gl::POLYGON_MODE, polygon_mode.as_mut_ptr());
+
+                gl::Disable(gl::DEPTH_TEST);
+                gl::Disable(gl::CULL_FACE);
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+
+                gl::DrawArrays(gl::TRIANGLES, 0, count as i32);
+
+                gl::PolygonMode(gl::FRONT_AND_BACK, polygon_mode[0] as u32);
+                if depth_was_on {
+                    gl::Enable(gl::DEPTH_TEST);
+                }
+                if cull_was_on {
+                    gl::Enable(gl::CULL_FACE);
+                }
+                if !blend_was_on {
+                    gl::Disable(gl::BLEND);
+                }
+
+                gl::BindVertexArray(0);
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+
+                gl::UseProgram(0);
+            }
+        }
+
+        // a draggable window, collapsed by its title's arrow; position is only where it starts, its height fits the
+        // widgets contents adds
+        pub fn window(&mut self, title: &str, position: Vec2, width: f32, contents: impl FnOnce(&mut Ui)) {
+            let id = hash(&(0u64, title));
+            let mut state = *self.windows.entry(id).or_insert(WindowState {
+                position,
+                width,
+                height: TITLE_HEIGHT,
+                collapsed: false,
+            });
+            state.width = width;
+
+            let title_bar = Rect::new(state.position, Vec2::new(width, TITLE_HEIGHT));
+            let hovered = self.hovered(title_bar);
+            if hovered && self.pressed && self.active.is_none() {
+                self.drag_offset = self.mouse - state.position;
+            }
+
+            let (held, clicked) = self.interact(id, hovered);
+            if clicked && Rect::new(title_bar.min, Vec2::splat(TITLE_HEIGHT)).contains(self.mouse) {
+                state.collapsed = !state.collapsed;
+            }
+            if held {
+                let limit = (self.screen - Vec2::new(width, TITLE_HEIGHT)).max(Vec2::ZERO);
+                state.position = (self.mouse - self.drag_offset).clamp(Vec2::ZERO, limit);
+            }
+
+            let start = self.vertices.len();
+            let parent = self.layout.replace(Layout {
+                id,
+                cursor: state.position + Vec2::new(PADDING, TITLE_HEIGHT + PADDING),
+                width: width - 2.0 * PADDING,
+            });
+
+            if !state.collapsed {
+                contents(self);
+            }
+
+            let layout = std::mem::replace(&mut self.layout, parent).unwrap();
+            state.height = if state.collapsed {
+                TITLE_HEIGHT
+            } else {
+                layout.cursor.y - SPACING + PADDING - state.position.y
+            };
+
+            // the background goes under what contents drew
+            let widgets = self.vertices.split_off(start);
+            let title_bar = Rect::new(state.position, Vec2::new(width, TITLE_HEIGHT));
+
+            self.rect(state.rect(), WINDOW_COLOR);
+            self.rect(title_bar, if hovered || held { TITLE_HOVERED_COLOR } else { TITLE_COLOR });
+            self.arrow(Rect::new(title_bar.min, Vec2::splat(TITLE_HEIGHT)), !state.collapsed);
+            self.text(Vec2::new(title_bar.min.x + TITLE_HEIGHT, text_top(title_bar)), title, TEXT_COLOR);
+            self.vertices.extend(widgets);
+
+            self.windows.insert(id, state);
+        }
+
+        pub fn label(&mut self, text: &str) {
+            let row = self.row(ROW_HEIGHT);
+            self.text(Vec2::new(row.min.x, text_top(row)), text, TEXT_COLOR);
+        }
+
+        // whether it was clicked
+        pub fn button(&mut self, text: &str) -> bool {
+            let id = self.id(text);
+            let row = self.row(ROW_HEIGHT);
+            let hovered = self.hovered(row);
+            let (held, clicked) = self.interact(id, hovered);
+
+            self.rect(row, frame_color(hovered, held));
+            self.text(Vec2::new(row.min.x + (row.size().x - text_width(text)) * 0.5, text_top(row)), text, TEXT_COLOR);
+
+            clicked
+        }
+
+        pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+            let id = self.id(label);
+            let row = self.row(ROW_HEIGHT);
+            let hovered = self.hovered(row);
+            let (held, clicked) = self.interact(id, hovered);
+
+            if clicked {
+                *value = !*value;
+            }
+
+            let check = Rect::new(row.min, Vec2::splat(ROW_HEIGHT));
+            self.rect(check, frame_color(hovered, held));
+            if *value {
+                let at = |x: f32, y: f32| check.min + check.size() * Vec2::new(x, y);
+                self.line(at(0.25, 0.5), at(0.42, 0.7), 2.0, FILL_COLOR);
+                self.line(at(0.42, 0.7), at(0.75, 0.3), 2.0, FILL_COLOR);
+            }
+            self.text(Vec2::new(check.max.x + SPACING, text_top(row)), label, TEXT_COLOR);
+
+            clicked
+        }
+
+        // dragged or clicked anywhere on its frame
+        pub fn slider(&mut self, label: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+            let (min, max) = (*range.start(), *range.end());
+            let span = max - min;
+            let fraction = if span > 0.0 { (*value - min) / span } else { 0.0 };
+
+            match self.slider_frame(label, fraction, |fraction| format!("{:.3}", min + fraction * span)) {
+                Some(fraction) => {
+                    let previous = *value;
+                    *value = min + fraction * span;
+                    *value != previous
+                }
+                None => false,
+            }
+        }
+
+        pub fn slider_int(&mut self, label: &str, value: &mut i32, range: RangeInclusive<i32>) -> bool {
+            let (min, max) = (*range.start(), *range.end());
+            let span = (max - min) as f32;
+            let fraction = if span > 0.0 { (*value - min) as f32 / span } else { 0.0 };
+            let at = move |fraction: f32| min + (fraction * span).round() as i32;
+
+            match self.slider_frame(label, fraction, |fraction| at(fraction).to_string()) {
+                Some(fraction) => {
+                    let previous = *value;
+                    *value = at(fraction);
+                    *value != previous
+                }
+                None => false,
+            }
+        }
+
+        // a swatch that opens a saturation and value square with a hue bar beside it
+        pub fn color(&mut self, label: &str, value: &mut Vec3) -> bool {
+            let id = self.id(label);
+            let row = self.row(ROW_HEIGHT);
+            let (frame, label_at) = split(row);
+            let hovered = self.hovered(frame);
+            let (_, clicked) = self.interact(id, hovered);
+
+            self.rect(frame, value.extend(1.0));
+            let readable = if value.dot(Vec3::new(0.299, 0.587, 0.114)) > 0.5 { Vec4::new(0.0, 0.0, 0.0, 1.0) } else { TEXT_COLOR };
+            let text = format!("{:.2} {:.2} {:.2}", value.x, value.y, value.z);
+            self.text(Vec2::new(frame.min.x + SPACING, text_top(frame)), &text, readable);
+            self.text(label_at, label, TEXT_COLOR);
+
+            if clicked {
+                self.toggle_popup(id, frame, rgb_to_hsv(*value).0);
+            }
+            if self.popup.as_ref().is_none_or(|popup| popup.owner != id) {
+                return false;
+            }
+
+            let rect = Rect::new(
+                Vec2::new(frame.min.x, frame.max.y + SPACING),
+                Vec2::new(PICKER_SIZE + HUE_WIDTH + SPACING, PICKER_SIZE) + 2.0 * PADDING,
+            );
+            let square = Rect::new(rect.min + PADDING, Vec2::splat(PICKER_SIZE));
+            let bar = Rect::new(Vec2::new(square.max.x + SPACING, square.min.y), Vec2::new(HUE_WIDTH, PICKER_SIZE));
+
+            let (_, mut saturation, mut brightness) = rgb_to_hsv(*value);
+            let mut hue = self.popup.as_ref().unwrap().hue;
+            let previous = *value;
+
+            let (held_square, _) = self.interact(hash(&(id, "square")), square.contains(self.mouse));
+            if held_square {
+                let at = square.fraction(self.mouse);
+                saturation = at.x;
+                brightness = 1.0 - at.y;
+            }
+            let (held_bar, _) = self.interact(hash(&(id, "hue")), bar.contains(self.mouse));
+            if held_bar {
+                hue = bar.fraction(self.mouse).y;
+            }
+            if held_square || held_bar {
+                *value = hsv_to_rgb(hue, saturation, brightness);
+            }
+
+            self.drawing_overlay = true;
+            self.rect(rect, WINDOW_COLOR);
+            // white to the pure hue across, then fading to black down
+            let pure = hsv_to_rgb(hue, 1.0, 1.0).extend(1.0);
+            self.gradient(square, [Vec4::ONE, pure, pure, Vec4::ONE]);
+            self.gradient(square, [Vec4::ZERO, Vec4::ZERO, Vec4::W, Vec4::W]);
+            for i in 0..6 {
+                let top = hsv_to_rgb(i as f32 / 6.0, 1.0, 1.0).extend(1.0);
+                let bottom = hsv_to_rgb((i + 1) as f32 / 6.0, 1.0, 1.0).extend(1.0);
+                let segment = Rect::new(bar.min + Vec2::new(0.0, bar.size().y * i as f32 / 6.0), Vec2::new(HUE_WIDTH, bar.size().y / 6.0));
+                self.gradient(segment, [top, top, bottom, bottom]);
+            }
+
+            let marker = square.min + square.size() * Vec2::new(saturation, 1.0 - brightness);
+            self.outline(Rect::new(marker - 3.0, Vec2::splat(6.0)), TEXT_COLOR);
+            let y = bar.min.y + bar.size().y * hue;
+            self.line(Vec2::new(bar.min.x - 2.0, y), Vec2::new(bar.max.x + 2.0, y), 2.0, TEXT_COLOR);
+            self.drawing_overlay = false;
+
+            let popup = self.popup.as_mut().unwrap();
+            popup.rect = rect;
+            popup.opener = frame;
+            popup.hue = hue;
+            popup.drawn = true;
+
+            *value != previous
+        }
+
+        // selected indexes items, the list opens below the frame
+        pub fn combo(&mut self, label: &str, selected: &mut usize, items: &[&str]) -> bool {
+            let id = self.id(label);
+            let row = self.row(ROW_HEIGHT);
+            let (frame, label_at) = split(row);
+            let hovered = self.hovered(frame);
+            let (held, clicked) = self.interact(id, hovered);
+
+            self.rect(frame, frame_color(hovered, held));
+            self.text(Vec2::new(frame.min.x + SPACING, text_top(frame)), items.get(*selected).copied().unwrap_or(""), TEXT_COLOR);
+            self.arrow(Rect::new(Vec2::new(frame.max.x - ROW_HEIGHT, frame.min.y), Vec2::splat(ROW_HEIGHT)), true);
+            self.text(label_at, label, TEXT_COLOR);
+
+            if clicked {
+                self.toggle_popup(id, frame, 0.0);
+            }
+            if self.popup.as_ref().is_none_or(|popup| popup.owner != id) {
+                return false;
+            }
+
+            let rect = Rect::new(Vec2::new(frame.min.x, frame.max.y), Vec2::new(frame.size().x, ROW_HEIGHT * items.len() as f32));
+            let mut changed = false;
+            let mut close = false;
+
+            self.drawing_overlay = true;
+            self.rect(rect, WINDOW_COLOR);
+            for (i, item) in items.iter().enumerate() {
+                let entry = Rect::new(rect.min + Vec2::new(0.0, ROW_HEIGHT * i as f32), Vec2::new(rect.size().x, ROW_HEIGHT));
+                let hovered = entry.contains(self.mouse);
+                let (held, clicked) = self.interact(hash(&(id, i)), hovered);
+
+                if clicked {
+                    changed = *selected != i;
+                    *selected = i;
+                    close = true;
+                }
+                if hovered || held || *selected == i {
+                    self.rect(entry, if *selected == i { FILL_COLOR } else { frame_color(hovered, held) });
+                }
+                self.text(Vec2::new(entry.min.x + SPACING, text_top(entry)), item, TEXT_COLOR);
+            }
+            self.drawing_overlay = false;
+
+            if close {
+                self.popup = None;
+            } else {
+                let popup = self.popup.as_mut().unwrap();
+                popup.rect = rect;
+                popup.opener = frame;
+                popup.drawn = true;
+            }
+
+            changed
+        }
+
+        // values oldest first, drawn across the frame with range from bottom to top, and the last one beside it
+        pub fn plot(&mut self, label: &str, values: &[f32], range: RangeInclusive<f32>) {
+            let row = self.row(PLOT_HEIGHT);
+            let (frame, label_at) = split(row);
+            let (min, max) = (*range.start(), *range.end());
+
+            self.rect(frame, FRAME_COLOR);
+            if values.len() >= 2 && max > min {
+                let step = frame.size().x / (values.len() - 1) as f32;
+                let at = |i: usize| {
+                    let height = ((values[i] - min) / (max - min)).clamp(0.0, 1.0);
+                    Vec2::new(frame.min.x + step * i as f32, frame.max.y - height * frame.size().y)
+                };
+
+                for i in 1..values.len() {
+                    self.line(at(i - 1), at(i), 1.0, FILL_COLOR);
+                }
+            }
+
+            self.text(label_at, label, TEXT_COLOR);
+            if let Some(last) = values.last() {
+                self.text(label_at + Vec2::new(0.0, ROW_HEIGHT), &format!("{:.2}", last), TEXT_COLOR);
+            }
+        }
+
+        // the frame of a slider, returns where on it the mouse is while it is held
+        fn slider_frame(&mut self, label: &str, fraction: f32, text: impl Fn(f32) -> String) -> Option<f32> {
+            let id = self.id(label);
+            let row = self.row(ROW_HEIGHT);
+            let (frame, label_at) = split(row);
+            let hovered = self.hovered(frame);
+            let (held, _) = self.interact(id, hovered);
+
+            let dragged = held.then(|| frame.fraction(self.mouse).x);
+            let fraction = dragged.unwrap_or(fraction).clamp(0.0, 1.0);
+            let text = text(fraction);
+
+            self.rect(frame, frame_color(hovered, held));
+            self.rect(Rect::new(frame.min, Vec2::new(frame.size().x * fraction, frame.size().y)), FILL_COLOR);
+            self.text(Vec2::new(frame.min.x + (frame.size().x - text_width(&text)) * 0.5, text_top(frame)), &text, TEXT_COLOR);
+            self.text(label_at, label, TEXT_COLOR);
+
+            dragged
+        }
+
+        fn toggle_popup(&mut self, owner: u64, opener: Rect, hue: f32) {
+            if self.popup.as_ref().is_some_and(|popup| popup.owner == owner) {
+                self.popup = None;
+            } else {
+                self.popup = Some(Popup {
+                    owner,
+                    rect: opener,
+                    opener,
+                    hue,
+                    drawn: true,
+                });
+            }
+        }
+
+        // the next row of the window being filled
+        fn row(&mut self, height: f32) -> Rect {
+            let layout = self.layout.as_mut().expect("widgets are declared inside Ui::window");
+            let row = Rect::new(layout.cursor, Vec2::new(layout.width, height));
+            layout.cursor.y += height + SPACING;
+            row
+        }
+
+        // labels only need to be unique within their window
+        fn id(&self, label: &str) -> u64 {
+            hash(&(self.layout.as_ref().map_or(0, |layout| layout.id), label))
+        }
+
+        // not through an open popup
+        fn hovered(&self, rect: Rect) -> bool {
+            rect.contains(self.mouse) && !self.popup.as_ref().is_some_and(|popup| popup.rect.contains(self.mouse))
+        }
+
+        // whether the widget is held this frame and whether it was clicked, pressed and released over it
+        fn interact(&mut self, id: u64, hovered: bool) -> (bool, bool) {
+            if hovered && self.pressed && self.active.is_none() {
+                self.active = Some(id);
+            }
+
+            let active = self.active == Some(id);
+            (active && (self.mouse_down || self.released), active && self.released && hovered)
+        }
+
+        fn push(&mut self, position: Vec2, color: Vec4) {
+            let vertices = if self.drawing_overlay { &mut self.overlay } else { &mut self.vertices };
+            vertices.push(UiVertex { position, color });
+        }
+
+        // corners clockwise on screen from the top left
+        fn quad(&mut self, corners: [Vec2; 4], colors: [Vec4; 4]) {
+            for i in [0, 1, 2, 0, 2, 3] {
+                self.push(corners[i], colors[i]);
+            }
+        }
+
+        fn rect(&mut self, rect: Rect, color: Vec4) {
+            self.gradient(rect, [color; 4]);
+        }
+
+        // colors go top left, top right, bottom right, bottom left
+        fn gradient(&mut self, rect: Rect, colors: [Vec4; 4]) {
+            self.quad([rect.min, Vec2::new(rect.max.x, rect.min.y), rect.max, Vec2::new(rect.min.x, rect.max.y)], colors);
+        }
+
+        fn outline(&mut self, rect: Rect, color: Vec4) {
+            let corners = [rect.min, Vec2::new(rect.max.x, rect.min.y), rect.max, Vec2::new(rect.min.x, rect.max.y)];
+            for i in 0..4 {
+                self.line(corners[i], corners[(i + 1) % 4], 1.0, color);
+            }
+        }
+
+        // a quad along the segment, run past both ends so strokes meet at corners
+        fn line(&mut self, from: Vec2, to: Vec2, thickness: f32, color: Vec4) {
+            let along = (to - from).normalize_or_zero() * thickness * 0.5;
+            let across = along.perp();
+            let (from, to) = (from - along, to + along);
+            self.quad([from - across, to - across, to + across, from + across], [color; 4]);
+        }
+
+        // a triangle in the middle of the square, pointing down or right
+        fn arrow(&mut self, square: Rect, down: bool) {
+            let at = |x: f32, y: f32| square.min + square.size() * Vec2::new(x, y);
+            let corners = if down { [at(0.3, 0.35), at(0.7, 0.35), at(0.5, 0.65)] } else { [at(0.35, 0.3), at(0.65, 0.5), at(0.35, 0.7)] };
+
+            for corner in corners {
+                self.push(corner, TEXT_COLOR);
+            }
+        }
+
+        fn text(&mut self, position: Vec2, text: &str, color: Vec4) {
+            for (i, c) in text.chars().enumerate() {
+                let origin = position + Vec2::new(i as f32 * GLYPH_ADVANCE * TEXT_HEIGHT, 0.0);
+                let at = |x: f32, y: f32| origin + Vec2::new(x, 1.0 - y) * TEXT_HEIGHT;
+
+                for (x0, y0, x1, y1) in strokes(c) {
+                    self.line(at(x0, y0), at(x1, y1), STROKE, color);
+                }
+            }
+        }
+    }
+
+    impl Default for Ui {
+        fn default() -> Self {
+            Ui::new()
+        }
+    }
+
+    impl Drop for Ui {
+        fn drop(&mut self) {
+            unsafe {
+                gl::DeleteVertexArrays(1, &self.vertex_array);
+                gl::DeleteBuffers(1, &self.vertex_buffer);
+            }
+        }
+    }
+
+    fn hash(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn frame_color(hovered: bool, held: bool) -> Vec4 {
+        if held {
+            FRAME_ACTIVE_COLOR
+        } else if hovered {
+            FRAME_HOVERED_COLOR
+        } else {
+            FRAME_COLOR
+        }
+    }
+
+    // the widget's part of a row, and where its label starts
+    fn split(row: Rect) -> (Rect, Vec2) {
+        let widget = Rect::new(row.min, Vec2::new(row.size().x * WIDGET_SHARE, ROW_HEIGHT));
+        (widget, Vec2::new(widget.max.x + SPACING, text_top(widget)))
+    }
+
+    // for text centred in a row
+    fn text_top(row: Rect) -> f32 {
+        row.min.y + (ROW_HEIGHT - TEXT_HEIGHT) * 0.5
+    }
+
+    // hue, saturation and value all 0 to 1
+    fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> Vec3 {
+        let sector = hue.clamp(0.0, 1.0) * 6.0;
+        let offset = sector - sector.floor();
+        let p = value * (1.0 - saturation);
+        let q = value * (1.0 - saturation * offset);
+        let t = value * (1.0 - saturation * (1.0 - offset));
+
+        match sector as i32 % 6 {
+            0 => Vec3::new(value, t, p),
+            1 => Vec3::new(q, value, p),
+            2 => Vec3::new(p, value, t),
+            3 => Vec3::new(p, q, value),
+            4 => Vec3::new(t, p, value),
+            _ => Vec3::new(value, p, q),
+        }
+    }
+
+    fn rgb_to_hsv(color: Vec3) -> (f32, f32, f32) {
+        let max = color.max_element();
+        let delta = max - color.min_element();
+
+        let hue = if delta <= 0.0 {
+            0.0
+        } else if max == color.x {
+            ((color.y - color.z) / delta).rem_euclid(6.0)
+        } else if max == color.y {
+            (color.z - color.x) / delta + 2.0
+        } else {
+            (color.x - color.y) / delta + 4.0
+        };
+        let saturation = if max > 0.0 { delta / max } else { 0.0 };
+
+        (hue / 6.0, saturation, max)
+    }
+}
@@ -7,12 +7,18 @@ const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::{Camera, Movement};
+pub mod font;
+use font::font::{strokes, GLYPH_ADVANCE};
 pub mod mesh;
 use mesh::mesh::{Texture, VertexArray};
 pub mod shader;
 use shader::shader::Program;
+pub mod ui;
+use ui::ui::Ui;
 
 struct BloomMip {
     size: Vec2,
@@ -285,6 +291,8 @@ fn main() {
     window.set_framebuffer_size_polling(true);
     window.set_cursor_pos_polling(true);
     window.set_scroll_polling(true);
+    window.set_key_polling(true);
+    window.set_mouse_button_polling(true);
 
     window.set_cursor_mode(CursorMode::Disabled);
     window.focus();
@@ -325,11 +333,14 @@ fn main() {
     let (x_pos, y_pos) = window.get_cursor_pos();
     let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), x_pos as f32, y_pos as f32);
 
-    let mut input_state = InputState {
-        bloom: true,
+    let ui_shader = Program::new().link("src/ui.vs", "src/ui.fs");
+    let mut ui = Ui::new();
+
+    let mut parameters = Parameters {
         exposure: 1.0,
-        program_choice: 1,
+        program_choice: 0,
         bloom_filter_radius: 0.005,
+        ui_focus: false,
     };
 
     let mut last_frame = 0.0;
@@ -339,7 +350,13 @@ fn main() {
         let delta_time = current_frame - last_frame;
         last_frame = current_frame;
 
-        process_input(&mut camera, &mut window, &mut input_state, delta_time);
+        process_input(&mut camera, &mut window, delta_time);
+
+        let (width, height) = window.get_size();
+        ui.begin_frame(width, height);
+        parameters_window(&mut ui, &mut parameters);
+        // the shaders number the programs from 1
+        let program_choice = parameters.program_choice as i32 + 1;
 
         ogl::clear_color(0.0, 0.0, 0.0, 1.0);
         ogl::clear();
@@ -403,12 +420,7 @@ fn main() {
         }
         ogl::unbind_framebuffer();
 
-        if input_state.program_choice < 1 || input_state.program_choice > 3 {
-            input_state.program_choice = 1;
-        }
-        input_state.bloom = input_state.program_choice != 1;
-
-        if input_state.program_choice == 2 {
+        if program_choice == 2 {
             shader_blur.apply();
 
             // first iteration
@@ -427,8 +439,8 @@ fn main() {
                 ping_pong.bounce();
             }
             ogl::unbind_framebuffer();
-        } else if input_state.program_choice == 3 {
-            bloom_renderer.render_bloom_texture(hdr_fbo.colour_buffers(1), input_state.bloom_filter_radius, &quad);
+        } else if program_choice == 3 {
+            bloom_renderer.render_bloom_texture(hdr_fbo.colour_buffers(1), parameters.bloom_filter_radius, &quad);
         }
 
         ogl::clear();
@@ -436,37 +448,51 @@ fn main() {
         ogl::active_texture(0);
         hdr_fbo.bind_texture(0);
         ogl::active_texture(1);
-        if input_state.program_choice == 1 {
+        if program_choice == 1 {
             ogl::bind_texture0();
-        } else if input_state.program_choice == 2 {
+        } else if program_choice == 2 {
             ping_pong.bind_texture();
-        } else if input_state.program_choice == 3 {
+        } else if program_choice == 3 {
             bloom_renderer.bloom_texture();
         }
-        shader_bloom_final.set_int("programChoice", input_state.program_choice);
-        shader_bloom_final.set_float("exposure", input_state.exposure);
+        shader_bloom_final.set_int("programChoice", program_choice);
+        shader_bloom_final.set_float("exposure", parameters.exposure);
         quad.bind();
         quad.draw();
 
+        ui.end_frame(&ui_shader);
+
         //println!("bloom: {}| exposure: {}", if bloom { "on" } else { "off" }, exposure);
 
         window.swap_buffers();
 
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut camera, &mut window, event);
+            handle_window_event(&mut camera, &mut window, &mut ui, &mut parameters.ui_focus, event);
         }
     }
 }
 
-struct InputState {
-    bloom: bool,
+// what the ui edits
+struct Parameters {
     exposure: f32,
-    program_choice: i32,
+    program_choice: usize,
     bloom_filter_radius: f32,
+    // whether the cursor is free for the ui instead of turning the camera
+    ui_focus: bool,
 }
 
-fn process_input(camera: &mut Camera, window: &mut Window, input_state: &mut InputState, delta_time: f32) {
+fn parameters_window(ui: &mut Ui, parameters: &mut Parameters) {
+    ui.window("bloom", Vec2::new(10.0, 10.0), 320.0, |ui| {
+        ui.label(if parameters.ui_focus { "tab: back to the camera" } else { "tab: use the mouse here" });
+
+        ui.combo("method", &mut parameters.program_choice, &["off", "gaussian blur", "physically based"]);
+        ui.slider("exposure", &mut parameters.exposure, 0.0..=5.0);
+        ui.slider("filter radius", &mut parameters.bloom_filter_radius, 0.001..=0.05);
+    });
+}
+
+fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
     }
@@ -483,35 +509,32 @@ fn process_input(camera: &mut Camera, window: &mut Window, input_state: &mut Inp
     if window.get_key(Key::D) == Action::Press {
         camera.process_keyboard(Movement::Right, delta_time);
     }
+}
 
-    if window.get_key(Key::Q) == Action::Press {
-        if input_state.exposure > 0.0 {
-            input_state.exposure -= 0.001;
-        } else {
-            input_state.exposure = 0.0;
-        }
-    }
-    if window.get_key(Key::E) == Action::Press {
-        input_state.exposure += 0.001;
-    }
-
-    if window.get_key(Key::Num1) == Action::Press {
-        input_state.program_choice = 1;
-    } else if window.get_key(Key::Num2) == Action::Press {
-        input_state.program_choice = 2;
-    } else if window.get_key(Key::Num3) == Action::Press {
-        input_state.program_choice = 3;
+fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, ui: &mut Ui, ui_focus: &mut bool, event: glfw::WindowEvent) {
+    if *ui_focus {
+        ui.handle_event(&event);
     }
-}
 
-fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, event: glfw::WindowEvent) {
     match event {
+        WindowEvent::Key(Key::Tab, _, Action::Release, _) => {
+            *ui_focus = !*ui_focus;
+            window.set_cursor_mode(if *ui_focus { CursorMode::Normal } else { CursorMode::Disabled });
+        }
         WindowEvent::FramebufferSize(width, height) => ogl::viewport(width, height),
-        WindowEvent::Scroll(_, y_offset) => camera.process_scroll(y_offset as f32),
+        WindowEvent::Scroll(_, y_offset) => {
+            if !(*ui_focus && ui.wants_mouse()) {
+                camera.process_scroll(y_offset as f32);
+            }
+        }
         WindowEvent::CursorPos(_x_pos, _y_pos) => {
             let (x_pos, y_pos) = window.get_cursor_pos();
 
-            camera.process_mouse(x_pos as f32, y_pos as f32, true);
+            if *ui_focus {
+                camera.set_cursor(x_pos as f32, y_pos as f32);
+            } else {
+                camera.process_mouse(x_pos as f32, y_pos as f32, true);
+            }
         }
         _ => {}
     }
@@ -139,6 +139,12 @@ pub mod camera {
             self.calculate_vectors();
         }
 
+        // where the cursor is now, for when it moved without turning the camera
+        pub fn set_cursor(&mut self, x_pos: f32, y_pos: f32) {
+            self.last_x = x_pos;
+            self.last_y = y_pos;
+        }
+
         pub fn process_scroll(&mut self, y_offset: f32) {
             self.zoom -= y_offset * BIAS;
 
@@ -7,39 +7,24 @@ const SCR_HEIGHT: u32 = 600;
 const SCR_NEAR: f32 = 0.1;
 const SCR_FAR: f32 = 100.0;
 
+pub mod builder;
+use builder::builder::{interleave, Attribute, MeshBuilder};
 pub mod camera;
 use camera::camera::{Camera, Movement};
+pub mod font;
+use font::font::{strokes, GLYPH_ADVANCE};
 pub mod mesh;
 use mesh::mesh::{Texture, VertexArray};
 pub mod shader;
 use shader::shader::Program;
+pub mod profiler;
+use profiler::profiler::Profiler;
+pub mod ui;
+use ui::ui::Ui;
 
 const LIGHT_COLOUR: Vec3 = Vec3::new(0.870588, 0.721569, 0.529412);
 const NUM_AREA_LIGHTS: usize = 16usize;
-static mut AREA_LIGHTS: [AreaLight; NUM_AREA_LIGHTS] = [AreaLight::new(Vec3::ZERO, 0.0, Vec3::ZERO); NUM_AREA_LIGHTS];
-
-#[repr(C)]
-#[derive(Clone, Copy)]
-struct VertexAL {
-    position: Vec3,
-    normal: Vec3,
-    texcoord: Vec2,
-}
-
-impl VertexAL {
-    pub fn to_array(&self) -> [f32; 8] {
-        [
-            self.position.x,
-            self.position.y,
-            self.position.z,
-            self.normal.x,
-            self.normal.y,
-            self.normal.z,
-            self.texcoord.x,
-            self.texcoord.y,
-        ]
-    }
-}
+const MATERIAL_COLOR: Vec3 = Vec3::new(0.439216, 0.501961, 0.564706);
 
 #[derive(Clone, Copy)]
 struct AreaLight {
@@ -57,57 +42,16 @@ impl AreaLight {
             offset,
             y_rotation,
             colour,
-            intensity: 4.0,
+            intensity: 2.0,
             two_sided: true,
         }
     }
 }
 
 const P_SIZE: f32 = 10.0;
-const PLANE_VERTICES: [VertexAL; 4] = [
-    VertexAL {
-        position: Vec3::new(-P_SIZE, 0.0, -P_SIZE),
-        normal: Vec3::Y,
-        texcoord: Vec2::ZERO,
-    },
-    VertexAL {
-        position: Vec3::new(-P_SIZE, 0.0, P_SIZE),
-        normal: Vec3::Y,
-        texcoord: Vec2::X,
-    },
-    VertexAL {
-        position: Vec3::new(P_SIZE, 0.0, -P_SIZE),
-        normal: Vec3::Y,
-        texcoord: Vec2::Y,
-    },
-    VertexAL {
-        position: Vec3::new(P_SIZE, 0.0, P_SIZE),
-        normal: Vec3::Y,
-        texcoord: Vec2::ONE,
-    },
-];
-const AREA_LIGHT_VERTICES: [VertexAL; 4] = [
-    VertexAL {
-        position: Vec3::new(-8.0, 2.4, -1.0),
-        normal: Vec3::X,
-        texcoord: Vec2::ZERO,
-    },
-    VertexAL {
-        position: Vec3::new(-8.0, 2.4, 1.0),
-        normal: Vec3::X,
-        texcoord: Vec2::Y,
-    },
-    VertexAL {
-        position: Vec3::new(-8.0, 0.4, -1.0),
-        normal: Vec3::X,
-        texcoord: Vec2::X,
-    },
-    VertexAL {
-        position: Vec3::new(-8.0, 0.4, 1.0),
-        normal: Vec3::X,
-        texcoord: Vec2::ONE,
-    },
-];
+// in the order the shader integrates them, the light faces +x
+const AREA_LIGHT_CORNERS: [Vec3; 4] = [Vec3::new(-8.0, 2.4, -1.0), Vec3::new(-8.0, 2.4, 1.0), Vec3::new(-8.0, 0.4, -1.0), Vec3::new(-8.0, 0.4, 1.0)];
+const ATTRIBUTES: [Attribute; 3] = [Attribute::Position, Attribute::Normal, Attribute::TexCoords];
 
 struct LTCMatrices {
     mat1: u32,
@@ -171,54 +115,73 @@ impl Drop for LTCMatrices {
     }
 }
 
-fn configure_area_lights() {
-    for i in 0..NUM_AREA_LIGHTS {
+fn configure_area_lights() -> [AreaLight; NUM_AREA_LIGHTS] {
+    let mut area_lights = [AreaLight::new(Vec3::ZERO, 0.0, Vec3::ZERO); NUM_AREA_LIGHTS];
+
+    for area_light in &mut area_lights {
         let x = fastrand::f32();
         let z = fastrand::f32();
 
-        unsafe {
-            AREA_LIGHTS[i].offset = Vec3::new(if x > 0.5 { x } else { -x }, 0.0, if z > 0.5 { z } else { -z }) * 0.8;
-            AREA_LIGHTS[i].y_rotation = fastrand::f32() * TAU;
-            AREA_LIGHTS[i].colour = Vec3::new(fastrand::f32(), fastrand::f32(), fastrand::f32());
-        }
+        area_light.offset = Vec3::new(if x > 0.5 { x } else { -x }, 0.0, if z > 0.5 { z } else { -z }) * 0.8;
+        area_light.y_rotation = fastrand::f32() * TAU;
+        area_light.colour = Vec3::new(fastrand::f32(), fastrand::f32(), fastrand::f32());
     }
+
+    area_lights
 }
 
-fn increment_roughness(step: f32, ltc_shader: &Program) {
-    const COLOUR: Vec3 = Vec3::new(0.439216, 0.501961, 0.564706);
-    static mut ROUGHNESS: f32 = 0.5;
-    unsafe {
-        ROUGHNESS += step;
-        ROUGHNESS = f32::min(f32::max(ROUGHNESS, 0.0), 1.0);
+fn area_light_shape() -> MeshBuilder {
+    let mut builder = MeshBuilder::new();
+    let tex_coords = [Vec2::ZERO, Vec2::Y, Vec2::X, Vec2::ONE];
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| builder.vertex(AREA_LIGHT_CORNERS[i], tex_coords[i]));
 
-        ltc_shader.apply();
-        ltc_shader.set_vec4("material.albedoRoughness", COLOUR.extend(ROUGHNESS));
-    }
-    ogl::unuse_program();
+    // the corners zigzag, so the outline runs a, b, d, c
+    builder.quad(a, b, d, c);
+    builder
+}
+
+fn area_light_model(area_light: &AreaLight) -> Mat4 {
+    Mat4::from_translation(area_light.offset) * Mat4::from_rotation_y(area_light.y_rotation)
 }
 
-fn increment_light_intensity(step: f32, ltc_shader: &Program) {
-    static mut INTENSITY: f32 = 4.0;
-    unsafe {
-        INTENSITY += step;
-        INTENSITY = f32::min(f32::max(INTENSITY, 0.0), 10.0);
+fn upload_area_lights(ltc_shader: &Program, area_lights: &[AreaLight]) {
+    ltc_shader.apply();
+    for (i, area_light) in area_lights.iter().enumerate() {
+        let model = area_light_model(area_light);
 
-        ltc_shader.apply();
-        ltc_shader.set_float("areaLight.intensity", INTENSITY);
+        let p0 = (model * AREA_LIGHT_CORNERS[0].extend(1.0)).truncate();
+        let p1 = (model * AREA_LIGHT_CORNERS[1].extend(1.0)).truncate();
+        let p2 = (model * AREA_LIGHT_CORNERS[2].extend(1.0)).truncate();
+        let p3 = (model * AREA_LIGHT_CORNERS[3].extend(1.0)).truncate();
+
+        let str_pos = "areaLights[".to_string() + &(i.to_string() + "].points");
+        let str_col = "areaLights[".to_string() + &(i.to_string() + "].color");
+        let str_int = "areaLights[".to_string() + &(i.to_string() + "].intensity");
+        let str_two = "areaLights[".to_string() + &(i.to_string() + "].twoSided");
+        ltc_shader.set_vec3(&(str_pos.clone() + "[0]"), p0);
+        ltc_shader.set_vec3(&(str_pos.clone() + "[1]"), p1);
+        ltc_shader.set_vec3(&(str_pos.clone() + "[2]"), p2);
+        ltc_shader.set_vec3(&(str_pos + "[3]"), p3);
+        ltc_shader.set_vec3(&str_col, area_light.colour);
+        ltc_shader.set_float(&str_int, area_light.intensity);
+        ltc_shader.set_int(&str_two, area_light.two_sided as i32);
     }
+    ltc_shader.set_int("numAreaLights", area_lights.len() as i32);
     ogl::unuse_program();
 }
 
-fn switch_two_sided(do_switch: bool, ltc_shader: &Program) {
-    static mut TWO_SIDED: bool = true;
-    unsafe {
-        if do_switch {
-            TWO_SIDED = !TWO_SIDED;
-        }
+// what the ui edits besides the lights themselves
+struct Parameters {
+    roughness: f32,
+    albedo: Vec3,
+    selected_light: usize,
+    // whether the cursor is free for the ui instead of turning the camera
+    ui_focus: bool,
+}
 
-        ltc_shader.apply();
-        ltc_shader.set_float("areaLight.twoSided", TWO_SIDED as i16 as f32);
-    }
+fn upload_material(ltc_shader: &Program, parameters: &Parameters) {
+    ltc_shader.apply();
+    ltc_shader.set_vec4("material.albedoRoughness", parameters.albedo.extend(parameters.roughness));
     ogl::unuse_program();
 }
 
@@ -237,14 +200,16 @@ fn main() {
     window.set_framebuffer_size_polling(true);
     window.set_cursor_pos_polling(true);
     window.set_scroll_polling(true);
+    window.set_key_polling(true);
+    window.set_mouse_button_polling(true);
 
     window.set_cursor_mode(CursorMode::Disabled);
     window.focus();
 
     gl::load_with(|s| window.get_proc_address(s) as *const _);
 
-    let plane = VertexArray::new_plane(&PLANE_VERTICES.iter().map(|&v| v.to_array()).flatten().collect());
-    let area_light = VertexArray::new_area_light(&AREA_LIGHT_VERTICES.iter().map(|&v| v.to_array()).flatten().collect());
+    let plane = VertexArray::from_builder(MeshBuilder::plane(2.0 * P_SIZE, 1), &ATTRIBUTES);
+    let area_light = VertexArray::from_builder(area_light_shape(), &ATTRIBUTES);
 
     ogl::depth_enable(true);
 
@@ -255,36 +220,22 @@ fn main() {
 
     let concrete_texture = Texture::new_hdr("resources/textures/concreteTexture.png");
 
-    configure_area_lights();
-
-    shader_ltc.apply();
-    for i in 0..NUM_AREA_LIGHTS {
-        let model = unsafe { Mat4::from_translation(AREA_LIGHTS[i].offset) * Mat4::from_rotation_y(AREA_LIGHTS[i].y_rotation) };
+    let mut area_lights = configure_area_lights();
+    let mut parameters = Parameters {
+        roughness: 0.5,
+        albedo: MATERIAL_COLOR,
+        selected_light: 0,
+        ui_focus: false,
+    };
+    let light_names = (0..NUM_AREA_LIGHTS).map(|i| format!("light {}", i)).collect::<Vec<_>>();
 
-        let p0 = (model * AREA_LIGHT_VERTICES[0].position.extend(1.0)).truncate();
-        let p1 = (model * AREA_LIGHT_VERTICES[1].position.extend(1.0)).truncate();
-        let p2 = (model * AREA_LIGHT_VERTICES[2].position.extend(1.0)).truncate();
-        let p3 = (model * AREA_LIGHT_VERTICES[3].position.extend(1.0)).truncate();
+    upload_area_lights(&shader_ltc, &area_lights);
+    upload_material(&shader_ltc, &parameters);
 
-        let str_pos = "areaLights[".to_string() + &(i.to_string() + "].points");
-        let str_col = "areaLights[".to_string() + &(i.to_string() + "].color");
-        let str_int = "areaLights[".to_string() + &(i.to_string() + "].intensity");
-        let str_two = "areaLights[".to_string() + &(i.to_string() + "].two_sided");
-        shader_ltc.set_vec3(&(str_pos.clone() + "[0]"), p0);
-        shader_ltc.set_vec3(&(str_pos.clone() + "[1]"), p1);
-        shader_ltc.set_vec3(&(str_pos.clone() + "[2]"), p2);
-        shader_ltc.set_vec3(&(str_pos + "[3]"), p3);
-        unsafe {
-            shader_ltc.set_vec3(&str_col, AREA_LIGHTS[i].colour);
-        }
-        shader_ltc.set_float(&str_int, 2.0);
-        shader_ltc.set_int(&str_two, 1);
-    }
-    shader_ltc.set_int("numAreaLights", NUM_AREA_LIGHTS as i32);
+    shader_ltc.apply();
     shader_ltc.set_int("LTC1", 0);
     shader_ltc.set_int("LTC2", 1);
     shader_ltc.set_int("material.diffuse", 2);
-    increment_roughness(0.0, &shader_ltc);
     ogl::unuse_program();
 
     shader_light_plane.apply();
@@ -292,7 +243,10 @@ fn main() {
     shader_light_plane.set_vec3("lightColor", LIGHT_COLOUR);
     ogl::unuse_program();
 
-    let mut time_query = TimeQuery::new();
+    let shader_ui = Program::new().link("src/ui.vs", "src/ui.fs");
+    let mut ui = Ui::new();
+
+    let mut profiler = Profiler::new();
 
     glfw.poll_events();
 
@@ -306,7 +260,25 @@ fn main() {
         let delta_time = current_frame - last_frame;
         last_frame = current_frame;
 
-        process_input(&mut camera, &mut window, &shader_ltc, delta_time);
+        profiler.begin_frame();
+
+        process_input(&mut camera, &mut window, delta_time);
+
+        profiler.begin("ui build");
+        let (width, height) = window.get_size();
+        ui.begin_frame(width, height);
+        let (lights_changed, material_changed) = parameters_window(&mut ui, &mut parameters, &mut area_lights, &light_names);
+        profiler.overlay(&mut ui, Vec2::new((width as f32 - 450.0).max(0.0), 10.0));
+        profiler.end();
+
+        profiler.begin_gpu("upload");
+        if lights_changed {
+            upload_area_lights(&shader_ltc, &area_lights);
+        }
+        if material_changed {
+            upload_material(&shader_ltc, &parameters);
+        }
+        profiler.end();
 
         ogl::clear_color(0.0, 0.0, 0.0, 1.0);
         ogl::clear();
@@ -327,100 +299,87 @@ fn main() {
         ogl::active_texture(2);
         concrete_texture.bind();
 
-        time_query.begin();
+        profiler.begin_gpu("plane");
         plane.bind();
         plane.draw();
-        time_query.end();
+        profiler.end();
 
         ogl::unuse_program();
 
+        profiler.begin_gpu("light planes");
         shader_light_plane.apply();
         shader_light_plane.set_mat4("view", view);
         shader_light_plane.set_mat4("projection", projection);
         let _sin_now_time = f32::sin(current_frame);
         area_light.bind();
-        unsafe {
-            for i in 0..NUM_AREA_LIGHTS {
-                let model = Mat4::from_translation(AREA_LIGHTS[i].offset) * Mat4::from_rotation_y(AREA_LIGHTS[i].y_rotation);
-                shader_light_plane.set_mat4("model", model);
-                shader_light_plane.set_vec3("lightColor", AREA_LIGHTS[i].colour);
-                area_light.draw();
-            }
+        for light in &area_lights {
+            shader_light_plane.set_mat4("model", area_light_model(light));
+            shader_light_plane.set_vec3("lightColor", light.colour);
+            area_light.draw();
         }
         ogl::unuse_program();
+        profiler.end();
 
-        time_query.fetch_timestamp();
+        profiler.begin_gpu("ui");
+        ui.end_frame(&shader_ui);
+        profiler.end();
+
+        profiler.end_frame();
+        match profiler.save_capture("profile.json") {
+            Ok(true) => println!("Wrote profile.json"),
+            Ok(false) => {}
+            Err(error) => println!("Failed to write profile.json: {}", error),
+        }
 
         window.swap_buffers();
 
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut camera, &mut window, event);
+            handle_window_event(&mut camera, &mut window, &mut ui, &mut parameters.ui_focus, event);
         }
     }
 
-    println!(
-        "Total average time(ms) = {}",
-        1.0e-6 * time_query.total_query_time_ns as f64 / time_query.num_queries as f64
-    );
+    println!("Average and 95th percentile times(ms) of the last frames:");
+    for summary in profiler.summaries() {
+        println!("{}", summary);
+    }
 }
 
-struct TimeQuery {
-    time_query: u32,
-    total_query_time_ns: u64,
-    num_queries: u64,
-}
+// returns whether the lights and whether the material changed
+fn parameters_window(
+    ui: &mut Ui,
+    parameters: &mut Parameters,
+    area_lights: &mut [AreaLight; NUM_AREA_LIGHTS],
+    light_names: &[String],
+) -> (bool, bool) {
+    let mut lights_changed = false;
+    let mut material_changed = false;
 
-impl TimeQuery {
-    pub fn new() -> Self {
-        let mut time_query = 0u32;
+    ui.window("area lights", Vec2::new(10.0, 10.0), 320.0, |ui| {
+        ui.label(if parameters.ui_focus { "tab: back to the camera" } else { "tab: use the mouse here" });
 
-        unsafe {
-            gl::GenQueries(1, &mut time_query);
-        }
+        material_changed |= ui.slider("roughness", &mut parameters.roughness, 0.0..=1.0);
+        material_changed |= ui.color("albedo", &mut parameters.albedo);
 
-        TimeQuery {
-            time_query,
-            total_query_time_ns: 0u64,
-            num_queries: 0u64,
-        }
-    }
+        let items = light_names.iter().map(String::as_str).collect::<Vec<_>>();
+        ui.combo("light", &mut parameters.selected_light, &items);
 
-    pub fn begin(&self) {
-        unsafe {
-            gl::BeginQuery(gl::TIME_ELAPSED, self.time_query);
-        }
-    }
+        let light = &mut area_lights[parameters.selected_light];
+        lights_changed |= ui.color("color", &mut light.colour);
+        lights_changed |= ui.slider("intensity", &mut light.intensity, 0.0..=10.0);
+        lights_changed |= ui.slider("rotation", &mut light.y_rotation, 0.0..=TAU);
+        lights_changed |= ui.checkbox("two sided", &mut light.two_sided);
 
-    pub fn end(&self) {
-        unsafe {
-            gl::EndQuery(gl::TIME_ELAPSED);
-        }
-    }
-
-    pub fn fetch_timestamp(&mut self) {
-        let mut elapsed = 0u64;
-
-        unsafe {
-            gl::GetQueryObjectui64v(self.time_query, gl::QUERY_RESULT, &mut elapsed);
+        if ui.button("scatter lights") {
+            *area_lights = configure_area_lights();
+            lights_changed = true;
         }
+    });
 
-        self.num_queries += 1;
-        self.total_query_time_ns += elapsed;
-    }
-}
-
-impl Drop for TimeQuery {
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteQueries(1, &self.time_query);
-        }
-    }
+    (lights_changed, material_changed)
 }
 
-fn process_input(camera: &mut Camera, window: &mut Window, shader: &Program, delta_time: f32) {
-    static mut MODE: Key = Key::Escape;
-
+fn process_input(camera: &mut Camera, window: &mut Window, delta_time: f32) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true);
     }
@@ -439,53 +398,37 @@ fn process_input(camera: &mut Camera, window: &mut Window, shader: &Program, del
     if window.get_key(Key::D) == Action::Press {
         camera.process_keyboard(Movement::Right, delta_time);
     }
+}
 
-    /*
-    if window.get_key(Key::B) == Action::Press {
-        switch_two_sided(true, shader);
+fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, ui: &mut Ui, ui_focus: &mut bool, event: glfw::WindowEvent) {
+    if *ui_focus {
+        ui.handle_event(&event);
     }
-    */
 
-    unsafe {
-        if window.get_key(Key::R) == Action::Press {
-            MODE = Key::R;
-        }
-        if MODE == Key::R {
-            if window.get_key(Key::LeftShift) == Action::Press {
-                increment_roughness(0.01, shader);
-            } else {
-                increment_roughness(-0.01, shader);
-            }
-        }
-
-        /*
-        if window.get_key(Key::I) == Action::Press {
-            MODE = Key::I;
-        }
-        if MODE == Key::I {
-            if window.get_key(Key::LeftShift) == Action::Press {
-                increment_light_intensity(0.025, shader);
-            } else {
-                increment_light_intensity(-0.025, shader);
-            }
-        }
-        */
-    }
-}
-
-fn handle_window_event(camera: &mut Camera, window: &mut glfw::Window, event: glfw::WindowEvent) {
     match event {
         WindowEvent::Key(key, _, action, _) => {
             if action == Action::Release && key == Key::Space {
                 ogl::polygon_mode();
             }
+            if action == Action::Release && key == Key::Tab {
+                *ui_focus = !*ui_focus;
+                window.set_cursor_mode(if *ui_focus { CursorMode::Normal } else { CursorMode::Disabled });
+            }
         }
         WindowEvent::FramebufferSize(width, height) => ogl::viewport(width, height),
-        WindowEvent::Scroll(_, y_offset) => camera.process_scroll(y_offset as f32),
+        WindowEvent::Scroll(_, y_offset) => {
+            if !(*ui_focus && ui.wants_mouse()) {
+                camera.process_scroll(y_offset as f32);
+            }
+        }
         WindowEvent::CursorPos(_x_pos, _y_pos) => {
             let (x_pos, y_pos) = window.get_cursor_pos();
 
-            camera.process_mouse(x_pos as f32, y_pos as f32, true);
+            if *ui_focus {
+                camera.set_cursor(x_pos as f32, y_pos as f32);
+            } else {
+                camera.process_mouse(x_pos as f32, y_pos as f32, true);
+            }
         }
         _ => {}
     }
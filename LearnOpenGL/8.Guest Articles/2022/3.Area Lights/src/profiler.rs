@@ -0,0 +1,420 @@
+pub mod profiler {
+    use crate::Ui;
+    use glam::Vec2;
+    use std::{
+        collections::HashMap,
+        fmt,
+        fs::File,
+        io::{self, BufWriter, Write},
+        time::Instant,
+    };
+
+    // frames of timestamp queries in flight, a frame's results are read this many frames later so the read never waits
+    const LATENCY: usize = 3;
+    // samples kept per scope for its averages and percentiles
+    const HISTORY: usize = 120;
+    const CAPTURE_FRAMES: u64 = 60;
+
+    // the last HISTORY durations of a scope, in milliseconds, oldest first
+    #[derive(Default)]
+    struct Samples {
+        values: Vec<f32>,
+    }
+
+    impl Samples {
+        fn push(&mut self, value: f32) {
+            if self.values.len() == HISTORY {
+                self.values.remove(0);
+            }
+            self.values.push(value);
+        }
+
+        fn summary(&self) -> Option<Summary> {
+            if self.values.is_empty() {
+                return None;
+            }
+
+            let mut sorted = self.values.clone();
+            sorted.sort_by(f32::total_cmp);
+            let percentile = |p: f32| sorted[((sorted.len() - 1) as f32 * p).round() as usize];
+
+            Some(Summary {
+                average: sorted.iter().sum::<f32>() / sorted.len() as f32,
+                median: percentile(0.5),
+                p95: percentile(0.95),
+                max: sorted[sorted.len() - 1],
+            })
+        }
+    }
+
+    // of the samples kept, in milliseconds
+    #[derive(Clone, Copy, Debug)]
+    pub struct Summary {
+        pub average: f32,
+        pub median: f32,
+        pub p95: f32,
+        pub max: f32,
+    }
+
+    pub struct ScopeSummary {
+        pub name: String,
+        // 0 for the frame, 1 for the scopes in it and so on
+        pub depth: usize,
+        pub cpu: Summary,
+        // None for scopes without gpu timing, or before their first results are in
+        pub gpu: Option<Summary>,
+    }
+
+    impl fmt::Display for ScopeSummary {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            // names are padded so the columns line up whatever the indent
+            let indent = 2 * self.depth;
+            let width = 16usize.saturating_sub(indent);
+            write!(f, "{:indent$}{:<width$} cpu {:6.2} {:6.2}", "", self.name, self.cpu.average, self.cpu.p95)?;
+            if let Some(gpu) = self.gpu {
+                write!(f, "  gpu {:6.2} {:6.2}", gpu.average, gpu.p95)?;
+            }
+            Ok(())
+        }
+    }
+
+    // a scope by where it is nested, so the same name under two parents is two scopes
+    struct ScopeStats {
+        name: String,
+        path: String,
+        parent: Option<usize>,
+        depth: usize,
+        cpu: Samples,
+        gpu: Samples,
+    }
+
+    struct OpenScope {
+        stats: usize,
+        start: Instant,
+        // the index of its starting timestamp in the frame's queries
+        gpu_start: Option<usize>,
+    }
+
+    struct GpuScope {
+        stats: usize,
+        start: usize,
+        end: usize,
+    }
+
+    // the timestamp queries of one frame, reused LATENCY frames later
+    #[derive(Default)]
+    struct FrameQueries {
+        frame: u64,
+        queries: Vec<gl::types::GLuint>,
+        used: usize,
+        scopes: Vec<GpuScope>,
+        // when the frame began, to line its gpu events up with the cpu ones in a trace
+        start_us: f64,
+    }
+
+    // a complete event of the chrome trace format, tid 1 for cpu and 2 for gpu
+    struct TraceEvent {
+        name: String,
+        tid: u32,
+        ts_us: f64,
+        dur_us: f64,
+    }
+
+    // times named scopes between begin_frame and end_frame; begin_gpu scopes are also timed on the gpu with timestamp
+    // queries. begin_frame opens a "frame" scope everything else nests in
+    pub struct Profiler {
+        epoch: Instant,
+        frame: u64,
+        frames: Vec<FrameQueries>,
+        stats: Vec<ScopeStats>,
+        by_path: HashMap<String, usize>,
+        stack: Vec<OpenScope>,
+        // frames whose gpu results weren't in when their queries were needed again
+        dropped: u64,
+        // the frames being recorded, from and not including to
+        capture: Option<(u64, u64)>,
+        events: Vec<TraceEvent>,
+    }
+
+    impl Profiler {
+        pub fn new() -> Self {
+            Profiler {
+                epoch: Instant::now(),
+                frame: 0,
+                frames: (0..LATENCY).map(|_| FrameQueries::default()).collect(),
+                stats: vec![],
+                by_path: HashMap::new(),
+                stack: vec![],
+                dropped: 0,
+                capture: None,
+                events: vec![],
+            }
+        }
+
+        // reads the gpu results of the frame LATENCY frames ago, then opens this frame's scope
+        pub fn begin_frame(&mut self) {
+            assert!(self.stack.is_empty(), "begin_frame inside a frame");
+
+            let slot = self.frame as usize % LATENCY;
+            self.resolve(slot);
+
+            let start_us = self.microseconds(Instant::now());
+            let frame = &mut self.frames[slot];
+            frame.frame = self.frame;
+            frame.used = 0;
+            frame.scopes.clear();
+            frame.start_us = start_us;
+
+            self.begin_gpu("frame");
+        }
+
+        pub fn end_frame(&mut self) {
+            self.end();
+            assert!(self.stack.is_empty(), "a scope was left open at the end of the frame");
+            self.frame += 1;
+        }
+
+        // a scope timed on the cpu only, closed by the next end
+        pub fn begin(&mut self, name: &str) {
+            let stats = self.stats_for(name);
+            self.stack.push(OpenScope {
+                stats,
+                start: Instant::now(),
+                gpu_start: None,
+            });
+        }
+
+        // a scope timed on the cpu and from when the gpu reaches its commands, closed by the next end
+        pub fn begin_gpu(&mut self, name: &str) {
+            let stats = self.stats_for(name);
+            let gpu_start = self.timestamp();
+            self.stack.push(OpenScope {
+                stats,
+                start: Instant::now(),
+                gpu_start: Some(gpu_start),
+            });
+        }
+
+        pub fn end(&mut self) {
+            let scope = self.stack.pop().expect("end without a scope to close");
+            let end = Instant::now();
+
+            if let Some(start) = scope.gpu_start {
+                let end = self.timestamp();
+                self.frames[self.frame as usize % LATENCY].scopes.push(GpuScope { stats: scope.stats, start, end });
+            }
+
+            let duration = end.duration_since(scope.start);
+            self.stats[scope.stats].cpu.push(duration.as_secs_f32() * 1000.0);
+
+            if self.is_captured(self.frame) {
+                self.events.push(TraceEvent {
+                    name: self.stats[scope.stats].name.clone(),
+                    tid: 1,
+                    ts_us: self.microseconds(scope.start),
+                    dur_us: duration.as_secs_f64() * 1.0e6,
+                });
+            }
+        }
+
+        // every scope seen so far, each followed by the ones inside it in the order they first ran
+        pub fn summaries(&self) -> Vec<ScopeSummary> {
+            let children = |parent: Option<usize>| (0..self.stats.len()).rev().filter(move |&i| self.stats[i].parent == parent);
+
+            let mut order = vec![];
+            let mut stack = children(None).collect::<Vec<_>>();
+            while let Some(i) = stack.pop() {
+                order.push(i);
+                stack.extend(children(Some(i)));
+            }
+
+            order
+                .into_iter()
+                .filter_map(|i| {
+                    let stats = &self.stats[i];
+                    Some(ScopeSummary {
+                        name: stats.name.clone(),
+                        depth: stats.depth,
+                        cpu: stats.cpu.summary()?,
+                        gpu: stats.gpu.summary(),
+                    })
+                })
+                .collect()
+        }
+
+        // records the next frames for save_capture
+        pub fn capture(&mut self, frames: u64) {
+            self.capture = Some((self.frame + 1, self.frame + 1 + frames));
+            self.events.clear();
+        }
+
+        pub fn is_capturing(&self) -> bool {
+            self.capture.is_some()
+        }
+
+        // once the capture's last gpu results are in, writes it to path as chrome trace event json and returns true
+        pub fn save_capture(&mut self, path: &str) -> io::Result<bool> {
+            match self.capture {
+                Some((_, to)) if self.frame >= to + LATENCY as u64 => {}
+                _ => return Ok(false),
+            }
+
+            self.capture = None;
+            self.events.sort_by(|a, b| a.ts_us.total_cmp(&b.ts_us));
+
+            let mut file = BufWriter::new(File::create(path)?);
+            writeln!(file, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+            writeln!(file, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":1,\"args\":{{\"name\":\"cpu\"}}}},")?;
+            write!(file, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":2,\"args\":{{\"name\":\"gpu\"}}}}")?;
+            for event in self.events.drain(..) {
+                write!(
+                    file,
+                    ",\n{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}}}",
+                    escape(&event.name),
+                    if event.tid == 1 { "cpu" } else { "gpu" },
+                    event.tid,
+                    event.ts_us,
+                    event.dur_us
+                )?;
+            }
+            writeln!(file, "\n]}}")?;
+            file.flush()?;
+
+            Ok(true)
+        }
+
+        // a window with the frame time plots and every scope's average and 95th percentile, and a button to capture a trace
+        pub fn overlay(&mut self, ui: &mut Ui, position: Vec2) {
+            ui.window("profiler", position, 440.0, |ui| {
+                if let Some(frame) = self.by_path.get("frame").map(|&i| &self.stats[i]) {
+                    ui.plot("frame cpu ms", &frame.cpu.values, 0.0..=33.3);
+                    ui.plot("frame gpu ms", &frame.gpu.values, 0.0..=33.3);
+                }
+
+                ui.label("ms, average and 95th percentile");
+                for summary in self.summaries() {
+                    ui.label(&summary.to_string());
+                }
+                if self.dropped > 0 {
+                    ui.label(&format!("gpu results dropped in {} frames", self.dropped));
+                }
+
+                if self.is_capturing() {
+                    ui.label("capturing");
+                } else if ui.button(&format!("capture {} frames", CAPTURE_FRAMES)) {
+                    self.capture(CAPTURE_FRAMES);
+                }
+            });
+        }
+
+        // the frame's scopes as their gpu timestamps say, if they are all in
+        fn resolve(&mut self, slot: usize) {
+            let frame = &self.frames[slot];
+            if frame.scopes.is_empty() {
+                return;
+            }
+
+            // the gpu writes them in order, so the last one being in means they all are
+            let mut available = 0;
+            unsafe {
+                gl::GetQueryObjectiv(frame.queries[frame.used - 1], gl::QUERY_RESULT_AVAILABLE, &mut available);
+            }
+            if available == 0 {
+                self.dropped += 1;
+                return;
+            }
+
+            let timestamps = frame.queries[..frame.used]
+                .iter()
+                .map(|&query| {
+                    let mut timestamp = 0u64;
+                    unsafe {
+                        gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut timestamp);
+                    }
+                    timestamp
+                })
+                .collect::<Vec<_>>();
+            let base = timestamps.iter().copied().min().unwrap_or(0);
+            let captured = self.is_captured(frame.frame);
+
+            for scope in &frame.scopes {
+                let nanoseconds = timestamps[scope.end].saturating_sub(timestamps[scope.start]);
+                self.stats[scope.stats].gpu.push(nanoseconds as f32 * 1.0e-6);
+
+                if captured {
+                    self.events.push(TraceEvent {
+                        name: self.stats[scope.stats].name.clone(),
+                        tid: 2,
+                        ts_us: frame.start_us + (timestamps[scope.start] - base) as f64 * 1.0e-3,
+                        dur_us: nanoseconds as f64 * 1.0e-3,
+                    });
+                }
+            }
+        }
+
+        // a query of the current frame's set, made the first time it is needed
+        fn timestamp(&mut self) -> usize {
+            let frame = &mut self.frames[self.frame as usize % LATENCY];
+
+            if frame.used == frame.queries.len() {
+                let mut query = 0;
+                unsafe {
+                    gl::GenQueries(1, &mut query);
+                }
+                frame.queries.push(query);
+            }
+
+            unsafe {
+                gl::QueryCounter(frame.queries[frame.used], gl::TIMESTAMP);
+            }
+            frame.used += 1;
+            frame.used - 1
+        }
+
+        // the scope called name in whatever scope is open
+        fn stats_for(&mut self, name: &str) -> usize {
+            let parent = self.stack.last().map(|scope| scope.stats);
+            let path = match parent {
+                Some(parent) => format!("{}/{}", self.stats[parent].path, name),
+                None => name.to_string(),
+            };
+
+            if let Some(&i) = self.by_path.get(&path) {
+                return i;
+            }
+
+            self.stats.push(ScopeStats {
+                name: name.to_string(),
+                path: path.clone(),
+                parent,
+                depth: self.stack.len(),
+                cpu: Samples::default(),
+                gpu: Samples::default(),
+            });
+            self.by_path.insert(path, self.stats.len() - 1);
+            self.stats.len() - 1
+        }
+
+        fn is_captured(&self, frame: u64) -> bool {
+            self.capture.is_some_and(|(from, to)| (from..to).contains(&frame))
+        }
+
+        fn microseconds(&self, instant: Instant) -> f64 {
+            instant.duration_since(self.epoch).as_secs_f64() * 1.0e6
+        }
+    }
+
+    impl Drop for Profiler {
+        fn drop(&mut self) {
+            for frame in &self.frames {
+                unsafe {
+                    gl::DeleteQueries(frame.queries.len() as i32, frame.queries.as_ptr());
+                }
+            }
+        }
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+}